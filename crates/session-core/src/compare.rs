@@ -0,0 +1,436 @@
+//! 两个会话的逐轮对比。
+//!
+//! 以用户的每条 prompt 为界把会话切成若干轮，统计每轮的请求、token、花费、
+//! 耗时、用到的工具和改动的文件，再按 prompt 相似度把两边的轮次对齐
+//! （类似 diff 的序列比对），没有对应轮次的一侧为空。
+//!
+//! 请求来自 [`crate::stats::get_session_cost`]，文件改动来自
+//! [`SessionProvider::file_changes`](crate::provider::SessionProvider::file_changes)，
+//! 不支持的来源只是没有文件列表。
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use chrono::{DateTime, Utc};
+
+use crate::models::compare::{
+    CompareSide, SessionComparison, ToolCount, TurnDelta, TurnPair, TurnStats,
+};
+use crate::models::file_history::SessionFileChanges;
+use crate::models::message::{DisplayContentBlock, DisplayMessage};
+use crate::models::stats::RequestRecord;
+use crate::paths::validate_session_file;
+use crate::provider;
+use crate::stats;
+
+const PROMPT_PREVIEW_CHARS: usize = 200;
+
+/// Text the CLIs put in user messages that the user did not type.
+const INJECTED_PREFIXES: &[&str] = &[
+    "<environment_context>",
+    "<user_instructions>",
+    "# AGENTS.md instructions",
+    "<local-command-",
+    "<system-reminder>",
+    "Caveat: The messages below",
+];
+
+pub fn compare_sessions(
+    source_a: &str,
+    file_path_a: &str,
+    source_b: &str,
+    file_path_b: &str,
+) -> Result<SessionComparison, String> {
+    let (side_a, turns_a) = load_side(source_a, file_path_a)?;
+    let (side_b, turns_b) = load_side(source_b, file_path_b)?;
+
+    let files_a: BTreeSet<&String> = side_a.files.iter().collect();
+    let files_b: BTreeSet<&String> = side_b.files.iter().collect();
+    let files_only_a = files_a
+        .difference(&files_b)
+        .map(|f| f.to_string())
+        .collect();
+    let files_only_b = files_b
+        .difference(&files_a)
+        .map(|f| f.to_string())
+        .collect();
+    let files_both = files_a
+        .intersection(&files_b)
+        .map(|f| f.to_string())
+        .collect();
+
+    Ok(SessionComparison {
+        a: side_a,
+        b: side_b,
+        turns: align_turns(turns_a, turns_b),
+        files_only_a,
+        files_only_b,
+        files_both,
+    })
+}
+
+fn load_side(source: &str, file_path: &str) -> Result<(CompareSide, Vec<TurnStats>), String> {
+    let path = validate_session_file(source, file_path)?;
+    let provider = provider::get(source)?;
+    let messages = provider.parse_all_messages(&path)?;
+    let cost = stats::get_session_cost(source, file_path)?;
+    // 不支持补丁导出的来源没有文件改动可看，不算错误。
+    let changes = provider.file_changes(&path).unwrap_or_default();
+
+    let turns = build_turns(&messages, &cost.requests, &changes);
+
+    let mut models: Vec<String> = Vec::new();
+    for request in &cost.requests {
+        if !request.model.is_empty() && !models.contains(&request.model) {
+            models.push(request.model.clone());
+        }
+    }
+    let mut tools: HashMap<String, usize> = HashMap::new();
+    let mut files: BTreeSet<String> = BTreeSet::new();
+    for turn in &turns {
+        for tool in &turn.tools {
+            *tools.entry(tool.name.clone()).or_default() += tool.count;
+        }
+        files.extend(turn.files.iter().cloned());
+    }
+    let times: Vec<DateTime<Utc>> = messages
+        .iter()
+        .filter_map(|m| parse_time(m.timestamp.as_deref()))
+        .collect();
+    let duration_ms = match (times.iter().min(), times.iter().max()) {
+        (Some(first), Some(last)) => Some((*last - *first).num_milliseconds().max(0) as u64),
+        _ => None,
+    };
+
+    let side = CompareSide {
+        source: source.to_string(),
+        file_path: file_path.to_string(),
+        models,
+        turn_count: turns.len(),
+        request_count: cost.request_count,
+        total_tokens: cost.total_tokens,
+        cost_usd: cost.cost_usd,
+        duration_ms,
+        tools: sorted_tool_counts(tools),
+        files: files.into_iter().collect(),
+    };
+    Ok((side, turns))
+}
+
+fn parse_time(timestamp: Option<&str>) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(timestamp?)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+/// The prompt text when `message` starts a turn.
+fn prompt_text(message: &DisplayMessage) -> Option<&str> {
+    if message.role != "user" {
+        return None;
+    }
+    message.content.iter().find_map(|block| match block {
+        DisplayContentBlock::Text { text } => {
+            let trimmed = text.trim();
+            let injected = INJECTED_PREFIXES.iter().any(|p| trimmed.starts_with(p));
+            (!trimmed.is_empty() && !injected).then_some(trimmed)
+        }
+        _ => None,
+    })
+}
+
+fn sorted_tool_counts(counts: HashMap<String, usize>) -> Vec<ToolCount> {
+    let mut tools: Vec<ToolCount> = counts
+        .into_iter()
+        .map(|(name, count)| ToolCount { name, count })
+        .collect();
+    tools.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+    tools
+}
+
+/// Split `messages` into turns and attribute requests and file changes to
+/// them. Requests made before the first prompt count towards the first turn.
+fn build_turns(
+    messages: &[DisplayMessage],
+    requests: &[RequestRecord],
+    changes: &SessionFileChanges,
+) -> Vec<TurnStats> {
+    let mut turns: Vec<TurnStats> = Vec::new();
+    let mut starts: Vec<Option<DateTime<Utc>>> = Vec::new();
+    let mut ends: Vec<Option<DateTime<Utc>>> = Vec::new();
+    let mut tools: Vec<HashMap<String, usize>> = Vec::new();
+    let mut turn_of_uuid: HashMap<&str, usize> = HashMap::new();
+
+    for message in messages {
+        let time = parse_time(message.timestamp.as_deref());
+        if let Some(prompt) = prompt_text(message) {
+            turns.push(TurnStats {
+                index: turns.len(),
+                prompt: prompt.chars().take(PROMPT_PREVIEW_CHARS).collect(),
+                message_uuid: message.uuid.clone(),
+                started_at: message.timestamp.clone(),
+                ..TurnStats::default()
+            });
+            starts.push(time);
+            ends.push(time);
+            tools.push(HashMap::new());
+        }
+        let Some(current) = turns.len().checked_sub(1) else {
+            continue;
+        };
+        if let Some(uuid) = &message.uuid {
+            turn_of_uuid.insert(uuid, current);
+        }
+        if time.is_some() && time > ends[current] {
+            ends[current] = time;
+        }
+        for block in &message.content {
+            let name = match block {
+                DisplayContentBlock::ToolUse { name, .. }
+                | DisplayContentBlock::FunctionCall { name, .. } => name,
+                _ => continue,
+            };
+            *tools[current].entry(name.clone()).or_default() += 1;
+        }
+    }
+    if turns.is_empty() {
+        return turns;
+    }
+
+    // Turn containing a point in time: the last one started at or before it.
+    let turn_at = |time: Option<DateTime<Utc>>| -> Option<usize> {
+        let time = time?;
+        let after = starts.partition_point(|s| s.is_none_or(|s| s <= time));
+        Some(after.saturating_sub(1))
+    };
+
+    for request in requests {
+        let turn = request
+            .message_uuid
+            .as_deref()
+            .and_then(|uuid| turn_of_uuid.get(uuid).copied())
+            .or_else(|| turn_at(parse_time(Some(&request.timestamp))));
+        let Some(turn) = turn.map(|t| &mut turns[t]) else {
+            continue;
+        };
+        turn.request_count += 1;
+        turn.input_tokens += request.input_tokens;
+        turn.output_tokens += request.output_tokens;
+        turn.cache_read_tokens += request.cache_read_tokens;
+        turn.cache_creation_tokens += request.cache_creation_tokens;
+        turn.total_tokens += request.total_tokens;
+        turn.cost_usd += request.cost_usd;
+    }
+
+    let mut files: BTreeMap<usize, BTreeSet<String>> = BTreeMap::new();
+    for change in &changes.changes {
+        let turn = turn_at(parse_time(change.timestamp.as_deref()))
+            .unwrap_or(change.turn.min(turns.len() - 1));
+        let entry = files.entry(turn).or_default();
+        entry.insert(changes.relative_path(&change.path));
+        if let Some(old_path) = &change.old_path {
+            entry.insert(changes.relative_path(old_path));
+        }
+    }
+
+    for (i, turn) in turns.iter_mut().enumerate() {
+        turn.duration_ms = match (starts[i], ends[i]) {
+            (Some(start), Some(end)) => Some((end - start).num_milliseconds().max(0) as u64),
+            _ => None,
+        };
+        turn.tools = sorted_tool_counts(std::mem::take(&mut tools[i]));
+        turn.files = files
+            .remove(&i)
+            .map(|f| f.into_iter().collect())
+            .unwrap_or_default();
+    }
+    turns
+}
+
+/// Words of a prompt for similarity; CJK characters count one each.
+fn prompt_words(prompt: &str) -> BTreeSet<String> {
+    let mut words = BTreeSet::new();
+    let mut word = String::new();
+    for c in prompt.chars().flat_map(char::to_lowercase) {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            word.push(c);
+            continue;
+        }
+        if !word.is_empty() {
+            words.insert(std::mem::take(&mut word));
+        }
+        if c.is_alphanumeric() {
+            words.insert(c.to_string());
+        }
+    }
+    if !word.is_empty() {
+        words.insert(word);
+    }
+    words
+}
+
+/// Jaccard overlap of the two prompts' words.
+fn prompt_similarity(a: &BTreeSet<String>, b: &BTreeSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 1.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+/// Align two turn lists in order, pairing as many turns as possible and
+/// preferring pairs whose prompts are alike.
+fn align_turns(a: Vec<TurnStats>, b: Vec<TurnStats>) -> Vec<TurnPair> {
+    let words_a: Vec<_> = a.iter().map(|t| prompt_words(&t.prompt)).collect();
+    let words_b: Vec<_> = b.iter().map(|t| prompt_words(&t.prompt)).collect();
+    let (n, m) = (a.len(), b.len());
+
+    // score[i][j]: best alignment of a[i..] with b[j..]. Every pair scores
+    // at least 0.5 so a match always beats two gaps.
+    let mut score = vec![vec![0.0_f64; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            let pair = 0.5 + prompt_similarity(&words_a[i], &words_b[j]) + score[i + 1][j + 1];
+            score[i][j] = pair.max(score[i + 1][j]).max(score[i][j + 1]);
+        }
+    }
+
+    let mut a = a.into_iter().map(Some).collect::<Vec<_>>();
+    let mut b = b.into_iter().map(Some).collect::<Vec<_>>();
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m {
+            let similarity = prompt_similarity(&words_a[i], &words_b[j]);
+            if score[i][j] == 0.5 + similarity + score[i + 1][j + 1] {
+                pairs.push(turn_pair(a[i].take(), b[j].take(), Some(similarity)));
+                i += 1;
+                j += 1;
+                continue;
+            }
+        }
+        if i < n && (j == m || score[i][j] == score[i + 1][j]) {
+            pairs.push(turn_pair(a[i].take(), None, None));
+            i += 1;
+        } else {
+            pairs.push(turn_pair(None, b[j].take(), None));
+            j += 1;
+        }
+    }
+    pairs
+}
+
+fn turn_pair(a: Option<TurnStats>, b: Option<TurnStats>, similarity: Option<f64>) -> TurnPair {
+    let delta = match (&a, &b) {
+        (Some(a), Some(b)) => Some(TurnDelta {
+            total_tokens: b.total_tokens as i64 - a.total_tokens as i64,
+            cost_usd: b.cost_usd - a.cost_usd,
+            duration_ms: match (a.duration_ms, b.duration_ms) {
+                (Some(da), Some(db)) => Some(db as i64 - da as i64),
+                _ => None,
+            },
+        }),
+        _ => None,
+    };
+    TurnPair {
+        a,
+        b,
+        prompt_similarity: similarity,
+        delta,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: &str, ts: &str, content: DisplayContentBlock) -> DisplayMessage {
+        DisplayMessage {
+            uuid: Some(format!("{}-{}", role, ts)),
+            parent_uuid: None,
+            role: role.to_string(),
+            timestamp: Some(format!("2025-01-01T00:00:{}Z", ts)),
+            model: None,
+            content: vec![content],
+            usage: None,
+        }
+    }
+
+    fn prompt(ts: &str, text: &str) -> DisplayMessage {
+        message(
+            "user",
+            ts,
+            DisplayContentBlock::Text {
+                text: text.to_string(),
+            },
+        )
+    }
+
+    fn tool(ts: &str, name: &str) -> DisplayMessage {
+        let call = DisplayContentBlock::ToolUse {
+            id: ts.to_string(),
+            name: name.to_string(),
+            input: "{}".to_string(),
+            subagent: None,
+        };
+        message("assistant", ts, call)
+    }
+
+    fn request(ts: &str, tokens: u64) -> RequestRecord {
+        RequestRecord {
+            timestamp: format!("2025-01-01T00:00:{}Z", ts),
+            source: "claude".to_string(),
+            project_id: String::new(),
+            session_id: String::new(),
+            file_path: String::new(),
+            model: "m".to_string(),
+            input_tokens: tokens,
+            output_tokens: 0,
+            cache_read_tokens: 0,
+            cache_creation_tokens: 0,
+            total_tokens: tokens,
+            cost_usd: tokens as f64 / 1000.0,
+            duration_ms: None,
+            message_uuid: None,
+        }
+    }
+
+    #[test]
+    fn splits_turns_and_aligns_by_prompt() {
+        let a = build_turns(
+            &[
+                prompt("00", "<environment_context>cwd</environment_context>"),
+                prompt("01", "add a login page"),
+                tool("02", "Edit"),
+                tool("05", "Edit"),
+                prompt("10", "now write tests for login"),
+                tool("11", "Bash"),
+            ],
+            &[request("02", 100), request("05", 50), request("11", 10)],
+            &SessionFileChanges::default(),
+        );
+        assert_eq!(a.len(), 2);
+        assert_eq!(a[0].total_tokens, 150);
+        assert_eq!(a[0].duration_ms, Some(4000));
+        assert_eq!(a[0].tools[0].name, "Edit");
+        assert_eq!(a[0].tools[0].count, 2);
+
+        let b = build_turns(
+            &[
+                prompt("01", "fix the build first"),
+                prompt("02", "add a login page please"),
+                prompt("03", "now write tests for login"),
+                tool("04", "Bash"),
+            ],
+            &[request("04", 30)],
+            &SessionFileChanges::default(),
+        );
+
+        let pairs = align_turns(a, b);
+        assert_eq!(pairs.len(), 3);
+        assert!(pairs[0].a.is_none());
+        assert_eq!(pairs[0].b.as_ref().unwrap().prompt, "fix the build first");
+        assert_eq!(pairs[1].b.as_ref().unwrap().index, 1);
+        let last = &pairs[2];
+        assert_eq!(last.prompt_similarity, Some(1.0));
+        assert_eq!(last.delta.as_ref().unwrap().total_tokens, 20);
+    }
+}
@@ -0,0 +1,273 @@
+//! 按数据源配置的数据根目录。
+//!
+//! 存在 `~/.claude-code-viewer/data-roots.json`，形如
+//!
+//! ```json
+//! {
+//!   "aider": [{ "name": "code", "path": "/home/me/code" }],
+//!   "claude": [{ "name": "ci-runner", "path": "/mnt/sync/ci/.claude" }]
+//! }
+//! ```
+//!
+//! - Claude / Codex / Gemini：每一项是一份额外的 CLI home 副本（同步来的
+//!   `~/.claude`、`~/.codex`、`~/.gemini`），与本机默认 home（名为 `local`）
+//!   一起浏览。除 `local` 外，项目 id 都带 `@<name>:` 前缀，避免不同机器上
+//!   同名项目互相覆盖。
+//! - Aider 没有集中的数据目录，聊天记录写在各个仓库根目录下，只能由用户指定
+//!   要扫描哪些目录；没有 `local`，项目 id 都是 `@<name>:<仓库路径>`。
+//!
+//! 旧版本只存路径字符串（`"aider": ["/home/me/code"]`），读取时仍然兼容，
+//! 名称取路径最后一段。
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+use crate::app_dir::get_app_dir;
+
+/// 本机默认 home（`~/.claude` 等）对应的根目录名，不能被配置项占用。
+pub const DEFAULT_ROOT: &str = "local";
+
+/// [`set_roots`] 成功一次加一。
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// 配置里的一项：名称 + 原始路径字符串（可含 `~`）。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataRoot {
+    pub name: String,
+    pub path: String,
+}
+
+/// 展开后的根目录，`path` 不保证存在。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RootDir {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredRoot {
+    Named(DataRoot),
+    Plain(String),
+}
+
+type DataRootsFile = BTreeMap<String, Vec<DataRoot>>;
+
+fn data_roots_path() -> Option<PathBuf> {
+    get_app_dir().map(|d| d.join("data-roots.json"))
+}
+
+fn load_file() -> DataRootsFile {
+    let raw: BTreeMap<String, Vec<StoredRoot>> = data_roots_path()
+        .and_then(|p| fs::read_to_string(p).ok())
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default();
+    raw.into_iter()
+        .map(|(source, entries)| {
+            let mut roots: Vec<DataRoot> = Vec::with_capacity(entries.len());
+            for entry in entries {
+                let root = match entry {
+                    StoredRoot::Named(root) => root,
+                    StoredRoot::Plain(path) => DataRoot {
+                        name: unique_name(&name_from_path(&path), &roots),
+                        path,
+                    },
+                };
+                roots.push(root);
+            }
+            (source, roots)
+        })
+        .collect()
+}
+
+fn save_file(file: &DataRootsFile) -> Result<(), String> {
+    let path = data_roots_path().ok_or_else(|| "Cannot determine app directory".to_string())?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create app directory: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(file)
+        .map_err(|e| format!("Failed to serialize data roots: {}", e))?;
+
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, json).map_err(|e| format!("Failed to write data roots tmp: {}", e))?;
+    fs::rename(&tmp_path, &path).map_err(|e| format!("Failed to rename data roots file: {}", e))?;
+    Ok(())
+}
+
+/// `~` / `~/…` 展开为 home 目录。
+fn expand_home(raw: &str) -> PathBuf {
+    if raw == "~" {
+        if let Some(home) = dirs::home_dir() {
+            return home;
+        }
+    }
+    if let Some(rest) = raw.strip_prefix("~/") {
+        if let Some(home) = dirs::home_dir() {
+            return home.join(rest);
+        }
+    }
+    PathBuf::from(raw)
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// 旧格式只有路径：取最后一段里的合法字符作为名称。
+fn name_from_path(path: &str) -> String {
+    let name: String = Path::new(path.trim_end_matches(['/', '\\']))
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("")
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .collect();
+    if name.is_empty() {
+        "root".to_string()
+    } else {
+        name
+    }
+}
+
+fn unique_name(base: &str, taken: &[DataRoot]) -> String {
+    let clashes = |n: &str| n == DEFAULT_ROOT || taken.iter().any(|r| r.name == n);
+    if !clashes(base) {
+        return base.to_string();
+    }
+    (2..)
+        .map(|i| format!("{}-{}", base, i))
+        .find(|n| !clashes(n))
+        .unwrap_or_default()
+}
+
+/// 配置里的原始条目（给设置界面回显）。
+pub fn get_roots(source: &str) -> Vec<DataRoot> {
+    load_file().remove(source).unwrap_or_default()
+}
+
+/// 展开后的配置根目录；不存在的目录也会返回，由调用方决定是否跳过。
+pub fn load_roots(source: &str) -> Vec<PathBuf> {
+    get_roots(source).iter().map(|r| expand_home(&r.path)).collect()
+}
+
+/// 本机默认 home（名为 [`DEFAULT_ROOT`]）在前，其后是配置的根目录。
+/// 默认 home 取不到时（没有 home 目录）只返回配置项。
+pub fn resolve_roots(source: &str, default_home: Option<PathBuf>) -> Vec<RootDir> {
+    let mut roots: Vec<RootDir> = default_home
+        .map(|path| RootDir {
+            name: DEFAULT_ROOT.to_string(),
+            path,
+        })
+        .into_iter()
+        .collect();
+    roots.extend(get_roots(source).into_iter().map(|r| RootDir {
+        path: expand_home(&r.path),
+        name: r.name,
+    }));
+    roots
+}
+
+/// 给某个根目录下的项目 id 加上命名空间；默认根目录保持原样，
+/// 这样已有的书签、元数据、回收站记录都不受影响。
+pub fn scoped_id(root: &str, id: &str) -> String {
+    if root == DEFAULT_ROOT {
+        id.to_string()
+    } else {
+        format!("@{}:{}", root, id)
+    }
+}
+
+/// [`scoped_id`] 的逆操作：返回 `(根目录名, 原始 id)`。
+pub fn split_scoped_id(id: &str) -> (&str, &str) {
+    if let Some((root, inner)) = id.strip_prefix('@').and_then(|rest| rest.split_once(':')) {
+        if is_valid_name(root) {
+            return (root, inner);
+        }
+    }
+    (DEFAULT_ROOT, id)
+}
+
+/// 根目录配置的版本号，文件监听比较它来判断要不要重建监听的目录。
+pub fn generation() -> u64 {
+    GENERATION.load(Ordering::Relaxed)
+}
+
+/// 覆盖某个数据源的根目录列表。名称只能含字母、数字、`-`、`_`，不能重复，
+/// 也不能是 `local`；路径必须是已存在的绝对路径目录。去掉首尾空白和重复
+/// 路径后保存，返回实际保存的列表。
+pub fn set_roots(source: &str, roots: Vec<DataRoot>) -> Result<Vec<DataRoot>, String> {
+    let provider = crate::provider::get(source)?;
+
+    let mut cleaned: Vec<DataRoot> = Vec::with_capacity(roots.len());
+    for raw in roots {
+        let path = raw.path.trim().to_string();
+        if path.is_empty() || cleaned.iter().any(|r| r.path == path) {
+            continue;
+        }
+        let name = raw.name.trim().to_string();
+        if !is_valid_name(&name) {
+            return Err(format!("Invalid data root name: {}", name));
+        }
+        if name == DEFAULT_ROOT || cleaned.iter().any(|r| r.name == name) {
+            return Err(format!("Duplicate data root name: {}", name));
+        }
+        let expanded = expand_home(&path);
+        if !expanded.is_absolute() {
+            return Err(format!("Data root must be an absolute path: {}", path));
+        }
+        if !expanded.is_dir() {
+            return Err(format!("Data root is not a directory: {}", path));
+        }
+        cleaned.push(DataRoot { name, path });
+    }
+
+    let mut file = load_file();
+    if cleaned.is_empty() {
+        file.remove(source);
+    } else {
+        file.insert(source.to_string(), cleaned.clone());
+    }
+    save_file(&file)?;
+    GENERATION.fetch_add(1, Ordering::Relaxed);
+
+    provider.invalidate_cache();
+    Ok(cleaned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scoped_ids_round_trip() {
+        assert_eq!(scoped_id(DEFAULT_ROOT, "-home-me-app"), "-home-me-app");
+        let id = scoped_id("ci-runner", "/home/ci/app");
+        assert_eq!(id, "@ci-runner:/home/ci/app");
+        assert_eq!(split_scoped_id(&id), ("ci-runner", "/home/ci/app"));
+        assert_eq!(split_scoped_id("-home-me-app"), (DEFAULT_ROOT, "-home-me-app"));
+        // 不是合法根目录名的 `@…:` 前缀原样保留
+        assert_eq!(split_scoped_id("@a b:c"), (DEFAULT_ROOT, "@a b:c"));
+    }
+
+    #[test]
+    fn legacy_plain_entries_get_names() {
+        let raw: Vec<StoredRoot> =
+            serde_json::from_str(r#"["/srv/code", {"name": "code", "path": "/x"}]"#).unwrap();
+        assert!(matches!(&raw[0], StoredRoot::Plain(p) if p == "/srv/code"));
+        assert!(matches!(&raw[1], StoredRoot::Named(r) if r.name == "code"));
+        let taken = vec![DataRoot {
+            name: "code".to_string(),
+            path: "/x".to_string(),
+        }];
+        assert_eq!(unique_name(&name_from_path("/srv/code/"), &taken), "code-2");
+        assert_eq!(unique_name("local", &[]), "local-2");
+    }
+}
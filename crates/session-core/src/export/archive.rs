@@ -0,0 +1,722 @@
+//! 多会话打包导出：把整个项目、某个标签下的会话或任意一批会话打成一个 zip。
+//!
+//! zip 内容：
+//! - `sessions/<项目>/<标题>.<ext>`：每个会话按所选 [`ExportFormat`] 渲染；
+//! - `index.html`：会话表格（标题、日期、费用、标签），标题链接到对应文件，
+//!   解压后直接用浏览器打开即可浏览；
+//! - `manifest.json`：同样的元数据，供脚本读取。
+//!
+//! [`write_archive`] 写入任意 `Write + Seek`（Tauri 直接写到用户选的文件）；
+//! [`stream_archive`] 每写完一个条目就把已定稿的字节交给回调，web 路由边打包
+//! 边下发，不必先在内存里攒出整个 zip。
+
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+use serde::{Deserialize, Serialize};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use super::{escape_html, render_session, ExportFormat, ExportOptions, HTML_STYLE};
+use crate::models::session::SessionIndexEntry;
+use crate::models::stats::RequestRecord;
+use crate::provider::{self, SessionProvider};
+
+/// Which sessions go into an archive.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum ArchiveSelection {
+    /// Every session of one project.
+    Project { project_id: String },
+    /// Every session carrying this tag, across projects.
+    Tag { tag: String },
+    /// These session files.
+    Sessions { file_paths: Vec<String> },
+}
+
+/// A session picked for an archive, with the index entry the table is built
+/// from.
+#[derive(Debug, Clone)]
+pub struct ArchiveSession {
+    pub project_id: String,
+    pub project_name: String,
+    pub session: SessionIndexEntry,
+}
+
+/// `manifest.json` at the root of the archive.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveManifest {
+    pub version: u32,
+    pub source: String,
+    pub format: ExportFormat,
+    pub exported_at: String,
+    pub sessions: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestEntry {
+    pub session_id: String,
+    pub project_id: String,
+    pub project_name: String,
+    pub title: String,
+    pub created: Option<String>,
+    pub modified: Option<String>,
+    pub message_count: u32,
+    pub git_branch: Option<String>,
+    pub tags: Vec<String>,
+    /// `None` when the source has no usage records for the session.
+    pub cost_usd: Option<f64>,
+    /// The original session file.
+    pub file_path: String,
+    /// Path of the rendered session inside the archive; `None` when
+    /// rendering failed (see `error`).
+    pub path: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Resolve `selection` to sessions, newest first.
+pub fn collect_archive(
+    source: &str,
+    selection: &ArchiveSelection,
+) -> Result<Vec<ArchiveSession>, String> {
+    let provider = provider::get(source)?;
+    let mut projects = provider.get_projects()?;
+    if let ArchiveSelection::Project { project_id } = selection {
+        projects.retain(|p| &p.id == project_id);
+        if projects.is_empty() {
+            return Err(format!("Project not found: {}", project_id));
+        }
+    }
+    let wanted: HashSet<&str> = match selection {
+        ArchiveSelection::Sessions { file_paths } => {
+            file_paths.iter().map(String::as_str).collect()
+        }
+        _ => HashSet::new(),
+    };
+
+    let mut sessions = Vec::new();
+    for project in projects {
+        let entries = match provider.get_sessions(&project.id) {
+            Ok(entries) => entries,
+            Err(e) if !matches!(selection, ArchiveSelection::Project { .. }) => {
+                eprintln!("[export] skipping project {}: {}", project.id, e);
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        let project_name = project.alias.clone().unwrap_or(project.short_name);
+        sessions.extend(
+            entries
+                .into_iter()
+                .filter(|s| match selection {
+                    ArchiveSelection::Project { .. } => true,
+                    ArchiveSelection::Tag { tag } => {
+                        s.tags.as_ref().is_some_and(|tags| tags.contains(tag))
+                    }
+                    ArchiveSelection::Sessions { .. } => wanted.contains(s.file_path.as_str()),
+                })
+                .map(|session| ArchiveSession {
+                    project_id: project.id.clone(),
+                    project_name: project_name.clone(),
+                    session,
+                }),
+        );
+    }
+
+    if let ArchiveSelection::Sessions { file_paths } = selection {
+        let found: HashSet<&str> = sessions
+            .iter()
+            .map(|s| s.session.file_path.as_str())
+            .collect();
+        if let Some(missing) = file_paths.iter().find(|p| !found.contains(p.as_str())) {
+            return Err(format!("Session not found: {}", missing));
+        }
+    }
+    if sessions.is_empty() {
+        return Err("No sessions to export".to_string());
+    }
+    sessions.sort_by(|a, b| session_date(&b.session).cmp(&session_date(&a.session)));
+    Ok(sessions)
+}
+
+/// Write `sessions` as a zip into `writer`. A session that fails to render
+/// (e.g. `patch` for a session without file edits) is listed in the index
+/// and manifest with its error instead of failing the whole archive.
+///
+/// `Read` is only there because zip gates `set_flush_on_finish_file` on it;
+/// nothing is read back.
+pub fn write_archive<W: Read + Write + Seek>(
+    source: &str,
+    sessions: &[ArchiveSession],
+    format: ExportFormat,
+    options: &ExportOptions,
+    writer: W,
+) -> Result<(W, ArchiveManifest), String> {
+    let provider = provider::get(source)?;
+    let mut zip = ZipWriter::new(writer);
+    // Flushes the writer after each entry, which is when stream_archive
+    // hands the finished bytes on.
+    zip.set_flush_on_finish_file(true);
+    let file_options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    let costs = session_costs(provider, sessions);
+    let mut used = HashSet::new();
+    let mut entries = Vec::with_capacity(sessions.len());
+    for (s, cost_usd) in sessions.iter().zip(costs) {
+        // The index and manifest travel with the sessions, so they get the
+        // same redaction.
+        let redact = |text: &str| options.redact.apply(text);
+        let title = redact(&session_title(&s.session));
+        let project_name = redact(&s.project_name);
+        let (path, error) = match render_session(source, &s.session.file_path, format, options) {
+            Ok(content) => {
+                let path = entry_path(&project_name, &title, format, &mut used);
+                add_file(&mut zip, &path, content.as_bytes(), file_options)?;
+                (Some(path), None)
+            }
+            Err(e) => (None, Some(redact(&e))),
+        };
+        entries.push(ManifestEntry {
+            session_id: s.session.session_id.clone(),
+            project_id: s.project_id.clone(),
+            project_name,
+            title,
+            created: s.session.created.clone(),
+            modified: s.session.modified.clone(),
+            message_count: s.session.message_count,
+            git_branch: s.session.git_branch.clone(),
+            tags: s.session.tags.clone().unwrap_or_default(),
+            cost_usd,
+            file_path: redact(&s.session.file_path),
+            path,
+            error,
+        });
+    }
+
+    let manifest = ArchiveManifest {
+        version: 1,
+        source: source.to_string(),
+        format,
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        sessions: entries,
+    };
+    let json = serde_json::to_vec_pretty(&manifest)
+        .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    add_file(
+        &mut zip,
+        "index.html",
+        render_index(&manifest).as_bytes(),
+        file_options,
+    )?;
+    add_file(&mut zip, "manifest.json", &json, file_options)?;
+    let writer = zip
+        .finish()
+        .map_err(|e| format!("Failed to write archive: {}", e))?;
+    Ok((writer, manifest))
+}
+
+/// Each session's cost, `None` without usage records. Unless the provider
+/// caches per-session costs, its requests are read once for all sessions
+/// rather than once per session.
+fn session_costs(provider: &dyn SessionProvider, sessions: &[ArchiveSession]) -> Vec<Option<f64>> {
+    let mut by_file: HashMap<String, Vec<RequestRecord>> = HashMap::new();
+    if !provider.session_cost_is_cached() {
+        for record in provider.collect_requests().unwrap_or_default() {
+            by_file
+                .entry(record.file_path.clone())
+                .or_default()
+                .push(record);
+        }
+    }
+    sessions
+        .iter()
+        .map(|s| {
+            let path = &s.session.file_path;
+            let cost = if provider.session_cost_is_cached() {
+                provider.session_cost(path).ok()?
+            } else {
+                let records = by_file.remove(path).unwrap_or_default();
+                crate::stats::build_session_summary_from_records(provider.id(), path, records)
+            };
+            (cost.request_count > 0).then_some(cost.cost_usd)
+        })
+        .collect()
+}
+
+/// [`write_archive`], handing the zip to `emit` in chunks as it is built —
+/// one per finished entry, plus the central directory at the end. An error
+/// from `emit` (e.g. the client went away) aborts the export.
+pub fn stream_archive<F>(
+    source: &str,
+    sessions: &[ArchiveSession],
+    format: ExportFormat,
+    options: &ExportOptions,
+    emit: F,
+) -> Result<ArchiveManifest, String>
+where
+    F: FnMut(Vec<u8>) -> io::Result<()>,
+{
+    let (mut sink, manifest) =
+        write_archive(source, sessions, format, options, ChunkSink::new(emit))?;
+    sink.flush()
+        .map_err(|e| format!("Failed to write archive: {}", e))?;
+    Ok(manifest)
+}
+
+fn add_file<W: Write + Seek>(
+    zip: &mut ZipWriter<W>,
+    path: &str,
+    data: &[u8],
+    options: SimpleFileOptions,
+) -> Result<(), String> {
+    zip.start_file(path, options)
+        .map_err(|e| format!("Failed to write archive: {}", e))?;
+    zip.write_all(data)
+        .map_err(|e| format!("Failed to write archive: {}", e))
+}
+
+/// `Write + Seek` over a callback. ZipWriter only ever seeks back into the
+/// entry it is writing, and flushes once the entry is finished, so on flush
+/// everything before the current position is final.
+struct ChunkSink<F> {
+    emit: F,
+    /// Bytes from `base` on, not yet emitted.
+    buf: Vec<u8>,
+    base: u64,
+    pos: u64,
+}
+
+impl<F> ChunkSink<F> {
+    fn new(emit: F) -> Self {
+        ChunkSink {
+            emit,
+            buf: Vec::new(),
+            base: 0,
+            pos: 0,
+        }
+    }
+}
+
+impl<F: FnMut(Vec<u8>) -> io::Result<()>> Write for ChunkSink<F> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let start = (self.pos - self.base) as usize;
+        let end = start + data.len();
+        if end > self.buf.len() {
+            self.buf.resize(end, 0);
+        }
+        self.buf[start..end].copy_from_slice(data);
+        self.pos += data.len() as u64;
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let done = (self.pos - self.base) as usize;
+        if done == 0 {
+            return Ok(());
+        }
+        let rest = self.buf.split_off(done);
+        let chunk = std::mem::replace(&mut self.buf, rest);
+        self.base = self.pos;
+        (self.emit)(chunk)
+    }
+}
+
+impl<F> Read for ChunkSink<F> {
+    fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Archive stream is write-only",
+        ))
+    }
+}
+
+impl<F> Seek for ChunkSink<F> {
+    fn seek(&mut self, to: SeekFrom) -> io::Result<u64> {
+        let end = self.base + self.buf.len() as u64;
+        let target = match to {
+            SeekFrom::Start(n) => Some(n),
+            SeekFrom::End(delta) => end.checked_add_signed(delta),
+            SeekFrom::Current(delta) => self.pos.checked_add_signed(delta),
+        };
+        match target {
+            Some(target) if target >= self.base => {
+                self.pos = target;
+                Ok(target)
+            }
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Cannot seek into archive data already sent",
+            )),
+        }
+    }
+}
+
+fn session_date(session: &SessionIndexEntry) -> Option<&str> {
+    session.created.as_deref().or(session.modified.as_deref())
+}
+
+/// Alias, then thread title, then the first line of the first prompt.
+fn session_title(session: &SessionIndexEntry) -> String {
+    [&session.alias, &session.thread_name, &session.first_prompt]
+        .into_iter()
+        .flatten()
+        .map(|t| t.lines().next().unwrap_or("").trim())
+        .find(|t| !t.is_empty())
+        .map(|t| t.chars().take(80).collect())
+        .unwrap_or_else(|| session.session_id.clone())
+}
+
+/// Same rules as the frontend's `sanitizeFilename`.
+fn sanitize_filename(base: &str) -> String {
+    let replaced: String = base
+        .chars()
+        .map(|c| {
+            if c.is_control() || matches!(c, '\\' | '/' | ':' | '*' | '?' | '"' | '<' | '>' | '|') {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+    let cleaned = replaced.split_whitespace().collect::<Vec<_>>().join(" ");
+    let cleaned = cleaned.trim_matches(|c| c == '.' || c == ' ');
+    if cleaned.is_empty() {
+        "session".to_string()
+    } else {
+        cleaned.chars().take(60).collect()
+    }
+}
+
+/// `sessions/<project>/<title>.<ext>`, with `_2`, `_3` … on collision.
+fn entry_path(
+    project_name: &str,
+    title: &str,
+    format: ExportFormat,
+    used: &mut HashSet<String>,
+) -> String {
+    let dir = format!("sessions/{}", sanitize_filename(project_name));
+    let base = sanitize_filename(title);
+    let mut path = format!("{}/{}.{}", dir, base, format.extension());
+    let mut n = 1;
+    while !used.insert(path.to_lowercase()) {
+        n += 1;
+        path = format!("{}/{}_{}.{}", dir, base, n, format.extension());
+    }
+    path
+}
+
+/// Relative link to an archive entry; escapes what a browser would read as
+/// a query, fragment or escape sequence.
+fn href(path: &str) -> String {
+    let mut out = String::with_capacity(path.len());
+    for c in path.chars() {
+        match c {
+            '%' => out.push_str("%25"),
+            '#' => out.push_str("%23"),
+            ' ' => out.push_str("%20"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn format_date(value: Option<&str>) -> String {
+    match value {
+        Some(v) => chrono::DateTime::parse_from_rfc3339(v)
+            .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|_| v.to_string()),
+        None => "—".to_string(),
+    }
+}
+
+fn format_cost(cost: Option<f64>) -> String {
+    cost.map(|c| format!("${:.4}", c))
+        .unwrap_or_else(|| "—".to_string())
+}
+
+const INDEX_STYLE: &str = r#"
+.container { max-width: 1100px; }
+.summary { color: #6b7280; margin: -1rem 0 1.5rem; font-size: .9rem; }
+table { width: 100%; border-collapse: collapse; background: #fff; border: 1px solid #e3e6ea; border-radius: 10px; overflow: hidden; font-size: .9rem; }
+th, td { text-align: left; padding: .5rem .75rem; border-bottom: 1px solid #e3e6ea; vertical-align: top; }
+th { background: #f1f3f5; font-weight: 600; white-space: nowrap; }
+td.num { text-align: right; white-space: nowrap; font-variant-numeric: tabular-nums; }
+td.date { white-space: nowrap; color: #6b7280; }
+a { color: #2563eb; text-decoration: none; }
+a:hover { text-decoration: underline; }
+.tag { display: inline-block; background: #eef2ff; color: #4338ca; border-radius: 999px; padding: 0 .5rem; margin: 0 .25rem .25rem 0; font-size: .75rem; }
+.error { color: #dc2626; font-size: .8rem; }
+@media (prefers-color-scheme: dark) {
+  table { background: #161b22; border-color: #30363d; }
+  th { background: #21262d; }
+  th, td { border-color: #30363d; }
+  a { color: #58a6ff; }
+  .tag { background: #1f2a44; color: #a5b4fc; }
+}
+"#;
+
+fn render_index(manifest: &ArchiveManifest) -> String {
+    let mut rows = String::new();
+    for entry in &manifest.sessions {
+        let title = match &entry.path {
+            Some(path) => format!(
+                "<a href=\"{}\">{}</a>",
+                escape_html(&href(path)),
+                escape_html(&entry.title)
+            ),
+            None => format!(
+                "{}<div class=\"error\">{}</div>",
+                escape_html(&entry.title),
+                escape_html(entry.error.as_deref().unwrap_or_default())
+            ),
+        };
+        let tags: String = entry
+            .tags
+            .iter()
+            .map(|t| format!("<span class=\"tag\">{}</span>", escape_html(t)))
+            .collect();
+        rows.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td class=\"date\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td>{}</td></tr>\n",
+            title,
+            escape_html(&entry.project_name),
+            escape_html(&format_date(entry.created.as_deref().or(entry.modified.as_deref()))),
+            entry.message_count,
+            format_cost(entry.cost_usd),
+            tags
+        ));
+    }
+
+    let total_cost: f64 = manifest.sessions.iter().filter_map(|e| e.cost_usd).sum();
+    let summary = format!(
+        "{} 个会话 · {} · 合计 {} · 导出于 {}",
+        manifest.sessions.len(),
+        manifest.format.extension(),
+        format_cost(Some(total_cost)),
+        format_date(Some(&manifest.exported_at))
+    );
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"zh\">\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>会话导出</title>\n<style>{}{}</style>\n</head>\n<body>\n<div class=\"container\">\n<h1>会话导出</h1>\n<p class=\"summary\">{}</p>\n<table>\n<thead><tr><th>标题</th><th>项目</th><th>日期</th><th>消息</th><th>费用</th><th>标签</th></tr></thead>\n<tbody>\n{}</tbody>\n</table>\n</div>\n</body>\n</html>\n",
+        HTML_STYLE,
+        INDEX_STYLE,
+        escape_html(&summary),
+        rows
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::models::message::{DisplayMessage, PaginatedMessages, RangeMessages};
+    use crate::models::project::ProjectEntry;
+    use crate::models::session::SessionStatus;
+    use crate::models::stats::TokenUsageSummary;
+    use crate::provider::claude::{DeleteLevel, DeleteResult};
+    use crate::provider::SearchTarget;
+
+    /// A source without a session cost cache; counts `collect_requests` calls.
+    struct Fixture {
+        collected: AtomicUsize,
+    }
+
+    fn record(file_path: &str, timestamp: &str, cost_usd: f64) -> RequestRecord {
+        RequestRecord {
+            timestamp: timestamp.to_string(),
+            source: "fixture".to_string(),
+            project_id: "p".to_string(),
+            session_id: file_path.to_string(),
+            file_path: file_path.to_string(),
+            model: "m".to_string(),
+            input_tokens: 10,
+            output_tokens: 5,
+            cache_read_tokens: 0,
+            cache_creation_tokens: 0,
+            total_tokens: 15,
+            cost_usd,
+            duration_ms: None,
+            message_uuid: None,
+        }
+    }
+
+    impl SessionProvider for Fixture {
+        fn id(&self) -> &'static str {
+            "fixture"
+        }
+
+        fn display_name(&self) -> &'static str {
+            "Fixture"
+        }
+
+        fn collect_requests(&self) -> Result<Vec<RequestRecord>, String> {
+            self.collected.fetch_add(1, Ordering::Relaxed);
+            Ok(vec![
+                record("/f/b", "2025-06-01T10:02:00Z", 0.3),
+                record("/f/a", "2025-06-01T10:01:00Z", 0.1),
+                record("/f/a", "2025-06-01T10:00:00Z", 0.2),
+                record("/f/other", "2025-06-01T10:00:00Z", 9.0),
+            ])
+        }
+
+        // Not used for costs.
+        fn get_projects(&self) -> Result<Vec<ProjectEntry>, String> {
+            unreachable!()
+        }
+        fn delete_project(&self, _: &str, _: DeleteLevel) -> Result<DeleteResult, String> {
+            unreachable!()
+        }
+        fn get_sessions(&self, _: &str) -> Result<Vec<SessionIndexEntry>, String> {
+            unreachable!()
+        }
+        fn refresh_sessions_cache(&self, _: &str) -> Result<Vec<SessionIndexEntry>, String> {
+            unreachable!()
+        }
+        fn get_invalid_sessions(&self, _: &str) -> Result<Vec<SessionIndexEntry>, String> {
+            unreachable!()
+        }
+        fn parse_session_messages(
+            &self,
+            _: &Path,
+            _: usize,
+            _: usize,
+            _: bool,
+        ) -> Result<PaginatedMessages, String> {
+            unreachable!()
+        }
+        fn parse_messages_range(
+            &self,
+            _: &Path,
+            _: usize,
+            _: usize,
+        ) -> Result<RangeMessages, String> {
+            unreachable!()
+        }
+        fn parse_all_messages(&self, _: &Path) -> Result<Vec<DisplayMessage>, String> {
+            unreachable!()
+        }
+        fn search_targets(&self) -> Vec<SearchTarget> {
+            unreachable!()
+        }
+        fn get_stats(&self) -> Result<TokenUsageSummary, String> {
+            unreachable!()
+        }
+        fn session_roots(&self) -> Vec<PathBuf> {
+            unreachable!()
+        }
+        fn validate_layout(&self, _: &Path, _: &Path) -> Result<(), String> {
+            unreachable!()
+        }
+        fn metadata_path(&self, _: &str) -> Option<PathBuf> {
+            unreachable!()
+        }
+        fn metadata_project_ids(&self) -> Vec<String> {
+            unreachable!()
+        }
+        fn invalidate_cache(&self) {}
+        fn invalidate_paths(&self, _: &[PathBuf]) {}
+    }
+
+    fn archive_session(file_path: &str) -> ArchiveSession {
+        ArchiveSession {
+            project_id: "p".to_string(),
+            project_name: "p".to_string(),
+            session: SessionIndexEntry {
+                source: "fixture".to_string(),
+                session_id: file_path.to_string(),
+                file_path: file_path.to_string(),
+                first_prompt: None,
+                thread_name: None,
+                message_count: 1,
+                created: None,
+                modified: None,
+                git_branch: None,
+                project_path: None,
+                is_sidechain: None,
+                cwd: None,
+                model_provider: None,
+                cli_version: None,
+                alias: None,
+                tags: None,
+                status: SessionStatus::Valid,
+            },
+        }
+    }
+
+    #[test]
+    fn costs_read_requests_once_and_match_session_cost() {
+        let fixture = Fixture {
+            collected: AtomicUsize::new(0),
+        };
+        let sessions: Vec<ArchiveSession> = ["/f/a", "/f/b", "/f/none"].map(archive_session).into();
+
+        let costs = session_costs(&fixture, &sessions);
+        assert_eq!(fixture.collected.load(Ordering::Relaxed), 1);
+
+        let expected: Vec<Option<f64>> = sessions
+            .iter()
+            .map(|s| {
+                let cost = fixture.session_cost(&s.session.file_path).unwrap();
+                (cost.request_count > 0).then_some(cost.cost_usd)
+            })
+            .collect();
+        assert_eq!(costs, expected);
+        assert_eq!(costs[2], None);
+    }
+
+    #[test]
+    fn streamed_chunks_form_the_same_zip() {
+        fn build<W: Read + Write + Seek>(writer: W) -> W {
+            let mut zip = ZipWriter::new(writer);
+            zip.set_flush_on_finish_file(true);
+            let entries = [("a.md", "x".repeat(10_000)), ("b.md", "hello".to_string())];
+            for (name, body) in &entries {
+                add_file(
+                    &mut zip,
+                    name,
+                    body.as_bytes(),
+                    SimpleFileOptions::default(),
+                )
+                .unwrap();
+            }
+            zip.finish().unwrap()
+        }
+
+        let mut chunks = Vec::new();
+        let mut sink = build(ChunkSink::new(|chunk: Vec<u8>| {
+            chunks.push(chunk);
+            Ok(())
+        }));
+        sink.flush().unwrap();
+        drop(sink);
+        assert_eq!(chunks.len(), 3);
+
+        let streamed: Vec<u8> = chunks.concat();
+        assert_eq!(streamed, build(Cursor::new(Vec::new())).into_inner());
+        let mut archive = zip::ZipArchive::new(Cursor::new(streamed)).unwrap();
+        let mut body = String::new();
+        archive
+            .by_name("b.md")
+            .unwrap()
+            .read_to_string(&mut body)
+            .unwrap();
+        assert_eq!(body, "hello");
+    }
+
+    #[test]
+    fn names_entries_like_the_frontend() {
+        assert_eq!(sanitize_filename("  fix: a/b  *test*  "), "fix_ a_b _test_");
+        assert_eq!(sanitize_filename("..."), "session");
+        assert_eq!(
+            href("sessions/我的 项目/#1 100%.md"),
+            "sessions/我的%20项目/%231%20100%25.md"
+        );
+    }
+}
@@ -0,0 +1,447 @@
+//! 微调数据集导出：把会话转成 OpenAI chat 微调 JSONL 或 ShareGPT 格式，每个会话
+//! 一行。
+//!
+//! 会话先归并成轮次：连续的用户文本合成一轮，连续的助手文本与工具调用合成一轮，
+//! 每个工具结果单独一轮（OpenAI 的 `tool` 消息 / ShareGPT 的 `observation`）。
+//! 思考块、图片和文档不进数据集。开头不是用户的轮次、结尾没有助手回复的轮次会被
+//! 裁掉，保证每条样本都以助手回答结束。
+//!
+//! 批量导出（[`export_dataset`]）把多个会话写进同一个 JSONL，完全相同的对话只
+//! 保留一条，并统计跳过了多少。
+
+use std::collections::{HashMap, HashSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::ops::Range;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use super::archive::ArchiveSession;
+use super::{session_messages, ExportFormat, ExportOptions};
+use crate::models::message::{DisplayContentBlock, DisplayMessage};
+
+#[derive(Debug, Clone, PartialEq)]
+struct ToolCall {
+    id: String,
+    name: String,
+    /// Compact JSON.
+    arguments: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Turn {
+    System(String),
+    User(String),
+    Assistant {
+        content: String,
+        tool_calls: Vec<ToolCall>,
+    },
+    Tool {
+        call_id: String,
+        content: String,
+    },
+}
+
+/// How a batch export went.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatasetStats {
+    pub sessions: usize,
+    /// Conversations written.
+    pub written: usize,
+    /// Skipped as identical to one already written.
+    pub duplicates: usize,
+    /// Skipped for having no user prompt answered by the assistant.
+    pub empty: usize,
+    /// Skipped because the session could not be read.
+    pub failed: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatasetExport {
+    /// JSONL, one conversation per line.
+    pub content: String,
+    pub stats: DatasetStats,
+}
+
+fn push_text(target: &mut String, text: &str) {
+    if !target.is_empty() {
+        target.push_str("\n\n");
+    }
+    target.push_str(text);
+}
+
+/// Tool input as compact JSON; input that isn't JSON is kept as is.
+fn compact_json(input: &str) -> String {
+    serde_json::from_str::<Value>(input)
+        .map(|v| v.to_string())
+        .unwrap_or_else(|_| input.to_string())
+}
+
+fn to_turns(messages: &[DisplayMessage]) -> Vec<Turn> {
+    let mut turns: Vec<Turn> = Vec::new();
+    for msg in messages {
+        for block in &msg.content {
+            match block {
+                DisplayContentBlock::Text { text } if !text.trim().is_empty() => {
+                    let first = turns.is_empty();
+                    match (msg.role.as_str(), turns.last_mut()) {
+                        ("user", Some(Turn::User(content))) => push_text(content, text),
+                        ("user", _) => turns.push(Turn::User(text.clone())),
+                        ("assistant", Some(Turn::Assistant { content, .. })) => {
+                            push_text(content, text)
+                        }
+                        ("assistant", _) => turns.push(Turn::Assistant {
+                            content: text.clone(),
+                            tool_calls: Vec::new(),
+                        }),
+                        ("system", _) if first => turns.push(Turn::System(text.clone())),
+                        _ => {}
+                    }
+                }
+                DisplayContentBlock::ToolUse {
+                    id, name, input, ..
+                } => push_call(&mut turns, id, name, input),
+                DisplayContentBlock::FunctionCall {
+                    name,
+                    arguments,
+                    call_id,
+                } => push_call(&mut turns, call_id, name, arguments),
+                DisplayContentBlock::ToolResult {
+                    tool_use_id,
+                    content,
+                    ..
+                } => push_result(&mut turns, tool_use_id, content),
+                DisplayContentBlock::FunctionCallOutput { call_id, output } => {
+                    push_result(&mut turns, call_id, output)
+                }
+                _ => {}
+            }
+        }
+    }
+    trim(turns)
+}
+
+fn push_call(turns: &mut Vec<Turn>, id: &str, name: &str, input: &str) {
+    let call = ToolCall {
+        id: id.to_string(),
+        name: name.to_string(),
+        arguments: compact_json(input),
+    };
+    match turns.last_mut() {
+        Some(Turn::Assistant { tool_calls, .. }) => tool_calls.push(call),
+        _ => turns.push(Turn::Assistant {
+            content: String::new(),
+            tool_calls: vec![call],
+        }),
+    }
+}
+
+/// Results whose call isn't in the conversation (e.g. cut off by a message
+/// range) are dropped: both schemas need the call first.
+fn push_result(turns: &mut Vec<Turn>, call_id: &str, content: &str) {
+    let called = turns.iter().any(|t| match t {
+        Turn::Assistant { tool_calls, .. } => tool_calls.iter().any(|c| c.id == call_id),
+        _ => false,
+    });
+    if called {
+        turns.push(Turn::Tool {
+            call_id: call_id.to_string(),
+            content: content.to_string(),
+        });
+    }
+}
+
+/// Start at the first user turn (after an optional system prompt), drop
+/// calls that never got a result, and end on an assistant turn.
+fn trim(turns: Vec<Turn>) -> Vec<Turn> {
+    let answered: HashSet<String> = turns
+        .iter()
+        .filter_map(|t| match t {
+            Turn::Tool { call_id, .. } => Some(call_id.clone()),
+            _ => None,
+        })
+        .collect();
+    let mut system = None;
+    let mut rest = Vec::with_capacity(turns.len());
+    for mut turn in turns {
+        if let Turn::Assistant {
+            content,
+            tool_calls,
+        } = &mut turn
+        {
+            tool_calls.retain(|c| answered.contains(&c.id));
+            if content.is_empty() && tool_calls.is_empty() {
+                continue;
+            }
+        }
+        match turn {
+            Turn::System(text) if rest.is_empty() => system = Some(text),
+            Turn::User(_) => rest.push(turn),
+            _ if !rest.is_empty() => rest.push(turn),
+            _ => {}
+        }
+    }
+
+    loop {
+        match rest.last_mut() {
+            Some(Turn::User(_)) | Some(Turn::Tool { .. }) | Some(Turn::System(_)) => {
+                rest.pop();
+            }
+            Some(Turn::Assistant {
+                content,
+                tool_calls,
+            }) if !tool_calls.is_empty() => {
+                // Unanswered calls at the end.
+                tool_calls.clear();
+                if content.is_empty() {
+                    rest.pop();
+                }
+            }
+            _ => break,
+        }
+    }
+    if rest.is_empty() {
+        return rest;
+    }
+    system.map(Turn::System).into_iter().chain(rest).collect()
+}
+
+fn openai_line(turns: &[Turn]) -> Value {
+    let messages: Vec<Value> = turns
+        .iter()
+        .map(|turn| match turn {
+            Turn::System(content) => json!({ "role": "system", "content": content }),
+            Turn::User(content) => json!({ "role": "user", "content": content }),
+            Turn::Assistant {
+                content,
+                tool_calls,
+            } => {
+                let mut msg = json!({ "role": "assistant" });
+                if !content.is_empty() {
+                    msg["content"] = json!(content);
+                }
+                if !tool_calls.is_empty() {
+                    msg["tool_calls"] = tool_calls
+                        .iter()
+                        .map(|c| {
+                            json!({
+                                "id": c.id,
+                                "type": "function",
+                                "function": { "name": c.name, "arguments": c.arguments },
+                            })
+                        })
+                        .collect();
+                }
+                msg
+            }
+            Turn::Tool { call_id, content } => {
+                json!({ "role": "tool", "tool_call_id": call_id, "content": content })
+            }
+        })
+        .collect();
+    json!({ "messages": messages })
+}
+
+/// LLaMA-Factory's ShareGPT flavour: `human` / `gpt`, tool calls as
+/// `function_call` (a list when the turn makes several) and results as one
+/// `observation`. Narration sent alongside a tool call is dropped, since the
+/// schema allows one assistant message per turn.
+fn sharegpt_line(turns: &[Turn]) -> Value {
+    let mut system = None;
+    let mut conversations: Vec<Value> = Vec::new();
+    for turn in turns {
+        match turn {
+            Turn::System(content) => system = Some(content.clone()),
+            Turn::User(content) => conversations.push(json!({ "from": "human", "value": content })),
+            Turn::Assistant {
+                content,
+                tool_calls,
+            } if tool_calls.is_empty() => {
+                conversations.push(json!({ "from": "gpt", "value": content }))
+            }
+            Turn::Assistant { tool_calls, .. } => {
+                let calls: Vec<Value> = tool_calls
+                    .iter()
+                    .map(|c| {
+                        let arguments = serde_json::from_str::<Value>(&c.arguments)
+                            .unwrap_or_else(|_| json!(c.arguments));
+                        json!({ "name": c.name, "arguments": arguments })
+                    })
+                    .collect();
+                let value = match calls.as_slice() {
+                    [single] => single.to_string(),
+                    _ => Value::Array(calls).to_string(),
+                };
+                conversations.push(json!({ "from": "function_call", "value": value }));
+            }
+            Turn::Tool { content, .. } => match conversations.last_mut() {
+                Some(last) if last["from"] == "observation" => {
+                    let joined = format!("{}\n\n{}", last["value"].as_str().unwrap_or(""), content);
+                    last["value"] = json!(joined);
+                }
+                _ => conversations.push(json!({ "from": "observation", "value": content })),
+            },
+        }
+    }
+    let mut line = json!({ "conversations": conversations });
+    if let Some(system) = system {
+        line["system"] = json!(system);
+    }
+    line
+}
+
+/// One JSONL line for `messages`, or `None` when no user prompt gets an
+/// assistant answer.
+pub(super) fn render_line(messages: &[DisplayMessage], format: ExportFormat) -> Option<String> {
+    let turns = to_turns(messages);
+    if turns.is_empty() {
+        return None;
+    }
+    let line = match format {
+        ExportFormat::ShareGpt => sharegpt_line(&turns),
+        _ => openai_line(&turns),
+    };
+    Some(line.to_string())
+}
+
+/// Write `sessions` as one JSONL dataset. Sessions that can't be read or have
+/// nothing to train on are skipped and counted, as are exact duplicates.
+pub fn export_dataset(
+    source: &str,
+    sessions: &[ArchiveSession],
+    format: ExportFormat,
+    options: &ExportOptions,
+) -> Result<DatasetExport, String> {
+    if !format.is_dataset() {
+        return Err(format!("Not a dataset format: {}", format.extension()));
+    }
+    let mut stats = DatasetStats {
+        sessions: sessions.len(),
+        ..Default::default()
+    };
+    // Line hash -> where lines with that hash sit in `content`, compared in
+    // full so a hash collision can't drop a distinct conversation.
+    let mut seen: HashMap<u64, Vec<Range<usize>>> = HashMap::new();
+    let mut content = String::new();
+    for s in sessions {
+        let messages = match session_messages(source, &s.session.file_path, options) {
+            Ok(messages) => messages,
+            Err(e) => {
+                eprintln!("[export] {}: {}", s.session.file_path, e);
+                stats.failed += 1;
+                continue;
+            }
+        };
+        let Some(line) = render_line(&messages, format) else {
+            stats.empty += 1;
+            continue;
+        };
+        let mut hasher = DefaultHasher::new();
+        line.hash(&mut hasher);
+        let written = seen.entry(hasher.finish()).or_default();
+        if written.iter().any(|r| content[r.clone()] == line) {
+            stats.duplicates += 1;
+            continue;
+        }
+        written.push(content.len()..content.len() + line.len());
+        content.push_str(&line);
+        content.push('\n');
+        stats.written += 1;
+    }
+    Ok(DatasetExport { content, stats })
+}
+
+pub(super) const NO_TURNS: &str = "No conversation turns to export";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: &str, content: Vec<DisplayContentBlock>) -> DisplayMessage {
+        DisplayMessage {
+            uuid: None,
+            parent_uuid: None,
+            role: role.to_string(),
+            timestamp: None,
+            model: None,
+            content,
+            usage: None,
+        }
+    }
+
+    fn text(text: &str) -> DisplayContentBlock {
+        DisplayContentBlock::Text {
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn maps_tool_calls_to_both_schemas() {
+        let messages = vec![
+            message("assistant", vec![text("stray greeting")]),
+            message("user", vec![text("list files")]),
+            message(
+                "assistant",
+                vec![
+                    DisplayContentBlock::Thinking {
+                        thinking: "hmm".to_string(),
+                    },
+                    text("Checking."),
+                ],
+            ),
+            message(
+                "assistant",
+                vec![DisplayContentBlock::ToolUse {
+                    id: "t1".to_string(),
+                    name: "Bash".to_string(),
+                    input: "{\n  \"command\": \"ls\"\n}".to_string(),
+                    subagent: None,
+                }],
+            ),
+            message(
+                "user",
+                vec![DisplayContentBlock::ToolResult {
+                    tool_use_id: "t1".to_string(),
+                    content: "a.rs".to_string(),
+                    is_error: false,
+                }],
+            ),
+            message("assistant", vec![text("One file: a.rs")]),
+            message("user", vec![text("thanks")]),
+        ];
+
+        let openai = render_line(&messages, ExportFormat::OpenAi).unwrap();
+        assert_eq!(
+            openai,
+            json!({ "messages": [
+                { "role": "user", "content": "list files" },
+                { "role": "assistant", "content": "Checking.", "tool_calls": [
+                    { "id": "t1", "type": "function",
+                      "function": { "name": "Bash", "arguments": "{\"command\":\"ls\"}" } }
+                ] },
+                { "role": "tool", "tool_call_id": "t1", "content": "a.rs" },
+                { "role": "assistant", "content": "One file: a.rs" },
+            ] })
+            .to_string()
+        );
+
+        let sharegpt = render_line(&messages, ExportFormat::ShareGpt).unwrap();
+        assert_eq!(
+            sharegpt,
+            json!({ "conversations": [
+                { "from": "human", "value": "list files" },
+                { "from": "function_call", "value": "{\"arguments\":{\"command\":\"ls\"},\"name\":\"Bash\"}" },
+                { "from": "observation", "value": "a.rs" },
+                { "from": "gpt", "value": "One file: a.rs" },
+            ] })
+            .to_string()
+        );
+
+        assert_eq!(
+            render_line(&[message("user", vec![text("hi")])], ExportFormat::OpenAi),
+            None
+        );
+    }
+}
@@ -0,0 +1,1289 @@
+//! 会话导出：把一个会话的全部消息渲染成 JSON / Markdown / HTML，或把会话里
+//! 的文件编辑还原成 patch 系列（单个 `.patch` 或可 `git am` 的 mbox）。
+//!
+//! 无 Tauri 依赖，Tauri 命令与 web 路由共用。文件名由调用方（前端）决定，
+//! 这里只负责把路径校验后读出消息并渲染：[`render_session`] 得到字符串，
+//! [`write_session`] 边读边写进任意 `io::Write`，几百 MB 的会话也不必整个放进内存。多个会话打成一个 zip 见
+//! [`archive`]，微调数据集（OpenAI / ShareGPT JSONL）见 [`dataset`]。
+//!
+//! 渲染前按 [`ExportOptions`] 裁剪消息：截取消息区间、去掉思考 / 工具调用 /
+//! 工具结果、截断过长的工具输出，并按脱敏配置遮掉家目录、邮箱和密钥。
+
+pub mod archive;
+pub mod dataset;
+mod redact;
+
+pub use redact::RedactionProfile;
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+
+use base64::Engine as _;
+use serde::Serialize;
+use similar::TextDiff;
+
+use crate::models::file_history::{FileChange, FileChangeBody, SessionFileChanges};
+use crate::models::message::{DisplayContentBlock, DisplayMessage};
+use crate::paths::validate_session_file;
+use crate::provider;
+
+/// 导出格式。前端以小写字符串传入（json / markdown / html / patch / mbox /
+/// openai / sharegpt）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Json,
+    Markdown,
+    Html,
+    /// 编辑工具调用还原出的 unified diff，依次拼成一个文件，`git apply` 可用。
+    Patch,
+    /// 同样的 diff，每组变更一封邮件，`git am` 可用。
+    Mbox,
+    /// OpenAI chat 微调格式：一行 `{"messages": [...]}`，工具调用为 `tool_calls`。
+    OpenAi,
+    /// ShareGPT 对话格式：一行 `{"conversations": [...]}`。
+    ShareGpt,
+}
+
+impl ExportFormat {
+    /// 容错解析：兼容 "md" / "htm" 等常见写法。
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "markdown" | "md" => Ok(Self::Markdown),
+            "html" | "htm" => Ok(Self::Html),
+            "patch" | "diff" => Ok(Self::Patch),
+            "mbox" => Ok(Self::Mbox),
+            "openai" | "openai-chat" => Ok(Self::OpenAi),
+            "sharegpt" => Ok(Self::ShareGpt),
+            other => Err(format!("Unknown export format: {}", other)),
+        }
+    }
+
+    /// File extension (without the dot) for this format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Markdown => "md",
+            Self::Html => "html",
+            Self::Patch => "patch",
+            Self::Mbox => "mbox",
+            Self::OpenAi | Self::ShareGpt => "jsonl",
+        }
+    }
+
+    /// One JSONL line per session, meant to be concatenated into a dataset.
+    pub fn is_dataset(self) -> bool {
+        matches!(self, Self::OpenAi | Self::ShareGpt)
+    }
+}
+
+/// patch / mbox 导出时变更的分组方式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PatchGrouping {
+    /// 每个工具调用一个 patch。
+    #[default]
+    Call,
+    /// 每轮对话（一个用户 prompt）的变更合成一个 patch。
+    Turn,
+}
+
+impl PatchGrouping {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "call" | "tool" => Ok(Self::Call),
+            "turn" => Ok(Self::Turn),
+            other => Err(format!("Unknown patch grouping: {}", other)),
+        }
+    }
+}
+
+/// 导出选项。不适用于所选格式的字段会被忽略：patch / mbox 只看
+/// `patch_grouping` 和 `redact`。
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ExportOptions {
+    pub patch_grouping: PatchGrouping,
+    /// Thinking / reasoning blocks.
+    pub include_thinking: bool,
+    /// Tool and function calls with their input.
+    pub include_tool_calls: bool,
+    /// Tool and function outputs.
+    pub include_tool_results: bool,
+    /// Keep at most this many lines of each tool output, and
+    /// [`MAX_BYTES_PER_LINE`] bytes per line.
+    pub max_tool_output_lines: Option<usize>,
+    /// First message to export, 0-based.
+    pub message_start: Option<usize>,
+    /// Message to stop before (exclusive).
+    pub message_end: Option<usize>,
+    pub redact: RedactionProfile,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        ExportOptions {
+            patch_grouping: PatchGrouping::default(),
+            include_thinking: true,
+            include_tool_calls: true,
+            include_tool_results: true,
+            max_tool_output_lines: None,
+            message_start: None,
+            message_end: None,
+            redact: RedactionProfile::default(),
+        }
+    }
+}
+
+/// 读取会话全部消息并渲染为指定格式的字符串。
+pub fn render_session(
+    source: &str,
+    file_path: &str,
+    format: ExportFormat,
+    options: &ExportOptions,
+) -> Result<String, String> {
+    let mut out = Vec::new();
+    write_session(source, file_path, format, options, &mut out)?;
+    String::from_utf8(out).map_err(|e| format!("Failed to render export: {}", e))
+}
+
+/// 把会话渲染进 `writer`。JSON / Markdown / HTML 逐条读消息逐条写出，内存里只有
+/// 当前这条消息；patch / mbox 和数据集格式要看整个会话，仍先渲染好再一次写出。
+///
+/// 出错时 `writer` 里可能已有部分内容，由调用方决定丢弃还是截断。
+pub fn write_session<W: Write>(
+    source: &str,
+    file_path: &str,
+    format: ExportFormat,
+    options: &ExportOptions,
+    mut writer: W,
+) -> Result<(), String> {
+    // 同删除一样，先校验路径落在数据源允许的根目录内，防止任意文件读取。
+    let path = validate_session_file(source, file_path)?;
+    let provider = provider::get(source)?;
+
+    let rendered = match format {
+        ExportFormat::Json | ExportFormat::Markdown | ExportFormat::Html => {
+            let mut out = MessageWriter {
+                format,
+                writer: &mut writer,
+                count: 0,
+            };
+            out.begin(source, &path, options.redact)?;
+            let mut index = 0;
+            provider.for_each_message(&path, &mut |msg| {
+                index += 1;
+                match select_message(index - 1, msg, options) {
+                    Some(msg) => out.message(&msg),
+                    None => Ok(()),
+                }
+            })?;
+            out.end()?;
+            None
+        }
+        ExportFormat::Patch | ExportFormat::Mbox => {
+            let changes = provider.file_changes(&path)?;
+            let author = PatchAuthor {
+                name: provider.display_name(),
+                source,
+            };
+            let mbox = format == ExportFormat::Mbox;
+            let patches = render_patches(&changes, options.patch_grouping, mbox, &author)?;
+            if options.redact.is_none() {
+                patches
+            } else {
+                options.redact.apply(&patches)
+            }
+            .into()
+        }
+        ExportFormat::OpenAi | ExportFormat::ShareGpt => {
+            let messages = select_messages(provider.parse_all_messages(&path)?, options);
+            dataset::render_line(&messages, format)
+                .ok_or_else(|| dataset::NO_TURNS.to_string())?
+                .into()
+        }
+    };
+    if let Some(rendered) = rendered {
+        writer.write_all(rendered.as_bytes()).map_err(write_error)?;
+    }
+    writer.flush().map_err(write_error)
+}
+
+fn write_error(e: std::io::Error) -> String {
+    format!("Failed to write export: {}", e)
+}
+
+/// JSON / Markdown / HTML 的流式写出：开头、每条消息、结尾分开写。
+struct MessageWriter<'a, W: Write> {
+    format: ExportFormat,
+    writer: &'a mut W,
+    count: usize,
+}
+
+impl<W: Write> MessageWriter<'_, W> {
+    fn write(&mut self, s: &str) -> Result<(), String> {
+        self.writer.write_all(s.as_bytes()).map_err(write_error)
+    }
+
+    fn begin(&mut self, source: &str, path: &Path, redact: RedactionProfile) -> Result<(), String> {
+        let head = match self.format {
+            ExportFormat::Json => json_head(source, &redact.apply(&path.to_string_lossy())),
+            ExportFormat::Html => html_head(),
+            _ => "# 会话导出\n\n".to_string(),
+        };
+        self.write(&head)
+    }
+
+    fn message(&mut self, msg: &DisplayMessage) -> Result<(), String> {
+        let body = match self.format {
+            ExportFormat::Json => json_message(msg, self.count == 0)?,
+            ExportFormat::Html => html_message(msg),
+            _ => markdown_message(msg),
+        };
+        self.count += 1;
+        self.write(&body)
+    }
+
+    fn end(&mut self) -> Result<(), String> {
+        let tail = match self.format {
+            ExportFormat::Json => json_tail(self.count),
+            ExportFormat::Html => HTML_TAIL.to_string(),
+            _ => String::new(),
+        };
+        self.write(&tail)
+    }
+}
+
+// ── Message selection ──
+
+/// The messages of a session that `options` asks for, checking the path as
+/// [`write_session`] does.
+pub(super) fn session_messages(
+    source: &str,
+    file_path: &str,
+    options: &ExportOptions,
+) -> Result<Vec<DisplayMessage>, String> {
+    let path = validate_session_file(source, file_path)?;
+    let provider = provider::get(source)?;
+    Ok(select_messages(provider.parse_all_messages(&path)?, options))
+}
+
+/// The messages `options` asks for: the range, minus excluded blocks (and
+/// messages left empty by that), with tool output truncated and text
+/// redacted.
+fn select_messages(messages: Vec<DisplayMessage>, options: &ExportOptions) -> Vec<DisplayMessage> {
+    messages
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, msg)| select_message(i, msg, options))
+        .collect()
+}
+
+/// [`select_messages`] for the message at `index`.
+fn select_message(
+    index: usize,
+    mut msg: DisplayMessage,
+    options: &ExportOptions,
+) -> Option<DisplayMessage> {
+    let start = options.message_start.unwrap_or(0);
+    let end = options.message_end.unwrap_or(usize::MAX);
+    if index < start || index >= end {
+        return None;
+    }
+    let had_content = !msg.content.is_empty();
+    msg.content.retain(|block| match block {
+        DisplayContentBlock::Thinking { .. } | DisplayContentBlock::Reasoning { .. } => {
+            options.include_thinking
+        }
+        DisplayContentBlock::ToolUse { .. } | DisplayContentBlock::FunctionCall { .. } => {
+            options.include_tool_calls
+        }
+        DisplayContentBlock::ToolResult { .. } | DisplayContentBlock::FunctionCallOutput { .. } => {
+            options.include_tool_results
+        }
+        _ => true,
+    });
+    if had_content && msg.content.is_empty() {
+        return None;
+    }
+    for block in &mut msg.content {
+        transform_block(block, options);
+    }
+    Some(msg)
+}
+
+fn transform_block(block: &mut DisplayContentBlock, options: &ExportOptions) {
+    if let Some(max_lines) = options.max_tool_output_lines {
+        if let DisplayContentBlock::ToolResult { content: output, .. }
+        | DisplayContentBlock::FunctionCallOutput { output, .. } = block
+        {
+            *output = truncate_lines(output, max_lines);
+        }
+    }
+    if options.redact.is_none() {
+        return;
+    }
+    let redact = |text: &mut String| *text = options.redact.apply(text);
+    match block {
+        DisplayContentBlock::Text { text } | DisplayContentBlock::Reasoning { text } => redact(text),
+        DisplayContentBlock::Thinking { thinking } => redact(thinking),
+        DisplayContentBlock::ToolUse { input, .. } => redact(input),
+        DisplayContentBlock::FunctionCall { arguments, .. } => redact(arguments),
+        DisplayContentBlock::ToolResult { content, .. } => redact(content),
+        DisplayContentBlock::FunctionCallOutput { output, .. } => redact(output),
+        DisplayContentBlock::Image { url, .. } => url.iter_mut().for_each(redact),
+        DisplayContentBlock::Document { title, url, .. } => {
+            title.iter_mut().chain(url.iter_mut()).for_each(redact)
+        }
+    }
+}
+
+/// Tool output kept per line of `max_tool_output_lines`, so a few very long
+/// lines (minified JSON, base64) don't get around the limit.
+pub const MAX_BYTES_PER_LINE: usize = 200;
+
+fn truncate_lines(text: &str, max_lines: usize) -> String {
+    let max_bytes = max_lines.saturating_mul(MAX_BYTES_PER_LINE);
+    let dropped = text.lines().count().saturating_sub(max_lines);
+    if dropped > 0 {
+        let mut out: String = text
+            .lines()
+            .take(max_lines)
+            .flat_map(|line| [line, "\n"])
+            .collect();
+        if out.len() <= max_bytes {
+            out.push_str(&format!("… [{} more lines truncated]", dropped));
+            return out;
+        }
+    } else if text.len() <= max_bytes {
+        return text.to_string();
+    }
+    let mut cut = max_bytes;
+    while !text.is_char_boundary(cut) {
+        cut -= 1;
+    }
+    format!("{}… [{} more bytes truncated]", &text[..cut], text.len() - cut)
+}
+
+// ── JSON ──
+//
+// `{ source, filePath, messages, messageCount }`, pretty-printed as serde
+// would. The count comes last since it is only known once every message is
+// out.
+
+fn json_head(source: &str, file_path: &str) -> String {
+    format!(
+        "{{\n  \"source\": {},\n  \"filePath\": {},\n  \"messages\": [",
+        serde_json::Value::from(source),
+        serde_json::Value::from(file_path)
+    )
+}
+
+fn json_message(msg: &DisplayMessage, first: bool) -> Result<String, String> {
+    let json = serde_json::to_string_pretty(msg)
+        .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
+    // Escaped strings hold no raw newlines, so every line is structure.
+    let mut out = String::from(if first { "\n" } else { ",\n" });
+    for (i, line) in json.lines().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        out.push_str("    ");
+        out.push_str(line);
+    }
+    Ok(out)
+}
+
+fn json_tail(count: usize) -> String {
+    let close = if count == 0 { "]" } else { "\n  ]" };
+    format!("{},\n  \"messageCount\": {}\n}}", close, count)
+}
+
+// ── Markdown ──
+
+fn role_heading(role: &str) -> &'static str {
+    match role {
+        "user" => "## 👤 User",
+        "assistant" => "## 🤖 Assistant",
+        "system" => "## ⚙️ System",
+        _ => "## 💬 Message",
+    }
+}
+
+fn markdown_message(msg: &DisplayMessage) -> String {
+    let mut out = String::new();
+    out.push_str(role_heading(&msg.role));
+    if let Some(ts) = &msg.timestamp {
+        out.push_str(&format!("  \n*{}*", ts));
+    }
+    if let Some(model) = &msg.model {
+        out.push_str(&format!("  \n`{}`", model));
+    }
+    out.push_str("\n\n");
+
+    for block in &msg.content {
+        render_block_markdown(block, &mut out);
+    }
+    out.push_str("\n---\n\n");
+    out
+}
+
+fn fenced(lang: &str, body: &str, out: &mut String) {
+    out.push_str("```");
+    out.push_str(lang);
+    out.push('\n');
+    out.push_str(body);
+    if !body.ends_with('\n') {
+        out.push('\n');
+    }
+    out.push_str("```\n\n");
+}
+
+fn render_block_markdown(block: &DisplayContentBlock, out: &mut String) {
+    match block {
+        DisplayContentBlock::Text { text } => {
+            out.push_str(text);
+            out.push_str("\n\n");
+        }
+        DisplayContentBlock::Thinking { thinking } => {
+            out.push_str("> 💭 **Thinking**\n>\n");
+            for line in thinking.lines() {
+                out.push_str("> ");
+                out.push_str(line);
+                out.push('\n');
+            }
+            out.push('\n');
+        }
+        DisplayContentBlock::Reasoning { text } => {
+            out.push_str("> 💭 **Reasoning**\n>\n");
+            for line in text.lines() {
+                out.push_str("> ");
+                out.push_str(line);
+                out.push('\n');
+            }
+            out.push('\n');
+        }
+        DisplayContentBlock::ToolUse { name, input, .. } => {
+            out.push_str(&format!("**🔧 Tool: `{}`**\n\n", name));
+            fenced("json", input, out);
+        }
+        DisplayContentBlock::FunctionCall { name, arguments, .. } => {
+            out.push_str(&format!("**🔧 Function: `{}`**\n\n", name));
+            fenced("json", arguments, out);
+        }
+        DisplayContentBlock::ToolResult { content, is_error, .. } => {
+            out.push_str(if *is_error {
+                "**❌ Tool Result (error)**\n\n"
+            } else {
+                "**✅ Tool Result**\n\n"
+            });
+            fenced("", content, out);
+        }
+        DisplayContentBlock::FunctionCallOutput { output, .. } => {
+            out.push_str("**✅ Function Output**\n\n");
+            fenced("", output, out);
+        }
+        DisplayContentBlock::Image { media_type, url, .. } => match url {
+            Some(url) => out.push_str(&format!("![image]({})\n\n", url)),
+            None => out.push_str(&format!("**🖼️ Image** (`{}`)\n\n", media_type)),
+        },
+        DisplayContentBlock::Document { media_type, title, .. } => {
+            out.push_str(&format!(
+                "**📄 Document: {}** (`{}`)\n\n",
+                title.as_deref().unwrap_or("untitled"),
+                media_type
+            ));
+        }
+    }
+}
+
+// ── HTML ──
+
+fn escape_html(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+const HTML_STYLE: &str = r#"
+:root { color-scheme: light dark; }
+* { box-sizing: border-box; }
+body { font-family: -apple-system, "Segoe UI", Roboto, "Helvetica Neue", Arial, "PingFang SC", "Microsoft YaHei", sans-serif; line-height: 1.6; margin: 0; padding: 2rem 1rem; background: #f6f7f9; color: #1f2328; }
+.container { max-width: 880px; margin: 0 auto; }
+h1 { font-size: 1.5rem; margin-bottom: 1.5rem; }
+.msg { border: 1px solid #e3e6ea; border-radius: 10px; padding: 1rem 1.25rem; margin-bottom: 1rem; background: #fff; }
+.msg.user { border-left: 4px solid #3b82f6; }
+.msg.assistant { border-left: 4px solid #10b981; }
+.msg.system { border-left: 4px solid #9ca3af; }
+.role { font-weight: 600; margin-bottom: .5rem; }
+.meta { font-size: .75rem; color: #6b7280; font-weight: 400; margin-left: .5rem; }
+.text { white-space: pre-wrap; word-break: break-word; }
+.thinking { background: #f1f3f5; border-radius: 6px; padding: .5rem .75rem; margin: .5rem 0; color: #4b5563; font-size: .9rem; white-space: pre-wrap; }
+.tool-label { font-size: .8rem; font-weight: 600; color: #6b7280; margin: .5rem 0 .25rem; }
+.tool-label.error { color: #dc2626; }
+pre { background: #0d1117; color: #e6edf3; border-radius: 6px; padding: .75rem 1rem; overflow-x: auto; font-family: "JetBrains Mono", ui-monospace, SFMono-Regular, Consolas, monospace; font-size: .82rem; white-space: pre-wrap; word-break: break-word; }
+@media (prefers-color-scheme: dark) {
+  body { background: #0d1117; color: #e6edf3; }
+  .msg { background: #161b22; border-color: #30363d; }
+  .thinking { background: #21262d; color: #b0b8c0; }
+}
+"#;
+
+fn html_head() -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"zh\">\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>会话导出</title>\n<style>{}</style>\n</head>\n<body>\n<div class=\"container\">\n<h1>会话导出</h1>\n",
+        HTML_STYLE
+    )
+}
+
+const HTML_TAIL: &str = "</div>\n</body>\n</html>\n";
+
+fn html_message(msg: &DisplayMessage) -> String {
+    let mut body = String::new();
+    let role_class = match msg.role.as_str() {
+        "user" => "user",
+        "assistant" => "assistant",
+        "system" => "system",
+        _ => "other",
+    };
+    body.push_str(&format!("<div class=\"msg {}\">", role_class));
+    body.push_str(&format!(
+        "<div class=\"role\">{}",
+        escape_html(&display_role(&msg.role))
+    ));
+    let mut meta = String::new();
+    if let Some(ts) = &msg.timestamp {
+        meta.push_str(&escape_html(ts));
+    }
+    if let Some(model) = &msg.model {
+        if !meta.is_empty() {
+            meta.push_str(" · ");
+        }
+        meta.push_str(&escape_html(model));
+    }
+    if !meta.is_empty() {
+        body.push_str(&format!("<span class=\"meta\">{}</span>", meta));
+    }
+    body.push_str("</div>");
+
+    for block in &msg.content {
+        render_block_html(block, &mut body);
+    }
+    body.push_str("</div>\n");
+    body
+}
+
+fn display_role(role: &str) -> String {
+    match role {
+        "user" => "👤 User".to_string(),
+        "assistant" => "🤖 Assistant".to_string(),
+        "system" => "⚙️ System".to_string(),
+        other => format!("💬 {}", other),
+    }
+}
+
+fn pre_block(body: &str, out: &mut String) {
+    out.push_str("<pre>");
+    out.push_str(&escape_html(body));
+    out.push_str("</pre>");
+}
+
+fn render_block_html(block: &DisplayContentBlock, out: &mut String) {
+    match block {
+        DisplayContentBlock::Text { text } => {
+            out.push_str(&format!("<div class=\"text\">{}</div>", escape_html(text)));
+        }
+        DisplayContentBlock::Thinking { thinking } => {
+            out.push_str(&format!(
+                "<div class=\"thinking\">💭 {}</div>",
+                escape_html(thinking)
+            ));
+        }
+        DisplayContentBlock::Reasoning { text } => {
+            out.push_str(&format!(
+                "<div class=\"thinking\">💭 {}</div>",
+                escape_html(text)
+            ));
+        }
+        DisplayContentBlock::ToolUse { name, input, .. } => {
+            out.push_str(&format!(
+                "<div class=\"tool-label\">🔧 Tool: {}</div>",
+                escape_html(name)
+            ));
+            pre_block(input, out);
+        }
+        DisplayContentBlock::FunctionCall { name, arguments, .. } => {
+            out.push_str(&format!(
+                "<div class=\"tool-label\">🔧 Function: {}</div>",
+                escape_html(name)
+            ));
+            pre_block(arguments, out);
+        }
+        DisplayContentBlock::ToolResult { content, is_error, .. } => {
+            out.push_str(&format!(
+                "<div class=\"tool-label{}\">{}</div>",
+                if *is_error { " error" } else { "" },
+                if *is_error { "❌ Tool Result (error)" } else { "✅ Tool Result" }
+            ));
+            pre_block(content, out);
+        }
+        DisplayContentBlock::FunctionCallOutput { output, .. } => {
+            out.push_str("<div class=\"tool-label\">✅ Function Output</div>");
+            pre_block(output, out);
+        }
+        DisplayContentBlock::Image { media_type, .. } => {
+            out.push_str(&format!(
+                "<div class=\"tool-label\">🖼️ Image ({})</div>",
+                escape_html(media_type)
+            ));
+        }
+        DisplayContentBlock::Document { media_type, title, .. } => {
+            out.push_str(&format!(
+                "<div class=\"tool-label\">📄 Document: {} ({})</div>",
+                escape_html(title.as_deref().unwrap_or("untitled")),
+                escape_html(media_type)
+            ));
+        }
+    }
+}
+
+// ── Patch series ──
+
+/// `From:` of the mbox patches.
+struct PatchAuthor<'a> {
+    name: &'a str,
+    source: &'a str,
+}
+
+/// One file's diff in a patch; consecutive whole-file changes are squashed.
+struct FileDiff {
+    path: String,
+    old_path: Option<String>,
+    body: FileChangeBody,
+}
+
+/// Turn fragment and deletion changes into whole-file ones when an earlier
+/// change of the session left the file's contents known.
+fn resolve_changes(changes: &[FileChange]) -> Vec<FileChange> {
+    let mut known: HashMap<&str, Option<String>> = HashMap::new();
+    changes
+        .iter()
+        .map(|change| {
+            let before = match &change.old_path {
+                Some(old) => known.remove(old.as_str()),
+                None => known.get(change.path.as_str()).cloned(),
+            };
+            let body = match (&change.body, before) {
+                (FileChangeBody::Contents { .. }, _) => change.body.clone(),
+                (FileChangeBody::Deleted, Some(Some(before))) => FileChangeBody::Contents {
+                    before: Some(before),
+                    after: None,
+                },
+                (FileChangeBody::Fragments(fragments), Some(Some(before))) => {
+                    apply_fragments(&before, fragments)
+                        .map(|after| FileChangeBody::Contents {
+                            before: Some(before),
+                            after: Some(after),
+                        })
+                        .unwrap_or_else(|| change.body.clone())
+                }
+                _ => change.body.clone(),
+            };
+            match &body {
+                FileChangeBody::Contents { after, .. } => {
+                    known.insert(change.path.as_str(), after.clone())
+                }
+                _ => known.remove(change.path.as_str()),
+            };
+            FileChange {
+                body,
+                ..change.clone()
+            }
+        })
+        .collect()
+}
+
+/// Apply each fragment where its old text occurs; `None` if one doesn't occur
+/// exactly once, as the place it was applied at isn't recorded.
+fn apply_fragments(content: &str, fragments: &[(String, String)]) -> Option<String> {
+    let mut content = content.to_string();
+    for (old, new) in fragments {
+        // Pure insertions carry no position.
+        if old.is_empty() {
+            return None;
+        }
+        let mut found = content.match_indices(old.as_str()).map(|(at, _)| at);
+        let (Some(at), None) = (found.next(), found.next()) else {
+            return None;
+        };
+        content.replace_range(at..at + old.len(), new);
+    }
+    Some(content)
+}
+
+fn group_changes(changes: &[FileChange], grouping: PatchGrouping) -> Vec<Vec<&FileChange>> {
+    let mut groups: Vec<Vec<&FileChange>> = Vec::new();
+    for change in changes {
+        let same_group = groups.last().and_then(|g| g.last()).is_some_and(|last| match grouping {
+            PatchGrouping::Call => last.tool_call_id == change.tool_call_id,
+            PatchGrouping::Turn => last.turn == change.turn,
+        });
+        match groups.last_mut() {
+            Some(group) if same_group => group.push(change),
+            _ => groups.push(vec![change]),
+        }
+    }
+    groups
+}
+
+fn squash_diffs(group: &[&FileChange]) -> Vec<FileDiff> {
+    let mut diffs: Vec<FileDiff> = Vec::new();
+    for change in group {
+        if let (
+            Some(FileDiff {
+                path,
+                body: FileChangeBody::Contents { after, .. },
+                ..
+            }),
+            FileChangeBody::Contents {
+                before: next_before,
+                after: next_after,
+            },
+        ) = (diffs.last_mut(), &change.body)
+        {
+            if *path == change.path && change.old_path.is_none() && after == next_before {
+                *after = next_after.clone();
+                continue;
+            }
+        }
+        diffs.push(FileDiff {
+            path: change.path.clone(),
+            old_path: change.old_path.clone(),
+            body: change.body.clone(),
+        });
+    }
+    diffs
+}
+
+fn push_hunks(old: &str, new: &str, context: usize, out: &mut String) {
+    let diff = TextDiff::from_lines(old, new);
+    for hunk in diff.unified_diff().context_radius(context).iter_hunks() {
+        out.push_str(&hunk.to_string());
+    }
+}
+
+/// Render one file's `diff --git` section; empty when nothing changed or the
+/// contents before the change are unknown.
+fn render_file_diff(diff: &FileDiff, changes: &SessionFileChanges) -> String {
+    let FileChangeBody::Contents { before, after } = &diff.body else {
+        // Without the old contents there's no hunk position `git apply` accepts.
+        return String::new();
+    };
+    let b = changes.relative_path(&diff.path);
+    let a = diff
+        .old_path
+        .as_deref()
+        .map(|p| changes.relative_path(p))
+        .unwrap_or_else(|| b.clone());
+    let mut out = format!("diff --git a/{} b/{}\n", a, b);
+    if a != b {
+        out.push_str(&format!("rename from {}\nrename to {}\n", a, b));
+    }
+
+    match (before, after) {
+        (None, None) => return String::new(),
+        (None, Some(_)) => {
+            out.push_str(&format!("new file mode 100644\n--- /dev/null\n+++ b/{}\n", b))
+        }
+        (Some(_), None) => out.push_str(&format!(
+            "deleted file mode 100644\n--- a/{}\n+++ /dev/null\n",
+            a
+        )),
+        (Some(old), Some(new)) if old == new => {
+            return if a != b { out } else { String::new() };
+        }
+        (Some(_), Some(_)) => out.push_str(&format!("--- a/{}\n+++ b/{}\n", a, b)),
+    }
+    push_hunks(
+        before.as_deref().unwrap_or(""),
+        after.as_deref().unwrap_or(""),
+        3,
+        &mut out,
+    );
+    out
+}
+
+fn first_line(text: &str, max_chars: usize) -> String {
+    let line = text.lines().map(str::trim).find(|l| !l.is_empty()).unwrap_or("");
+    if line.chars().count() <= max_chars {
+        line.to_string()
+    } else {
+        format!("{}...", line.chars().take(max_chars).collect::<String>())
+    }
+}
+
+fn patch_subject(
+    group: &[&FileChange],
+    grouping: PatchGrouping,
+    changes: &SessionFileChanges,
+) -> String {
+    let first = group[0];
+    match grouping {
+        PatchGrouping::Turn => first
+            .turn_prompt
+            .as_deref()
+            .map(|p| first_line(p, 72))
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| format!("Turn {}", first.turn)),
+        PatchGrouping::Call => {
+            let mut paths: Vec<String> = group.iter().map(|c| changes.relative_path(&c.path)).collect();
+            paths.dedup();
+            match paths.as_slice() {
+                [path] => format!("{} {}", first.tool_name, path),
+                _ => format!("{} {} files", first.tool_name, paths.len()),
+            }
+        }
+    }
+}
+
+fn patch_body(
+    group: &[&FileChange],
+    skipped: &[&FileChange],
+    grouping: PatchGrouping,
+    changes: &SessionFileChanges,
+) -> String {
+    let first = group[0];
+    let mut body = match grouping {
+        PatchGrouping::Turn => {
+            let mut body = first.turn_prompt.as_deref().unwrap_or("").trim().to_string();
+            if !body.is_empty() {
+                body.push_str("\n\n");
+            }
+            for change in group {
+                body.push_str(&format!(
+                    "- {} {}\n",
+                    change.tool_name,
+                    changes.relative_path(&change.path)
+                ));
+            }
+            body
+        }
+        PatchGrouping::Call => match first.turn_prompt.as_deref() {
+            Some(prompt) => format!("Prompt: {}\n", first_line(prompt, 200)),
+            None => String::new(),
+        },
+    };
+    body.push_str(&skipped_note(skipped, changes));
+    body
+}
+
+/// List the changes left out of the patch, or an empty string.
+fn skipped_note(skipped: &[&FileChange], changes: &SessionFileChanges) -> String {
+    if skipped.is_empty() {
+        return String::new();
+    }
+    let mut note =
+        "\nNot applicable, the contents before these edits were not recorded:\n".to_string();
+    for change in skipped {
+        note.push_str(&format!(
+            "  {} {}\n",
+            change.tool_name,
+            changes.relative_path(&change.path)
+        ));
+    }
+    note
+}
+
+/// RFC 2047 encoding for non-ASCII header values.
+fn mail_header_value(value: &str) -> String {
+    if value.is_ascii() {
+        value.to_string()
+    } else {
+        format!(
+            "=?UTF-8?B?{}?=",
+            base64::engine::general_purpose::STANDARD.encode(value)
+        )
+    }
+}
+
+fn render_patches(
+    changes: &SessionFileChanges,
+    grouping: PatchGrouping,
+    mbox: bool,
+    author: &PatchAuthor,
+) -> Result<String, String> {
+    let resolved = resolve_changes(&changes.changes);
+    // Changes that can't be rendered are listed in the next patch's message,
+    // as `git am` rejects a patch without a diff.
+    let mut skipped: Vec<&FileChange> = Vec::new();
+    let mut patches: Vec<(Vec<&FileChange>, Vec<&FileChange>, String)> = Vec::new();
+    for group in group_changes(&resolved, grouping) {
+        skipped.extend(
+            group
+                .iter()
+                .filter(|c| !matches!(c.body, FileChangeBody::Contents { .. })),
+        );
+        let diff: String = squash_diffs(&group)
+            .iter()
+            .map(|d| render_file_diff(d, changes))
+            .collect();
+        if !diff.is_empty() {
+            patches.push((group, std::mem::take(&mut skipped), diff));
+        }
+    }
+    match patches.last_mut() {
+        Some((_, last, _)) => last.append(&mut skipped),
+        None if skipped.is_empty() => {
+            return Err("No file changes found in this session".to_string())
+        }
+        None => {
+            return Err(
+                "No file change of this session can be exported as a patch: \
+                 the contents before the edits were not recorded"
+                    .to_string(),
+            )
+        }
+    }
+    if !mbox {
+        // `git apply` ignores the text before the first diff.
+        let all: Vec<&FileChange> = patches
+            .iter()
+            .flat_map(|(_, skipped, _)| skipped.iter().copied())
+            .collect();
+        let mut out = skipped_note(&all, changes).trim_start().to_string();
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.extend(patches.into_iter().map(|(_, _, diff)| diff));
+        return Ok(out);
+    }
+
+    let total = patches.len();
+    let mut out = String::new();
+    for (index, (group, skipped, diff)) in patches.iter().enumerate() {
+        out.push_str("From 0000000000000000000000000000000000000000 Mon Sep 17 00:00:00 2001\n");
+        out.push_str(&format!(
+            "From: {} <{}@localhost>\n",
+            mail_header_value(author.name),
+            author.source
+        ));
+        let date = group[0]
+            .timestamp
+            .as_deref()
+            .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok());
+        if let Some(date) = date {
+            out.push_str(&format!("Date: {}\n", date.to_rfc2822()));
+        }
+        let subject = format!(
+            "[PATCH {}/{}] {}",
+            index + 1,
+            total,
+            patch_subject(group, grouping, changes)
+        );
+        out.push_str(&format!("Subject: {}\n", mail_header_value(&subject)));
+        out.push_str("MIME-Version: 1.0\n");
+        out.push_str("Content-Type: text/plain; charset=UTF-8\n");
+        out.push_str("Content-Transfer-Encoding: 8bit\n\n");
+        for line in patch_body(group, skipped, grouping, changes).lines() {
+            // mboxrd: body lines that look like a message separator.
+            if line.trim_start_matches('>').starts_with("From ") {
+                out.push('>');
+            }
+            out.push_str(line);
+            out.push('\n');
+        }
+        out.push_str("---\n");
+        out.push_str(diff);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(id: &str, turn: usize, path: &str, body: FileChangeBody) -> FileChange {
+        FileChange {
+            tool_call_id: id.to_string(),
+            tool_name: "Edit".to_string(),
+            timestamp: Some("2025-06-01T10:00:00Z".to_string()),
+            turn,
+            turn_prompt: Some("修复登录\nmore".to_string()),
+            path: path.to_string(),
+            old_path: None,
+            body,
+        }
+    }
+
+    fn contents(before: Option<&str>, after: &str) -> FileChangeBody {
+        FileChangeBody::Contents {
+            before: before.map(str::to_string),
+            after: Some(after.to_string()),
+        }
+    }
+
+    fn message(role: &str, content: Vec<DisplayContentBlock>) -> DisplayMessage {
+        DisplayMessage {
+            uuid: None,
+            parent_uuid: None,
+            role: role.to_string(),
+            timestamp: None,
+            model: None,
+            content,
+            usage: None,
+        }
+    }
+
+    fn text(text: &str) -> DisplayContentBlock {
+        DisplayContentBlock::Text {
+            text: text.to_string(),
+        }
+    }
+
+    fn tool_result(content: &str) -> DisplayContentBlock {
+        DisplayContentBlock::ToolResult {
+            tool_use_id: "t1".to_string(),
+            content: content.to_string(),
+            is_error: false,
+        }
+    }
+
+    #[test]
+    fn message_range_is_clamped_to_the_session() {
+        let messages = || {
+            (0..3)
+                .map(|i| message("user", vec![text(&i.to_string())]))
+                .collect()
+        };
+        let range = |start, end| ExportOptions {
+            message_start: start,
+            message_end: end,
+            ..ExportOptions::default()
+        };
+
+        assert!(select_messages(messages(), &range(Some(3), None)).is_empty());
+        assert!(select_messages(messages(), &range(Some(7), Some(9))).is_empty());
+        let tail = select_messages(messages(), &range(Some(1), Some(99)));
+        assert_eq!(tail.len(), 2);
+        assert!(matches!(&tail[0].content[0], DisplayContentBlock::Text { text } if text == "1"));
+        assert!(select_message(2, messages().remove(2), &range(None, Some(2))).is_none());
+    }
+
+    #[test]
+    fn excluded_blocks_drop_messages_left_empty() {
+        let tool_use = DisplayContentBlock::ToolUse {
+            id: "t1".to_string(),
+            name: "Read".to_string(),
+            input: "{}".to_string(),
+            subagent: None,
+        };
+        let thinking = DisplayContentBlock::Thinking {
+            thinking: "hmm".to_string(),
+        };
+        let messages = vec![
+            message("user", vec![text("read it")]),
+            message("assistant", vec![thinking, text("ok"), tool_use]),
+            // Claude sends tool results back as user messages.
+            message("user", vec![tool_result("contents")]),
+            message("assistant", vec![]),
+        ];
+        let options = ExportOptions {
+            include_thinking: false,
+            include_tool_calls: false,
+            include_tool_results: false,
+            ..ExportOptions::default()
+        };
+
+        let selected = select_messages(messages, &options);
+        let roles: Vec<&str> = selected.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, ["user", "assistant", "assistant"]);
+        assert_eq!(selected[1].content.len(), 1);
+        assert!(
+            matches!(&selected[1].content[0], DisplayContentBlock::Text { text } if text == "ok")
+        );
+    }
+
+    #[test]
+    fn tool_output_is_truncated_and_text_redacted() {
+        let options = ExportOptions {
+            max_tool_output_lines: Some(2),
+            redact: RedactionProfile::STANDARD,
+            ..ExportOptions::default()
+        };
+        let mut output = tool_result("a\nb\nc\nd\n");
+        transform_block(&mut output, &options);
+        assert!(
+            matches!(&output, DisplayContentBlock::ToolResult { content, .. }
+            if content == "a\nb\n… [2 more lines truncated]")
+        );
+
+        let mut reply = text("mail me at dev@example.com");
+        transform_block(&mut reply, &options);
+        assert!(matches!(&reply, DisplayContentBlock::Text { text } if !text.contains("dev@")));
+    }
+
+    #[test]
+    fn truncates_at_the_line_and_byte_limits() {
+        assert_eq!(truncate_lines("a\nb\n", 2), "a\nb\n");
+        assert_eq!(
+            truncate_lines("a\nb\nc", 2),
+            "a\nb\n… [1 more lines truncated]"
+        );
+
+        let long = "é".repeat(MAX_BYTES_PER_LINE);
+        let cut = truncate_lines(&long, 1);
+        // 200 bytes of a 400-byte line, on a char boundary.
+        assert_eq!(
+            cut,
+            format!(
+                "{}… [200 more bytes truncated]",
+                "é".repeat(MAX_BYTES_PER_LINE / 2)
+            )
+        );
+        // Byte 200 falls inside an "é"; cut before it.
+        let odd = format!("x{}", long);
+        assert!(truncate_lines(&odd, 1).ends_with("é… [202 more bytes truncated]"));
+
+        // CRLF lines within the line limit but over the byte limit: no line
+        // was dropped, only bytes.
+        let crlf = format!("{}\r\n", "x".repeat(MAX_BYTES_PER_LINE + 99)).repeat(2);
+        assert_eq!(
+            truncate_lines(&crlf, 3),
+            format!("{}… [2 more bytes truncated]", &crlf[..crlf.len() - 2])
+        );
+        let crlf = format!("{}\r\n", "x".repeat(MAX_BYTES_PER_LINE - 1)).repeat(2);
+        assert_eq!(
+            truncate_lines(&crlf, 2),
+            format!("{}… [2 more bytes truncated]", &crlf[..crlf.len() - 2])
+        );
+    }
+
+    #[test]
+    fn renders_edits_as_git_am_mbox() {
+        let changes = SessionFileChanges {
+            cwd: Some("/repo".to_string()),
+            changes: vec![
+                change("t1", 1, "/repo/src/new.rs", contents(None, "fn main() {}\n")),
+                change("t2", 1, "/repo/src/a.rs", contents(Some("a\nb\nc\n"), "a\nB\nc\n")),
+                change("t3", 1, "/repo/src/a.rs", contents(Some("a\nB\nc\n"), "a\nB\nC\n")),
+                change(
+                    "t4",
+                    2,
+                    "/repo/src/b.rs",
+                    FileChangeBody::Fragments(vec![("x\ny\n".to_string(), "x\nz\n".to_string())]),
+                ),
+                change(
+                    "t5",
+                    3,
+                    "/repo/src/new.rs",
+                    FileChangeBody::Fragments(vec![("{}".to_string(), "{ run() }".to_string())]),
+                ),
+                change("t6", 3, "/repo/src/new.rs", FileChangeBody::Deleted),
+            ],
+        };
+        let author = PatchAuthor {
+            name: "Claude",
+            source: "claude",
+        };
+
+        let per_call = render_patches(&changes, PatchGrouping::Call, true, &author).unwrap();
+        assert_eq!(per_call.matches("\nSubject: [PATCH ").count() + 1, 6);
+        assert!(per_call.contains("Subject: [PATCH 1/5] Edit src/new.rs\n"));
+        assert!(per_call.contains("new file mode 100644\n--- /dev/null\n+++ b/src/new.rs\n@@ -0,0 +1 @@\n+fn main() {}\n"));
+        assert!(per_call.contains("@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n"));
+
+        let per_turn = render_patches(&changes, PatchGrouping::Turn, true, &author).unwrap();
+        assert!(per_turn.contains(&format!(
+            "Subject: {}\n",
+            mail_header_value("[PATCH 1/2] 修复登录")
+        )));
+        // Both edits of src/a.rs squash into one diff.
+        assert_eq!(per_turn.matches("diff --git a/src/a.rs").count(), 1);
+        assert!(per_turn.contains(" a\n-b\n-c\n+B\n+C\n"));
+        // A fragment without the file's contents has no position to apply at.
+        assert!(!per_turn.contains("b/src/b.rs"));
+        assert!(per_turn.contains("  Edit src/b.rs\n---\n"));
+        // Fragments and deletions of a file with known contents are whole-file diffs.
+        assert!(per_call.contains("@@ -1 +1 @@\n-fn main() {}\n+fn main() { run() }\n"));
+        assert!(per_turn.contains(
+            "deleted file mode 100644\n--- a/src/new.rs\n+++ /dev/null\n@@ -1 +0,0 @@\n"
+        ));
+        assert!(!per_turn.contains("unidiff-zero"));
+
+        let patch = render_patches(&changes, PatchGrouping::Turn, false, &author).unwrap();
+        assert!(patch.starts_with("Not applicable, "));
+        assert!(patch.contains("  Edit src/b.rs\n\ndiff --git a/src/new.rs b/src/new.rs\n"));
+
+        let fragments_only = SessionFileChanges {
+            cwd: None,
+            changes: changes.changes[3..4].to_vec(),
+        };
+        assert!(render_patches(&fragments_only, PatchGrouping::Call, true, &author).is_err());
+    }
+
+    #[test]
+    fn fragments_apply_only_where_unambiguous() {
+        let fragment = |old: &str, new: &str| (old.to_string(), new.to_string());
+        let edits = [fragment("b\n", "B\n"), fragment("B\nc", "B\nC")];
+        assert_eq!(
+            apply_fragments("a\nb\nc\n", &edits),
+            Some("a\nB\nC\n".to_string())
+        );
+        assert_eq!(apply_fragments("a\nb\n", &[fragment("x", "y")]), None);
+        assert_eq!(apply_fragments("a\nb\n", &[fragment("", "x")]), None);
+        // The same line twice: which one was edited isn't known.
+        assert_eq!(apply_fragments("}\n}\n", &[fragment("}\n", "};\n")]), None);
+    }
+
+    #[test]
+    fn streamed_json_parses_back() {
+        let message = |role: &str, text: &str| DisplayMessage {
+            uuid: None,
+            parent_uuid: None,
+            role: role.to_string(),
+            timestamp: None,
+            model: None,
+            content: vec![DisplayContentBlock::Text {
+                text: format!("{}\n  \"quoted\"", text),
+            }],
+            usage: None,
+        };
+        let messages = [message("user", "hi"), message("assistant", "hello")];
+
+        for count in [0, 2] {
+            let mut out = Vec::new();
+            let mut writer = MessageWriter {
+                format: ExportFormat::Json,
+                writer: &mut out,
+                count: 0,
+            };
+            writer
+                .begin("claude", Path::new("/home/a/s.jsonl"), RedactionProfile::STANDARD)
+                .unwrap();
+            for msg in &messages[..count] {
+                writer.message(msg).unwrap();
+            }
+            writer.end().unwrap();
+
+            let parsed: serde_json::Value = serde_json::from_slice(&out).unwrap();
+            assert_eq!(parsed["filePath"], "~/s.jsonl");
+            assert_eq!(parsed["messageCount"], count);
+            assert_eq!(
+                parsed["messages"],
+                serde_json::to_value(&messages[..count]).unwrap()
+            );
+        }
+    }
+}
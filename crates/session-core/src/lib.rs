@@ -2,18 +2,24 @@ pub mod app_dir;
 pub mod bookmarks;
 pub mod cli;
 pub mod cli_config;
+pub mod compare;
 pub mod codex_app_server;
+pub mod data_roots;
 pub mod export;
 pub mod metadata;
 pub mod model_list;
 pub mod models;
 pub mod parser;
 pub mod paths;
+pub mod plans;
+pub mod prompt_history;
 pub mod provider;
 pub mod provider_sync;
 pub mod recyclebin;
+pub mod saved_searches;
 pub mod scan_progress;
 pub mod search;
+pub mod shares;
 pub mod skills;
 pub mod state;
 pub mod stats;
@@ -4,8 +4,7 @@ use std::collections::HashMap;
 use std::fs;
 use std::path::{Component, Path, PathBuf};
 
-use crate::parser::path_encoder::get_projects_dir;
-use crate::provider::codex;
+use crate::provider;
 
 /// Per-session metadata (alias + tags)
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -32,21 +31,15 @@ impl Default for MetadataFile {
     }
 }
 
+/// File name of the per-project (or per-source) metadata file.
+pub const METADATA_FILE_NAME: &str = ".session-viewer-meta.json";
+
 /// Resolve the metadata file path for a given source and project
 fn metadata_path(source: &str, project_id: &str) -> Option<PathBuf> {
-    match source {
-        "claude" => {
-            resolve_claude_project_dir(project_id).map(|dir| dir.join(".session-viewer-meta.json"))
-        }
-        "codex" => {
-            let codex_home = codex::get_sessions_dir()?.parent()?.to_path_buf();
-            Some(codex_home.join(".session-viewer-meta.json"))
-        }
-        _ => None,
-    }
+    provider::get(source).ok()?.metadata_path(project_id)
 }
 
-fn is_single_normal_component(value: &str) -> bool {
+pub(crate) fn is_single_normal_component(value: &str) -> bool {
     let mut components = Path::new(value).components();
     matches!(components.next(), Some(Component::Normal(_))) && components.next().is_none()
 }
@@ -77,31 +70,6 @@ pub fn validate_session_id(session_id: &str) -> Result<(), String> {
     Ok(())
 }
 
-fn resolve_claude_project_dir(project_id: &str) -> Option<PathBuf> {
-    if !is_single_normal_component(project_id) {
-        return None;
-    }
-
-    let projects_dir = get_projects_dir()?;
-    let canonical_base = fs::canonicalize(&projects_dir).ok()?;
-    let project_dir = projects_dir.join(project_id);
-    if !project_dir.exists() {
-        return None;
-    }
-
-    let canonical_dir = fs::canonicalize(project_dir).ok()?;
-    if !canonical_dir.is_dir() {
-        return None;
-    }
-    let relative = canonical_dir.strip_prefix(&canonical_base).ok()?;
-
-    if relative.components().count() != 1 {
-        return None;
-    }
-
-    Some(canonical_dir)
-}
-
 /// Load metadata file; returns default if not found
 pub fn load_metadata(source: &str, project_id: &str) -> MetadataFile {
     let path = match metadata_path(source, project_id) {
@@ -160,16 +128,28 @@ pub fn update_session_meta(
     save_metadata(source, project_id, &meta)
 }
 
+/// Set a session's alias in the metadata file, keeping its tags.
+pub(crate) fn set_session_alias(
+    source: &str,
+    project_id: &str,
+    session_id: &str,
+    alias: Option<&str>,
+) -> Result<(), String> {
+    let tags = load_metadata(source, project_id)
+        .sessions
+        .remove(session_id)
+        .map(|s| s.tags)
+        .unwrap_or_default();
+    update_session_meta(source, project_id, session_id, alias.map(str::to_string), tags)
+}
+
 /// Rename a chat session's alias by project path (used by /rename in ChatInput).
 ///
-/// For Claude: encodes the project_path → encoded directory name, finds
-/// `<projects>/<encoded>/<session_id>.jsonl`, and appends a custom-title record
-/// the same way the metadata editor does. Existing tags are preserved.
-///
-/// For Codex: writes the alias to the global metadata file. Existing tags
-/// are preserved.
+/// Where the alias is stored is up to the provider, see
+/// [`provider::SessionProvider::rename_chat_session`]. Existing tags are
+/// preserved.
 ///
-/// Returns the encoded project_id used so callers can invalidate caches.
+/// Returns the project_id used so callers can invalidate caches.
 pub fn rename_chat_session(
     source: &str,
     project_path: &str,
@@ -180,54 +160,8 @@ pub fn rename_chat_session(
     // it's joined into a filesystem path.
     validate_session_id(session_id)?;
 
-    let trimmed = new_alias
-        .map(|s| s.trim())
-        .filter(|s| !s.is_empty())
-        .map(|s| s.to_string());
-
-    match source {
-        "claude" => {
-            let encoded = crate::parser::path_encoder::encode_project_path(project_path);
-            let projects_dir = crate::parser::path_encoder::get_projects_dir()
-                .ok_or_else(|| "Cannot resolve Claude projects directory".to_string())?;
-            let project_dir = projects_dir.join(&encoded);
-            if !project_dir.is_dir() {
-                return Err(format!(
-                    "Claude project directory not found for path: {}",
-                    project_path
-                ));
-            }
-            let jsonl_path = project_dir.join(format!("{}.jsonl", session_id));
-            if !jsonl_path.exists() {
-                return Err(format!("Session file not found: {}", jsonl_path.display()));
-            }
-            crate::parser::jsonl::append_custom_title(
-                &jsonl_path,
-                session_id,
-                trimmed.as_deref(),
-            )?;
-
-            let existing = load_metadata("claude", &encoded);
-            let tags = existing
-                .sessions
-                .get(session_id)
-                .map(|s| s.tags.clone())
-                .unwrap_or_default();
-            update_session_meta("claude", &encoded, session_id, None, tags)?;
-            Ok(encoded)
-        }
-        "codex" => {
-            let existing = load_metadata("codex", "");
-            let tags = existing
-                .sessions
-                .get(session_id)
-                .map(|s| s.tags.clone())
-                .unwrap_or_default();
-            update_session_meta("codex", project_path, session_id, trimmed, tags)?;
-            Ok(project_path.to_string())
-        }
-        _ => Err(format!("Unknown source: {}", source)),
-    }
+    let trimmed = new_alias.map(|s| s.trim()).filter(|s| !s.is_empty());
+    provider::get(source)?.rename_chat_session(project_path, session_id, trimmed)
 }
 
 /// Remove metadata for a single session
@@ -268,51 +202,20 @@ pub fn get_all_tags(source: &str, project_id: &str) -> Vec<String> {
 /// available rayon thread count, which usually means a 4-8x speedup on
 /// SSDs and even more on NVMe.
 pub fn get_all_cross_project_tags(source: &str) -> HashMap<String, Vec<String>> {
-    match source {
-        "claude" => {
-            let projects_dir = match get_projects_dir() {
-                Some(d) if d.exists() => d,
-                _ => return HashMap::new(),
-            };
-
-            let encoded_names: Vec<String> = match fs::read_dir(&projects_dir) {
-                Ok(entries) => entries
-                    .flatten()
-                    .filter_map(|entry| {
-                        let path = entry.path();
-                        if !path.is_dir() {
-                            return None;
-                        }
-                        path.file_name()
-                            .and_then(|n| n.to_str())
-                            .map(str::to_string)
-                    })
-                    .collect(),
-                Err(_) => return HashMap::new(),
-            };
+    let Ok(provider) = provider::get(source) else {
+        return HashMap::new();
+    };
 
-            encoded_names
-                .into_par_iter()
-                .filter_map(|encoded_name| {
-                    let tags = get_all_tags("claude", &encoded_name);
-                    if tags.is_empty() {
-                        None
-                    } else {
-                        Some((encoded_name, tags))
-                    }
-                })
-                .collect()
-        }
-        "codex" => {
-            // Codex stores all session metadata in a single file, so there's
-            // nothing to parallelize on this branch — one read either way.
-            let tags = get_all_tags("codex", "");
-            let mut result = HashMap::new();
-            if !tags.is_empty() {
-                result.insert(String::new(), tags);
+    provider
+        .metadata_project_ids()
+        .into_par_iter()
+        .filter_map(|project_id| {
+            let tags = get_all_tags(source, &project_id);
+            if tags.is_empty() {
+                None
+            } else {
+                Some((project_id, tags))
             }
-            result
-        }
-        _ => HashMap::new(),
-    }
+        })
+        .collect()
 }
@@ -0,0 +1,83 @@
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolCount {
+    pub name: String,
+    pub count: usize,
+}
+
+/// One user turn: a prompt and everything up to the next one.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TurnStats {
+    /// 0-based within its session.
+    pub index: usize,
+    pub prompt: String,
+    /// The prompt message, to jump to it.
+    pub message_uuid: Option<String>,
+    pub started_at: Option<String>,
+    /// From the prompt to the turn's last message.
+    pub duration_ms: Option<u64>,
+    pub request_count: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub cache_creation_tokens: u64,
+    pub total_tokens: u64,
+    pub cost_usd: f64,
+    /// Most used first.
+    pub tools: Vec<ToolCount>,
+    /// Files the turn edited, relative to the session's working directory.
+    pub files: Vec<String>,
+}
+
+/// `b − a` of an aligned turn pair.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TurnDelta {
+    pub total_tokens: i64,
+    pub cost_usd: f64,
+    pub duration_ms: Option<i64>,
+}
+
+/// Two turns matched by [`crate::compare::compare_sessions`]; a side is
+/// `None` when the other session has no counterpart.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TurnPair {
+    pub a: Option<TurnStats>,
+    pub b: Option<TurnStats>,
+    /// 0–1 word overlap of the two prompts.
+    pub prompt_similarity: Option<f64>,
+    pub delta: Option<TurnDelta>,
+}
+
+/// Totals of one side of a comparison.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompareSide {
+    pub source: String,
+    pub file_path: String,
+    pub models: Vec<String>,
+    pub turn_count: usize,
+    pub request_count: u64,
+    pub total_tokens: u64,
+    pub cost_usd: f64,
+    /// First to last message.
+    pub duration_ms: Option<u64>,
+    pub tools: Vec<ToolCount>,
+    pub files: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionComparison {
+    pub a: CompareSide,
+    pub b: CompareSide,
+    /// In session order on both sides.
+    pub turns: Vec<TurnPair>,
+    pub files_only_a: Vec<String>,
+    pub files_only_b: Vec<String>,
+    pub files_both: Vec<String>,
+}
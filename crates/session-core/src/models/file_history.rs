@@ -0,0 +1,116 @@
+use serde::Serialize;
+
+/// One pre-edit backup of a file under `~/.claude/file-history/<session>/`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileBackup {
+    pub version: u32,
+    /// Prompt whose snapshot first recorded this version.
+    pub message_uuid: String,
+    pub backup_time: Option<String>,
+    /// `false` when the file did not exist yet at that point.
+    pub exists: bool,
+}
+
+/// An `Edit` / `MultiEdit` / `Write` tool call on a tracked file.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileEdit {
+    pub tool_use_id: String,
+    pub tool_name: String,
+    pub message_uuid: String,
+    pub timestamp: Option<String>,
+    /// Backup version the edit was applied on top of.
+    pub base_version: Option<u32>,
+    /// The tool call failed or was rejected, so the file was not changed.
+    pub is_error: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackedFile {
+    pub file_path: String,
+    /// Oldest first.
+    pub backups: Vec<FileBackup>,
+    /// In transcript order.
+    pub edits: Vec<FileEdit>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileHistoryIndex {
+    pub session_id: String,
+    pub files: Vec<TrackedFile>,
+}
+
+/// A file's contents around one message.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileContentsAtMessage {
+    pub file_path: String,
+    pub message_uuid: String,
+    /// `None` when the file did not exist.
+    pub before: Option<String>,
+    /// After the message's own edits to the file.
+    pub after: Option<String>,
+    /// `false` when some edit since the last snapshot could not be replayed
+    /// and was skipped.
+    pub exact: bool,
+}
+
+/// One tool call's change to one file, reconstructed for patch export.
+#[derive(Debug, Clone)]
+pub struct FileChange {
+    pub tool_call_id: String,
+    pub tool_name: String,
+    pub timestamp: Option<String>,
+    /// Index of the user turn the change was made in.
+    pub turn: usize,
+    /// That turn's prompt.
+    pub turn_prompt: Option<String>,
+    pub path: String,
+    /// Previous path when the file was moved.
+    pub old_path: Option<String>,
+    pub body: FileChangeBody,
+}
+
+#[derive(Debug, Clone)]
+pub enum FileChangeBody {
+    /// Whole file on both sides; `None` when it doesn't exist.
+    Contents {
+        before: Option<String>,
+        after: Option<String>,
+    },
+    /// Old / new text pairs (including any context lines) whose position in
+    /// the file is unknown, e.g. `apply_patch` hunks or edits whose base
+    /// contents were not recorded.
+    Fragments(Vec<(String, String)>),
+    /// Deleted, previous contents unknown.
+    Deleted,
+}
+
+/// Every file change of a session, in order.
+#[derive(Debug, Clone, Default)]
+pub struct SessionFileChanges {
+    /// Working directory, to show paths relative to it.
+    pub cwd: Option<String>,
+    pub changes: Vec<FileChange>,
+}
+
+impl SessionFileChanges {
+    /// `path` with `/` separators, relative to the working directory when it
+    /// is under it.
+    pub fn relative_path(&self, path: &str) -> String {
+        let path = path.replace('\\', "/");
+        if let Some(cwd) = &self.cwd {
+            let cwd = cwd.replace('\\', "/");
+            if let Some(rest) = path
+                .strip_prefix(cwd.trim_end_matches('/'))
+                .and_then(|r| r.strip_prefix('/'))
+            {
+                return rest.to_string();
+            }
+        }
+        path.trim_start_matches('/').to_string()
+    }
+}
@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use crate::models::pricing;
+
 // ── Claude raw record types ──
 
 /// A raw JSONL record from a Claude session file
@@ -12,6 +14,9 @@ pub struct RawRecord {
     pub record_type: String,
     pub uuid: Option<String>,
     pub parent_uuid: Option<String>,
+    /// Set instead of `parent_uuid` on the first record after a compaction,
+    /// pointing at the last record before it.
+    pub logical_parent_uuid: Option<String>,
     pub session_id: Option<String>,
     pub timestamp: Option<String>,
     pub message: Option<RawMessage>,
@@ -21,13 +26,44 @@ pub struct RawRecord {
     pub version: Option<String>,
     pub git_branch: Option<String>,
     pub slug: Option<String>,
+    /// Subagent id, set on records of a `subagents/agent-<id>.jsonl` transcript.
+    pub agent_id: Option<String>,
+    /// Structured result Claude Code attaches to tool_result records; only
+    /// the Task tool's `agentId` is read.
+    pub tool_use_result: Option<ToolUseResult>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum ToolUseResult {
+    Agent {
+        #[serde(rename = "agentId")]
+        agent_id: String,
+    },
+    Other(serde::de::IgnoredAny),
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct RawMessage {
+    /// API message id; Claude Code writes one record per content block, all
+    /// sharing the same id and usage.
+    pub id: Option<String>,
     pub role: String,
     pub content: ContentValue,
     pub model: Option<String>,
+    pub usage: Option<RawUsage>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct RawUsage {
+    #[serde(default)]
+    pub input_tokens: u64,
+    #[serde(default)]
+    pub output_tokens: u64,
+    #[serde(default)]
+    pub cache_read_input_tokens: u64,
+    #[serde(default)]
+    pub cache_creation_input_tokens: u64,
 }
 
 /// Content can be a simple string or an array of content blocks
@@ -59,10 +95,62 @@ pub enum ContentBlock {
         #[serde(default)]
         is_error: Option<bool>,
     },
+    #[serde(rename = "image")]
+    Image { source: MediaSource },
+    #[serde(rename = "document")]
+    Document {
+        source: MediaSource,
+        #[serde(default)]
+        title: Option<String>,
+    },
     #[serde(other)]
     Unknown,
 }
 
+/// `source` of an image / document block.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub enum MediaSource {
+    #[serde(rename = "base64")]
+    Base64 { media_type: String, data: String },
+    #[serde(rename = "url")]
+    Url { url: String },
+    /// Plain-text documents.
+    #[serde(rename = "text")]
+    Text {
+        #[serde(default)]
+        media_type: Option<String>,
+        data: String,
+    },
+    #[serde(other)]
+    Unknown,
+}
+
+impl MediaSource {
+    pub fn media_type(&self) -> &str {
+        match self {
+            MediaSource::Base64 { media_type, .. } => media_type,
+            MediaSource::Text { media_type, .. } => media_type.as_deref().unwrap_or("text/plain"),
+            MediaSource::Url { .. } | MediaSource::Unknown => "application/octet-stream",
+        }
+    }
+
+    /// Remote URL for `url` sources; embedded payloads have none.
+    pub fn url(&self) -> Option<String> {
+        match self {
+            MediaSource::Url { url } => Some(url.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// Decoded payload of an image / document block.
+#[derive(Debug, Clone)]
+pub struct MessageMedia {
+    pub media_type: String,
+    pub data: Vec<u8>,
+}
+
 // ── Unified display types (sent to frontend) ──
 
 /// A display-ready message for the frontend
@@ -75,6 +163,11 @@ pub struct DisplayMessage {
     pub timestamp: Option<String>,
     pub model: Option<String>,
     pub content: Vec<DisplayContentBlock>,
+    /// Tokens / cost of the model request that produced this message. Set
+    /// on one message per request (Claude writes a record per content block
+    /// of a response, all with the same usage).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<MessageUsage>,
 }
 
 /// Unified content block enum covering both Claude and Codex types
@@ -92,6 +185,10 @@ pub enum DisplayContentBlock {
         id: String,
         name: String,
         input: String,
+        /// Claude Task tool only: the subagent transcript this call spawned.
+        /// The messages themselves are loaded on demand.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        subagent: Option<SubagentSummary>,
     },
     #[serde(rename = "tool_result")]
     ToolResult {
@@ -99,6 +196,25 @@ pub enum DisplayContentBlock {
         content: String,
         is_error: bool,
     },
+    /// Pasted image. The payload is not inlined: it is fetched on demand by
+    /// message uuid + `blockIndex` (the block's index in the raw record).
+    #[serde(rename = "image", rename_all = "camelCase")]
+    Image {
+        media_type: String,
+        block_index: usize,
+        /// Set for images referenced by URL instead of embedded.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        url: Option<String>,
+    },
+    /// Attached document (PDF, plain text); loaded like [`Self::Image`].
+    #[serde(rename = "document", rename_all = "camelCase")]
+    Document {
+        media_type: String,
+        title: Option<String>,
+        block_index: usize,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        url: Option<String>,
+    },
     // Codex-specific
     #[serde(rename = "reasoning")]
     Reasoning { text: String },
@@ -112,6 +228,113 @@ pub enum DisplayContentBlock {
     FunctionCallOutput { call_id: String, output: String },
 }
 
+/// Token / cost of one model request, or summed over a thread.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub cache_creation_tokens: u64,
+    pub total_tokens: u64,
+    pub cost_usd: f64,
+}
+
+impl MessageUsage {
+    /// One request's usage, priced with [`pricing::compute_cost`].
+    pub fn priced(
+        model: &str,
+        input_tokens: u64,
+        output_tokens: u64,
+        cache_read_tokens: u64,
+        cache_creation_tokens: u64,
+    ) -> Self {
+        MessageUsage {
+            input_tokens,
+            output_tokens,
+            cache_read_tokens,
+            cache_creation_tokens,
+            total_tokens: input_tokens + output_tokens + cache_read_tokens + cache_creation_tokens,
+            cost_usd: pricing::compute_cost(
+                model,
+                input_tokens,
+                cache_creation_tokens,
+                cache_read_tokens,
+                output_tokens,
+            ),
+        }
+    }
+
+    pub fn add(&mut self, other: &MessageUsage) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_read_tokens += other.cache_read_tokens;
+        self.cache_creation_tokens += other.cache_creation_tokens;
+        self.total_tokens += other.total_tokens;
+        self.cost_usd += other.cost_usd;
+    }
+}
+
+/// A subagent (sidechain) transcript linked to the Task tool call that
+/// spawned it, without its messages.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubagentSummary {
+    /// Claude Code's agent id, or the root record uuid for sidechains that
+    /// older builds wrote inline into the parent session file.
+    pub agent_id: String,
+    pub message_count: usize,
+    pub usage: MessageUsage,
+}
+
+/// A subagent transcript as a child thread of its parent session.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubagentThread {
+    pub agent_id: String,
+    pub messages: Vec<DisplayMessage>,
+    pub usage: MessageUsage,
+}
+
+/// One displayed message in a session's uuid / parentUuid tree.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TreeNode {
+    pub uuid: String,
+    /// Nearest displayed ancestor; records that are never displayed (system,
+    /// progress, …) are skipped over.
+    pub parent_uuid: Option<String>,
+    /// Child uuids in file order, i.e. oldest attempt first.
+    pub children: Vec<String>,
+    pub role: String,
+    pub timestamp: Option<String>,
+    pub preview: String,
+    /// On the path from the root to the active leaf.
+    pub active: bool,
+}
+
+/// Every branch of a session: rewinds, edited prompts and retries all fork
+/// the tree at the message they were continued from.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationTree {
+    /// All nodes in file order.
+    pub nodes: Vec<TreeNode>,
+    pub roots: Vec<String>,
+    /// Leaf the session continues from — the last message written.
+    pub active_leaf: Option<String>,
+    pub leaf_count: usize,
+}
+
+/// One root-to-leaf path of a [`ConversationTree`] as a linear message list.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchMessages {
+    pub leaf_uuid: String,
+    pub active: bool,
+    pub messages: Vec<DisplayMessage>,
+}
+
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PaginatedMessages {
@@ -1,6 +1,10 @@
+pub mod compare;
+pub mod file_history;
 pub mod message;
+pub mod plan;
 pub mod pricing;
 pub mod project;
+pub mod prompt_history;
 pub mod session;
 pub mod skill;
 pub mod stats;
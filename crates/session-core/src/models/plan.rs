@@ -0,0 +1,59 @@
+use serde::Serialize;
+
+/// One todo of a TodoWrite / `update_plan` list.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TodoItem {
+    pub content: String,
+    /// `pending`, `in_progress` or `completed`.
+    pub status: String,
+    pub active_form: Option<String>,
+}
+
+/// Final state of an agent's todo list.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TodoList {
+    /// Subagent id; `None` for the session's own agent.
+    pub agent_id: Option<String>,
+    pub items: Vec<TodoItem>,
+    pub completed: usize,
+    /// When the list was last written, if known.
+    pub updated_at: Option<String>,
+}
+
+impl TodoList {
+    pub fn new(agent_id: Option<String>, items: Vec<TodoItem>, updated_at: Option<String>) -> Self {
+        let completed = items.iter().filter(|t| t.status == "completed").count();
+        TodoList {
+            agent_id,
+            items,
+            completed,
+            updated_at,
+        }
+    }
+}
+
+/// A plan the agent presented for approval (Claude's `ExitPlanMode`).
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanDocument {
+    pub tool_use_id: String,
+    /// Message carrying the tool call, to jump to it in the transcript.
+    pub message_uuid: Option<String>,
+    pub timestamp: Option<String>,
+    /// Markdown.
+    pub plan: String,
+    /// Whether the user accepted it; `None` while unanswered.
+    pub approved: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionPlan {
+    pub todos: Option<TodoList>,
+    /// Todo lists of subagents the session spawned.
+    pub agent_todos: Vec<TodoList>,
+    /// In the order they were proposed.
+    pub plans: Vec<PlanDocument>,
+}
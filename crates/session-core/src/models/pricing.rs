@@ -1,7 +1,7 @@
 //! Per-model token pricing and cost computation.
 //!
 //! Prices are USD per million tokens (USD/MTok). The table below mirrors
-//! Anthropic's and OpenAI's published list pricing as of 2026-04. The table
+//! Anthropic's, OpenAI's and Google's published list pricing as of 2026-04. The table
 //! is updated alongside app releases — there is no runtime sync, since list
 //! prices change rarely and a stale entry is better than a network failure
 //! at viewer launch.
@@ -19,6 +19,12 @@
 //! `output_tokens` only; cache hits are reflected inside `input_tokens`. The
 //! pricing function treats `cache_*` as 0 for Codex models so the math still
 //! works without double-counting.
+//!
+//! Google (Gemini CLI) reports `cached` tokens as a subset of the prompt; the
+//! Gemini provider splits them out into `cache_read` (0.25× input price) so
+//! `input` is the non-cached remainder. Gemini 2.5 Pro doubles its price
+//! above 200k prompt tokens — the table uses the ≤200k tier, which is what a
+//! CLI turn almost always lands in.
 
 /// USD per million input / cache_creation / cache_read / output tokens.
 #[derive(Debug, Clone, Copy)]
@@ -43,6 +49,17 @@ impl ModelPrice {
         }
     }
 
+    const fn google(input: f64, output: f64) -> Self {
+        Self {
+            input_per_mtok: input,
+            output_per_mtok: output,
+            // Implicit context caching has no write surcharge; cache storage
+            // is billed per hour and not visible in CLI logs.
+            cache_creation_multiplier: 1.0,
+            cache_read_multiplier: 0.25,
+        }
+    }
+
     const fn openai(input: f64, output: f64) -> Self {
         Self {
             input_per_mtok: input,
@@ -116,6 +133,35 @@ pub fn lookup(model: &str) -> Option<ModelPrice> {
     if m.starts_with("o1") {
         return Some(ModelPrice::openai(15.00, 60.00));
     }
+    // ── Google Gemini family ───────────────────────────────────────────────
+    // `-lite` must be checked before the plain `-flash` prefix.
+    if m.starts_with("gemini-3-pro") {
+        return Some(ModelPrice::google(2.00, 12.00));
+    }
+    if m.starts_with("gemini-3-flash") {
+        return Some(ModelPrice::google(0.50, 3.00));
+    }
+    if m.starts_with("gemini-2.5-pro") {
+        return Some(ModelPrice::google(1.25, 10.00));
+    }
+    if m.starts_with("gemini-2.5-flash-lite") {
+        return Some(ModelPrice::google(0.10, 0.40));
+    }
+    if m.starts_with("gemini-2.5-flash") {
+        return Some(ModelPrice::google(0.30, 2.50));
+    }
+    if m.starts_with("gemini-2.0-flash-lite") {
+        return Some(ModelPrice::google(0.075, 0.30));
+    }
+    if m.starts_with("gemini-2.0-flash") {
+        return Some(ModelPrice::google(0.10, 0.40));
+    }
+    if m.starts_with("gemini-1.5-pro") {
+        return Some(ModelPrice::google(1.25, 5.00));
+    }
+    if m.starts_with("gemini-1.5-flash") {
+        return Some(ModelPrice::google(0.075, 0.30));
+    }
     None
 }
 
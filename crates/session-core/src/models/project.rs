@@ -25,4 +25,8 @@ pub struct ProjectEntry {
     /// bucketed by the rollout file's date. Real cwd projects keep this false.
     #[serde(default)]
     pub is_virtual: bool,
+    /// Name of the configured data root the project was found under;
+    /// `None` for the local default home.
+    #[serde(default)]
+    pub data_root: Option<String>,
 }
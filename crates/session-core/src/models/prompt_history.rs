@@ -0,0 +1,53 @@
+use serde::Serialize;
+
+/// One prompt from Claude Code's `history.jsonl`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptHistoryEntry {
+    /// The prompt as typed; pasted content shows up as markers such as
+    /// `[Pasted text #1 +12 lines]` / `[Image #1]`.
+    pub display: String,
+    pub timestamp: Option<String>,
+    /// Working directory the prompt was sent from.
+    pub project: Option<String>,
+    /// Data root the history file belongs to; `None` for the local home.
+    pub data_root: Option<String>,
+    pub pasted: Vec<PastedContentRef>,
+    /// Session containing the prompt, when it could be resolved.
+    pub session_id: Option<String>,
+    pub project_id: Option<String>,
+    pub file_path: Option<String>,
+}
+
+/// A pasted-content marker of a prompt; the content itself is not returned.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PastedContentRef {
+    pub id: u64,
+    /// `text` or `image`.
+    pub kind: String,
+    /// Text pastes only, when the content is stored inline.
+    pub line_count: Option<usize>,
+    pub preview: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptHistoryProject {
+    pub project: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptHistoryPage {
+    /// Newest first.
+    pub entries: Vec<PromptHistoryEntry>,
+    pub total: usize,
+    pub page: usize,
+    pub page_size: usize,
+    pub has_more: bool,
+    /// Every project in the history with its prompt count, for the filter
+    /// list; independent of the current filter.
+    pub projects: Vec<PromptHistoryProject>,
+}
@@ -0,0 +1,637 @@
+//! Claude Code file-history snapshots.
+//!
+//! Claude Code 在修改文件前把原内容备份到
+//! `~/.claude/file-history/<session-id>/<hash>@v<version>`，并在会话里写
+//! `file-history-snapshot` 记录：每个用户 prompt 一条，列出此时所有被跟踪文件
+//! 对应的备份版本（`backupFileName` 为 null 表示文件当时还不存在）；回合中途
+//! 首次修改的文件通过 `isSnapshotUpdate` 记录补进同一个 prompt 的快照。
+//!
+//! 某条消息前后的文件内容 = 该消息之前最近一次快照里的备份，再按顺序重放
+//! 其后的 `Edit` / `MultiEdit` / `Write` 工具调用。
+
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::models::file_history::{
+    FileBackup, FileChange, FileChangeBody, FileContentsAtMessage, FileEdit, FileHistoryIndex,
+    SessionFileChanges, TrackedFile,
+};
+use crate::models::message::{ContentBlock, ContentValue, RawRecord};
+
+const EDIT_TOOLS: &[&str] = &["Edit", "MultiEdit", "Write"];
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SnapshotRecord {
+    message_id: String,
+    snapshot: Snapshot,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Snapshot {
+    #[serde(default)]
+    tracked_file_backups: HashMap<String, BackupRef>,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct BackupRef {
+    backup_file_name: Option<String>,
+    #[serde(default)]
+    version: u32,
+    backup_time: Option<String>,
+}
+
+/// `toolUseResult` of an edit tool call. Parsed on its own so the shared
+/// [`RawRecord`] doesn't carry whole file contents around.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EditResultRecord {
+    tool_use_result: EditResult,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EditResult {
+    /// The file right before the edit.
+    original_file: Option<String>,
+    /// `create` / `update` for Write.
+    #[serde(rename = "type")]
+    kind: Option<String>,
+}
+
+/// Snapshot of one prompt, with update records merged in.
+struct SnapshotAt {
+    message_uuid: String,
+    position: usize,
+    backups: HashMap<String, BackupRef>,
+}
+
+enum EditOp {
+    Replace { old: String, new: String, all: bool },
+    Write(String),
+}
+
+struct EditCall {
+    position: usize,
+    message_uuid: String,
+    timestamp: Option<String>,
+    tool_use_id: String,
+    tool_name: String,
+    file_path: String,
+    ops: Vec<EditOp>,
+}
+
+/// Everything file-history needs from one session transcript, positions
+/// being record indexes in the file.
+#[derive(Default)]
+struct SessionHistory {
+    positions: HashMap<String, usize>,
+    snapshots: Vec<SnapshotAt>,
+    edits: Vec<EditCall>,
+    failed: HashSet<String>,
+    results: HashMap<String, EditResult>,
+    /// User prompts as (position, text).
+    prompts: Vec<(usize, String)>,
+    cwd: Option<String>,
+}
+
+impl SessionHistory {
+    fn load(path: &Path) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+        let reader = BufReader::new(file);
+        let mut history = SessionHistory::default();
+        // Snapshot records come right before their prompt; place them at
+        // the prompt once it has been seen.
+        let mut snapshot_lines: Vec<(usize, SnapshotRecord)> = Vec::new();
+
+        for (index, line) in reader.lines().enumerate() {
+            let line = match line {
+                Ok(l) => l,
+                Err(_) => continue,
+            };
+            let trimmed = line.trim();
+            if trimmed.contains("\"type\":\"file-history-snapshot\"") {
+                if let Ok(record) = serde_json::from_str::<SnapshotRecord>(trimmed) {
+                    snapshot_lines.push((index, record));
+                }
+                continue;
+            }
+            if !trimmed.contains("\"type\":\"user\"") && !trimmed.contains("\"type\":\"assistant\"")
+            {
+                continue;
+            }
+            if let Ok(record) = serde_json::from_str::<RawRecord>(trimmed) {
+                let edit_result = (trimmed.contains("\"toolUseResult\"")
+                    && trimmed.contains("\"filePath\""))
+                .then(|| serde_json::from_str::<EditResultRecord>(trimmed).ok())
+                .flatten()
+                .map(|r| r.tool_use_result);
+                history.add_record(index, record, edit_result);
+            }
+        }
+
+        for (index, record) in snapshot_lines {
+            let backups = record
+                .snapshot
+                .tracked_file_backups
+                .into_iter()
+                .map(|(file, backup)| (absolute_path(&file, history.cwd.as_deref()), backup));
+            if let Some(existing) = history
+                .snapshots
+                .iter_mut()
+                .find(|s| s.message_uuid == record.message_id)
+            {
+                existing.backups.extend(backups);
+                continue;
+            }
+            let position = history
+                .positions
+                .get(&record.message_id)
+                .copied()
+                .unwrap_or(index);
+            history.snapshots.push(SnapshotAt {
+                message_uuid: record.message_id,
+                position,
+                backups: backups.collect(),
+            });
+        }
+        history.snapshots.sort_by_key(|s| s.position);
+        Ok(history)
+    }
+
+    fn add_record(&mut self, position: usize, record: RawRecord, mut edit_result: Option<EditResult>) {
+        if self.cwd.is_none() {
+            self.cwd = record.cwd.clone();
+        }
+        let Some(uuid) = record.uuid else {
+            return;
+        };
+        self.positions.insert(uuid.clone(), position);
+
+        let is_prompt = record.record_type == "user" && record.is_sidechain != Some(true);
+        let blocks = match record.message.map(|m| m.content) {
+            Some(ContentValue::Blocks(blocks)) => blocks,
+            Some(ContentValue::Text(text)) => {
+                if is_prompt {
+                    self.prompts.push((position, text));
+                }
+                return;
+            }
+            None => return,
+        };
+        if is_prompt && !blocks.iter().any(|b| matches!(b, ContentBlock::ToolResult { .. })) {
+            let text = blocks.iter().find_map(|b| match b {
+                ContentBlock::Text { text } => Some(text.clone()),
+                _ => None,
+            });
+            if let Some(text) = text {
+                self.prompts.push((position, text));
+            }
+        }
+        for block in blocks {
+            match block {
+                ContentBlock::ToolUse { id, name, input }
+                    if EDIT_TOOLS.contains(&name.as_str()) =>
+                {
+                    let Some(file_path) = input.get("file_path").and_then(|p| p.as_str()) else {
+                        continue;
+                    };
+                    self.edits.push(EditCall {
+                        position,
+                        message_uuid: uuid.clone(),
+                        timestamp: record.timestamp.clone(),
+                        tool_use_id: id,
+                        file_path: absolute_path(file_path, self.cwd.as_deref()),
+                        ops: edit_ops(&name, &input),
+                        tool_name: name,
+                    });
+                }
+                ContentBlock::ToolResult {
+                    tool_use_id,
+                    is_error,
+                    ..
+                } => {
+                    if let Some(result) = edit_result.take() {
+                        self.results.insert(tool_use_id.clone(), result);
+                    }
+                    if is_error == Some(true) {
+                        self.failed.insert(tool_use_id);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Latest snapshot at or before `position` tracking `file`, else the
+    /// first one that does (the file was not touched before it).
+    fn base_snapshot(&self, file: &str, position: usize) -> Option<&SnapshotAt> {
+        let mut tracking = self
+            .snapshots
+            .iter()
+            .filter(|s| s.backups.contains_key(file));
+        let first = tracking.clone().next();
+        tracking.rfind(|s| s.position <= position).or(first)
+    }
+}
+
+fn absolute_path(file: &str, cwd: Option<&str>) -> String {
+    match cwd {
+        Some(cwd) if !Path::new(file).is_absolute() => {
+            Path::new(cwd).join(file).to_string_lossy().to_string()
+        }
+        _ => file.to_string(),
+    }
+}
+
+fn edit_ops(tool_name: &str, input: &Value) -> Vec<EditOp> {
+    let replace = |edit: &Value| {
+        Some(EditOp::Replace {
+            old: edit.get("old_string")?.as_str()?.to_string(),
+            new: edit.get("new_string")?.as_str()?.to_string(),
+            all: edit
+                .get("replace_all")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+        })
+    };
+    match tool_name {
+        "Write" => input
+            .get("content")
+            .and_then(|c| c.as_str())
+            .map(|c| vec![EditOp::Write(c.to_string())])
+            .unwrap_or_default(),
+        "MultiEdit" => input
+            .get("edits")
+            .and_then(|e| e.as_array())
+            .map(|edits| edits.iter().filter_map(replace).collect())
+            .unwrap_or_default(),
+        _ => replace(input).into_iter().collect(),
+    }
+}
+
+/// Apply one edit the way the tool does; `None` if it doesn't apply.
+fn apply_op(content: Option<&str>, op: &EditOp) -> Option<Option<String>> {
+    match op {
+        EditOp::Write(text) => Some(Some(text.clone())),
+        // An empty old_string creates the file.
+        EditOp::Replace { old, new, .. } if old.is_empty() => match content {
+            None | Some("") => Some(Some(new.clone())),
+            Some(_) => None,
+        },
+        EditOp::Replace { old, new, all } => {
+            let content = content?;
+            if !content.contains(old.as_str()) {
+                return None;
+            }
+            Some(Some(if *all {
+                content.replace(old.as_str(), new)
+            } else {
+                content.replacen(old.as_str(), new, 1)
+            }))
+        }
+    }
+}
+
+/// Apply all of an edit's operations; `None` if one doesn't apply.
+fn replay(content: Option<&str>, ops: &[EditOp]) -> Option<Option<String>> {
+    let mut content = content.map(str::to_string);
+    for op in ops {
+        content = apply_op(content.as_deref(), op)?;
+    }
+    Some(content)
+}
+
+fn fragments(ops: &[EditOp]) -> FileChangeBody {
+    // A whole-file write with unknown previous contents is shown as a creation.
+    if let Some(EditOp::Write(text)) = ops.last() {
+        return FileChangeBody::Contents {
+            before: None,
+            after: Some(text.clone()),
+        };
+    }
+    FileChangeBody::Fragments(
+        ops.iter()
+            .filter_map(|op| match op {
+                EditOp::Replace { old, new, .. } => Some((old.clone(), new.clone())),
+                EditOp::Write(_) => None,
+            })
+            .collect(),
+    )
+}
+
+fn read_backup(history_dir: &Path, backup: &BackupRef) -> Result<Option<String>, String> {
+    let Some(name) = backup.backup_file_name.as_deref() else {
+        return Ok(None);
+    };
+    let valid = !name.is_empty()
+        && !name.contains("..")
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '@' | '.' | '-' | '_'));
+    if !valid {
+        return Err(format!("Invalid backup file name: {}", name));
+    }
+    fs::read_to_string(history_dir.join(name))
+        .map(Some)
+        .map_err(|e| format!("Failed to read backup {}: {}", name, e))
+}
+
+/// Files a session edited, with their backups and edit tool calls.
+pub fn file_history_index(session_path: &Path) -> Result<FileHistoryIndex, String> {
+    let history = SessionHistory::load(session_path)?;
+
+    let mut paths: Vec<&str> = history
+        .snapshots
+        .iter()
+        .flat_map(|s| s.backups.keys())
+        .chain(history.edits.iter().map(|e| &e.file_path))
+        .map(String::as_str)
+        .collect();
+    paths.sort_unstable();
+    paths.dedup();
+
+    let files = paths
+        .into_iter()
+        .map(|file| {
+            let mut backups: Vec<FileBackup> = Vec::new();
+            for snapshot in &history.snapshots {
+                let Some(backup) = snapshot.backups.get(file) else {
+                    continue;
+                };
+                if backups.iter().any(|b| b.version == backup.version) {
+                    continue;
+                }
+                backups.push(FileBackup {
+                    version: backup.version,
+                    message_uuid: snapshot.message_uuid.clone(),
+                    backup_time: backup.backup_time.clone(),
+                    exists: backup.backup_file_name.is_some(),
+                });
+            }
+            let edits = history
+                .edits
+                .iter()
+                .filter(|e| e.file_path == file)
+                .map(|e| FileEdit {
+                    tool_use_id: e.tool_use_id.clone(),
+                    tool_name: e.tool_name.clone(),
+                    message_uuid: e.message_uuid.clone(),
+                    timestamp: e.timestamp.clone(),
+                    base_version: history
+                        .base_snapshot(file, e.position)
+                        .and_then(|s| s.backups.get(file))
+                        .map(|b| b.version),
+                    is_error: history.failed.contains(&e.tool_use_id),
+                })
+                .collect();
+            TrackedFile {
+                file_path: file.to_string(),
+                backups,
+                edits,
+            }
+        })
+        .collect();
+
+    Ok(FileHistoryIndex {
+        session_id: session_path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        files,
+    })
+}
+
+/// Contents of `file` before and after message `uuid`, rebuilt from the
+/// session's backups in `history_dir`.
+pub fn file_contents_at(
+    session_path: &Path,
+    history_dir: &Path,
+    file: &str,
+    uuid: &str,
+) -> Result<FileContentsAtMessage, String> {
+    let history = SessionHistory::load(session_path)?;
+    let position = *history
+        .positions
+        .get(uuid)
+        .ok_or_else(|| format!("Message not found: {}", uuid))?;
+    let file_path = absolute_path(file, history.cwd.as_deref());
+    let base = history
+        .base_snapshot(&file_path, position)
+        .ok_or_else(|| format!("No file-history backup for: {}", file))?;
+
+    let mut content = read_backup(history_dir, &base.backups[&file_path])?;
+    let mut before = None;
+    let mut exact = true;
+    let edits = history.edits.iter().filter(|e| {
+        e.file_path == file_path
+            && e.position >= base.position
+            && e.position <= position
+            && !history.failed.contains(&e.tool_use_id)
+    });
+    for edit in edits {
+        if edit.position == position && before.is_none() {
+            before = Some(content.clone());
+        }
+        for op in &edit.ops {
+            match apply_op(content.as_deref(), op) {
+                Some(next) => content = next,
+                None => exact = false,
+            }
+        }
+    }
+
+    Ok(FileContentsAtMessage {
+        file_path,
+        message_uuid: uuid.to_string(),
+        before: before.unwrap_or_else(|| content.clone()),
+        after: content,
+        exact,
+    })
+}
+
+/// Every successful edit tool call of a session as a whole-file change.
+///
+/// The contents before an edit come from, in order of preference: the
+/// `originalFile` Claude Code recorded with the result, the replay of the
+/// earlier edits, the file-history backup. When none is available the edit
+/// is returned as fragments.
+pub fn session_file_changes(
+    session_path: &Path,
+    history_dir: &Path,
+) -> Result<SessionFileChanges, String> {
+    let history = SessionHistory::load(session_path)?;
+    let mut known: HashMap<&str, Option<String>> = HashMap::new();
+    let mut changes = Vec::new();
+
+    for edit in &history.edits {
+        if history.failed.contains(&edit.tool_use_id) {
+            continue;
+        }
+        let file = edit.file_path.as_str();
+        let before = match history.results.get(&edit.tool_use_id) {
+            Some(EditResult {
+                original_file: Some(original),
+                ..
+            }) => Some(Some(original.clone())),
+            Some(EditResult {
+                kind: Some(kind), ..
+            }) if kind == "create" => Some(None),
+            _ => known.get(file).cloned().or_else(|| {
+                history
+                    .base_snapshot(file, edit.position)
+                    .filter(|s| s.position <= edit.position)
+                    .and_then(|s| read_backup(history_dir, &s.backups[file]).ok())
+            }),
+        };
+
+        let body = match before.and_then(|b| replay(b.as_deref(), &edit.ops).map(|a| (b, a))) {
+            Some((before, after)) => {
+                known.insert(file, after.clone());
+                FileChangeBody::Contents { before, after }
+            }
+            None => {
+                known.remove(file);
+                fragments(&edit.ops)
+            }
+        };
+
+        let turn = history.prompts.partition_point(|(p, _)| *p < edit.position);
+        changes.push(FileChange {
+            tool_call_id: edit.tool_use_id.clone(),
+            tool_name: edit.tool_name.clone(),
+            timestamp: edit.timestamp.clone(),
+            turn,
+            turn_prompt: turn
+                .checked_sub(1)
+                .map(|i| history.prompts[i].1.clone()),
+            path: edit.file_path.clone(),
+            old_path: None,
+            body,
+        });
+    }
+
+    Ok(SessionFileChanges {
+        cwd: history.cwd,
+        changes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replays_edits_on_top_of_snapshots() {
+        let dir = std::env::temp_dir().join(format!("asv-file-history-{}", uuid::Uuid::new_v4()));
+        let history_dir = dir.join("file-history");
+        fs::create_dir_all(&history_dir).unwrap();
+        fs::write(history_dir.join("abc@v1"), "one\n").unwrap();
+        fs::write(history_dir.join("abc@v2"), "two\n").unwrap();
+
+        let snapshot = |message: &str, version: u32| {
+            format!(
+                r#"{{"type":"file-history-snapshot","messageId":"{m}","snapshot":{{"messageId":"{m}","trackedFileBackups":{{"src/a.txt":{{"backupFileName":"abc@v{v}","version":{v},"backupTime":"t"}}}}}},"isSnapshotUpdate":false}}"#,
+                m = message,
+                v = version
+            )
+        };
+        let tool_use = |uuid: &str, id: &str, name: &str, input: &str| {
+            format!(
+                r#"{{"type":"assistant","uuid":"{}","cwd":"/repo","message":{{"role":"assistant","content":[{{"type":"tool_use","id":"{}","name":"{}","input":{}}}]}}}}"#,
+                uuid, id, name, input
+            )
+        };
+        let result = |uuid: &str, id: &str, is_error: bool| {
+            format!(
+                r#"{{"type":"user","uuid":"{}","cwd":"/repo","message":{{"role":"user","content":[{{"type":"tool_result","tool_use_id":"{}","content":"ok","is_error":{}}}]}}}}"#,
+                uuid, id, is_error
+            )
+        };
+        let prompt = |uuid: &str| {
+            format!(
+                r#"{{"type":"user","uuid":"{}","cwd":"/repo","message":{{"role":"user","content":"go"}}}}"#,
+                uuid
+            )
+        };
+        let lines = [
+            snapshot("u1", 1),
+            prompt("u1"),
+            tool_use(
+                "a1",
+                "t1",
+                "Edit",
+                r#"{"file_path":"/repo/src/a.txt","old_string":"one","new_string":"two"}"#,
+            ),
+            result("r1", "t1", false),
+            tool_use(
+                "a2",
+                "t2",
+                "Edit",
+                r#"{"file_path":"/repo/src/a.txt","old_string":"two","new_string":"nope"}"#,
+            ),
+            result("r2", "t2", true),
+            snapshot("u2", 2),
+            prompt("u2"),
+            tool_use(
+                "a3",
+                "t3",
+                "Write",
+                r#"{"file_path":"/repo/src/a.txt","content":"final\n"}"#,
+            ),
+        ];
+        let session = dir.join("s1.jsonl");
+        fs::write(&session, lines.join("\n")).unwrap();
+
+        let index = file_history_index(&session).unwrap();
+        assert_eq!(index.files.len(), 1);
+        let file = &index.files[0];
+        assert_eq!(file.file_path, "/repo/src/a.txt");
+        assert_eq!(file.backups.len(), 2);
+        let bases: Vec<_> = file
+            .edits
+            .iter()
+            .map(|e| (e.base_version, e.is_error))
+            .collect();
+        assert_eq!(
+            bases,
+            vec![(Some(1), false), (Some(1), true), (Some(2), false)]
+        );
+
+        let at =
+            |uuid: &str| file_contents_at(&session, &history_dir, "/repo/src/a.txt", uuid).unwrap();
+        let a1 = at("a1");
+        assert_eq!(
+            (a1.before.as_deref(), a1.after.as_deref()),
+            (Some("one\n"), Some("two\n"))
+        );
+        let a2 = at("a2");
+        assert_eq!(
+            (a2.before.as_deref(), a2.after.as_deref()),
+            (Some("two\n"), Some("two\n"))
+        );
+        let a3 = at("a3");
+        assert_eq!(
+            (a3.before.as_deref(), a3.after.as_deref()),
+            (Some("two\n"), Some("final\n"))
+        );
+        assert!(a3.exact);
+
+        let changes = session_file_changes(&session, &history_dir).unwrap().changes;
+        let turns: Vec<_> = changes.iter().map(|c| (c.tool_call_id.as_str(), c.turn)).collect();
+        assert_eq!(turns, vec![("t1", 1), ("t3", 2)]);
+        assert!(matches!(
+            &changes[1].body,
+            FileChangeBody::Contents { before: Some(b), after: Some(a) } if b == "two\n" && a == "final\n"
+        ));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -1,10 +1,14 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs::{self, File};
 use std::io::{BufRead, BufReader, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+use base64::Engine as _;
 
 use crate::models::message::{
-    ContentBlock, ContentValue, DisplayContentBlock, DisplayMessage, PaginatedMessages, RawRecord,
+    BranchMessages, ContentBlock, ContentValue, ConversationTree, DisplayContentBlock,
+    DisplayMessage, MediaSource, MessageMedia, PaginatedMessages, RawRecord, RawUsage,
+    SubagentSummary, SubagentThread, MessageUsage, ToolUseResult, TreeNode,
 };
 use crate::models::session::{SessionsIndex, SessionsIndexFileEntry};
 use crate::state::{
@@ -63,6 +67,10 @@ fn parse_tail_messages(path: &Path, page: usize, page_size: usize) -> Result<Pag
     // session and only needing the most recent messages.
     let mut tail_records: VecDeque<RawRecord> = VecDeque::with_capacity(window_len);
     let mut total = 0usize;
+    let mut links = SidechainLinks::default();
+    // API message ids whose usage a record before the window already carries,
+    // so the window attributes usage exactly like a full parse.
+    let mut usage_seen = HashSet::new();
 
     for line in reader.lines() {
         let line = match line {
@@ -95,6 +103,11 @@ fn parse_tail_messages(path: &Path, page: usize, page_size: usize) -> Result<Pag
             Err(_) => continue,
         };
 
+        // Sidechain records belong to a subagent thread, not the main list.
+        if links.observe(&record) {
+            continue;
+        }
+
         // `record_is_displayable` exactly mirrors the Some/None decision of
         // `display_message_from_record` (without building any strings), so
         // `total` stays identical to a full parse.
@@ -104,16 +117,21 @@ fn parse_tail_messages(path: &Path, page: usize, page_size: usize) -> Result<Pag
 
         total += 1;
         if tail_records.len() == window_len {
-            tail_records.pop_front();
+            if let Some(msg) = tail_records.pop_front().and_then(|r| r.message) {
+                if let (Some(_), Some(id)) = (msg.usage, msg.id) {
+                    usage_seen.insert(id);
+                }
+            }
         }
         tail_records.push_back(record);
     }
 
     // Convert only the retained tail window.
-    let messages: Vec<DisplayMessage> = tail_records
+    let mut messages: Vec<DisplayMessage> = tail_records
         .into_iter()
-        .filter_map(display_message_from_record)
+        .filter_map(|record| display_message_from_record(record, &mut usage_seen))
         .collect();
+    links.attach(path, &mut messages);
     let range_start = total.saturating_sub(messages.len());
     let _ = store_partial_messages(path, total, range_start, &messages);
 
@@ -145,6 +163,7 @@ fn content_produces_block(content: &ContentValue) -> bool {
             ContentBlock::Thinking { thinking } => !thinking.trim().is_empty(),
             ContentBlock::ToolUse { .. } => true,
             ContentBlock::ToolResult { .. } => true,
+            ContentBlock::Image { .. } | ContentBlock::Document { .. } => true,
             ContentBlock::Unknown => false,
         }),
     }
@@ -197,9 +216,62 @@ pub fn parse_all_messages(path: &Path) -> Result<Vec<DisplayMessage>, String> {
         return Ok(cached);
     }
 
+    let mut messages: Vec<DisplayMessage> = Vec::new();
+    let mut links = SidechainLinks::default();
+    let mut usage_seen = HashSet::new();
+    for_each_record(path, |record| {
+        if links.observe(&record) {
+            return;
+        }
+        if let Some(message) = display_message_from_record(record, &mut usage_seen) {
+            messages.push(message);
+        }
+    })?;
+    links.attach(path, &mut messages);
+
+    let _ = store_full_messages(path, &messages);
+    Ok(messages)
+}
+
+/// Feed the messages of a JSONL file to `f` one at a time, for exports of
+/// sessions too large to hold in memory. Unlike [`parse_all_messages`],
+/// Task calls carry no `subagent` summary: linking them needs the whole
+/// file. Stops at the first error `f` returns.
+pub fn for_each_message(
+    path: &Path,
+    f: &mut dyn FnMut(DisplayMessage) -> Result<(), String>,
+) -> Result<(), String> {
+    if let Ok(Some(cached)) = get_cached_full_messages(path) {
+        return cached.into_iter().try_for_each(f);
+    }
+
+    let mut usage_seen = HashSet::new();
+    try_for_each_record(path, |record| {
+        if record.is_sidechain == Some(true) {
+            return Ok(());
+        }
+        match display_message_from_record(record, &mut usage_seen) {
+            Some(message) => f(message),
+            None => Ok(()),
+        }
+    })
+}
+
+/// Stream every parseable record of a JSONL file, skipping [`SKIP_TYPES`].
+fn for_each_record(path: &Path, mut f: impl FnMut(RawRecord)) -> Result<(), String> {
+    try_for_each_record(path, |record| {
+        f(record);
+        Ok(())
+    })
+}
+
+/// [`for_each_record`], stopping at the first error `f` returns.
+fn try_for_each_record(
+    path: &Path,
+    mut f: impl FnMut(RawRecord) -> Result<(), String>,
+) -> Result<(), String> {
     let file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
     let reader = BufReader::new(file);
-    let mut messages: Vec<DisplayMessage> = Vec::new();
 
     for line in reader.lines() {
         let line = match line {
@@ -221,17 +293,27 @@ pub fn parse_all_messages(path: &Path) -> Result<Vec<DisplayMessage>, String> {
             Ok(r) => r,
             Err(_) => continue,
         };
-
-        if let Some(message) = display_message_from_record(record) {
-            messages.push(message);
-        }
+        f(record)?;
     }
+    Ok(())
+}
 
-    let _ = store_full_messages(path, &messages);
-    Ok(messages)
+fn priced_usage(model: Option<&str>, usage: RawUsage) -> MessageUsage {
+    MessageUsage::priced(
+        model.unwrap_or("unknown"),
+        usage.input_tokens,
+        usage.output_tokens,
+        usage.cache_read_input_tokens,
+        usage.cache_creation_input_tokens,
+    )
 }
 
-fn display_message_from_record(record: RawRecord) -> Option<DisplayMessage> {
+/// `usage_seen` holds the API message ids whose usage has already been put
+/// on an earlier message, so a multi-block response is counted once.
+fn display_message_from_record(
+    record: RawRecord,
+    usage_seen: &mut HashSet<String>,
+) -> Option<DisplayMessage> {
     if record.record_type != "user" && record.record_type != "assistant" {
         return None;
     }
@@ -243,6 +325,14 @@ fn display_message_from_record(record: RawRecord) -> Option<DisplayMessage> {
         return None;
     }
 
+    let usage = match (&msg.usage, &msg.id) {
+        (Some(usage), Some(id)) if usage_seen.insert(id.clone()) => {
+            Some(priced_usage(msg.model.as_deref(), *usage))
+        }
+        (Some(usage), None) => Some(priced_usage(msg.model.as_deref(), *usage)),
+        _ => None,
+    };
+
     let role = if msg.role == "user"
         && display_blocks
             .iter()
@@ -260,6 +350,7 @@ fn display_message_from_record(record: RawRecord) -> Option<DisplayMessage> {
         timestamp: record.timestamp,
         model: msg.model,
         content: display_blocks,
+        usage,
     })
 }
 
@@ -412,7 +503,7 @@ fn convert_content(content: &ContentValue) -> Vec<DisplayContentBlock> {
         }
         ContentValue::Blocks(blocks) => {
             let mut result = Vec::new();
-            for block in blocks {
+            for (block_index, block) in blocks.iter().enumerate() {
                 match block {
                     ContentBlock::Text { text } => {
                         if !text.trim().is_empty() {
@@ -433,6 +524,7 @@ fn convert_content(content: &ContentValue) -> Vec<DisplayContentBlock> {
                             id: id.clone(),
                             name: name.clone(),
                             input: input_str,
+                            subagent: None,
                         });
                     }
                     ContentBlock::ToolResult {
@@ -464,6 +556,21 @@ fn convert_content(content: &ContentValue) -> Vec<DisplayContentBlock> {
                             is_error: is_error.unwrap_or(false),
                         });
                     }
+                    ContentBlock::Image { source } => {
+                        result.push(DisplayContentBlock::Image {
+                            media_type: source.media_type().to_string(),
+                            block_index,
+                            url: source.url(),
+                        });
+                    }
+                    ContentBlock::Document { source, title } => {
+                        result.push(DisplayContentBlock::Document {
+                            media_type: source.media_type().to_string(),
+                            title: title.clone(),
+                            block_index,
+                            url: source.url(),
+                        });
+                    }
                     ContentBlock::Unknown => {}
                 }
             }
@@ -472,6 +579,637 @@ fn convert_content(content: &ContentValue) -> Vec<DisplayContentBlock> {
     }
 }
 
+// ── Image / document payloads ──
+
+/// Decode the image / document payload at `block_index` (index into the raw
+/// content array, as reported on the display block) of the record `uuid`.
+/// Only the matching line is deserialized, so this stays cheap on large
+/// sessions.
+pub fn read_message_media(path: &Path, uuid: &str, block_index: usize) -> Result<MessageMedia, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let reader = BufReader::new(file);
+    let needle = format!("\"uuid\":\"{}\"", uuid);
+
+    for line in reader.lines() {
+        let line = match line {
+            Ok(l) => l,
+            Err(_) => continue,
+        };
+        if !line.contains(&needle) {
+            continue;
+        }
+        let record: RawRecord = match serde_json::from_str(line.trim()) {
+            Ok(r) => r,
+            Err(_) => continue,
+        };
+        if record.uuid.as_deref() != Some(uuid) {
+            continue;
+        }
+        let source = match record.message.map(|m| m.content) {
+            Some(ContentValue::Blocks(blocks)) => match blocks.into_iter().nth(block_index) {
+                Some(ContentBlock::Image { source }) | Some(ContentBlock::Document { source, .. }) => {
+                    Some(source)
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+        return match source {
+            Some(source) => decode_media(source),
+            None => Err(format!(
+                "No image or document at block {} of message {}",
+                block_index, uuid
+            )),
+        };
+    }
+    Err(format!("Message not found: {}", uuid))
+}
+
+fn decode_media(source: MediaSource) -> Result<MessageMedia, String> {
+    let media_type = source.media_type().to_string();
+    match source {
+        MediaSource::Base64 { data, .. } => {
+            let cleaned: String = data.chars().filter(|c| !c.is_ascii_whitespace()).collect();
+            let data = base64::engine::general_purpose::STANDARD
+                .decode(cleaned)
+                .map_err(|e| format!("Failed to decode media: {}", e))?;
+            Ok(MessageMedia { media_type, data })
+        }
+        MediaSource::Text { data, .. } => Ok(MessageMedia {
+            media_type,
+            data: data.into_bytes(),
+        }),
+        MediaSource::Url { url } => Err(format!("Media is not embedded in the session: {}", url)),
+        MediaSource::Unknown => Err("Unsupported media source".to_string()),
+    }
+}
+
+// ── Subagent (sidechain) threads ──
+//
+// Task-tool subagents run as a separate "sidechain" conversation. Current
+// Claude Code builds write each one to `<session-id>/subagents/agent-<id>.jsonl`
+// next to the session file and report the `agentId` in the Task call's
+// `toolUseResult`; older builds wrote the sidechain records (`isSidechain:
+// true`) inline into the parent file. Either way the thread is linked to the
+// spawning `ToolUse` block — by `agentId` when recorded, otherwise by matching
+// the thread's first prompt against the Task call's `prompt` input — and kept
+// out of the main message list.
+
+/// Tool names that spawn a subagent (`Agent` is the newer name of `Task`).
+const TASK_TOOL_NAMES: &[&str] = &["Task", "Agent"];
+
+fn subagents_dir(session_path: &Path) -> PathBuf {
+    session_path.with_extension("").join("subagents")
+}
+
+fn is_valid_agent_id(agent_id: &str) -> bool {
+    !agent_id.is_empty()
+        && agent_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn first_text(content: &ContentValue) -> Option<String> {
+    match content {
+        ContentValue::Text(s) => Some(s.clone()),
+        ContentValue::Blocks(blocks) => blocks.iter().find_map(|block| match block {
+            ContentBlock::Text { text } => Some(text.clone()),
+            _ => None,
+        }),
+    }
+}
+
+
+/// Running totals of one subagent thread.
+#[derive(Default)]
+struct ThreadAccumulator {
+    first_prompt: Option<String>,
+    message_count: usize,
+    usage: MessageUsage,
+    /// Usage is repeated on every record of a multi-block API message.
+    seen_message_ids: HashSet<String>,
+}
+
+impl ThreadAccumulator {
+    fn add(&mut self, record: &RawRecord) {
+        let Some(msg) = &record.message else {
+            return;
+        };
+        if record.record_type == "assistant" {
+            if let Some(usage) = msg.usage {
+                let fresh = msg
+                    .id
+                    .as_ref()
+                    .map(|id| self.seen_message_ids.insert(id.clone()))
+                    .unwrap_or(true);
+                if fresh {
+                    self.usage.add(&priced_usage(msg.model.as_deref(), usage));
+                }
+            }
+        }
+        if !record_is_displayable(record) {
+            return;
+        }
+        self.message_count += 1;
+        if self.first_prompt.is_none() && record.record_type == "user" {
+            self.first_prompt = first_text(&msg.content);
+        }
+    }
+
+    fn into_summary(self, agent_id: String) -> SubagentSummary {
+        SubagentSummary {
+            agent_id,
+            message_count: self.message_count,
+            usage: self.usage,
+        }
+    }
+}
+
+/// Groups inline sidechain records into threads by following `parentUuid`
+/// back to the thread's first record, whose uuid names the thread.
+#[derive(Default)]
+struct InlineChains {
+    roots: HashMap<String, String>,
+}
+
+impl InlineChains {
+    fn root_of(&mut self, record: &RawRecord) -> Option<String> {
+        let uuid = record.uuid.clone()?;
+        let root = record
+            .parent_uuid
+            .as_ref()
+            .and_then(|parent| self.roots.get(parent))
+            .cloned()
+            .unwrap_or_else(|| uuid.clone());
+        self.roots.insert(uuid, root.clone());
+        Some(root)
+    }
+}
+
+/// Link information collected while streaming a session file.
+#[derive(Default)]
+struct SidechainLinks {
+    /// Task tool_use id → the `prompt` it was called with, in file order.
+    task_prompts: Vec<(String, String)>,
+    /// Task tool_use id → the `agentId` its tool_result reported.
+    agent_by_tool: HashMap<String, String>,
+    chains: InlineChains,
+    /// Inline sidechain threads keyed by root uuid, in file order.
+    inline_threads: Vec<(String, ThreadAccumulator)>,
+}
+
+impl SidechainLinks {
+    /// Record what `record` says about subagent links. Returns `true` when it
+    /// is an inline sidechain record, which the caller must not display.
+    fn observe(&mut self, record: &RawRecord) -> bool {
+        if record.is_sidechain == Some(true) {
+            if let Some(root) = self.chains.root_of(record) {
+                let idx = match self.inline_threads.iter().position(|(r, _)| *r == root) {
+                    Some(idx) => idx,
+                    None => {
+                        self.inline_threads.push((root, ThreadAccumulator::default()));
+                        self.inline_threads.len() - 1
+                    }
+                };
+                self.inline_threads[idx].1.add(record);
+            }
+            return true;
+        }
+
+        let Some(ContentValue::Blocks(blocks)) = record.message.as_ref().map(|m| &m.content) else {
+            return false;
+        };
+        for block in blocks {
+            match block {
+                ContentBlock::ToolUse { id, name, input }
+                    if TASK_TOOL_NAMES.contains(&name.as_str()) =>
+                {
+                    let prompt = input.get("prompt").and_then(|p| p.as_str()).unwrap_or("");
+                    self.task_prompts.push((id.clone(), prompt.to_string()));
+                }
+                ContentBlock::ToolResult { tool_use_id, .. } => {
+                    if let Some(ToolUseResult::Agent { agent_id }) = &record.tool_use_result {
+                        self.agent_by_tool
+                            .insert(tool_use_id.clone(), agent_id.clone());
+                    }
+                }
+                _ => {}
+            }
+        }
+        false
+    }
+
+    /// Link every subagent thread of the session to its Task call and set
+    /// `subagent` on the matching `ToolUse` blocks of `messages`.
+    fn attach(self, session_path: &Path, messages: &mut [DisplayMessage]) {
+        if self.task_prompts.is_empty() {
+            return;
+        }
+
+        let mut threads = scan_subagent_files(session_path);
+        threads.extend(self.inline_threads);
+
+        let tool_by_agent: HashMap<&str, &str> = self
+            .agent_by_tool
+            .iter()
+            .map(|(tool, agent)| (agent.as_str(), tool.as_str()))
+            .collect();
+        let mut linked: HashMap<String, SubagentSummary> = HashMap::new();
+        let mut unmatched: Vec<(String, ThreadAccumulator)> = Vec::new();
+        for (agent_id, acc) in threads {
+            match tool_by_agent.get(agent_id.as_str()) {
+                Some(tool_id) if !linked.contains_key(*tool_id) => {
+                    linked.insert(tool_id.to_string(), acc.into_summary(agent_id));
+                }
+                _ => unmatched.push((agent_id, acc)),
+            }
+        }
+        // Fallback for builds that don't record agentId: pair each remaining
+        // thread with the first unclaimed Task call sent the same prompt.
+        for (agent_id, acc) in unmatched {
+            let Some(prompt) = acc.first_prompt.as_deref().map(str::trim) else {
+                continue;
+            };
+            let tool_id = self
+                .task_prompts
+                .iter()
+                .find(|(id, p)| !linked.contains_key(id) && p.trim() == prompt)
+                .map(|(id, _)| id.clone());
+            if let Some(tool_id) = tool_id {
+                linked.insert(tool_id, acc.into_summary(agent_id));
+            }
+        }
+
+        if linked.is_empty() {
+            return;
+        }
+        for message in messages.iter_mut() {
+            for block in message.content.iter_mut() {
+                if let DisplayContentBlock::ToolUse { id, subagent, .. } = block {
+                    if let Some(summary) = linked.get(id.as_str()) {
+                        *subagent = Some(summary.clone());
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Accumulate every `subagents/agent-<id>.jsonl` transcript of a session.
+fn scan_subagent_files(session_path: &Path) -> Vec<(String, ThreadAccumulator)> {
+    let Ok(entries) = fs::read_dir(subagents_dir(session_path)) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && p.extension().map(|e| e == "jsonl").unwrap_or(false))
+        .collect();
+    files.sort();
+
+    files
+        .into_iter()
+        .filter_map(|file| {
+            let stem = file.file_stem()?.to_str()?;
+            let mut agent_id = stem.strip_prefix("agent-").unwrap_or(stem).to_string();
+            let mut acc = ThreadAccumulator::default();
+            for_each_record(&file, |record| {
+                if let Some(id) = &record.agent_id {
+                    agent_id.clone_from(id);
+                }
+                acc.add(&record);
+            })
+            .ok()?;
+            Some((agent_id, acc))
+        })
+        .collect()
+}
+
+/// Load one subagent thread of a session: the `subagents/agent-<id>.jsonl`
+/// transcript, or for older builds the inline sidechain whose first record
+/// has uuid `agent_id`.
+pub fn parse_subagent_thread(session_path: &Path, agent_id: &str) -> Result<SubagentThread, String> {
+    if !is_valid_agent_id(agent_id) {
+        return Err(format!("Invalid agent id: {}", agent_id));
+    }
+
+    let mut acc = ThreadAccumulator::default();
+    let mut messages: Vec<DisplayMessage> = Vec::new();
+    let mut usage_seen = HashSet::new();
+    let mut push = |record: RawRecord, acc: &mut ThreadAccumulator| {
+        acc.add(&record);
+        if let Some(message) = display_message_from_record(record, &mut usage_seen) {
+            messages.push(message);
+        }
+    };
+
+    let file = subagents_dir(session_path).join(format!("agent-{}.jsonl", agent_id));
+    if file.is_file() {
+        for_each_record(&file, |record| push(record, &mut acc))?;
+    } else {
+        let mut chains = InlineChains::default();
+        for_each_record(session_path, |record| {
+            if record.is_sidechain == Some(true)
+                && chains.root_of(&record).as_deref() == Some(agent_id)
+            {
+                push(record, &mut acc);
+            }
+        })?;
+    }
+
+    if messages.is_empty() {
+        return Err(format!("Subagent thread not found: {}", agent_id));
+    }
+    Ok(SubagentThread {
+        agent_id: agent_id.to_string(),
+        messages,
+        usage: acc.usage,
+    })
+}
+
+// ── Conversation tree (uuid / parentUuid branches) ──
+//
+// Claude Code never rewrites a session file: a rewind, an edited prompt or a
+// retried reply appends new records whose `parentUuid` points back at the
+// message they continue from, leaving the old continuation in place. The
+// flat message list shows both one after the other; the tree below keeps
+// them apart.
+
+/// Just the linking fields, for records that are never displayed.
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RecordLink {
+    #[serde(rename = "type")]
+    record_type: String,
+    uuid: Option<String>,
+    parent_uuid: Option<String>,
+    logical_parent_uuid: Option<String>,
+    #[serde(default)]
+    is_sidechain: Option<bool>,
+}
+
+/// uuid DAG of one session file, reduced to displayed messages.
+#[derive(Default)]
+struct SessionGraph {
+    /// Raw parent of every non-sidechain record with a uuid.
+    parents: HashMap<String, Option<String>>,
+    /// Displayed messages by uuid, plus their file order.
+    messages: HashMap<String, DisplayMessage>,
+    order: Vec<String>,
+    /// Last user / assistant record written.
+    last_uuid: Option<String>,
+    links: SidechainLinks,
+    usage_seen: HashSet<String>,
+}
+
+impl SessionGraph {
+    fn load(path: &Path) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+        let reader = BufReader::new(file);
+        let mut graph = SessionGraph::default();
+
+        // Unlike `for_each_record`, progress records are kept: later
+        // records may be parented to them.
+        for line in reader.lines() {
+            let line = match line {
+                Ok(l) => l,
+                Err(_) => continue,
+            };
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.contains("\"type\":\"file-history-snapshot\"") {
+                continue;
+            }
+
+            if trimmed.contains("\"type\":\"user\"") || trimmed.contains("\"type\":\"assistant\"") {
+                if let Ok(record) = serde_json::from_str::<RawRecord>(trimmed) {
+                    graph.add_record(record);
+                    continue;
+                }
+            }
+            if let Ok(link) = serde_json::from_str::<RecordLink>(trimmed) {
+                graph.add_link(link);
+            }
+        }
+        Ok(graph)
+    }
+
+    fn add_link(&mut self, link: RecordLink) {
+        if link.is_sidechain == Some(true) {
+            return;
+        }
+        let Some(uuid) = link.uuid else {
+            return;
+        };
+        if link.record_type == "user" || link.record_type == "assistant" {
+            self.last_uuid = Some(uuid.clone());
+        }
+        // Resumed sessions may repeat records; the first copy wins.
+        self.parents
+            .entry(uuid)
+            .or_insert(link.parent_uuid.or(link.logical_parent_uuid));
+    }
+
+    fn add_record(&mut self, record: RawRecord) {
+        if self.links.observe(&record) {
+            return;
+        }
+        if record.record_type != "user" && record.record_type != "assistant" {
+            self.add_link(RecordLink {
+                record_type: record.record_type,
+                uuid: record.uuid,
+                parent_uuid: record.parent_uuid,
+                logical_parent_uuid: record.logical_parent_uuid,
+                is_sidechain: record.is_sidechain,
+            });
+            return;
+        }
+        let Some(uuid) = record.uuid.clone() else {
+            return;
+        };
+        let duplicate = self.parents.contains_key(&uuid);
+        self.add_link(RecordLink {
+            record_type: record.record_type.clone(),
+            uuid: Some(uuid.clone()),
+            parent_uuid: record.parent_uuid.clone(),
+            logical_parent_uuid: record.logical_parent_uuid.clone(),
+            is_sidechain: None,
+        });
+        if duplicate {
+            return;
+        }
+        if let Some(message) = display_message_from_record(record, &mut self.usage_seen) {
+            self.order.push(uuid.clone());
+            self.messages.insert(uuid, message);
+        }
+    }
+
+    /// Nearest displayed ancestor-or-self of `uuid`. Bounded by the record
+    /// count so a malformed (cyclic) file can't loop forever.
+    fn displayed_at_or_above(&self, uuid: &str) -> Option<String> {
+        let mut current = Some(uuid.to_string());
+        for _ in 0..=self.parents.len() {
+            let id = current?;
+            if self.messages.contains_key(&id) {
+                return Some(id);
+            }
+            current = self.parents.get(&id).cloned().flatten();
+        }
+        None
+    }
+
+    fn display_parent(&self, uuid: &str) -> Option<String> {
+        let parent = self.parents.get(uuid).cloned().flatten()?;
+        self.displayed_at_or_above(&parent)
+    }
+
+    fn active_leaf(&self) -> Option<String> {
+        self.last_uuid
+            .as_deref()
+            .and_then(|uuid| self.displayed_at_or_above(uuid))
+    }
+
+    /// Display parent and children of every displayed message.
+    fn edges(&self) -> (HashMap<String, Option<String>>, HashMap<String, Vec<String>>) {
+        let mut parent_of: HashMap<String, Option<String>> = HashMap::new();
+        let mut children: HashMap<String, Vec<String>> = HashMap::new();
+        for uuid in &self.order {
+            let parent = self.display_parent(uuid);
+            if let Some(parent) = &parent {
+                children.entry(parent.clone()).or_default().push(uuid.clone());
+            }
+            parent_of.insert(uuid.clone(), parent);
+        }
+        (parent_of, children)
+    }
+}
+
+/// Root-first path ending at `leaf`.
+fn path_to(leaf: &str, parent_of: &HashMap<String, Option<String>>) -> Vec<String> {
+    let mut path = vec![leaf.to_string()];
+    let mut seen: HashSet<String> = HashSet::from([leaf.to_string()]);
+    while let Some(Some(parent)) = parent_of.get(path.last().unwrap()) {
+        if !seen.insert(parent.clone()) {
+            break;
+        }
+        path.push(parent.clone());
+    }
+    path.reverse();
+    path
+}
+
+fn message_preview(message: &DisplayMessage) -> String {
+    let text = message
+        .content
+        .iter()
+        .find_map(|block| match block {
+            DisplayContentBlock::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .or_else(|| {
+            message.content.iter().find_map(|block| match block {
+                DisplayContentBlock::ToolUse { name, .. } => Some(name.as_str()),
+                DisplayContentBlock::ToolResult { content, .. } => Some(content.as_str()),
+                DisplayContentBlock::Thinking { thinking } => Some(thinking.as_str()),
+                _ => None,
+            })
+        })
+        .unwrap_or("");
+    truncate_string(text.trim(), 120)
+}
+
+/// Build the full uuid / parentUuid tree of a session and mark the branch
+/// the session currently continues from.
+pub fn parse_conversation_tree(path: &Path) -> Result<ConversationTree, String> {
+    let graph = SessionGraph::load(path)?;
+    let (parent_of, mut children) = graph.edges();
+    let active_leaf = graph.active_leaf();
+    let active: HashSet<String> = active_leaf
+        .as_deref()
+        .map(|leaf| path_to(leaf, &parent_of).into_iter().collect())
+        .unwrap_or_default();
+
+    let mut roots = Vec::new();
+    let mut leaf_count = 0;
+    let nodes = graph
+        .order
+        .iter()
+        .map(|uuid| {
+            let message = &graph.messages[uuid];
+            let parent_uuid = parent_of.get(uuid).cloned().flatten();
+            if parent_uuid.is_none() {
+                roots.push(uuid.clone());
+            }
+            let children = children.remove(uuid).unwrap_or_default();
+            if children.is_empty() {
+                leaf_count += 1;
+            }
+            TreeNode {
+                uuid: uuid.clone(),
+                parent_uuid,
+                children,
+                role: message.role.clone(),
+                timestamp: message.timestamp.clone(),
+                preview: message_preview(message),
+                active: active.contains(uuid),
+            }
+        })
+        .collect();
+
+    Ok(ConversationTree {
+        nodes,
+        roots,
+        active_leaf,
+        leaf_count,
+    })
+}
+
+/// Load one branch of a session as a linear message list, from the root to
+/// a leaf. `uuid` may be any message: below it the branch follows the active
+/// path where it can, otherwise the most recent child.
+pub fn parse_branch_messages(path: &Path, uuid: &str) -> Result<BranchMessages, String> {
+    let graph = SessionGraph::load(path)?;
+    if !graph.messages.contains_key(uuid) {
+        return Err(format!("Message not found: {}", uuid));
+    }
+    let (parent_of, children) = graph.edges();
+    let active_leaf = graph.active_leaf();
+    let active: HashSet<String> = active_leaf
+        .as_deref()
+        .map(|leaf| path_to(leaf, &parent_of).into_iter().collect())
+        .unwrap_or_default();
+
+    let mut leaf = uuid.to_string();
+    let mut seen: HashSet<String> = HashSet::from([leaf.clone()]);
+    while let Some(kids) = children.get(&leaf) {
+        let next = kids
+            .iter()
+            .find(|k| active.contains(*k))
+            .or_else(|| kids.last())
+            .cloned();
+        match next {
+            Some(next) if seen.insert(next.clone()) => leaf = next,
+            _ => break,
+        }
+    }
+
+    let SessionGraph {
+        mut messages,
+        links,
+        ..
+    } = graph;
+    let mut branch: Vec<DisplayMessage> = path_to(&leaf, &parent_of)
+        .iter()
+        .filter_map(|id| messages.remove(id))
+        .collect();
+    links.attach(path, &mut branch);
+
+    Ok(BranchMessages {
+        active: active_leaf.as_deref() == Some(leaf.as_str()),
+        leaf_uuid: leaf,
+        messages: branch,
+    })
+}
+
 // ── Single-pass session file scanner ──
 
 /// Result of a single-pass scan of a JSONL session file.
@@ -855,3 +1593,170 @@ pub fn fork_session_from_message(
         first_prompt,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_lines(path: &Path, lines: &[&str]) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, lines.join("\n")).unwrap();
+    }
+
+    fn subagent_of(messages: &[DisplayMessage], tool_id: &str) -> Option<SubagentSummary> {
+        messages.iter().flat_map(|m| &m.content).find_map(|b| match b {
+            DisplayContentBlock::ToolUse { id, subagent, .. } if id == tool_id => subagent.clone(),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn links_subagent_threads_to_task_calls() {
+        let dir = std::env::temp_dir().join(format!("asv-sidechain-{}", uuid::Uuid::new_v4()));
+        let session = dir.join("s1.jsonl");
+        write_lines(&session, &[
+            r#"{"type":"user","uuid":"u1","message":{"role":"user","content":"go"}}"#,
+            r#"{"type":"assistant","uuid":"a1","parentUuid":"u1","message":{"role":"assistant","content":[{"type":"tool_use","id":"t1","name":"Task","input":{"prompt":"find it"}},{"type":"tool_use","id":"t2","name":"Task","input":{"prompt":"old style"}}]}}"#,
+            r#"{"type":"user","uuid":"s1","isSidechain":true,"message":{"role":"user","content":"old style"}}"#,
+            r#"{"type":"assistant","uuid":"s2","parentUuid":"s1","isSidechain":true,"message":{"id":"m9","role":"assistant","model":"claude-sonnet-4-5","content":[{"type":"text","text":"done"}],"usage":{"input_tokens":10,"output_tokens":5}}}"#,
+            r#"{"type":"user","uuid":"r1","toolUseResult":{"agentId":"abc","status":"completed"},"message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"t1","content":"ok"}]}}"#,
+        ]);
+        write_lines(&dir.join("s1").join("subagents").join("agent-abc.jsonl"), &[
+            r#"{"type":"user","uuid":"x1","isSidechain":true,"agentId":"abc","message":{"role":"user","content":"something else"}}"#,
+            r#"{"type":"assistant","uuid":"x2","isSidechain":true,"agentId":"abc","message":{"id":"m1","role":"assistant","model":"claude-sonnet-4-5","content":[{"type":"thinking","thinking":"hm"}],"usage":{"input_tokens":100,"output_tokens":20}}}"#,
+            r#"{"type":"assistant","uuid":"x3","isSidechain":true,"agentId":"abc","message":{"id":"m1","role":"assistant","model":"claude-sonnet-4-5","content":[{"type":"text","text":"found"}],"usage":{"input_tokens":100,"output_tokens":20}}}"#,
+        ]);
+
+        let messages = parse_all_messages(&session).unwrap();
+        // Inline sidechain records stay out of the main list.
+        assert_eq!(messages.len(), 3);
+        let tail = parse_session_messages(&session, 0, 50, true).unwrap();
+        assert_eq!(tail.total, 3);
+
+        let file_thread = subagent_of(&messages, "t1").unwrap();
+        assert_eq!(file_thread.agent_id, "abc");
+        assert_eq!(file_thread.message_count, 3);
+        assert_eq!(file_thread.usage.total_tokens, 120);
+
+        let inline_thread = subagent_of(&messages, "t2").unwrap();
+        assert_eq!(inline_thread.agent_id, "s1");
+        assert_eq!(inline_thread.usage.total_tokens, 15);
+
+        let thread = parse_subagent_thread(&session, "s1").unwrap();
+        assert_eq!(thread.messages.len(), 2);
+        let abc = parse_subagent_thread(&session, "abc").unwrap();
+        assert_eq!(abc.messages.len(), 3);
+        // Both records of API message m1 carry its usage; only the first shows it.
+        assert_eq!(abc.messages[1].usage.map(|u| u.total_tokens), Some(120));
+        assert!(abc.messages[2].usage.is_none());
+        assert!(parse_subagent_thread(&session, "../x").is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn tail_window_attributes_usage_like_full_parse() {
+        let dir = std::env::temp_dir().join(format!("asv-usage-{}", uuid::Uuid::new_v4()));
+        let session = dir.join("s1.jsonl");
+        write_lines(&session, &[
+            r#"{"type":"user","uuid":"u1","message":{"role":"user","content":"go"}}"#,
+            r#"{"type":"assistant","uuid":"a1","message":{"id":"m1","role":"assistant","model":"claude-sonnet-4-5","content":[{"type":"thinking","thinking":"hm"}],"usage":{"input_tokens":100,"output_tokens":20}}}"#,
+            r#"{"type":"assistant","uuid":"a2","message":{"id":"m1","role":"assistant","model":"claude-sonnet-4-5","content":[{"type":"text","text":"done"}],"usage":{"input_tokens":100,"output_tokens":20}}}"#,
+            r#"{"type":"user","uuid":"u2","message":{"role":"user","content":"again"}}"#,
+            r#"{"type":"assistant","uuid":"a3","message":{"id":"m2","role":"assistant","model":"claude-sonnet-4-5","content":[{"type":"text","text":"ok"}],"usage":{"input_tokens":30,"output_tokens":5}}}"#,
+            r#"{"type":"user","uuid":"u3","message":{"role":"user","content":"thanks"}}"#,
+        ]);
+
+        // A one-message page warms a 4-message tail window: a2..u3, which
+        // starts on the second record of API message m1.
+        parse_tail_messages(&session, 0, 1).unwrap();
+        let (window, total) = crate::state::get_cached_range(&session, 2, 6)
+            .unwrap()
+            .unwrap();
+        assert_eq!(total, 6);
+
+        let full = parse_all_messages(&session).unwrap();
+        let usage = |m: &DisplayMessage| m.usage.map(|u| u.total_tokens);
+        // m1 is counted once, on its first record.
+        assert_eq!(usage(&full[1]), Some(120));
+        assert_eq!(usage(&full[2]), None);
+        assert_eq!(usage(&full[4]), Some(35));
+        assert_eq!(
+            window.iter().map(usage).collect::<Vec<_>>(),
+            full[2..].iter().map(usage).collect::<Vec<_>>()
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn builds_branch_tree_from_parent_uuids() {
+        let dir = std::env::temp_dir().join(format!("asv-tree-{}", uuid::Uuid::new_v4()));
+        let session = dir.join("s1.jsonl");
+        // u1 → a1 → u2 → a2, then a rewind edits u2 into u3 → a3 (via a progress record).
+        write_lines(&session, &[
+            r#"{"type":"user","uuid":"u1","parentUuid":null,"message":{"role":"user","content":"hi"}}"#,
+            r#"{"type":"assistant","uuid":"a1","parentUuid":"u1","message":{"role":"assistant","content":[{"type":"text","text":"hello"}]}}"#,
+            r#"{"type":"user","uuid":"u2","parentUuid":"a1","message":{"role":"user","content":"first try"}}"#,
+            r#"{"type":"assistant","uuid":"a2","parentUuid":"u2","message":{"role":"assistant","content":[{"type":"text","text":"one"}]}}"#,
+            r#"{"type":"system","uuid":"sys","parentUuid":"a1","content":"rewound"}"#,
+            r#"{"type":"user","uuid":"u3","parentUuid":"sys","message":{"role":"user","content":"second try"}}"#,
+            r#"{"type":"progress","uuid":"p1","parentUuid":"u3","data":{}}"#,
+            r#"{"type":"assistant","uuid":"a3","parentUuid":"p1","message":{"role":"assistant","content":[{"type":"text","text":"two"}]}}"#,
+        ]);
+
+        let tree = parse_conversation_tree(&session).unwrap();
+        assert_eq!(tree.nodes.len(), 6);
+        assert_eq!(tree.roots, vec!["u1"]);
+        assert_eq!(tree.leaf_count, 2);
+        assert_eq!(tree.active_leaf.as_deref(), Some("a3"));
+        let a1 = tree.nodes.iter().find(|n| n.uuid == "a1").unwrap();
+        assert_eq!(a1.children, vec!["u2", "u3"]);
+        let a3 = tree.nodes.iter().find(|n| n.uuid == "a3").unwrap();
+        assert_eq!(a3.parent_uuid.as_deref(), Some("u3"));
+        assert!(a3.active);
+        assert!(!tree.nodes.iter().find(|n| n.uuid == "u2").unwrap().active);
+
+        let old = parse_branch_messages(&session, "u2").unwrap();
+        assert_eq!(old.leaf_uuid, "a2");
+        assert!(!old.active);
+        let uuids: Vec<_> = old.messages.iter().filter_map(|m| m.uuid.as_deref()).collect();
+        assert_eq!(uuids, vec!["u1", "a1", "u2", "a2"]);
+
+        // From the fork point the active branch is followed.
+        let current = parse_branch_messages(&session, "a1").unwrap();
+        assert_eq!(current.leaf_uuid, "a3");
+        assert!(current.active);
+        assert!(parse_branch_messages(&session, "nope").is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+
+    #[test]
+    fn image_blocks_are_listed_and_decoded_on_demand() {
+        let dir = std::env::temp_dir().join(format!("asv-media-{}", uuid::Uuid::new_v4()));
+        let session = dir.join("s1.jsonl");
+        write_lines(&session, &[
+            r#"{"type":"user","uuid":"u1","message":{"role":"user","content":[{"type":"text","text":"look"},{"type":"image","source":{"type":"base64","media_type":"image/png","data":"aGVs\nbG8="}},{"type":"document","title":"notes","source":{"type":"text","media_type":"text/plain","data":"plain"}}]}}"#,
+        ]);
+
+        let messages = parse_all_messages(&session).unwrap();
+        let json = serde_json::to_value(&messages[0].content).unwrap();
+        assert_eq!(json[1]["type"], "image");
+        assert_eq!(json[1]["mediaType"], "image/png");
+        assert_eq!(json[1]["blockIndex"], 1);
+        assert!(json[1].get("data").is_none());
+        assert_eq!(json[2]["title"], "notes");
+
+        let image = read_message_media(&session, "u1", 1).unwrap();
+        assert_eq!(image.media_type, "image/png");
+        assert_eq!(image.data, b"hello");
+        assert_eq!(read_message_media(&session, "u1", 2).unwrap().data, b"plain");
+        assert!(read_message_media(&session, "u1", 0).is_err());
+        assert!(read_message_media(&session, "u2", 1).is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+}
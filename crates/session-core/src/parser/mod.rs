@@ -1,2 +1,3 @@
+pub mod file_history;
 pub mod jsonl;
 pub mod path_encoder;
@@ -3,37 +3,21 @@
 //! Every API entry that accepts a user-supplied session file path *must* run
 //! it through [`validate_session_file`] before reading or modifying the file.
 //! The validation rejects:
-//!   - non-existent or non-`.jsonl` paths
-//!   - paths outside the source's allowed root
-//!     (`~/.claude/projects/` or `~/.codex/sessions/`)
+//!   - non-existent paths, or files without the source's session extension
+//!     (`.jsonl` for Claude / Codex, `.json` for Gemini, `.md` for Aider)
+//!   - paths outside the source's allowed roots
+//!     (`~/.claude/projects/`, `~/.codex/sessions/`, configured Aider roots …)
 //!   - paths with the wrong layout (e.g. a Codex rollout file not under
 //!     `<year>/<month>/<day>/`)
 //!
-//! Both backends call into here so neither one can drift past the other.
+//! The root and layout rules come from each source's
+//! [`crate::provider::SessionProvider`]; both backends call into here so
+//! neither one can drift past the other.
 //! Without this, a path like `~/.ssh/id_rsa` could be passed in and acted on.
 
-use std::path::{Component, Path, PathBuf};
+use std::path::PathBuf;
 
-use crate::parser::path_encoder::get_projects_dir;
-use crate::provider::codex;
-
-/// One of the two supported session sources. Mirrors the string `"claude"` /
-/// `"codex"` carried over the wire.
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-pub enum SessionSourceKind {
-    Claude,
-    Codex,
-}
-
-impl SessionSourceKind {
-    pub fn parse(source: &str) -> Result<Self, String> {
-        match source {
-            "claude" => Ok(Self::Claude),
-            "codex" => Ok(Self::Codex),
-            _ => Err(format!("Unknown source: {}", source)),
-        }
-    }
-}
+use crate::provider;
 
 fn canonicalize_dir(path: PathBuf, label: &str) -> Result<PathBuf, String> {
     let canonical = path
@@ -45,60 +29,19 @@ fn canonicalize_dir(path: PathBuf, label: &str) -> Result<PathBuf, String> {
     Ok(canonical)
 }
 
-fn canonical_claude_root() -> Result<PathBuf, String> {
-    let path =
-        get_projects_dir().ok_or_else(|| "Could not find Claude projects directory".to_string())?;
-    canonicalize_dir(path, "Claude projects directory")
-}
-
-fn canonical_codex_root() -> Result<PathBuf, String> {
-    let path = codex::get_sessions_dir()
-        .ok_or_else(|| "Could not find Codex sessions directory".to_string())?;
-    canonicalize_dir(path, "Codex sessions directory")
-}
-
-fn validate_claude_layout(path: &Path, base: &Path) -> Result<(), String> {
-    let relative = path
-        .strip_prefix(base)
-        .map_err(|_| "Session file is outside the Claude projects directory".to_string())?;
-    if relative.components().count() != 2 {
-        return Err(
-            "Claude session file must live directly under a project directory".to_string(),
-        );
-    }
-    Ok(())
-}
-
-fn validate_codex_layout(path: &Path, base: &Path) -> Result<(), String> {
-    let relative = path
-        .strip_prefix(base)
-        .map_err(|_| "Session file is outside the Codex sessions directory".to_string())?;
-    let components: Vec<_> = relative.components().collect();
-    if components.len() != 4
-        || components.iter().any(|c| !matches!(c, Component::Normal(_)))
-    {
-        return Err(
-            "Codex session file must live under sessions/<year>/<month>/<day>/".to_string(),
-        );
-    }
-    let file_name = path
-        .file_name()
-        .and_then(|name| name.to_str())
-        .ok_or_else(|| "Invalid session file name".to_string())?;
-    if !file_name.starts_with("rollout-") {
-        return Err("Codex session file name must start with 'rollout-'".to_string());
-    }
-    Ok(())
-}
-
 /// Canonicalize and validate a user-supplied session file path. Returns the
 /// canonical path on success; returns an error if anything looks suspicious.
+///
+/// For sources that address sessions as `<file>#<selector>` the selector is
+/// split off, the file part validated, and the selector re-attached to the
+/// returned canonical path.
 pub fn validate_session_file(source: &str, file_path: &str) -> Result<PathBuf, String> {
     if file_path.trim().is_empty() {
         return Err("Session file path is required".to_string());
     }
-    let kind = SessionSourceKind::parse(source)?;
-    let requested = PathBuf::from(file_path);
+    let provider = provider::get(source)?;
+    let (file_part, selector) = provider.split_session_selector(file_path);
+    let requested = PathBuf::from(file_part);
     let canonical = requested
         .canonicalize()
         .map_err(|e| format!("Failed to resolve session file: {}", e))?;
@@ -106,20 +49,25 @@ pub fn validate_session_file(source: &str, file_path: &str) -> Result<PathBuf, S
     if !canonical.is_file() {
         return Err(format!("Session file not found: {}", file_path));
     }
-    if canonical.extension().and_then(|ext| ext.to_str()) != Some("jsonl") {
-        return Err("Session file must be a .jsonl file".to_string());
+    let extension = provider.session_file_extension();
+    if canonical.extension().and_then(|ext| ext.to_str()) != Some(extension) {
+        return Err(format!("Session file must be a .{} file", extension));
     }
 
-    match kind {
-        SessionSourceKind::Claude => {
-            let base = canonical_claude_root()?;
-            validate_claude_layout(&canonical, &base)?;
-        }
-        SessionSourceKind::Codex => {
-            let base = canonical_codex_root()?;
-            validate_codex_layout(&canonical, &base)?;
-        }
+    let label = format!("{} session directory", provider.display_name());
+    let roots = provider.session_roots();
+    if roots.is_empty() {
+        return Err(format!("Could not find {}", label));
     }
+    let base = roots
+        .into_iter()
+        .filter_map(|root| canonicalize_dir(root, &label).ok())
+        .find(|base| canonical.starts_with(base))
+        .ok_or_else(|| format!("Session file is outside the {}", label))?;
+    provider.validate_layout(&canonical, &base)?;
 
-    Ok(canonical)
+    Ok(match selector {
+        Some(selector) => PathBuf::from(format!("{}#{}", canonical.display(), selector)),
+        None => canonical,
+    })
 }
@@ -0,0 +1,245 @@
+//! 会话的 todo 列表和计划文档。
+//!
+//! 两者都以工具调用的形式记录在会话里：Claude 的 `TodoWrite` / `ExitPlanMode`，
+//! Codex 的 `update_plan`。todo 列表每次都整份重写，取最后一次调用即最终状态。
+//!
+//! Claude 另外把每个 agent 的最终 todo 状态存在
+//! `~/.claude/todos/<session>-agent-<agent>.json`（主 agent 的 agent id 就是
+//! session id），有内容时以文件为准，子代理的列表也只能从这里拿到。
+
+use std::fs;
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::models::message::{DisplayContentBlock, DisplayMessage};
+use crate::models::plan::{PlanDocument, SessionPlan, TodoItem, TodoList};
+
+const TODO_WRITE_TOOL: &str = "TodoWrite";
+const EXIT_PLAN_MODE_TOOL: &str = "ExitPlanMode";
+const CODEX_UPDATE_PLAN: &str = "update_plan";
+
+/// Items of a todo array; `text_key` is `content` (Claude) or `step` (Codex).
+fn todo_items(list: &Value, text_key: &str) -> Option<Vec<TodoItem>> {
+    let items = list
+        .as_array()?
+        .iter()
+        .filter_map(|item| {
+            let content = item.get(text_key)?.as_str()?.to_string();
+            Some(TodoItem {
+                content,
+                status: item
+                    .get("status")
+                    .and_then(|s| s.as_str())
+                    .unwrap_or("pending")
+                    .to_string(),
+                active_form: item
+                    .get("activeForm")
+                    .and_then(|s| s.as_str())
+                    .map(str::to_string),
+            })
+        })
+        .collect();
+    Some(items)
+}
+
+/// Todo lists and plans found in a session's tool calls.
+pub fn plan_from_messages(messages: &[DisplayMessage]) -> SessionPlan {
+    let mut todos: Option<TodoList> = None;
+    let mut plans: Vec<PlanDocument> = Vec::new();
+
+    for message in messages {
+        for block in &message.content {
+            match block {
+                DisplayContentBlock::ToolUse {
+                    id, name, input, ..
+                } => {
+                    if name != TODO_WRITE_TOOL && name != EXIT_PLAN_MODE_TOOL {
+                        continue;
+                    }
+                    let Ok(input) = serde_json::from_str::<Value>(input) else {
+                        continue;
+                    };
+                    if name == TODO_WRITE_TOOL {
+                        if let Some(items) =
+                            input.get("todos").and_then(|t| todo_items(t, "content"))
+                        {
+                            todos = Some(TodoList::new(None, items, message.timestamp.clone()));
+                        }
+                    } else if let Some(plan) = input.get("plan").and_then(|p| p.as_str()) {
+                        plans.push(PlanDocument {
+                            tool_use_id: id.clone(),
+                            message_uuid: message.uuid.clone(),
+                            timestamp: message.timestamp.clone(),
+                            plan: plan.to_string(),
+                            approved: None,
+                        });
+                    }
+                }
+                DisplayContentBlock::FunctionCall {
+                    name, arguments, ..
+                } if name == CODEX_UPDATE_PLAN => {
+                    let items = serde_json::from_str::<Value>(arguments)
+                        .ok()
+                        .and_then(|args| args.get("plan").and_then(|p| todo_items(p, "step")));
+                    if let Some(items) = items {
+                        todos = Some(TodoList::new(None, items, message.timestamp.clone()));
+                    }
+                }
+                DisplayContentBlock::ToolResult {
+                    tool_use_id,
+                    is_error,
+                    ..
+                } => {
+                    if let Some(plan) = plans.iter_mut().find(|p| p.tool_use_id == *tool_use_id) {
+                        plan.approved = Some(!is_error);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    SessionPlan {
+        todos,
+        agent_todos: Vec::new(),
+        plans,
+    }
+}
+
+/// Overlay Claude's `todos/<session>-agent-*.json` files on `plan`.
+pub fn merge_claude_todo_files(plan: &mut SessionPlan, todos_dir: &Path, session_id: &str) {
+    let Ok(entries) = fs::read_dir(todos_dir) else {
+        return;
+    };
+    let prefix = format!("{}-agent-", session_id);
+    let mut files: Vec<(String, std::path::PathBuf)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_str()?.to_string();
+            let agent_id = name
+                .strip_prefix(&prefix)?
+                .strip_suffix(".json")?
+                .to_string();
+            Some((agent_id, entry.path()))
+        })
+        .collect();
+    files.sort();
+
+    for (agent_id, path) in files {
+        let Some(items) = fs::read_to_string(&path)
+            .ok()
+            .and_then(|c| serde_json::from_str::<Value>(&c).ok())
+            .and_then(|v| todo_items(&v, "content"))
+        else {
+            continue;
+        };
+        // An emptied file says nothing about what was done; keep the
+        // transcript's last list instead.
+        if items.is_empty() {
+            continue;
+        }
+        let updated_at = fs::metadata(&path)
+            .and_then(|m| m.modified())
+            .ok()
+            .map(|t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339());
+        if agent_id == session_id {
+            plan.todos = Some(TodoList::new(None, items, updated_at));
+        } else {
+            plan.agent_todos
+                .push(TodoList::new(Some(agent_id), items, updated_at));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool_use(name: &str, id: &str, input: &str) -> DisplayMessage {
+        DisplayMessage {
+            uuid: Some(format!("m-{}", id)),
+            parent_uuid: None,
+            role: "assistant".to_string(),
+            timestamp: None,
+            model: None,
+            content: vec![DisplayContentBlock::ToolUse {
+                id: id.to_string(),
+                name: name.to_string(),
+                input: input.to_string(),
+                subagent: None,
+            }],
+            usage: None,
+        }
+    }
+
+    #[test]
+    fn keeps_last_todo_list_and_plan_outcomes() {
+        let messages = vec![
+            tool_use("ExitPlanMode", "p1", r#"{"plan":"1. do it"}"#),
+            DisplayMessage {
+                role: "tool".to_string(),
+                content: vec![DisplayContentBlock::ToolResult {
+                    tool_use_id: "p1".to_string(),
+                    content: "User has approved your plan.".to_string(),
+                    is_error: false,
+                }],
+                ..tool_use("x", "r1", "")
+            },
+            tool_use(
+                "TodoWrite",
+                "t1",
+                r#"{"todos":[{"content":"a","status":"pending","activeForm":"Doing a"}]}"#,
+            ),
+            tool_use(
+                "TodoWrite",
+                "t2",
+                r#"{"todos":[{"content":"a","status":"completed"},{"content":"b","status":"in_progress"}]}"#,
+            ),
+            tool_use("ExitPlanMode", "p2", r#"{"plan":"2. again"}"#),
+        ];
+        let plan = plan_from_messages(&messages);
+
+        let todos = plan.todos.unwrap();
+        assert_eq!(todos.items.len(), 2);
+        assert_eq!(todos.completed, 1);
+        assert_eq!(plan.plans.len(), 2);
+        assert_eq!(plan.plans[0].approved, Some(true));
+        assert_eq!(plan.plans[0].message_uuid.as_deref(), Some("m-p1"));
+        assert_eq!(plan.plans[1].approved, None);
+    }
+
+    #[test]
+    fn todo_files_override_transcript_and_add_subagents() {
+        let dir = std::env::temp_dir().join(format!("asv-todos-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("s1-agent-s1.json"),
+            r#"[{"content":"ship","status":"completed"}]"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("s1-agent-sub.json"),
+            r#"[{"content":"dig","status":"pending"}]"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("s2-agent-s2.json"),
+            r#"[{"content":"other","status":"pending"}]"#,
+        )
+        .unwrap();
+
+        let mut plan = plan_from_messages(&[tool_use(
+            "TodoWrite",
+            "t1",
+            r#"{"todos":[{"content":"ship","status":"pending"}]}"#,
+        )]);
+        merge_claude_todo_files(&mut plan, &dir, "s1");
+
+        assert_eq!(plan.todos.as_ref().map(|t| t.completed), Some(1));
+        assert_eq!(plan.agent_todos.len(), 1);
+        assert_eq!(plan.agent_todos[0].agent_id.as_deref(), Some("sub"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
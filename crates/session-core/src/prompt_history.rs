@@ -0,0 +1,400 @@
+//! Claude Code 的输入历史（`~/.claude/history.jsonl`）。
+//!
+//! 每行是用户敲过的一条 prompt：
+//!
+//! ```json
+//! {"display":"fix the bug [Pasted text #1 +12 lines]","pastedContents":{"1":{"id":1,"type":"text","content":"…"}},
+//!  "timestamp":1760000000000,"project":"/home/me/app","sessionId":"…"}
+//! ```
+//!
+//! 较早的 CLI 不写 `sessionId`，这时按项目目录 + 时间落在哪个会话的
+//! created..modified 区间来反查所属会话。每个数据根目录各有一份历史文件，
+//! 按 (mtime, len) 缓存解析结果。
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::SystemTime;
+
+use parking_lot::Mutex;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::data_roots::{resolve_roots, scoped_id, DEFAULT_ROOT};
+use crate::models::prompt_history::{
+    PastedContentRef, PromptHistoryEntry, PromptHistoryPage, PromptHistoryProject,
+};
+use crate::models::session::SessionIndexEntry;
+use crate::parser::path_encoder::{encode_project_path, get_claude_home};
+use crate::provider::claude;
+
+const PREVIEW_CHARS: usize = 200;
+
+/// Filter parameters for [`get_prompt_history`].
+#[derive(Debug, Default, Clone)]
+pub struct PromptHistoryFilter {
+    /// Whitespace-separated terms, all of which must appear (case-insensitive)
+    /// in the prompt or its pasted text.
+    pub query: Option<String>,
+    /// Exact project path.
+    pub project: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawHistoryLine {
+    display: String,
+    #[serde(default)]
+    pasted_contents: serde_json::Map<String, Value>,
+    timestamp: Option<i64>,
+    project: Option<String>,
+    session_id: Option<String>,
+}
+
+#[derive(Clone)]
+struct HistoryRecord {
+    entry: PromptHistoryEntry,
+    root: String,
+    timestamp_ms: Option<i64>,
+    /// Lowercased prompt + pasted text, for search.
+    haystack: String,
+}
+
+struct CachedHistory {
+    mtime: SystemTime,
+    len: u64,
+    records: Arc<Vec<HistoryRecord>>,
+}
+
+fn history_cache() -> &'static Mutex<HashMap<PathBuf, CachedHistory>> {
+    static CACHE: OnceLock<Mutex<HashMap<PathBuf, CachedHistory>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn pasted_refs(raw: &serde_json::Map<String, Value>, haystack: &mut String) -> Vec<PastedContentRef> {
+    let mut refs: Vec<PastedContentRef> = raw
+        .iter()
+        .map(|(key, item)| {
+            let id = item
+                .get("id")
+                .and_then(|v| v.as_u64())
+                .or_else(|| key.parse().ok())
+                .unwrap_or(0);
+            let kind = item
+                .get("type")
+                .and_then(|v| v.as_str())
+                .unwrap_or("text")
+                .to_string();
+            let content = item
+                .get("content")
+                .and_then(|v| v.as_str())
+                .filter(|_| kind == "text");
+            if let Some(content) = content {
+                haystack.push('\n');
+                haystack.push_str(&content.to_lowercase());
+            }
+            PastedContentRef {
+                id,
+                kind,
+                line_count: content.map(|c| c.lines().count()),
+                preview: content.map(|c| c.chars().take(PREVIEW_CHARS).collect()),
+            }
+        })
+        .collect();
+    refs.sort_by_key(|r| r.id);
+    refs
+}
+
+fn parse_history_file(path: &Path, root: &str) -> Vec<HistoryRecord> {
+    let Ok(file) = File::open(path) else {
+        return Vec::new();
+    };
+    let reader = BufReader::new(file);
+    let data_root = (root != DEFAULT_ROOT).then(|| root.to_string());
+
+    let mut records = Vec::new();
+    for line in reader.lines() {
+        let line = match line {
+            Ok(l) => l,
+            Err(_) => continue,
+        };
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let raw: RawHistoryLine = match serde_json::from_str(trimmed) {
+            Ok(r) => r,
+            Err(_) => continue,
+        };
+
+        let mut haystack = raw.display.to_lowercase();
+        let pasted = pasted_refs(&raw.pasted_contents, &mut haystack);
+        let timestamp = raw
+            .timestamp
+            .and_then(chrono::DateTime::from_timestamp_millis)
+            .map(|t| t.to_rfc3339());
+        records.push(HistoryRecord {
+            entry: PromptHistoryEntry {
+                display: raw.display,
+                timestamp,
+                project: raw.project.filter(|p| !p.is_empty()),
+                data_root: data_root.clone(),
+                pasted,
+                session_id: raw.session_id.filter(|s| !s.is_empty()),
+                project_id: None,
+                file_path: None,
+            },
+            root: root.to_string(),
+            timestamp_ms: raw.timestamp,
+            haystack,
+        });
+    }
+    records
+}
+
+/// Parsed records of one history file, re-read only when it changed.
+fn load_history_file(path: &Path, root: &str) -> Arc<Vec<HistoryRecord>> {
+    let Ok(meta) = fs::metadata(path) else {
+        return Arc::default();
+    };
+    let mtime = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+    let len = meta.len();
+
+    if let Some(cached) = history_cache().lock().get(path) {
+        if cached.mtime == mtime && cached.len == len {
+            return cached.records.clone();
+        }
+    }
+    let records = Arc::new(parse_history_file(path, root));
+    history_cache().lock().insert(
+        path.to_path_buf(),
+        CachedHistory {
+            mtime,
+            len,
+            records: records.clone(),
+        },
+    );
+    records
+}
+
+fn matches(record: &HistoryRecord, filter: &PromptHistoryFilter, terms: &[String]) -> bool {
+    if let Some(project) = &filter.project {
+        if record.entry.project.as_deref() != Some(project.as_str()) {
+            return false;
+        }
+    }
+    terms.iter().all(|t| record.haystack.contains(t.as_str()))
+}
+
+fn rfc3339_ms(ts: Option<&str>) -> Option<i64> {
+    chrono::DateTime::parse_from_rfc3339(ts?)
+        .ok()
+        .map(|t| t.timestamp_millis())
+}
+
+/// Resolves history entries to session files, memoizing directory scans and
+/// session lists across the entries of one page.
+struct SessionLinker {
+    projects_dirs: HashMap<String, PathBuf>,
+    /// Per root: session id → (project id, file).
+    by_session_id: HashMap<String, HashMap<String, (String, PathBuf)>>,
+    sessions_by_project: HashMap<String, Vec<SessionIndexEntry>>,
+}
+
+impl SessionLinker {
+    fn new() -> Self {
+        SessionLinker {
+            projects_dirs: claude::projects_dirs().into_iter().collect(),
+            by_session_id: HashMap::new(),
+            sessions_by_project: HashMap::new(),
+        }
+    }
+
+    fn session_index(&mut self, root: &str) -> &HashMap<String, (String, PathBuf)> {
+        let projects_dir = self.projects_dirs.get(root).cloned();
+        self.by_session_id.entry(root.to_string()).or_insert_with(|| {
+            let mut index = HashMap::new();
+            let Some(dirs) = projects_dir.and_then(|d| fs::read_dir(d).ok()) else {
+                return index;
+            };
+            for dir in dirs.flatten() {
+                let Some(encoded) = dir.file_name().to_str().map(str::to_string) else {
+                    continue;
+                };
+                let Ok(files) = fs::read_dir(dir.path()) else {
+                    continue;
+                };
+                for file in files.flatten() {
+                    let path = file.path();
+                    if path.extension().map(|e| e == "jsonl").unwrap_or(false) {
+                        if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                            index.insert(stem.to_string(), (scoped_id(root, &encoded), path.clone()));
+                        }
+                    }
+                }
+            }
+            index
+        })
+    }
+
+    /// `(project id, session id, file)` of the session an entry was typed in.
+    fn link(&mut self, record: &HistoryRecord) -> Option<(String, String, PathBuf)> {
+        let projects_dir = self.projects_dirs.get(&record.root)?.clone();
+        let encoded = record.entry.project.as_deref().map(encode_project_path);
+
+        if let Some(session_id) = &record.entry.session_id {
+            if let Some(encoded) = &encoded {
+                let path = projects_dir.join(encoded).join(format!("{}.jsonl", session_id));
+                if path.is_file() {
+                    return Some((scoped_id(&record.root, encoded), session_id.clone(), path));
+                }
+            }
+            let (project_id, path) = self.session_index(&record.root).get(session_id)?.clone();
+            return Some((project_id, session_id.clone(), path));
+        }
+
+        // No session id: the session of that project whose time span covers
+        // the prompt; the latest one wins when several overlap.
+        let encoded = encoded?;
+        let ts = record.timestamp_ms?;
+        if !projects_dir.join(&encoded).is_dir() {
+            return None;
+        }
+        let project_id = scoped_id(&record.root, &encoded);
+        let sessions = self
+            .sessions_by_project
+            .entry(project_id.clone())
+            .or_insert_with(|| claude::get_sessions(&project_id).unwrap_or_default());
+        sessions
+            .iter()
+            .filter_map(|s| {
+                let created = rfc3339_ms(s.created.as_deref())?;
+                let modified = rfc3339_ms(s.modified.as_deref()).unwrap_or(created);
+                (created <= ts && ts <= modified).then_some((created, s))
+            })
+            .max_by_key(|(created, _)| *created)
+            .map(|(_, s)| (project_id.clone(), s.session_id.clone(), PathBuf::from(&s.file_path)))
+    }
+}
+
+/// One page of the prompt history across every Claude data root, newest
+/// first, with each prompt linked to its session where possible.
+pub fn get_prompt_history(
+    filter: PromptHistoryFilter,
+    page: usize,
+    page_size: usize,
+) -> Result<PromptHistoryPage, String> {
+    let files: Vec<Arc<Vec<HistoryRecord>>> = resolve_roots("claude", get_claude_home())
+        .into_iter()
+        .map(|root| load_history_file(&root.path.join("history.jsonl"), &root.name))
+        .collect();
+
+    let mut project_counts: HashMap<&str, usize> = HashMap::new();
+    for record in files.iter().flat_map(|f| f.iter()) {
+        if let Some(project) = record.entry.project.as_deref() {
+            *project_counts.entry(project).or_insert(0) += 1;
+        }
+    }
+    let mut projects: Vec<PromptHistoryProject> = project_counts
+        .into_iter()
+        .map(|(project, count)| PromptHistoryProject {
+            project: project.to_string(),
+            count,
+        })
+        .collect();
+    projects.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.project.cmp(&b.project)));
+
+    let terms: Vec<String> = filter
+        .query
+        .as_deref()
+        .unwrap_or("")
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect();
+    let mut matched: Vec<&HistoryRecord> = files
+        .iter()
+        .flat_map(|f| f.iter())
+        .filter(|r| matches(r, &filter, &terms))
+        .collect();
+    // Each file is chronological; merge roots by timestamp, newest first.
+    matched.reverse();
+    matched.sort_by_key(|r| std::cmp::Reverse(r.timestamp_ms));
+
+    let total = matched.len();
+    let page_size = page_size.max(1);
+    let start = (page * page_size).min(total);
+    let end = (start + page_size).min(total);
+
+    let mut linker = SessionLinker::new();
+    let entries = matched[start..end]
+        .iter()
+        .map(|record| {
+            let mut entry = record.entry.clone();
+            if let Some((project_id, session_id, path)) = linker.link(record) {
+                entry.project_id = Some(project_id);
+                entry.session_id = Some(session_id);
+                entry.file_path = Some(path.to_string_lossy().into_owned());
+            }
+            entry
+        })
+        .collect();
+
+    Ok(PromptHistoryPage {
+        entries,
+        total,
+        page,
+        page_size,
+        has_more: end < total,
+        projects,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_prompts_pastes_and_filters() {
+        let dir = std::env::temp_dir().join(format!("asv-history-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("history.jsonl");
+        fs::write(
+            &path,
+            [
+                r#"{"display":"fix login","pastedContents":{},"timestamp":1760000000000,"project":"/home/me/app"}"#,
+                r#"{"display":"look [Pasted text #2 +2 lines] [Image #1]","pastedContents":{"2":{"id":2,"type":"text","content":"Stack TRACE\nline two"},"1":{"id":1,"type":"image"}},"timestamp":1760000060000,"project":"/home/me/api","sessionId":"s-2"}"#,
+                "not json",
+            ]
+            .join("\n"),
+        )
+        .unwrap();
+
+        let records = parse_history_file(&path, "ci");
+        assert_eq!(records.len(), 2);
+        let second = &records[1].entry;
+        assert_eq!(second.data_root.as_deref(), Some("ci"));
+        assert_eq!(second.session_id.as_deref(), Some("s-2"));
+        assert_eq!(second.timestamp.as_deref(), Some("2025-10-09T08:54:20+00:00"));
+        let kinds: Vec<&str> = second.pasted.iter().map(|p| p.kind.as_str()).collect();
+        assert_eq!(kinds, ["image", "text"]);
+        assert_eq!(second.pasted[1].line_count, Some(2));
+
+        let by_paste = PromptHistoryFilter {
+            query: Some("stack trace".to_string()),
+            project: None,
+        };
+        let terms = vec!["stack".to_string(), "trace".to_string()];
+        assert!(!matches(&records[0], &by_paste, &terms));
+        assert!(matches(&records[1], &by_paste, &terms));
+        let by_project = PromptHistoryFilter {
+            query: None,
+            project: Some("/home/me/app".to_string()),
+        };
+        assert!(matches(&records[0], &by_project, &[]));
+        assert!(!matches(&records[1], &by_project, &[]));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
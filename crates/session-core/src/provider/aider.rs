@@ -0,0 +1,1074 @@
+//! Aider 数据源。
+//!
+//! Aider 没有集中的数据目录，而是在每个仓库根目录下写两个文件：
+//!   - `.aider.chat.history.md`：完整对话的 Markdown，每次启动追加一个
+//!     `# aider chat started at <本地时间>` 标题，这里按标题切成多个会话；
+//!     `#### ` 开头的行是用户输入，`> ` 开头的行是 Aider 自身的输出
+//!     （启动信息、应用编辑、提交、token 统计），其余是模型回复
+//!   - `.aider.input.history`：用户输入日志，`# <本地时间>` 后跟 `+` 开头的输入行，
+//!     只用来给用户消息补时间戳
+//!
+//! 要扫描哪些目录由 [`crate::data_roots`] 配置。项目 id 是带根目录名前缀的
+//! 仓库绝对路径（`@<根目录名>:<路径>`），元数据只按路径存，同一个仓库换个根目录
+//! 配置也不会丢别名。
+//! 一个历史文件里有多个会话，会话路径用 `<历史文件>#<序号>` 表示（序号从 1 开始，
+//! 文件只追加所以序号稳定），见 [`SessionProvider::split_session_selector`]。
+//! 别名 / 标签元数据放在应用目录下，不往用户仓库里写文件。
+
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use rayon::prelude::*;
+
+use crate::app_dir::get_app_dir;
+use crate::data_roots::{scoped_id, split_scoped_id};
+use crate::models::message::{
+    DisplayContentBlock, DisplayMessage, PaginatedMessages, RangeMessages,
+};
+use crate::models::pricing;
+use crate::models::project::ProjectEntry;
+use crate::models::session::{SessionIndexEntry, SessionStatus};
+use crate::models::stats::{RequestRecord, TokenUsageSummary};
+use crate::parser::path_encoder::{encode_project_path, short_name_from_path};
+
+use super::claude::{DeleteLevel, DeleteResult};
+use super::{paginate_all, slice_range, SearchTarget, SessionProvider};
+
+const CHAT_HISTORY_FILE: &str = ".aider.chat.history.md";
+const INPUT_HISTORY_FILE: &str = ".aider.input.history";
+const SESSION_HEADER: &str = "# aider chat started at ";
+
+/// 从配置的根目录往下找历史文件的最大深度。
+const MAX_SCAN_DEPTH: usize = 6;
+/// 不会是仓库根目录、又可能非常大的目录。隐藏目录一律跳过。
+const SKIP_DIRS: &[&str] = &[
+    "node_modules",
+    "target",
+    "vendor",
+    "dist",
+    "build",
+    "__pycache__",
+];
+
+const MAX_TEXT_BLOCK_SIZE: usize = 20_000;
+const MAX_OUTPUT_BLOCK_SIZE: usize = 30_000;
+
+// ── Directory scanning ──
+
+/// 配置的根目录（名称, 规范化路径），不存在的跳过。
+fn canonical_roots() -> Vec<(String, PathBuf)> {
+    crate::data_roots::resolve_roots("aider", None)
+        .into_iter()
+        .filter_map(|r| Some((r.name, r.path.canonicalize().ok()?)))
+        .filter(|(_, path)| path.is_dir())
+        .collect()
+}
+
+fn find_history_dirs(dir: &Path, depth: usize, out: &mut Vec<PathBuf>) {
+    if dir.join(CHAT_HISTORY_FILE).is_file() {
+        out.push(dir.to_path_buf());
+    }
+    if depth >= MAX_SCAN_DEPTH {
+        return;
+    }
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        // 不跟随符号链接，避免环路和重复项目。
+        if !file_type.is_dir() {
+            continue;
+        }
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with('.') || SKIP_DIRS.contains(&name.as_ref()) {
+            continue;
+        }
+        find_history_dirs(&entry.path(), depth + 1, out);
+    }
+}
+
+/// 所有配置根目录下含有 `.aider.chat.history.md` 的目录（已规范化），带上
+/// 项目 id。根目录互相嵌套时，仓库只归到配置里靠前的那个。
+fn project_dirs() -> Vec<(String, PathBuf)> {
+    let mut dirs: Vec<(String, PathBuf)> = canonical_roots()
+        .par_iter()
+        .flat_map_iter(|(name, root)| {
+            let mut out = Vec::new();
+            find_history_dirs(root, 0, &mut out);
+            out.into_iter()
+                .map(|dir| (scoped_id(name, &dir.to_string_lossy()), dir))
+        })
+        .collect();
+    // The sort is stable, so the first root's entry survives the dedup.
+    dirs.sort_by(|a, b| a.1.cmp(&b.1));
+    dirs.dedup_by(|a, b| a.1 == b.1);
+    dirs
+}
+
+/// Resolve a `@root:`-scoped project id (the repository path) to its
+/// canonical directory. The directory must sit under that Aider root and
+/// contain a chat history file.
+pub fn resolve_project_dir(project_id: &str) -> Result<PathBuf, String> {
+    let (root, path) = split_scoped_id(project_id);
+    let requested = Path::new(path);
+    if !requested.is_absolute() {
+        return Err(format!("Invalid project id: {}", project_id));
+    }
+    let base = canonical_roots()
+        .into_iter()
+        .find(|(name, _)| name == root)
+        .map(|(_, dir)| dir)
+        .ok_or_else(|| format!("Unknown Aider data root: {}", root))?;
+    let canonical = requested
+        .canonicalize()
+        .map_err(|_| format!("Project directory not found: {}", project_id))?;
+    if !canonical.starts_with(&base) {
+        return Err(format!(
+            "Project is outside the configured Aider roots: {}",
+            project_id
+        ));
+    }
+    if !canonical.join(CHAT_HISTORY_FILE).is_file() {
+        return Err(format!("No Aider chat history in project: {}", project_id));
+    }
+    Ok(canonical)
+}
+
+fn systemtime_to_rfc3339(t: std::time::SystemTime) -> String {
+    let d = t.duration_since(std::time::UNIX_EPOCH).unwrap_or_default();
+    chrono::DateTime::from_timestamp(d.as_secs() as i64, d.subsec_nanos())
+        .map(|dt| dt.to_rfc3339())
+        .unwrap_or_default()
+}
+
+fn file_modified(path: &Path) -> Option<String> {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .map(systemtime_to_rfc3339)
+}
+
+/// Aider 写的都是不带时区的本地时间。
+fn parse_local_time(raw: &str) -> Option<DateTime<Local>> {
+    let raw = raw.trim();
+    let naive = NaiveDateTime::parse_from_str(raw, "%Y-%m-%d %H:%M:%S%.f")
+        .or_else(|_| NaiveDateTime::parse_from_str(raw, "%Y-%m-%d %H:%M:%S"))
+        .ok()?;
+    Local.from_local_datetime(&naive).earliest()
+}
+
+// ── Session paths ──
+
+fn session_id_for(index: usize) -> String {
+    format!("chat-{}", index)
+}
+
+fn session_file_path(history: &Path, index: usize) -> PathBuf {
+    PathBuf::from(format!("{}#{}", history.display(), index))
+}
+
+fn split_selector(file_path: &str) -> (&str, Option<&str>) {
+    match file_path.rsplit_once('#') {
+        Some((file, selector))
+            if file.ends_with(CHAT_HISTORY_FILE)
+                && !selector.is_empty()
+                && selector.bytes().all(|b| b.is_ascii_digit()) =>
+        {
+            (file, Some(selector))
+        }
+        _ => (file_path, None),
+    }
+}
+
+/// 会话路径 → (历史文件, 会话序号)。没有序号时表示整个文件。
+fn split_session_path(path: &Path) -> (PathBuf, Option<usize>) {
+    let raw = path.to_string_lossy();
+    let (file, selector) = split_selector(&raw);
+    (PathBuf::from(file), selector.and_then(|s| s.parse().ok()))
+}
+
+// ── Parsing ──
+
+struct RawSession<'a> {
+    index: usize,
+    started_at: Option<DateTime<Local>>,
+    body: &'a str,
+}
+
+/// 按 `# aider chat started at` 标题切分。标题之前若有非空内容
+/// （被手工编辑过的文件），单独算作第一个会话。
+fn split_sessions(content: &str) -> Vec<RawSession<'_>> {
+    // (body start, body end, started at, has header)
+    let mut bounds: Vec<(usize, usize, Option<DateTime<Local>>, bool)> = Vec::new();
+    let mut current = (0usize, None, false);
+    let mut offset = 0usize;
+
+    for line in content.split_inclusive('\n') {
+        if let Some(ts) = line.strip_prefix(SESSION_HEADER) {
+            bounds.push((current.0, offset, current.1, current.2));
+            current = (offset + line.len(), parse_local_time(ts), true);
+        }
+        offset += line.len();
+    }
+    bounds.push((current.0, content.len(), current.1, current.2));
+
+    let mut sessions = Vec::new();
+    for (start, end, started_at, has_header) in bounds {
+        let body = &content[start..end];
+        if !has_header && body.trim().is_empty() {
+            continue;
+        }
+        sessions.push(RawSession {
+            index: sessions.len() + 1,
+            started_at,
+            body,
+        });
+    }
+    sessions
+}
+
+/// `.aider.input.history` 的一条输入：时间 + 去掉 `+` 前缀后的文本。
+struct InputEntry {
+    at: DateTime<Local>,
+    text: String,
+}
+
+fn parse_input_history(content: &str) -> Vec<InputEntry> {
+    let mut entries: Vec<InputEntry> = Vec::new();
+    let mut current: Option<(DateTime<Local>, Vec<&str>)> = None;
+
+    let mut flush = |current: &mut Option<(DateTime<Local>, Vec<&str>)>| {
+        if let Some((at, lines)) = current.take() {
+            entries.push(InputEntry {
+                at,
+                text: lines.join("\n").trim().to_string(),
+            });
+        }
+    };
+
+    for line in content.lines() {
+        if let Some(ts) = line.strip_prefix("# ") {
+            flush(&mut current);
+            current = parse_local_time(ts).map(|at| (at, Vec::new()));
+        } else if let (Some((_, lines)), Some(text)) = (current.as_mut(), line.strip_prefix('+')) {
+            lines.push(text);
+        }
+    }
+    flush(&mut current);
+    entries.sort_by_key(|e| e.at);
+    entries
+}
+
+/// Aider 自身输出里的一行 token 统计，例如
+/// `Tokens: 12k sent, 1.1k cache write, 3.4k cache hit, 512 received. Cost: $0.02 message, $0.10 session.`
+#[derive(Debug, Default, PartialEq)]
+struct TokenLine {
+    sent: u64,
+    received: u64,
+    cache_write: u64,
+    cache_hit: u64,
+    cost: Option<f64>,
+}
+
+/// `1,234` / `1.2k` / `12k` / `1.5M` → token 数。
+fn parse_token_count(raw: &str) -> Option<u64> {
+    let raw = raw.trim().replace(',', "");
+    let (number, scale) = if let Some(n) = raw.strip_suffix(['k', 'K']) {
+        (n, 1_000.0)
+    } else if let Some(n) = raw.strip_suffix(['m', 'M']) {
+        (n, 1_000_000.0)
+    } else {
+        (raw.as_str(), 1.0)
+    };
+    number
+        .parse::<f64>()
+        .ok()
+        .map(|n| (n * scale).round() as u64)
+}
+
+fn parse_token_line(line: &str) -> Option<TokenLine> {
+    let rest = line.trim().strip_prefix("Tokens:")?;
+    let (counts, cost) = match rest.split_once(". Cost:") {
+        Some((counts, cost)) => (counts, Some(cost)),
+        None => (rest.trim_end_matches('.'), None),
+    };
+
+    let mut parsed = TokenLine::default();
+    for part in counts.split(", ") {
+        let part = part.trim();
+        let Some((number, label)) = part.split_once(' ') else {
+            continue;
+        };
+        let Some(n) = parse_token_count(number) else {
+            continue;
+        };
+        match label.trim() {
+            "sent" => parsed.sent = n,
+            "received" => parsed.received = n,
+            "cache write" => parsed.cache_write = n,
+            "cache hit" => parsed.cache_hit = n,
+            _ => {}
+        }
+    }
+    parsed.cost = cost.and_then(|c| {
+        let c = c.trim().strip_prefix('$')?;
+        c.split_whitespace().next()?.parse::<f64>().ok()
+    });
+    Some(parsed)
+}
+
+/// 启动信息里的模型名：`Model: X with diff edit format`、
+/// `Models: X with …, weak model Y`、`Main model: X with …`。
+fn parse_model_line(line: &str) -> Option<String> {
+    let line = line.trim();
+    let rest = line
+        .strip_prefix("Main model:")
+        .or_else(|| line.strip_prefix("Models:"))
+        .or_else(|| line.strip_prefix("Model:"))?;
+    rest.split_whitespace()
+        .next()
+        .map(|m| m.trim_end_matches(',').to_string())
+}
+
+struct UsageEntry {
+    message_uuid: Option<String>,
+    timestamp: Option<String>,
+    model: String,
+    tokens: TokenLine,
+}
+
+#[derive(Default)]
+struct ParsedSession {
+    messages: Vec<DisplayMessage>,
+    usage: Vec<UsageEntry>,
+    last_timestamp: Option<String>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum BlockKind {
+    User,
+    Assistant,
+    Output,
+}
+
+fn truncate_string(s: &str, max_len: usize) -> String {
+    if s.len() <= max_len {
+        return s.to_string();
+    }
+    let mut end = max_len;
+    while end > 0 && !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}...", &s[..end])
+}
+
+/// 把一段会话正文按行分类成 user / assistant / Aider 输出三种块。
+fn classify_blocks(body: &str) -> Vec<(BlockKind, String)> {
+    let mut blocks: Vec<(BlockKind, Vec<&str>)> = Vec::new();
+    let mut in_fence = false;
+
+    for raw in body.lines() {
+        // Aider 在 `>` / `####` 行末追加两个空格做 Markdown 换行。
+        let line = raw.trim_end();
+        let current = blocks.last().map(|(kind, _)| *kind);
+
+        let (kind, text) = if in_fence && current == Some(BlockKind::Assistant) {
+            (BlockKind::Assistant, raw)
+        } else if let Some(text) = line.strip_prefix("#### ") {
+            (BlockKind::User, text)
+        } else if line == "####" {
+            (BlockKind::User, "")
+        } else if let Some(text) = line.strip_prefix("> ") {
+            (BlockKind::Output, text)
+        } else if line == ">" {
+            (BlockKind::Output, "")
+        } else if line.is_empty() {
+            // 空行归属当前块，块之间的空行最后会被 trim 掉。
+            if let Some((_, lines)) = blocks.last_mut() {
+                lines.push("");
+            }
+            continue;
+        } else {
+            (BlockKind::Assistant, raw)
+        };
+
+        if kind == BlockKind::Assistant && line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+        }
+        match blocks.last_mut() {
+            Some((last, lines)) if *last == kind => lines.push(text),
+            _ => blocks.push((kind, vec![text])),
+        }
+    }
+
+    blocks
+        .into_iter()
+        .map(|(kind, lines)| (kind, lines.join("\n").trim().to_string()))
+        .filter(|(_, text)| !text.is_empty())
+        .collect()
+}
+
+/// 在输入日志里按顺序找与用户输入相同的一条，返回它的时间。
+/// `cursor` 只前进不回退，避免相同文本的输入被重复匹配。
+fn match_input_time(inputs: &[InputEntry], cursor: &mut usize, text: &str) -> Option<String> {
+    let text = text.trim();
+    let found = inputs[*cursor..]
+        .iter()
+        .position(|entry| entry.text == text)?;
+    let entry = &inputs[*cursor + found];
+    *cursor += found + 1;
+    Some(entry.at.to_rfc3339())
+}
+
+fn parse_session(raw: &RawSession<'_>, inputs: &[InputEntry]) -> ParsedSession {
+    let mut parsed = ParsedSession::default();
+    let started = raw.started_at.map(|t| t.to_rfc3339());
+    let mut cursor = raw
+        .started_at
+        .map(|start| inputs.partition_point(|e| e.at < start))
+        .unwrap_or(inputs.len());
+
+    let mut model: Option<String> = None;
+    let mut timestamp = started.clone();
+    let mut last_assistant: Option<String> = None;
+    let mut prev_uuid: Option<String> = None;
+
+    for (i, (kind, text)) in classify_blocks(raw.body).into_iter().enumerate() {
+        let uuid = Some(format!("{}-{}", raw.index, i));
+        let message = match kind {
+            BlockKind::User => {
+                if let Some(at) = match_input_time(inputs, &mut cursor, &text) {
+                    timestamp = Some(at);
+                }
+                DisplayMessage {
+                    uuid: uuid.clone(),
+                    parent_uuid: prev_uuid.clone(),
+                    role: "user".to_string(),
+                    timestamp: timestamp.clone(),
+                    model: None,
+                    content: vec![DisplayContentBlock::Text {
+                        text: truncate_string(&text, MAX_TEXT_BLOCK_SIZE),
+                    }],
+                    usage: None,
+                }
+            }
+            BlockKind::Assistant => {
+                last_assistant = uuid.clone();
+                DisplayMessage {
+                    uuid: uuid.clone(),
+                    parent_uuid: prev_uuid.clone(),
+                    role: "assistant".to_string(),
+                    timestamp: timestamp.clone(),
+                    model: model.clone(),
+                    content: vec![DisplayContentBlock::Text {
+                        text: truncate_string(&text, MAX_TEXT_BLOCK_SIZE),
+                    }],
+                    usage: None,
+                }
+            }
+            BlockKind::Output => {
+                for line in text.lines() {
+                    if let Some(m) = parse_model_line(line) {
+                        model = Some(m);
+                    } else if let Some(tokens) = parse_token_line(line) {
+                        parsed.usage.push(UsageEntry {
+                            message_uuid: last_assistant.clone(),
+                            timestamp: timestamp.clone(),
+                            model: model.clone().unwrap_or_else(|| "unknown".to_string()),
+                            tokens,
+                        });
+                    }
+                }
+                // Aider 的输出（应用编辑、提交、报错）按工具输出展示。
+                DisplayMessage {
+                    uuid: uuid.clone(),
+                    parent_uuid: prev_uuid.clone(),
+                    role: "tool".to_string(),
+                    timestamp: timestamp.clone(),
+                    model: None,
+                    content: vec![DisplayContentBlock::ToolResult {
+                        tool_use_id: String::new(),
+                        content: truncate_string(&text, MAX_OUTPUT_BLOCK_SIZE),
+                        is_error: false,
+                    }],
+                    usage: None,
+                }
+            }
+        };
+        parsed.messages.push(message);
+        prev_uuid = uuid;
+    }
+
+    parsed.last_timestamp = timestamp;
+    parsed
+}
+
+/// 读取历史文件并解析出全部会话；输入日志缺失时用户消息只是没有时间戳。
+fn parse_history_file(
+    history: &Path,
+) -> Result<Vec<(usize, Option<String>, ParsedSession)>, String> {
+    let content = fs::read_to_string(history).map_err(|e| format!("Failed to read file: {}", e))?;
+    let inputs = history
+        .parent()
+        .and_then(|dir| fs::read_to_string(dir.join(INPUT_HISTORY_FILE)).ok())
+        .map(|c| parse_input_history(&c))
+        .unwrap_or_default();
+
+    Ok(split_sessions(&content)
+        .iter()
+        .map(|raw| {
+            (
+                raw.index,
+                raw.started_at.map(|t| t.to_rfc3339()),
+                parse_session(raw, &inputs),
+            )
+        })
+        .collect())
+}
+
+fn load_session(path: &Path) -> Result<ParsedSession, String> {
+    let (history, index) = split_session_path(path);
+    let sessions = parse_history_file(&history)?;
+    match index {
+        Some(index) => sessions
+            .into_iter()
+            .find(|(i, _, _)| *i == index)
+            .map(|(_, _, parsed)| parsed)
+            .ok_or_else(|| format!("Aider session not found: {}", path.display())),
+        // 不带序号时把整个文件当成一个会话。
+        None => Ok(sessions
+            .into_iter()
+            .fold(ParsedSession::default(), |mut acc, (_, _, s)| {
+                acc.messages.extend(s.messages);
+                acc.usage.extend(s.usage);
+                acc.last_timestamp = s.last_timestamp.or(acc.last_timestamp);
+                acc
+            })),
+    }
+}
+
+// ── Projects / sessions ──
+
+fn first_prompt(messages: &[DisplayMessage]) -> Option<String> {
+    messages.iter().filter(|m| m.role == "user").find_map(|m| {
+        m.content.iter().find_map(|b| match b {
+            DisplayContentBlock::Text { text } if !text.trim().is_empty() => {
+                Some(truncate_string(text.trim(), 200))
+            }
+            _ => None,
+        })
+    })
+}
+
+fn scan_project_sessions(project_dir: &Path) -> Result<Vec<SessionIndexEntry>, String> {
+    let history = project_dir.join(CHAT_HISTORY_FILE);
+    let project_path = project_dir.to_string_lossy().to_string();
+    let mut entries: Vec<SessionIndexEntry> = parse_history_file(&history)?
+        .into_iter()
+        .map(|(index, started, parsed)| SessionIndexEntry {
+            source: "aider".to_string(),
+            session_id: session_id_for(index),
+            file_path: session_file_path(&history, index)
+                .to_string_lossy()
+                .to_string(),
+            first_prompt: first_prompt(&parsed.messages),
+            thread_name: None,
+            message_count: parsed.messages.len() as u32,
+            modified: parsed.last_timestamp.clone().or_else(|| started.clone()),
+            created: started,
+            git_branch: None,
+            project_path: Some(project_path.clone()),
+            is_sidechain: None,
+            cwd: Some(project_path.clone()),
+            model_provider: None,
+            cli_version: None,
+            alias: None,
+            tags: None,
+            status: if parsed.messages.iter().any(|m| m.role == "user") {
+                SessionStatus::Valid
+            } else {
+                SessionStatus::Empty
+            },
+        })
+        .collect();
+    entries.sort_by(|a, b| b.modified.cmp(&a.modified));
+    Ok(entries)
+}
+
+pub fn get_projects() -> Result<Vec<ProjectEntry>, String> {
+    let mut projects: Vec<ProjectEntry> = project_dirs()
+        .into_par_iter()
+        .filter_map(|(project_id, dir)| {
+            let history = dir.join(CHAT_HISTORY_FILE);
+            let content = fs::read_to_string(&history).ok()?;
+            let session_count = split_sessions(&content).len();
+            if session_count == 0 {
+                return None;
+            }
+            let display_path = dir.to_string_lossy().to_string();
+            let data_root = split_scoped_id(&project_id).0.to_string();
+            Some(ProjectEntry {
+                source: "aider".to_string(),
+                short_name: short_name_from_path(&display_path),
+                id: project_id,
+                display_path,
+                session_count,
+                last_modified: file_modified(&history),
+                model_provider: None,
+                alias: None,
+                path_exists: true,
+                is_virtual: false,
+                data_root: Some(data_root),
+            })
+        })
+        .collect();
+    projects.sort_by(|a, b| b.last_modified.cmp(&a.last_modified));
+    Ok(projects)
+}
+
+pub fn get_sessions(project_id: &str) -> Result<Vec<SessionIndexEntry>, String> {
+    let project_dir = resolve_project_dir(project_id)?;
+    Ok(scan_project_sessions(&project_dir)?
+        .into_iter()
+        .filter(|s| s.status == SessionStatus::Valid)
+        .collect())
+}
+
+pub fn get_invalid_sessions(project_id: &str) -> Result<Vec<SessionIndexEntry>, String> {
+    let project_dir = resolve_project_dir(project_id)?;
+    Ok(scan_project_sessions(&project_dir)?
+        .into_iter()
+        .filter(|s| s.status != SessionStatus::Valid)
+        .collect())
+}
+
+/// 删除一个 Aider 项目：把聊天历史和输入日志移入回收站（可还原），
+/// 并清掉应用目录里的元数据。仓库本身不动。
+pub fn delete_project(project_id: &str) -> Result<DeleteResult, String> {
+    let project_dir = resolve_project_dir(project_id)?;
+    let history = project_dir.join(CHAT_HISTORY_FILE);
+    let sessions_deleted = fs::read_to_string(&history)
+        .map(|c| split_sessions(&c).len())
+        .unwrap_or(0);
+    let project_name = short_name_from_path(&project_dir.to_string_lossy());
+
+    for file in [history, project_dir.join(INPUT_HISTORY_FILE)] {
+        if !file.exists() {
+            continue;
+        }
+        crate::recyclebin::move_to_recyclebin(
+            &file,
+            "project",
+            "ManualDelete",
+            "aider",
+            project_id,
+            None,
+            Some(project_name.clone()),
+        )?;
+    }
+
+    if let Some(meta_path) = metadata_path(project_id) {
+        if meta_path.exists() {
+            fs::remove_file(&meta_path).map_err(|e| format!("Failed to remove metadata: {}", e))?;
+        }
+    }
+
+    Ok(DeleteResult {
+        sessions_deleted,
+        config_cleaned: false,
+        bookmarks_removed: 0,
+    })
+}
+
+/// Keyed by the repository path alone, without the root prefix.
+fn metadata_path(project_id: &str) -> Option<PathBuf> {
+    let path = split_scoped_id(project_id).1;
+    get_app_dir().map(|d| {
+        d.join("aider-meta")
+            .join(format!("{}.json", encode_project_path(path)))
+    })
+}
+
+// ── Tokens / cost ──
+
+/// Aider 报告的 `sent` 已包含缓存读写部分，拆开后 input 只算未命中缓存的部分。
+/// 花费优先用 Aider 自己算好的单条消息花费。
+fn usage_to_record(
+    usage: &UsageEntry,
+    project_id: &str,
+    session_id: &str,
+    file_path: &str,
+    fallback_time: &str,
+) -> RequestRecord {
+    let t = &usage.tokens;
+    let input = t.sent.saturating_sub(t.cache_hit + t.cache_write);
+    let cost_usd = t.cost.unwrap_or_else(|| {
+        pricing::compute_cost(&usage.model, input, t.cache_write, t.cache_hit, t.received)
+    });
+    RequestRecord {
+        timestamp: usage
+            .timestamp
+            .clone()
+            .unwrap_or_else(|| fallback_time.to_string()),
+        source: "aider".to_string(),
+        project_id: project_id.to_string(),
+        session_id: session_id.to_string(),
+        file_path: file_path.to_string(),
+        model: usage.model.clone(),
+        input_tokens: input,
+        output_tokens: t.received,
+        cache_read_tokens: t.cache_hit,
+        cache_creation_tokens: t.cache_write,
+        total_tokens: input + t.cache_write + t.cache_hit + t.received,
+        cost_usd,
+        duration_ms: None,
+        message_uuid: usage.message_uuid.clone(),
+    }
+}
+
+/// (会话数, 消息数, 请求记录)，一次扫描同时供 stats 和 request log 使用。
+fn scan_usage() -> (u64, u64, Vec<RequestRecord>) {
+    let per_project: Vec<(u64, u64, Vec<RequestRecord>)> = project_dirs()
+        .into_par_iter()
+        .filter_map(|(project_id, dir)| {
+            let history = dir.join(CHAT_HISTORY_FILE);
+            let sessions = parse_history_file(&history).ok()?;
+            let fallback_time = file_modified(&history).unwrap_or_default();
+
+            let mut session_count = 0;
+            let mut message_count = 0;
+            let mut records = Vec::new();
+            for (index, _, parsed) in sessions {
+                if parsed.messages.is_empty() {
+                    continue;
+                }
+                session_count += 1;
+                message_count += parsed.messages.len() as u64;
+                let session_id = session_id_for(index);
+                let file_path = session_file_path(&history, index)
+                    .to_string_lossy()
+                    .to_string();
+                records.extend(parsed.usage.iter().map(|u| {
+                    usage_to_record(u, &project_id, &session_id, &file_path, &fallback_time)
+                }));
+            }
+            Some((session_count, message_count, records))
+        })
+        .collect();
+
+    per_project.into_iter().fold(
+        (0, 0, Vec::new()),
+        |(sessions, messages, mut records), (s, m, r)| {
+            records.extend(r);
+            (sessions + s, messages + m, records)
+        },
+    )
+}
+
+pub fn collect_requests() -> Result<Vec<RequestRecord>, String> {
+    Ok(scan_usage().2)
+}
+
+pub fn get_stats() -> Result<TokenUsageSummary, String> {
+    let (session_count, message_count, records) = scan_usage();
+    Ok(crate::stats::summary_from_records(
+        &records,
+        session_count,
+        message_count,
+    ))
+}
+
+// ── SessionProvider ──
+
+pub struct AiderProvider;
+
+impl SessionProvider for AiderProvider {
+    fn id(&self) -> &'static str {
+        "aider"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Aider"
+    }
+
+    fn get_projects(&self) -> Result<Vec<ProjectEntry>, String> {
+        get_projects()
+    }
+
+    fn validate_project_id(&self, project_id: &str) -> Result<(), String> {
+        resolve_project_dir(project_id).map(|_| ())
+    }
+
+    fn delete_project(
+        &self,
+        project_id: &str,
+        _level: DeleteLevel,
+    ) -> Result<DeleteResult, String> {
+        delete_project(project_id)
+    }
+
+    fn get_sessions(&self, project_id: &str) -> Result<Vec<SessionIndexEntry>, String> {
+        get_sessions(project_id)
+    }
+
+    fn refresh_sessions_cache(&self, project_id: &str) -> Result<Vec<SessionIndexEntry>, String> {
+        get_sessions(project_id)
+    }
+
+    fn get_invalid_sessions(&self, project_id: &str) -> Result<Vec<SessionIndexEntry>, String> {
+        get_invalid_sessions(project_id)
+    }
+
+    fn verify_session_owner(
+        &self,
+        path: &Path,
+        project_id: Option<&str>,
+        session_id: Option<&str>,
+    ) -> Result<(), String> {
+        let (history, index) = split_session_path(path);
+        if let Some(pid) = project_id {
+            let project_dir = resolve_project_dir(pid)?;
+            if history.parent() != Some(project_dir.as_path()) {
+                return Err("Session file does not belong to the requested project".to_string());
+            }
+        }
+        if let Some(sid) = session_id {
+            if index.map(session_id_for).as_deref() != Some(sid) {
+                return Err("Session id does not match the requested Aider session".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    fn check_session_deletable(&self, _path: &Path) -> Result<(), String> {
+        Err(
+            "Aider sessions share one chat history file and can't be deleted individually"
+                .to_string(),
+        )
+    }
+
+    fn parse_session_messages(
+        &self,
+        path: &Path,
+        page: usize,
+        page_size: usize,
+        from_end: bool,
+    ) -> Result<PaginatedMessages, String> {
+        let messages = load_session(path)?.messages;
+        Ok(paginate_all(&messages, page, page_size, from_end))
+    }
+
+    fn parse_messages_range(
+        &self,
+        path: &Path,
+        start: usize,
+        end: usize,
+    ) -> Result<RangeMessages, String> {
+        let messages = load_session(path)?.messages;
+        Ok(slice_range(&messages, start, end))
+    }
+
+    fn parse_all_messages(&self, path: &Path) -> Result<Vec<DisplayMessage>, String> {
+        Ok(load_session(path)?.messages)
+    }
+
+    fn search_targets(&self) -> Vec<SearchTarget> {
+        project_dirs()
+            .into_par_iter()
+            .flat_map_iter(|(project_id, dir)| {
+                let history = dir.join(CHAT_HISTORY_FILE);
+                let project_name = short_name_from_path(&dir.to_string_lossy());
+                let meta = crate::metadata::load_metadata("aider", &project_id);
+                let count = fs::read_to_string(&history)
+                    .map(|c| split_sessions(&c).len())
+                    .unwrap_or(0);
+
+                (1..=count)
+                    .map(|index| {
+                        let session_id = session_id_for(index);
+                        let session_meta = meta.sessions.get(&session_id);
+                        let alias = session_meta.and_then(|s| s.alias.clone());
+                        let tags = session_meta
+                            .map(|s| s.tags.clone())
+                            .filter(|t| !t.is_empty());
+                        let mut target = SearchTarget {
+                            project_id: project_id.clone(),
+                            project_name: project_name.clone(),
+                            session_id,
+                            file_path: session_file_path(&history, index),
+                            thread_name: None,
+                            alias: alias.clone(),
+                            search_aliases: Vec::with_capacity(1),
+                            tags,
+                            git_branch: None,
+                        };
+                        target.push_search_alias(alias);
+                        target
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    fn read_session_text(&self, path: &Path) -> std::io::Result<String> {
+        let (history, index) = split_session_path(path);
+        let content = fs::read_to_string(&history)?;
+        let Some(index) = index else {
+            return Ok(content);
+        };
+        Ok(split_sessions(&content)
+            .into_iter()
+            .find(|s| s.index == index)
+            .map(|s| s.body.to_string())
+            .unwrap_or_default())
+    }
+
+    fn get_stats(&self) -> Result<TokenUsageSummary, String> {
+        get_stats()
+    }
+
+    fn collect_requests(&self) -> Result<Vec<RequestRecord>, String> {
+        collect_requests()
+    }
+
+    fn session_file_extension(&self) -> &'static str {
+        "md"
+    }
+
+    fn split_session_selector<'a>(&self, file_path: &'a str) -> (&'a str, Option<&'a str>) {
+        split_selector(file_path)
+    }
+
+    fn session_roots(&self) -> Vec<PathBuf> {
+        crate::data_roots::load_roots("aider")
+    }
+
+    fn validate_layout(&self, path: &Path, base: &Path) -> Result<(), String> {
+        let relative = path
+            .strip_prefix(base)
+            .map_err(|_| "Session file is outside the configured Aider roots".to_string())?;
+        let components: Vec<_> = relative.components().collect();
+        if components.is_empty()
+            || components.len() > MAX_SCAN_DEPTH + 1
+            || components
+                .iter()
+                .any(|c| !matches!(c, Component::Normal(_)))
+        {
+            return Err("Invalid Aider session file path".to_string());
+        }
+        if path.file_name().and_then(|n| n.to_str()) != Some(CHAT_HISTORY_FILE) {
+            return Err(format!("Aider session file must be {}", CHAT_HISTORY_FILE));
+        }
+        Ok(())
+    }
+
+    fn is_watched_file(&self, path: &Path) -> bool {
+        matches!(
+            path.file_name().and_then(|n| n.to_str()),
+            Some(CHAT_HISTORY_FILE) | Some(INPUT_HISTORY_FILE)
+        )
+    }
+
+    fn metadata_path(&self, project_id: &str) -> Option<PathBuf> {
+        metadata_path(project_id)
+    }
+
+    fn metadata_project_ids(&self) -> Vec<String> {
+        project_dirs().into_iter().map(|(id, _)| id).collect()
+    }
+
+    /// 没有列表缓存，每次都直接读历史文件。
+    fn invalidate_cache(&self) {
+        crate::search::mark_unsynced(self.id());
+    }
+
+    fn invalidate_paths(&self, changed: &[PathBuf]) {
+        crate::search::mark_dirty(changed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HISTORY: &str = "
+# aider chat started at 2026-03-01 10:00:00
+
+> aider --model sonnet  
+> Aider v0.86.0  
+> Main model: anthropic/claude-sonnet-4-5 with diff edit format, infinite output  
+
+#### add a hello function  
+
+Here is the change:
+
+```python
+#### not a prompt inside a fence
+def hello(): ...
+```
+
+> Tokens: 2.1k sent, 1,024 cache hit, 85 received. Cost: $0.0074 message, $0.0074 session.  
+> Applied edit to hello.py  
+
+# aider chat started at 2026-03-02 09:30:00
+
+#### second session  
+";
+
+    #[test]
+    fn splits_sessions_and_classifies_blocks() {
+        let sessions = split_sessions(HISTORY);
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[1].index, 2);
+
+        let inputs = parse_input_history("\n# 2026-03-01 10:00:05.123456\n+add a hello function\n");
+        let parsed = parse_session(&sessions[0], &inputs);
+        let roles: Vec<&str> = parsed.messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, ["tool", "user", "assistant", "tool"]);
+        assert!(parsed.messages[1].timestamp.is_some());
+        assert_eq!(
+            parsed.messages[2].model.as_deref(),
+            Some("anthropic/claude-sonnet-4-5")
+        );
+
+        assert_eq!(parsed.usage.len(), 1);
+        let usage = &parsed.usage[0];
+        assert_eq!(usage.message_uuid.as_deref(), Some("1-2"));
+        assert_eq!(
+            usage.tokens,
+            TokenLine {
+                sent: 2100,
+                received: 85,
+                cache_write: 0,
+                cache_hit: 1024,
+                cost: Some(0.0074),
+            }
+        );
+    }
+
+    #[test]
+    fn metadata_ignores_the_root_prefix() {
+        assert_eq!(
+            metadata_path(&scoped_id("code", "/r/app")),
+            metadata_path("/r/app")
+        );
+    }
+
+    #[test]
+    fn selector_only_applies_to_chat_history() {
+        assert_eq!(
+            split_selector("/r/.aider.chat.history.md#3"),
+            ("/r/.aider.chat.history.md", Some("3"))
+        );
+        assert_eq!(split_selector("/r/notes#3.md"), ("/r/notes#3.md", None));
+        assert_eq!(
+            split_selector("/r/.aider.chat.history.md#x"),
+            ("/r/.aider.chat.history.md#x", None)
+        );
+    }
+}
@@ -6,17 +6,29 @@ use std::sync::OnceLock;
 use parking_lot::Mutex;
 use rayon::prelude::*;
 
-use crate::models::message::{DisplayMessage, PaginatedMessages};
+use crate::models::file_history::{FileContentsAtMessage, FileHistoryIndex, SessionFileChanges};
+use crate::models::message::{
+    BranchMessages, ConversationTree, DisplayMessage, MessageMedia, PaginatedMessages,
+    RangeMessages, SubagentThread,
+};
+use crate::models::plan::SessionPlan;
 use crate::models::project::ProjectEntry;
 use crate::models::session::{
     SessionIndexEntry, SessionStatus, SessionsIndex, SessionsIndexFileEntry,
 };
 use crate::parser::jsonl as claude_parser;
 use crate::scan_progress::{self, Phase};
+use crate::data_roots::{scoped_id, split_scoped_id, DEFAULT_ROOT};
 use crate::parser::path_encoder::{
-    decode_project_path_validated, get_projects_dir, short_name_from_path,
+    decode_project_path_validated, get_claude_home, short_name_from_path,
+};
+use crate::models::stats::{
+    ProjectCostEntry, RequestLogPage, RequestRecord, SessionCostSummary, TokenUsageSummary,
 };
 use crate::state::{clear_message_cache, clear_message_cache_for_path};
+use crate::stats::RequestLogFilter;
+
+use super::{SearchTarget, SessionProvider};
 
 #[derive(serde::Deserialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -190,6 +202,31 @@ pub fn invalidate_project(project_id: &str) {
     save_cache(&cache);
 }
 
+/// `projects/` directory of every Claude home to browse — `~/.claude` (root
+/// [`DEFAULT_ROOT`]) plus the configured data roots — paired with the root name.
+pub(crate) fn projects_dirs() -> Vec<(String, PathBuf)> {
+    crate::data_roots::resolve_roots("claude", get_claude_home())
+        .into_iter()
+        .map(|root| (root.name, root.path.join("projects")))
+        .collect()
+}
+
+/// Split a (possibly `@root:`-scoped) project id into the projects directory of
+/// its root and the encoded directory name.
+fn locate_project(project_id: &str) -> Result<(PathBuf, &str), String> {
+    let (root, encoded) = split_scoped_id(project_id);
+    projects_dirs()
+        .into_iter()
+        .find(|(name, _)| name == root)
+        .map(|(_, dir)| (dir, encoded))
+        .ok_or_else(|| format!("Unknown Claude data root: {}", root))
+}
+
+/// Display path for a project id, decoded from its encoded directory name.
+pub fn project_display_path(project_id: &str) -> String {
+    decode_project_path_validated(split_scoped_id(project_id).1).display_path
+}
+
 /// Map a changed path to its encoded project id (the first directory component
 /// under `~/.claude/projects/`). `None` if the path isn't under `projects_dir`.
 fn project_id_from_path(path: &Path, projects_dir: &Path) -> Option<String> {
@@ -200,6 +237,27 @@ fn project_id_from_path(path: &Path, projects_dir: &Path) -> Option<String> {
     }
 }
 
+/// Session file owning a subagent transcript at
+/// `<projects>/<project>/<session-id>/subagents/agent-<id>.jsonl`.
+fn subagent_parent_session(path: &Path, roots: &[(String, PathBuf)]) -> Option<PathBuf> {
+    roots.iter().find_map(|(_, projects_dir)| {
+        let rel = path.strip_prefix(projects_dir).ok()?;
+        let parts: Vec<&str> = rel
+            .components()
+            .map(|c| match c {
+                Component::Normal(name) => name.to_str(),
+                _ => None,
+            })
+            .collect::<Option<_>>()?;
+        match parts.as_slice() {
+            [project, session, "subagents", _] => {
+                Some(projects_dir.join(project).join(format!("{}.jsonl", session)))
+            }
+            _ => None,
+        }
+    })
+}
+
 /// Incrementally update the cache for a set of changed paths (called by the fs
 /// watcher). When all of a project's changed paths are `.jsonl` files and that
 /// project's session list is already cached, only those files are re-scanned
@@ -209,21 +267,32 @@ fn project_id_from_path(path: &Path, projects_dir: &Path) -> Option<String> {
 /// path, or a cold project) falls back to the existing project- / full-level
 /// invalidation.
 pub fn invalidate_paths(changed: &[PathBuf]) {
-    let projects_dir = match get_projects_dir() {
-        Some(dir) => dir,
-        None => {
-            invalidate_cache();
-            return;
-        }
-    };
+    crate::search::mark_dirty(changed);
+    let roots = projects_dirs();
 
-    let mut by_project: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    // scoped project id → (project dir, changed paths)
+    let mut by_project: HashMap<String, (PathBuf, Vec<PathBuf>)> = HashMap::new();
     for path in changed {
         if path.extension().map(|e| e == "jsonl").unwrap_or(false) {
             clear_message_cache_for_path(path);
+            // A subagent transcript only changes the parent session's thread
+            // summaries, not the session list.
+            if let Some(parent) = subagent_parent_session(path, &roots) {
+                clear_message_cache_for_path(&parent);
+                continue;
+            }
         }
-        match project_id_from_path(path, &projects_dir) {
-            Some(pid) => by_project.entry(pid).or_default().push(path.clone()),
+        let mapped = roots.iter().find_map(|(root, projects_dir)| {
+            let encoded = project_id_from_path(path, projects_dir)?;
+            let project_dir = projects_dir.join(&encoded);
+            Some((scoped_id(root, &encoded), project_dir))
+        });
+        match mapped {
+            Some((pid, project_dir)) => by_project
+                .entry(pid)
+                .or_insert_with(|| (project_dir, Vec::new()))
+                .1
+                .push(path.clone()),
             // Unmappable path (e.g. the projects dir itself) → be safe.
             None => {
                 invalidate_cache();
@@ -233,14 +302,13 @@ pub fn invalidate_paths(changed: &[PathBuf]) {
     }
 
     let mut touched = false;
-    for (pid, paths) in by_project {
+    for (pid, (project_dir, paths)) in by_project {
         let all_jsonl = paths
             .iter()
             .all(|p| p.extension().map(|e| e == "jsonl").unwrap_or(false));
 
         match (all_jsonl, cached_sessions(&pid)) {
             (true, Some(mut entries)) => {
-                let project_dir = projects_dir.join(&pid);
                 for p in &paths {
                     let Some(sid) = p.file_stem().and_then(|s| s.to_str()).map(String::from) else {
                         continue;
@@ -297,9 +365,8 @@ fn read_sessions_index(project_dir: &Path) -> Option<SessionsIndex> {
 /// 读取项目别名。文件不存在或 alias 字段缺失时返回 Ok(None)，不报错。
 /// 使用 canonicalize + starts_with 防止路径遍历。
 pub fn get_project_alias(project_id: &str) -> Result<Option<String>, String> {
-    let projects_dir = get_projects_dir()
-        .ok_or_else(|| "Cannot find Claude projects directory".to_string())?;
-    let project_dir = projects_dir.join(project_id);
+    let (projects_dir, encoded) = locate_project(project_id)?;
+    let project_dir = projects_dir.join(encoded);
     if !project_dir.exists() {
         return Ok(None);
     }
@@ -331,9 +398,8 @@ pub fn get_project_alias(project_id: &str) -> Result<Option<String>, String> {
 ///
 /// 使用 canonicalize + starts_with 防止路径遍历。
 pub fn set_project_alias(project_id: &str, alias: Option<String>) -> Result<(), String> {
-    let projects_dir = get_projects_dir()
-        .ok_or_else(|| "Cannot find Claude projects directory".to_string())?;
-    let project_dir = projects_dir.join(project_id);
+    let (projects_dir, encoded) = locate_project(project_id)?;
+    let project_dir = projects_dir.join(encoded);
 
     if !project_dir.exists() {
         return Err(format!("Project not found: {}", project_id));
@@ -391,28 +457,36 @@ pub fn set_project_alias(project_id: &str, alias: Option<String>) -> Result<(),
     Ok(())
 }
 
-fn scan_projects_from_disk(projects_dir: &Path) -> Result<Vec<ProjectEntry>, String> {
-    if !projects_dir.exists() {
-        return Ok(Vec::new());
-    }
-
+/// Scan the project directories of every root. Project ids from roots other
+/// than [`DEFAULT_ROOT`] are scoped as `@<root>:<encoded>`.
+fn scan_projects_from_disk(roots: &[(String, PathBuf)]) -> Result<Vec<ProjectEntry>, String> {
     let cache = std::sync::Arc::new(load_cache());
 
     // Collect all project directory entries first (fast, sequential)
-    let dir_entries: Vec<PathBuf> = fs::read_dir(projects_dir)
-        .map_err(|e| format!("Failed to read projects dir: {}", e))?
-        .flatten()
-        .map(|e| e.path())
-        .filter(|p| p.is_dir())
-        .collect();
+    let mut dir_entries: Vec<(&str, PathBuf)> = Vec::new();
+    for (root, projects_dir) in roots {
+        if !projects_dir.exists() {
+            continue;
+        }
+        let entries = fs::read_dir(projects_dir)
+            .map_err(|e| format!("Failed to read projects dir: {}", e))?;
+        dir_entries.extend(
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.is_dir())
+                .map(|p| (root.as_str(), p)),
+        );
+    }
 
     // Parallel processing: read index.json + count files for each project dir
     scan_progress::begin(Phase::Projects, dir_entries.len() as u64);
     let mut projects: Vec<ProjectEntry> = dir_entries
         .into_par_iter()
-        .filter_map(|path| {
+        .filter_map(|(root, path)| {
             let result = (|| {
             let encoded_name = path.file_name().and_then(|n| n.to_str())?.to_string();
+            let project_id = scoped_id(root, &encoded_name);
 
             let parsed_index = read_sessions_index(&path);
 
@@ -436,7 +510,7 @@ fn scan_projects_from_disk(projects_dir: &Path) -> Result<Vec<ProjectEntry>, Str
 
             let session_count = cache
                 .sessions_by_project
-                .get(&encoded_name)
+                .get(&project_id)
                 .map(|cached| cached.entries.len())
                 .unwrap_or_else(|| count_valid_jsonl_files(&path));
             if session_count == 0 {
@@ -453,10 +527,10 @@ fn scan_projects_from_disk(projects_dir: &Path) -> Result<Vec<ProjectEntry>, Str
                         .unwrap_or_default()
                 });
 
-            let alias = get_project_alias(&encoded_name).unwrap_or(None);
+            let alias = get_project_alias(&project_id).unwrap_or(None);
             Some(ProjectEntry {
                 source: "claude".to_string(),
-                id: encoded_name,
+                id: project_id,
                 display_path,
                 short_name,
                 session_count,
@@ -465,6 +539,7 @@ fn scan_projects_from_disk(projects_dir: &Path) -> Result<Vec<ProjectEntry>, Str
                 alias,
                 path_exists,
                 is_virtual: false,
+                data_root: (root != DEFAULT_ROOT).then(|| root.to_string()),
             })
             })();
             scan_progress::inc();
@@ -478,8 +553,7 @@ fn scan_projects_from_disk(projects_dir: &Path) -> Result<Vec<ProjectEntry>, Str
 }
 
 pub fn refresh_projects_cache() -> Result<Vec<ProjectEntry>, String> {
-    let projects_dir = get_projects_dir().ok_or("Could not find Claude projects directory")?;
-    let projects = scan_projects_from_disk(&projects_dir)?;
+    let projects = scan_projects_from_disk(&projects_dirs())?;
     store_projects_cache(&projects);
     Ok(projects)
 }
@@ -503,8 +577,8 @@ pub fn get_projects() -> Result<Vec<ProjectEntry>, String> {
 /// 这样把过去 SessionsPage 同时打两个 RPC（getSessions + getInvalidSessions）
 /// 合并成一个 RPC，IO 减半，**避免冷缓存下两个调用各自启动一次完整扫描**。
 pub fn refresh_sessions_cache(encoded_name: &str) -> Result<Vec<SessionIndexEntry>, String> {
-    let projects_dir = get_projects_dir().ok_or("Could not find Claude projects directory")?;
-    let project_dir = projects_dir.join(encoded_name);
+    let (projects_dir, dir_name) = locate_project(encoded_name)?;
+    let project_dir = projects_dir.join(dir_name);
 
     if !project_dir.exists() {
         return Err(format!("Project directory not found: {}", encoded_name));
@@ -570,16 +644,21 @@ pub fn parse_all_messages(path: &std::path::Path) -> Result<Vec<DisplayMessage>,
 
 /// Collect all JSONL files for search
 pub fn collect_all_jsonl_files() -> Vec<(String, String, PathBuf)> {
-    let projects_dir = match get_projects_dir() {
-        Some(d) if d.exists() => d,
-        _ => return Vec::new(),
-    };
-
     let mut files: Vec<(String, String, PathBuf)> = Vec::new();
+    for (root, projects_dir) in projects_dirs() {
+        collect_root_jsonl_files(&root, &projects_dir, &mut files);
+    }
+    files
+}
 
-    let project_dirs = match fs::read_dir(&projects_dir) {
+fn collect_root_jsonl_files(
+    root: &str,
+    projects_dir: &Path,
+    files: &mut Vec<(String, String, PathBuf)>,
+) {
+    let project_dirs = match fs::read_dir(projects_dir) {
         Ok(d) => d,
-        Err(_) => return files,
+        Err(_) => return,
     };
 
     for entry in project_dirs.flatten() {
@@ -604,18 +683,17 @@ pub fn collect_all_jsonl_files() -> Vec<(String, String, PathBuf)> {
             })
             .unwrap_or_else(|| decode_project_path_validated(&encoded_name).display_path);
         let project_name = short_name_from_path(&display_path);
+        let project_id = scoped_id(root, &encoded_name);
 
         if let Ok(dir_files) = fs::read_dir(&path) {
             for file_entry in dir_files.flatten() {
                 let file_path = file_entry.path();
                 if file_path.extension().map(|e| e == "jsonl").unwrap_or(false) {
-                    files.push((encoded_name.clone(), project_name.clone(), file_path));
+                    files.push((project_id.clone(), project_name.clone(), file_path));
                 }
             }
         }
     }
-
-    files
 }
 
 // ── internal helpers ──
@@ -784,14 +862,14 @@ fn enrich_with_index(project_dir: &Path, sessions: &mut [SessionIndexEntry]) {
 
 /// 删除项目目录，并根据 level 执行额外清理。
 pub fn delete_project(project_id: &str, level: DeleteLevel) -> Result<DeleteResult, String> {
+    let (root, encoded) = split_scoped_id(project_id);
     // 安全检查：project_id 不能为空、"."、".."
-    if project_id.is_empty() || project_id == "." || project_id == ".." {
+    if encoded.is_empty() || encoded == "." || encoded == ".." {
         return Err(format!("Invalid project id: {}", project_id));
     }
 
-    let projects_dir = get_projects_dir()
-        .ok_or_else(|| "Cannot find Claude projects directory".to_string())?;
-    let dir = projects_dir.join(project_id);
+    let (projects_dir, _) = locate_project(project_id)?;
+    let dir = projects_dir.join(encoded);
     if !dir.exists() {
         return Err(format!("Project not found: {}", project_id));
     }
@@ -811,7 +889,7 @@ pub fn delete_project(project_id: &str, level: DeleteLevel) -> Result<DeleteResu
     let project_path = read_sessions_index(&canonical_dir)
         .as_ref()
         .and_then(project_path_from_index)
-        .unwrap_or_else(|| decode_project_path_validated(project_id).display_path);
+        .unwrap_or_else(|| decode_project_path_validated(encoded).display_path);
     let real_path = if level == DeleteLevel::WithCcConfig || level == DeleteLevel::WithHistory {
         Some(project_path.clone())
     } else {
@@ -853,8 +931,10 @@ pub fn delete_project(project_id: &str, level: DeleteLevel) -> Result<DeleteResu
     let mut bookmarks_removed = 0;
 
     if level == DeleteLevel::WithCcConfig || level == DeleteLevel::WithHistory {
-        // Level 2a：清理 ~/.claude.json
-        config_cleaned = clean_claude_config(real_path.as_deref().unwrap_or(""));
+        // Level 2a：清理 ~/.claude.json（只对应本机 home，其他数据根目录没有这份配置）
+        if root == DEFAULT_ROOT {
+            config_cleaned = clean_claude_config(real_path.as_deref().unwrap_or(""));
+        }
 
         // Level 2b：清理书签
         bookmarks_removed = clean_bookmarks_for_project(project_id);
@@ -957,24 +1037,27 @@ fn clean_bookmarks_for_project(project_id: &str) -> usize {
 /// 扫描所有项目目录，将孤儿 UUID 子目录批量移入回收站。
 /// 返回成功移入的数量。
 pub fn cleanup_all_orphan_dirs() -> Result<usize, String> {
-    let projects_dir = get_projects_dir()
-        .ok_or_else(|| "Cannot find Claude projects directory".to_string())?;
-    if !projects_dir.exists() {
-        return Ok(0);
+    let mut project_dirs: Vec<(String, PathBuf)> = Vec::new();
+    for (root, projects_dir) in projects_dirs() {
+        if !projects_dir.exists() {
+            continue;
+        }
+        let entries = fs::read_dir(&projects_dir)
+            .map_err(|e| format!("Failed to read projects dir: {}", e))?;
+        project_dirs.extend(
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.is_dir())
+                .map(|p| (root.clone(), p)),
+        );
     }
 
-    let project_dirs: Vec<PathBuf> = fs::read_dir(&projects_dir)
-        .map_err(|e| format!("Failed to read projects dir: {}", e))?
-        .flatten()
-        .map(|e| e.path())
-        .filter(|p| p.is_dir())
-        .collect();
-
     let mut moved = 0usize;
 
-    for project_dir in project_dirs {
+    for (root, project_dir) in project_dirs {
         let project_id = match project_dir.file_name().and_then(|n| n.to_str()) {
-            Some(n) => n.to_string(),
+            Some(n) => scoped_id(&root, n),
             None => continue,
         };
 
@@ -1074,3 +1157,383 @@ fn count_valid_jsonl_files(dir: &Path) -> usize {
         })
         .unwrap_or(0)
 }
+
+/// Claude home a main session file lives under:
+/// `<home>/projects/<project>/<session>.jsonl`.
+fn session_claude_home(path: &Path) -> Option<&Path> {
+    path.parent()?.parent()?.parent()
+}
+
+fn session_file_id(path: &Path) -> Option<&str> {
+    path.file_stem()?.to_str()
+}
+
+fn file_history_dir(path: &Path) -> Result<PathBuf, String> {
+    session_claude_home(path)
+        .zip(session_file_id(path))
+        .map(|(home, session_id)| home.join("file-history").join(session_id))
+        .ok_or_else(|| format!("No file history for: {}", path.display()))
+}
+
+/// Resolve a Claude project id (encoded dir name, optionally `@root:`-scoped)
+/// to its canonical directory, rejecting anything that isn't exactly one
+/// existing directory directly under that root's `projects/`.
+pub fn resolve_project_dir(project_id: &str) -> Result<PathBuf, String> {
+    let (projects_dir, encoded) = locate_project(project_id)?;
+    if !crate::metadata::is_single_normal_component(encoded) {
+        return Err(format!("Invalid project id: {}", project_id));
+    }
+
+    let base = projects_dir
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve Claude projects directory: {}", e))?;
+    let candidate = base.join(encoded);
+
+    if !candidate.exists() {
+        return Err(format!("Project directory not found: {}", project_id));
+    }
+
+    let canonical = candidate
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve project directory: {}", e))?;
+    if !canonical.is_dir() {
+        return Err(format!("Project directory not found: {}", project_id));
+    }
+    let relative = canonical
+        .strip_prefix(&base)
+        .map_err(|_| format!("Invalid project id: {}", project_id))?;
+    if relative.components().count() != 1 {
+        return Err(format!("Invalid project id: {}", project_id));
+    }
+
+    Ok(canonical)
+}
+
+// ── SessionProvider ──
+
+pub struct ClaudeProvider;
+
+impl SessionProvider for ClaudeProvider {
+    fn id(&self) -> &'static str {
+        "claude"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Claude"
+    }
+
+    fn get_projects(&self) -> Result<Vec<ProjectEntry>, String> {
+        get_projects()
+    }
+
+    fn refresh_projects_cache(&self) -> Result<Vec<ProjectEntry>, String> {
+        refresh_projects_cache()
+    }
+
+    fn validate_project_id(&self, project_id: &str) -> Result<(), String> {
+        resolve_project_dir(project_id).map(|_| ())
+    }
+
+    fn project_display_name(&self, project_id: &str) -> String {
+        project_display_path(project_id)
+    }
+
+    fn delete_project(&self, project_id: &str, level: DeleteLevel) -> Result<DeleteResult, String> {
+        delete_project(project_id, level)
+    }
+
+    fn set_project_alias(&self, project_id: &str, alias: Option<String>) -> Result<(), String> {
+        set_project_alias(project_id, alias)
+    }
+
+    fn cleanup_orphan_dirs(&self) -> Result<usize, String> {
+        cleanup_all_orphan_dirs()
+    }
+
+    fn get_sessions(&self, project_id: &str) -> Result<Vec<SessionIndexEntry>, String> {
+        get_sessions(project_id)
+    }
+
+    fn refresh_sessions_cache(&self, project_id: &str) -> Result<Vec<SessionIndexEntry>, String> {
+        refresh_sessions_cache(project_id)
+    }
+
+    fn get_invalid_sessions(&self, project_id: &str) -> Result<Vec<SessionIndexEntry>, String> {
+        get_invalid_sessions(project_id)
+    }
+
+    fn stores_alias_in_session_file(&self) -> bool {
+        true
+    }
+
+    fn write_session_alias(
+        &self,
+        path: &Path,
+        session_id: &str,
+        alias: Option<&str>,
+    ) -> Result<(), String> {
+        claude_parser::append_custom_title(path, session_id, alias)
+    }
+
+    fn rename_chat_session(
+        &self,
+        project_path: &str,
+        session_id: &str,
+        alias: Option<&str>,
+    ) -> Result<String, String> {
+        let encoded = crate::parser::path_encoder::encode_project_path(project_path);
+        let projects_dir = crate::parser::path_encoder::get_projects_dir()
+            .ok_or_else(|| "Cannot resolve Claude projects directory".to_string())?;
+        let project_dir = projects_dir.join(&encoded);
+        if !project_dir.is_dir() {
+            return Err(format!(
+                "Claude project directory not found for path: {}",
+                project_path
+            ));
+        }
+        let jsonl_path = project_dir.join(format!("{}.jsonl", session_id));
+        if !jsonl_path.exists() {
+            return Err(format!("Session file not found: {}", jsonl_path.display()));
+        }
+        self.write_session_alias(&jsonl_path, session_id, alias)?;
+        // The title record wins; drop any alias the metadata file still has.
+        crate::metadata::set_session_alias(self.id(), &encoded, session_id, None)?;
+        Ok(encoded)
+    }
+
+    fn verify_session_owner(
+        &self,
+        path: &Path,
+        project_id: Option<&str>,
+        session_id: Option<&str>,
+    ) -> Result<(), String> {
+        if let Some(pid) = project_id {
+            let project_dir = resolve_project_dir(pid)?;
+            let parent = path
+                .parent()
+                .ok_or_else(|| "Invalid session file path".to_string())?;
+            if parent != project_dir.as_path() {
+                return Err("Session file does not belong to the requested project".to_string());
+            }
+        }
+
+        if let Some(sid) = session_id {
+            let file_stem = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .ok_or_else(|| "Invalid session file name".to_string())?;
+            if file_stem != sid {
+                return Err(
+                    "Session id does not match the requested Claude session file".to_string(),
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    fn parse_session_messages(
+        &self,
+        path: &Path,
+        page: usize,
+        page_size: usize,
+        from_end: bool,
+    ) -> Result<PaginatedMessages, String> {
+        parse_session_messages(path, page, page_size, from_end)
+    }
+
+    fn parse_messages_range(
+        &self,
+        path: &Path,
+        start: usize,
+        end: usize,
+    ) -> Result<RangeMessages, String> {
+        parse_messages_range(path, start, end)
+    }
+
+    fn parse_all_messages(&self, path: &Path) -> Result<Vec<DisplayMessage>, String> {
+        parse_all_messages(path)
+    }
+
+    fn for_each_message(
+        &self,
+        path: &Path,
+        f: &mut dyn FnMut(DisplayMessage) -> Result<(), String>,
+    ) -> Result<(), String> {
+        claude_parser::for_each_message(path, f)
+    }
+
+    fn parse_subagent_thread(&self, path: &Path, agent_id: &str) -> Result<SubagentThread, String> {
+        claude_parser::parse_subagent_thread(path, agent_id)
+    }
+
+    fn parse_conversation_tree(&self, path: &Path) -> Result<ConversationTree, String> {
+        claude_parser::parse_conversation_tree(path)
+    }
+
+    fn parse_branch_messages(&self, path: &Path, uuid: &str) -> Result<BranchMessages, String> {
+        claude_parser::parse_branch_messages(path, uuid)
+    }
+
+    fn session_plan(&self, path: &Path) -> Result<SessionPlan, String> {
+        let mut plan = crate::plans::plan_from_messages(&parse_all_messages(path)?);
+        if let (Some(home), Some(session_id)) = (session_claude_home(path), session_file_id(path)) {
+            crate::plans::merge_claude_todo_files(&mut plan, &home.join("todos"), session_id);
+        }
+        Ok(plan)
+    }
+
+    fn read_message_media(
+        &self,
+        path: &Path,
+        uuid: &str,
+        block_index: usize,
+    ) -> Result<MessageMedia, String> {
+        claude_parser::read_message_media(path, uuid, block_index)
+    }
+
+    fn file_history(&self, path: &Path) -> Result<FileHistoryIndex, String> {
+        crate::parser::file_history::file_history_index(path)
+    }
+
+    fn file_contents_at(
+        &self,
+        path: &Path,
+        file: &str,
+        uuid: &str,
+    ) -> Result<FileContentsAtMessage, String> {
+        crate::parser::file_history::file_contents_at(path, &file_history_dir(path)?, file, uuid)
+    }
+
+    fn file_changes(&self, path: &Path) -> Result<SessionFileChanges, String> {
+        crate::parser::file_history::session_file_changes(path, &file_history_dir(path)?)
+    }
+
+    fn search_targets(&self) -> Vec<SearchTarget> {
+        let jsonl_files = collect_all_jsonl_files();
+
+        // Pre-load metadata per project for alias lookup
+        let mut meta_cache: HashMap<String, crate::metadata::MetadataFile> = HashMap::new();
+        for (encoded_name, _, _) in &jsonl_files {
+            meta_cache
+                .entry(encoded_name.clone())
+                .or_insert_with(|| crate::metadata::load_metadata("claude", encoded_name));
+        }
+
+        jsonl_files
+            .into_par_iter()
+            .map(|(encoded_name, project_name, file_path)| {
+                let session_id = file_path
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or("")
+                    .to_string();
+
+                let session_meta = meta_cache
+                    .get(&encoded_name)
+                    .and_then(|m| m.sessions.get(&session_id));
+                let metadata_alias = session_meta.and_then(|s| s.alias.clone());
+                let tags = session_meta
+                    .map(|s| s.tags.clone())
+                    .filter(|t| !t.is_empty());
+                let scan = claude_parser::scan_session_file_once(&file_path);
+                let custom_title = scan.as_ref().and_then(|scan| scan.custom_title.clone());
+                let git_branch = scan.and_then(|scan| scan.git_branch);
+
+                let mut target = SearchTarget {
+                    project_id: encoded_name,
+                    project_name,
+                    session_id,
+                    file_path,
+                    thread_name: None,
+                    alias: custom_title.clone().or(metadata_alias.clone()),
+                    search_aliases: Vec::with_capacity(2),
+                    tags,
+                    git_branch,
+                };
+                target.push_search_alias(custom_title);
+                target.push_search_alias(metadata_alias);
+                target
+            })
+            .collect()
+    }
+
+    fn get_stats(&self) -> Result<TokenUsageSummary, String> {
+        crate::stats::claude_stats()
+    }
+
+    fn collect_requests(&self) -> Result<Vec<RequestRecord>, String> {
+        crate::stats::claude_collect_requests()
+    }
+
+    fn request_log(
+        &self,
+        filter: &RequestLogFilter,
+        page: usize,
+        page_size: usize,
+    ) -> Result<RequestLogPage, String> {
+        crate::stats::claude_request_log(filter, page, page_size)
+    }
+
+    fn project_costs(&self) -> Result<Vec<ProjectCostEntry>, String> {
+        crate::stats::claude_project_costs()
+    }
+
+    fn session_cost_is_cached(&self) -> bool {
+        true
+    }
+
+    fn session_cost(&self, file_path: &str) -> Result<SessionCostSummary, String> {
+        crate::stats::claude_session_cost(file_path)
+    }
+
+    fn session_roots(&self) -> Vec<PathBuf> {
+        projects_dirs().into_iter().map(|(_, dir)| dir).collect()
+    }
+
+    fn validate_layout(&self, path: &Path, base: &Path) -> Result<(), String> {
+        let relative = path
+            .strip_prefix(base)
+            .map_err(|_| "Session file is outside the Claude projects directory".to_string())?;
+        if relative.components().count() != 2 {
+            return Err(
+                "Claude session file must live directly under a project directory".to_string(),
+            );
+        }
+        Ok(())
+    }
+
+    fn metadata_path(&self, project_id: &str) -> Option<PathBuf> {
+        resolve_project_dir(project_id)
+            .ok()
+            .map(|dir| dir.join(crate::metadata::METADATA_FILE_NAME))
+    }
+
+    fn metadata_project_ids(&self) -> Vec<String> {
+        let mut ids = Vec::new();
+        for (root, projects_dir) in projects_dirs() {
+            let Ok(entries) = fs::read_dir(&projects_dir) else {
+                continue;
+            };
+            ids.extend(entries.flatten().filter_map(|entry| {
+                let path = entry.path();
+                if !path.is_dir() {
+                    return None;
+                }
+                path.file_name()
+                    .and_then(|n| n.to_str())
+                    .map(|n| scoped_id(&root, n))
+            }));
+        }
+        ids
+    }
+
+    fn invalidate_cache(&self) {
+        invalidate_cache();
+        crate::search::mark_unsynced(self.id());
+    }
+
+    fn invalidate_paths(&self, changed: &[PathBuf]) {
+        invalidate_paths(changed);
+    }
+}
@@ -3,22 +3,29 @@ use std::collections::HashSet;
 use std::collections::VecDeque;
 use std::fs;
 use std::io::{BufRead, BufReader};
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use std::sync::OnceLock;
 
 use parking_lot::Mutex;
 use rayon::prelude::*;
 use serde_json::Value;
 
-use crate::models::message::{DisplayContentBlock, DisplayMessage, PaginatedMessages};
+use crate::data_roots::{scoped_id, split_scoped_id, DEFAULT_ROOT};
+use crate::models::file_history::{FileChange, FileChangeBody, SessionFileChanges};
+use crate::models::message::{
+    DisplayContentBlock, DisplayMessage, MessageUsage, PaginatedMessages, RangeMessages,
+};
 use crate::models::project::ProjectEntry;
 use crate::models::session::{SessionIndexEntry, SessionStatus};
-use crate::models::stats::{DailyTokenEntry, TokenUsageSummary};
+use crate::models::stats::{DailyTokenEntry, RequestRecord, TokenUsageSummary};
 use crate::state::{
     clear_message_cache, clear_message_cache_for_path, get_cached_full_messages, get_cached_page,
     paginate_from_range, store_full_messages, store_partial_messages, tail_window_len,
 };
 
+use super::claude::{DeleteLevel, DeleteResult};
+use super::{SearchTarget, SessionProvider};
+
 // Bumped to 3 in v2.12.1 to invalidate stale caches built by v2.12.0 and
 // earlier — those builds' `extract_session_meta` only scanned the first 5
 // JSONL lines and had a too-narrow `is_interactive` allowlist, so projects
@@ -39,7 +46,11 @@ use crate::state::{
 // Desktop's synthetic per-chat scratch workspaces (`…/Codex/<date>/<slug>`)
 // into a `<codex-direct>/DATE` "直连对话" virtual project. Old indexes lack the
 // field, so force a rebuild.
-const DISK_CACHE_VERSION: u32 = 7;
+// Bumped to 8: `file_index` entries now record which data root (`local` or a
+// configured synced `~/.codex` copy) the rollout came from, and project ids
+// outside `local` are scoped as `@<root>:<id>`. Old indexes lack the root, so
+// force a rebuild.
+const DISK_CACHE_VERSION: u32 = 8;
 
 /// Sentinel prefix for project IDs synthesized from sessions with no cwd.
 /// Format: `<codex-unrooted>/YYYY-MM-DD`. The angle brackets are invalid in
@@ -129,6 +140,8 @@ struct CodexDiskCache {
 #[serde(rename_all = "camelCase")]
 struct CodexFileMeta {
     path: String,
+    /// Data root the rollout lives under (see [`crate::data_roots`]).
+    root: String,
     id: String,
     cwd: String,
     cli_version: Option<String>,
@@ -285,23 +298,45 @@ pub fn get_sessions_dir() -> Option<PathBuf> {
     get_codex_home().map(|h| h.join("sessions"))
 }
 
-/// `~/.codex/session_index.jsonl` — Codex Desktop's index of human-readable
-/// thread titles, one JSON object per line: `{ "id", "thread_name", "updated_at" }`.
-fn session_index_path() -> Option<PathBuf> {
-    get_codex_home().map(|h| h.join("session_index.jsonl"))
+/// Every Codex home to browse — `~/.codex` (root [`DEFAULT_ROOT`]) plus the
+/// configured data roots.
+fn codex_homes() -> Vec<crate::data_roots::RootDir> {
+    crate::data_roots::resolve_roots("codex", get_codex_home())
 }
 
-/// Build a `session_id → thread_name` map from `session_index.jsonl`. Read once
-/// per session-list build and looked up per entry. Missing file / blank titles
-/// are simply absent from the map (callers fall back to `first_prompt`). Cheap:
-/// one small file, line-by-line, no full rollout reads.
+/// `sessions/` directory of every Codex home, paired with the root name.
+fn sessions_dirs() -> Vec<(String, PathBuf)> {
+    codex_homes()
+        .into_iter()
+        .map(|root| (root.name, root.path.join("sessions")))
+        .collect()
+}
+
+/// Name of the root whose `sessions/` directory contains `path`.
+fn root_for_path<'a>(path: &Path, dirs: &'a [(String, PathBuf)]) -> Option<&'a str> {
+    dirs.iter()
+        .find(|(_, dir)| path.starts_with(dir))
+        .map(|(name, _)| name.as_str())
+}
+
+/// Build a `session_id → thread_name` map from every root's
+/// `session_index.jsonl` — Codex Desktop's index of human-readable thread
+/// titles, one JSON object per line: `{ "id", "thread_name", "updated_at" }`.
+/// Read once per session-list build and looked up per entry. Missing file /
+/// blank titles are simply absent from the map (callers fall back to
+/// `first_prompt`). Cheap: one small file per root, line-by-line, no full
+/// rollout reads. Session ids are UUIDs, so one map serves every root.
 pub fn load_thread_names() -> HashMap<String, String> {
     let mut map = HashMap::new();
-    let Some(path) = session_index_path() else {
-        return map;
-    };
-    let Ok(file) = fs::File::open(&path) else {
-        return map;
+    for home in codex_homes() {
+        read_thread_names(&home.path.join("session_index.jsonl"), &mut map);
+    }
+    map
+}
+
+fn read_thread_names(path: &Path, map: &mut HashMap<String, String>) {
+    let Ok(file) = fs::File::open(path) else {
+        return;
     };
     for line in BufReader::new(file).lines() {
         let Ok(line) = line else { continue };
@@ -323,18 +358,33 @@ pub fn load_thread_names() -> HashMap<String, String> {
             map.insert(id.to_string(), name.to_string());
         }
     }
-    map
 }
 
+/// Every rollout file under every root's `sessions/` directory.
 pub fn scan_all_session_files() -> Vec<PathBuf> {
-    let sessions_dir = match get_sessions_dir() {
-        Some(d) if d.exists() => d,
-        _ => return Vec::new(),
-    };
+    scan_session_files_by_root()
+        .into_iter()
+        .map(|(_, path)| path)
+        .collect()
+}
 
+/// Like [`scan_all_session_files`], paired with the data root name.
+fn scan_session_files_by_root() -> Vec<(String, PathBuf)> {
+    let mut files = Vec::new();
+    for (root, sessions_dir) in sessions_dirs() {
+        files.extend(
+            scan_session_files(&sessions_dir)
+                .into_iter()
+                .map(|path| (root.clone(), path)),
+        );
+    }
+    files
+}
+
+fn scan_session_files(sessions_dir: &Path) -> Vec<PathBuf> {
     let mut files: Vec<PathBuf> = Vec::new();
 
-    let year_dirs = match fs::read_dir(&sessions_dir) {
+    let year_dirs = match fs::read_dir(sessions_dir) {
         Ok(d) => d,
         Err(_) => return files,
     };
@@ -764,7 +814,7 @@ fn systemtime_to_rfc3339(t: std::time::SystemTime) -> String {
 
 /// Read one rollout file's `session_meta` + timestamps into an index entry.
 /// `None` when the file has no `session_meta` row (or can't be read).
-fn file_meta_for(path: &Path) -> Option<CodexFileMeta> {
+fn file_meta_for(root: &str, path: &Path) -> Option<CodexFileMeta> {
     let meta = extract_session_meta(path)?;
     let file_meta = fs::metadata(path).ok();
     let modified = file_meta
@@ -777,6 +827,7 @@ fn file_meta_for(path: &Path) -> Option<CodexFileMeta> {
         .map(systemtime_to_rfc3339);
     Some(CodexFileMeta {
         path: path.to_string_lossy().to_string(),
+        root: root.to_string(),
         id: meta.id,
         cwd: meta.cwd,
         cli_version: meta.cli_version,
@@ -794,14 +845,17 @@ fn file_meta_for(path: &Path) -> Option<CodexFileMeta> {
 /// - `<codex-direct>/DATE` for Codex Desktop direct-chat scratch workspaces,
 /// - `<codex-unrooted>/DATE` when it has no recorded cwd,
 /// - otherwise its real cwd.
+///
+/// Scoped as `@<root>:<key>` when the file comes from a configured data root.
 fn project_key_for(fm: &CodexFileMeta) -> String {
-    if let Some(date) = direct_chat_date(fm) {
+    let key = if let Some(date) = direct_chat_date(fm) {
         direct_project_id(&date)
     } else if fm.cwd.is_empty() {
         virtual_project_id(fm.date.as_deref().unwrap_or("unknown"))
     } else {
         fm.cwd.clone()
-    }
+    };
+    scoped_id(&fm.root, &key)
 }
 
 /// Build the per-file index with a single parallel pass over every rollout
@@ -809,12 +863,12 @@ fn project_key_for(fm: &CodexFileMeta) -> String {
 /// This is the only place that opens every file; the project/session lists are
 /// then derived from this in-memory index.
 fn build_file_index() -> Vec<CodexFileMeta> {
-    let files = scan_all_session_files();
+    let files = scan_session_files_by_root();
     crate::scan_progress::begin(crate::scan_progress::Phase::Index, files.len() as u64);
     let index = files
         .into_par_iter()
-        .filter_map(|file_path| {
-            let meta = file_meta_for(&file_path);
+        .filter_map(|(root, file_path)| {
+            let meta = file_meta_for(&root, &file_path);
             crate::scan_progress::inc();
             meta
         })
@@ -828,6 +882,7 @@ fn build_file_index() -> Vec<CodexFileMeta> {
 /// re-read; the affected projects' session lists and the project aggregate are
 /// dropped so they rebuild from the still-warm index — no full tree walk.
 pub fn invalidate_paths(changed: &[PathBuf]) {
+    crate::search::mark_dirty(changed);
     let jsonl: Vec<&PathBuf> = changed
         .iter()
         .filter(|p| p.extension().map(|e| e == "jsonl").unwrap_or(false))
@@ -853,11 +908,15 @@ pub fn invalidate_paths(changed: &[PathBuf]) {
     }
 
     // Re-read changed files off-lock (file I/O), then splice under lock.
+    let dirs = sessions_dirs();
     let updates: Vec<(String, Option<CodexFileMeta>)> = jsonl
         .iter()
         .map(|&p| {
             let path_str = p.to_string_lossy().to_string();
-            let new_meta = if p.exists() { file_meta_for(p) } else { None };
+            let new_meta = match root_for_path(p, &dirs) {
+                Some(root) if p.exists() => file_meta_for(root, p),
+                _ => None,
+            };
             (path_str, new_meta)
         })
         .collect();
@@ -941,12 +1000,13 @@ fn scan_projects_from_meta() -> Result<Vec<ProjectEntry>, String> {
         // - sessions with no cwd → `<codex-unrooted>/DATE` per day,
         // - real cwd sessions → the cwd itself.
         let project_key = project_key_for(fm);
+        let (_, unscoped_key) = split_scoped_id(&project_key);
         let (is_virtual, display_path, short_name, path_exists) =
-            if let Some(date) = parse_direct_project_id(&project_key) {
+            if let Some(date) = parse_direct_project_id(unscoped_key) {
                 let display = format!("Codex 直连对话 · {date}");
                 let short = format!("Codex 直连对话 · {date}");
                 (true, display, short, true)
-            } else if let Some(date) = parse_virtual_project_id(&project_key) {
+            } else if let Some(date) = parse_virtual_project_id(unscoped_key) {
                 let display = format!("未归属会话 · {date}");
                 let short = format!("未归属 · {date}");
                 (true, display, short, true)
@@ -970,6 +1030,7 @@ fn scan_projects_from_meta() -> Result<Vec<ProjectEntry>, String> {
                 alias: None,
                 path_exists,
                 is_virtual,
+                data_root: (fm.root != DEFAULT_ROOT).then(|| fm.root.clone()),
             });
 
         entry.session_count += 1;
@@ -1010,10 +1071,11 @@ pub fn get_projects() -> Result<Vec<ProjectEntry>, String> {
 /// 独立 rollout 文件，没有 Claude 那样的项目目录，所以删除即把该项目下每个
 /// rollout 文件移入回收站（可还原），并清理对应会话元数据。
 ///
-/// `project_id` 可以是真实 cwd，也可以是 `<codex-unrooted>/DATE` 虚拟桶。
+/// `project_id` 可以是真实 cwd，也可以是 `<codex-unrooted>/DATE` 虚拟桶；
+/// 来自其他数据根目录的项目带 `@<root>:` 前缀。
 /// 复用 [`super::claude::DeleteResult`] 作为统一返回类型（codex 无 cc 配置 /
 /// 书签清理，对应字段恒为 false / 0）。
-pub fn delete_project(project_id: &str) -> Result<super::claude::DeleteResult, String> {
+pub fn delete_project(project_id: &str) -> Result<DeleteResult, String> {
     if project_id.is_empty() {
         return Err("Invalid project id".to_string());
     }
@@ -1021,12 +1083,13 @@ pub fn delete_project(project_id: &str) -> Result<super::claude::DeleteResult, S
     let sessions = get_sessions(project_id)?;
 
     // 回收站条目展示用的项目名：直连/未归属虚拟桶用其日期描述，真实 cwd 取末段目录名。
-    let project_name = if let Some(date) = parse_direct_project_id(project_id) {
+    let (_, unscoped_id) = split_scoped_id(project_id);
+    let project_name = if let Some(date) = parse_direct_project_id(unscoped_id) {
         format!("Codex 直连对话 · {date}")
-    } else if let Some(date) = parse_virtual_project_id(project_id) {
+    } else if let Some(date) = parse_virtual_project_id(unscoped_id) {
         format!("未归属 · {date}")
     } else {
-        short_name_from_path(project_id)
+        short_name_from_path(unscoped_id)
     };
 
     let mut sessions_deleted = 0;
@@ -1056,7 +1119,7 @@ pub fn delete_project(project_id: &str) -> Result<super::claude::DeleteResult, S
 
     invalidate_sessions_cache();
 
-    Ok(super::claude::DeleteResult {
+    Ok(DeleteResult {
         sessions_deleted,
         config_cleaned: false,
         bookmarks_removed: 0,
@@ -1193,6 +1256,8 @@ pub fn parse_all_messages(path: &Path) -> Result<Vec<DisplayMessage>, String> {
     let file = fs::File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
     let reader = BufReader::new(file);
     let mut messages: Vec<DisplayMessage> = Vec::new();
+    let mut turn_usage = TurnUsage::default();
+    let mut turn_start = 0usize;
 
     for line in reader.lines() {
         let line = match line {
@@ -1208,6 +1273,11 @@ pub fn parse_all_messages(path: &Path) -> Result<Vec<DisplayMessage>, String> {
             Ok(v) => v,
             Err(_) => continue,
         };
+        if let Some(usage) = turn_usage.observe(&row) {
+            attach_turn_usage(messages[turn_start..].iter_mut(), usage);
+            turn_start = messages.len();
+            continue;
+        }
         if let Some(message) = display_message_from_row(&row) {
             messages.push(message);
         }
@@ -1217,6 +1287,48 @@ pub fn parse_all_messages(path: &Path) -> Result<Vec<DisplayMessage>, String> {
     Ok(messages)
 }
 
+/// [`parse_all_messages`] one message at a time. Messages are held back
+/// until their turn's `token_count` event puts usage on them, so at most one
+/// turn is in memory.
+pub fn for_each_message(
+    path: &Path,
+    f: &mut dyn FnMut(DisplayMessage) -> Result<(), String>,
+) -> Result<(), String> {
+    if let Ok(Some(cached)) = get_cached_full_messages(path) {
+        return cached.into_iter().try_for_each(f);
+    }
+
+    let file = fs::File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let reader = BufReader::new(file);
+    let mut turn: Vec<DisplayMessage> = Vec::new();
+    let mut turn_usage = TurnUsage::default();
+
+    for line in reader.lines() {
+        let line = match line {
+            Ok(l) => l,
+            Err(_) => continue,
+        };
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        let row: Value = match serde_json::from_str(trimmed) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        if let Some(usage) = turn_usage.observe(&row) {
+            attach_turn_usage(turn.iter_mut(), usage);
+            turn.drain(..).try_for_each(&mut *f)?;
+            continue;
+        }
+        if let Some(message) = display_message_from_row(&row) {
+            turn.push(message);
+        }
+    }
+    turn.into_iter().try_for_each(f)
+}
+
 /// Load `[start, end)` slice for the windowed message view. Mirrors the
 /// claude implementation: serve from the partial-range cache when
 /// possible, otherwise full-parse + memoize.
@@ -1260,6 +1372,8 @@ fn parse_tail_messages(path: &Path, page: usize, page_size: usize) -> Result<Pag
     let window_len = tail_window_len(page, page_size);
     let mut tail_messages: VecDeque<DisplayMessage> = VecDeque::with_capacity(window_len);
     let mut total = 0usize;
+    let mut turn_usage = TurnUsage::default();
+    let mut turn_start = 0usize;
 
     for line in reader.lines() {
         let line = match line {
@@ -1276,6 +1390,15 @@ fn parse_tail_messages(path: &Path, page: usize, page_size: usize) -> Result<Pag
             Err(_) => continue,
         };
 
+        if let Some(usage) = turn_usage.observe(&row) {
+            // Window index of the first message of this turn still retained.
+            let window_start = total - tail_messages.len();
+            let skip = turn_start.saturating_sub(window_start);
+            attach_turn_usage(tail_messages.iter_mut().skip(skip), usage);
+            turn_start = total;
+            continue;
+        }
+
         if let Some(message) = display_message_from_row(&row) {
             total += 1;
             if tail_messages.len() == window_len {
@@ -1328,6 +1451,7 @@ fn display_message_from_row(row: &Value) -> Option<DisplayMessage> {
                 timestamp,
                 model: None,
                 content: content_blocks,
+                usage: None,
             })
         }
         "function_call" => {
@@ -1368,6 +1492,7 @@ fn display_message_from_row(row: &Value) -> Option<DisplayMessage> {
                     arguments: truncate_string(&arguments, MAX_ARGS_SIZE),
                     call_id,
                 }],
+                usage: None,
             })
         }
         "function_call_output" => {
@@ -1397,6 +1522,7 @@ fn display_message_from_row(row: &Value) -> Option<DisplayMessage> {
                     call_id,
                     output: truncate_string(&output, MAX_OUTPUT_BLOCK_SIZE),
                 }],
+                usage: None,
             })
         }
         "reasoning" => {
@@ -1428,6 +1554,7 @@ fn display_message_from_row(row: &Value) -> Option<DisplayMessage> {
                 timestamp,
                 model: None,
                 content: vec![DisplayContentBlock::Reasoning { text }],
+                usage: None,
             })
         }
         _ => None,
@@ -1628,8 +1755,7 @@ fn extract_token_events(path: &Path) -> Vec<TokenEvent> {
     };
     let reader = BufReader::new(file);
     let mut events: Vec<TokenEvent> = Vec::new();
-    let mut prev_input: u64 = 0;
-    let mut prev_output: u64 = 0;
+    let mut deltas = TokenDeltas::default();
 
     for line in reader.lines() {
         let line = match line {
@@ -1653,63 +1779,115 @@ fn extract_token_events(path: &Path) -> Vec<TokenEvent> {
         let Some(payload) = row.get("payload") else {
             continue;
         };
-        if payload.get("type").and_then(|v| v.as_str()) != Some("token_count") {
+        let Some((delta_input, delta_output)) = deltas.next(payload) else {
             continue;
-        }
+        };
 
-        // Prefer the per-turn `last_token_usage` field when present; otherwise
-        // derive deltas from cumulative totals.
         let timestamp = row
             .get("timestamp")
             .and_then(|v| v.as_str())
             .unwrap_or("")
             .to_string();
 
+        events.push(TokenEvent {
+            timestamp,
+            input_tokens: delta_input,
+            output_tokens: delta_output,
+        });
+    }
+
+    events
+}
+
+struct TokenEvent {
+    timestamp: String,
+    input_tokens: u64,
+    output_tokens: u64,
+}
+
+/// Per-request `(input, output)` deltas from `token_count` event payloads.
+#[derive(Default)]
+struct TokenDeltas {
+    prev_input: u64,
+    prev_output: u64,
+}
+
+impl TokenDeltas {
+    /// `None` for anything but a `token_count` payload with non-zero usage.
+    fn next(&mut self, payload: &Value) -> Option<(u64, u64)> {
+        if payload.get("type").and_then(|v| v.as_str()) != Some("token_count") {
+            return None;
+        }
+
+        // Prefer the per-turn `last_token_usage` field when present; otherwise
+        // derive deltas from cumulative totals.
         let info = payload.get("info");
         let last = info.and_then(|i| i.get("last_token_usage"));
         let total = info.and_then(|i| i.get("total_token_usage"));
+        let tokens = |v: &Value, key: &str| v.get(key).and_then(|t| t.as_u64()).unwrap_or(0);
 
         let (delta_input, delta_output) = if let Some(last) = last {
-            (
-                last.get("input_tokens").and_then(|v| v.as_u64()).unwrap_or(0),
-                last.get("output_tokens").and_then(|v| v.as_u64()).unwrap_or(0),
-            )
+            (tokens(last, "input_tokens"), tokens(last, "output_tokens"))
         } else if let Some(total) = total {
-            let cur_input = total
-                .get("input_tokens")
-                .and_then(|v| v.as_u64())
-                .unwrap_or(0);
-            let cur_output = total
-                .get("output_tokens")
-                .and_then(|v| v.as_u64())
-                .unwrap_or(0);
-            let di = cur_input.saturating_sub(prev_input);
-            let do_ = cur_output.saturating_sub(prev_output);
-            prev_input = cur_input;
-            prev_output = cur_output;
+            let cur_input = tokens(total, "input_tokens");
+            let cur_output = tokens(total, "output_tokens");
+            let di = cur_input.saturating_sub(self.prev_input);
+            let do_ = cur_output.saturating_sub(self.prev_output);
+            self.prev_input = cur_input;
+            self.prev_output = cur_output;
             (di, do_)
         } else {
-            continue;
+            return None;
         };
 
         if delta_input == 0 && delta_output == 0 {
-            continue;
+            return None;
         }
-
-        events.push(TokenEvent {
-            timestamp,
-            input_tokens: delta_input,
-            output_tokens: delta_output,
-        });
+        Some((delta_input, delta_output))
     }
+}
 
-    events
+/// Turns `token_count` events into per-message usage while streaming a
+/// rollout. Each event follows the model response it reports on, so its
+/// usage goes to the last assistant message since the previous event.
+#[derive(Default)]
+struct TurnUsage {
+    deltas: TokenDeltas,
+    /// From the latest `turn_context` row; used for pricing.
+    model: Option<String>,
+}
+
+impl TurnUsage {
+    /// Usage reported by `row` if it is a `token_count` event.
+    fn observe(&mut self, row: &Value) -> Option<MessageUsage> {
+        let payload = row.get("payload")?;
+        match row.get("type").and_then(|v| v.as_str()) {
+            Some("turn_context") => {
+                if let Some(model) = payload.get("model").and_then(|v| v.as_str()) {
+                    self.model = Some(model.to_string());
+                }
+                None
+            }
+            Some("event_msg") => {
+                let (input, output) = self.deltas.next(payload)?;
+                // Codex folds cache hits into `input_tokens`; see `pricing`.
+                let model = self.model.as_deref().unwrap_or("unknown");
+                Some(MessageUsage::priced(model, input, output, 0, 0))
+            }
+            _ => None,
+        }
+    }
 }
 
-struct TokenEvent {
-    timestamp: String,
-    input_tokens: u64,
-    output_tokens: u64,
+/// Put `usage` on the last assistant message of `turn` (the messages since
+/// the previous `token_count` event).
+fn attach_turn_usage<'a>(
+    turn: impl DoubleEndedIterator<Item = &'a mut DisplayMessage>,
+    usage: MessageUsage,
+) {
+    if let Some(message) = turn.rev().find(|m| m.role == "assistant") {
+        message.usage = Some(usage);
+    }
 }
 
 /// Collect per-turn request records across all rollouts. Codex doesn't
@@ -1720,10 +1898,10 @@ pub fn collect_requests() -> Result<Vec<crate::models::stats::RequestRecord>, St
     use crate::models::pricing;
     use crate::models::stats::RequestRecord;
 
-    let files = scan_all_session_files();
+    let files = scan_session_files_by_root();
     let mut all: Vec<RequestRecord> = Vec::new();
 
-    for file_path in files {
+    for (root, file_path) in files {
         let meta = extract_session_meta(&file_path);
         let session_id = meta
             .as_ref()
@@ -1732,13 +1910,14 @@ pub fn collect_requests() -> Result<Vec<crate::models::stats::RequestRecord>, St
         let project_id = meta
             .as_ref()
             .map(|m| {
-                if m.cwd.is_empty() {
+                let key = if m.cwd.is_empty() {
                     let date = extract_date_from_path(&file_path)
                         .unwrap_or_else(|| "unknown".to_string());
                     virtual_project_id(&date)
                 } else {
                     m.cwd.clone()
-                }
+                };
+                scoped_id(&root, &key)
             })
             .unwrap_or_default();
         let model = meta
@@ -1862,6 +2041,222 @@ struct DailyAccum {
 }
 
 
+// ── apply_patch file changes ──
+
+const BEGIN_PATCH: &str = "*** Begin Patch";
+const END_PATCH: &str = "*** End Patch";
+
+/// Find the `apply_patch` envelope in a tool call's input, whichever way it
+/// was sent: `apply_patch` custom/function call input, or `shell` argv /
+/// heredoc.
+fn find_patch_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => {
+            let start = s.find(BEGIN_PATCH)?;
+            let end = s[start..]
+                .find(END_PATCH)
+                .map(|i| start + i + END_PATCH.len())
+                .unwrap_or(s.len());
+            Some(s[start..end].to_string())
+        }
+        Value::Array(items) => items.iter().find_map(find_patch_text),
+        Value::Object(map) => map.values().find_map(find_patch_text),
+        _ => None,
+    }
+}
+
+/// Split an `apply_patch` envelope into (path, moved from, body) per file.
+fn parse_apply_patch(text: &str) -> Vec<(String, Option<String>, FileChangeBody)> {
+    enum Pending {
+        Add(String, Vec<String>),
+        Update {
+            path: String,
+            move_to: Option<String>,
+            chunks: Vec<(String, String)>,
+        },
+    }
+
+    fn finish(pending: Option<Pending>, out: &mut Vec<(String, Option<String>, FileChangeBody)>) {
+        match pending {
+            Some(Pending::Add(path, lines)) => {
+                let mut content = lines.join("\n");
+                content.push('\n');
+                out.push((
+                    path,
+                    None,
+                    FileChangeBody::Contents {
+                        before: None,
+                        after: Some(content),
+                    },
+                ));
+            }
+            Some(Pending::Update {
+                path,
+                move_to,
+                chunks,
+            }) => match move_to {
+                Some(to) => out.push((to, Some(path), FileChangeBody::Fragments(chunks))),
+                None => out.push((path, None, FileChangeBody::Fragments(chunks))),
+            },
+            None => {}
+        }
+    }
+
+    let mut out = Vec::new();
+    let mut pending: Option<Pending> = None;
+    for line in text.lines() {
+        if let Some(path) = line.strip_prefix("*** Add File: ") {
+            finish(pending.take(), &mut out);
+            pending = Some(Pending::Add(path.trim().to_string(), Vec::new()));
+        } else if let Some(path) = line.strip_prefix("*** Delete File: ") {
+            finish(pending.take(), &mut out);
+            out.push((path.trim().to_string(), None, FileChangeBody::Deleted));
+        } else if let Some(path) = line.strip_prefix("*** Update File: ") {
+            finish(pending.take(), &mut out);
+            pending = Some(Pending::Update {
+                path: path.trim().to_string(),
+                move_to: None,
+                chunks: Vec::new(),
+            });
+        } else if line.starts_with("*** ") {
+            // `*** Move to:` is handled below; Begin / End Patch and
+            // `*** End of File` carry nothing.
+            if let (Some(to), Some(Pending::Update { move_to, .. })) =
+                (line.strip_prefix("*** Move to: "), pending.as_mut())
+            {
+                *move_to = Some(to.trim().to_string());
+            }
+        } else {
+            match pending.as_mut() {
+                Some(Pending::Add(_, lines)) => {
+                    lines.push(line.strip_prefix('+').unwrap_or(line).to_string());
+                }
+                Some(Pending::Update { chunks, .. }) => {
+                    if line.starts_with("@@") {
+                        chunks.push((String::new(), String::new()));
+                        continue;
+                    }
+                    if chunks.is_empty() {
+                        chunks.push((String::new(), String::new()));
+                    }
+                    let Some((old, new)) = chunks.last_mut() else {
+                        continue;
+                    };
+                    if let Some(removed) = line.strip_prefix('-') {
+                        old.push_str(removed);
+                        old.push('\n');
+                    } else if let Some(added) = line.strip_prefix('+') {
+                        new.push_str(added);
+                        new.push('\n');
+                    } else {
+                        // Context; a blank line may have lost its leading space.
+                        let context = line.strip_prefix(' ').unwrap_or(line);
+                        old.push_str(context);
+                        old.push('\n');
+                        new.push_str(context);
+                        new.push('\n');
+                    }
+                }
+                None => {}
+            }
+        }
+    }
+    finish(pending, &mut out);
+    out
+}
+
+/// Whether a tool call output reports a non-zero exit code.
+fn output_failed(output: &Value) -> bool {
+    let parsed = match output {
+        Value::String(s) => serde_json::from_str::<Value>(s).ok(),
+        other => Some(other.clone()),
+    };
+    parsed
+        .as_ref()
+        .and_then(|v| v.pointer("/metadata/exit_code"))
+        .and_then(|c| c.as_i64())
+        .is_some_and(|code| code != 0)
+}
+
+/// File changes made by the session's `apply_patch` calls, in order.
+/// Failed calls are left out.
+pub fn extract_file_changes(path: &Path) -> Result<SessionFileChanges, String> {
+    let file = fs::File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let reader = BufReader::new(file);
+    let mut result = SessionFileChanges::default();
+    let mut failed: HashSet<String> = HashSet::new();
+    let mut turn = 0usize;
+    let mut turn_prompt: Option<String> = None;
+
+    for line in reader.lines() {
+        let line = match line {
+            Ok(l) => l,
+            Err(_) => continue,
+        };
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let row: Value = match serde_json::from_str(trimmed) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        let row_type = row.get("type").and_then(|v| v.as_str()).unwrap_or("");
+        let Some(payload) = row.get("payload") else {
+            continue;
+        };
+        let payload_type = payload.get("type").and_then(|v| v.as_str()).unwrap_or("");
+
+        match (row_type, payload_type) {
+            ("session_meta", _) if result.cwd.is_none() => {
+                result.cwd = payload.get("cwd").and_then(|v| v.as_str()).map(String::from);
+            }
+            ("event_msg", "user_message") => {
+                turn += 1;
+                turn_prompt = payload.get("message").and_then(|v| v.as_str()).map(String::from);
+            }
+            ("response_item", "function_call" | "custom_tool_call") => {
+                let input = match payload_type {
+                    "custom_tool_call" => payload.get("input").cloned(),
+                    _ => payload.get("arguments").map(|a| match a.as_str() {
+                        Some(s) => serde_json::from_str(s).unwrap_or_else(|_| a.clone()),
+                        None => a.clone(),
+                    }),
+                };
+                let Some(patch) = input.as_ref().and_then(find_patch_text) else {
+                    continue;
+                };
+                let call_id = payload.get("call_id").and_then(|v| v.as_str()).unwrap_or("");
+                let timestamp = row.get("timestamp").and_then(|v| v.as_str()).map(String::from);
+                for (file_path, old_path, body) in parse_apply_patch(&patch) {
+                    result.changes.push(FileChange {
+                        tool_call_id: call_id.to_string(),
+                        // Also when sent through `shell`.
+                        tool_name: "apply_patch".to_string(),
+                        timestamp: timestamp.clone(),
+                        turn,
+                        turn_prompt: turn_prompt.clone(),
+                        path: file_path,
+                        old_path,
+                        body,
+                    });
+                }
+            }
+            ("response_item", "function_call_output" | "custom_tool_call_output")
+                if payload.get("output").is_some_and(output_failed) =>
+            {
+                if let Some(call_id) = payload.get("call_id").and_then(|v| v.as_str()) {
+                    failed.insert(call_id.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    result.changes.retain(|c| !failed.contains(&c.tool_call_id));
+    Ok(result)
+}
+
 fn truncate_string(s: &str, max_len: usize) -> String {
     if s.len() <= max_len {
         s.to_string()
@@ -1870,3 +2265,278 @@ fn truncate_string(s: &str, max_len: usize) -> String {
         format!("{}...", truncated)
     }
 }
+
+// ── SessionProvider ──
+
+pub struct CodexProvider;
+
+impl SessionProvider for CodexProvider {
+    fn id(&self) -> &'static str {
+        "codex"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Codex"
+    }
+
+    fn get_projects(&self) -> Result<Vec<ProjectEntry>, String> {
+        get_projects()
+    }
+
+    /// Codex 的项目列表本身就由 file_index 增量维护，刷新即重新读取。
+    fn refresh_projects_cache(&self) -> Result<Vec<ProjectEntry>, String> {
+        get_projects()
+    }
+
+    fn delete_project(
+        &self,
+        project_id: &str,
+        _level: DeleteLevel,
+    ) -> Result<DeleteResult, String> {
+        delete_project(project_id)
+    }
+
+    fn get_sessions(&self, project_id: &str) -> Result<Vec<SessionIndexEntry>, String> {
+        get_sessions(project_id)
+    }
+
+    fn refresh_sessions_cache(&self, project_id: &str) -> Result<Vec<SessionIndexEntry>, String> {
+        refresh_sessions_cache(project_id)
+    }
+
+    fn get_invalid_sessions(&self, project_id: &str) -> Result<Vec<SessionIndexEntry>, String> {
+        get_invalid_sessions(project_id)
+    }
+
+    fn rename_chat_session(
+        &self,
+        project_path: &str,
+        session_id: &str,
+        alias: Option<&str>,
+    ) -> Result<String, String> {
+        // Aliases live in the global metadata file; the project id is the cwd.
+        crate::metadata::set_session_alias(self.id(), project_path, session_id, alias)?;
+        Ok(project_path.to_string())
+    }
+
+    fn verify_session_owner(
+        &self,
+        path: &Path,
+        project_id: Option<&str>,
+        session_id: Option<&str>,
+    ) -> Result<(), String> {
+        let session_meta = extract_session_meta(path)
+            .ok_or_else(|| "Failed to read Codex session metadata".to_string())?;
+
+        if let Some(pid) = project_id {
+            if session_meta.cwd != split_scoped_id(pid).1 {
+                return Err(
+                    "Session file does not belong to the requested Codex project".to_string(),
+                );
+            }
+        }
+
+        if let Some(sid) = session_id {
+            if session_meta.id != sid {
+                return Err(
+                    "Session id does not match the requested Codex session file".to_string(),
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    fn parse_session_messages(
+        &self,
+        path: &Path,
+        page: usize,
+        page_size: usize,
+        from_end: bool,
+    ) -> Result<PaginatedMessages, String> {
+        parse_session_messages(path, page, page_size, from_end)
+    }
+
+    fn parse_messages_range(
+        &self,
+        path: &Path,
+        start: usize,
+        end: usize,
+    ) -> Result<RangeMessages, String> {
+        parse_messages_range(path, start, end)
+    }
+
+    fn parse_all_messages(&self, path: &Path) -> Result<Vec<DisplayMessage>, String> {
+        parse_all_messages(path)
+    }
+
+    fn for_each_message(
+        &self,
+        path: &Path,
+        f: &mut dyn FnMut(DisplayMessage) -> Result<(), String>,
+    ) -> Result<(), String> {
+        for_each_message(path, f)
+    }
+
+    fn file_changes(&self, path: &Path) -> Result<SessionFileChanges, String> {
+        extract_file_changes(path)
+    }
+
+    fn search_targets(&self) -> Vec<SearchTarget> {
+        let files = scan_session_files_by_root();
+        // Pre-load codex metadata (single file for all sessions)
+        let codex_meta = crate::metadata::load_metadata("codex", "");
+        // Pre-load Codex Desktop thread titles (session_index.jsonl, one small file)
+        let thread_names = load_thread_names();
+
+        files
+            .into_par_iter()
+            .map(|(root, file_path)| {
+                let (session_id, cwd, git_branch) = match extract_session_meta(&file_path) {
+                    Some(m) => (m.id, m.cwd, m.git_branch),
+                    None => {
+                        let stem = file_path
+                            .file_stem()
+                            .and_then(|s| s.to_str())
+                            .unwrap_or("")
+                            .to_string();
+                        (stem, String::new(), None)
+                    }
+                };
+                let project_name = cwd
+                    .rsplit(['/', '\\'])
+                    .find(|s| !s.is_empty())
+                    .unwrap_or(&cwd)
+                    .to_string();
+
+                let session_meta = codex_meta.sessions.get(&session_id);
+                let alias = session_meta.and_then(|s| s.alias.clone());
+                let tags = session_meta
+                    .map(|s| s.tags.clone())
+                    .filter(|t| !t.is_empty());
+
+                let mut target = SearchTarget {
+                    thread_name: thread_names.get(&session_id).cloned(),
+                    project_id: scoped_id(&root, &cwd),
+                    project_name,
+                    session_id,
+                    file_path,
+                    alias: alias.clone(),
+                    search_aliases: Vec::with_capacity(1),
+                    tags,
+                    git_branch,
+                };
+                target.push_search_alias(alias);
+                target
+            })
+            .collect()
+    }
+
+    fn get_stats(&self) -> Result<TokenUsageSummary, String> {
+        get_stats()
+    }
+
+    fn collect_requests(&self) -> Result<Vec<RequestRecord>, String> {
+        collect_requests()
+    }
+
+    fn session_roots(&self) -> Vec<PathBuf> {
+        sessions_dirs().into_iter().map(|(_, dir)| dir).collect()
+    }
+
+    fn validate_layout(&self, path: &Path, base: &Path) -> Result<(), String> {
+        let relative = path
+            .strip_prefix(base)
+            .map_err(|_| "Session file is outside the Codex sessions directory".to_string())?;
+        let components: Vec<_> = relative.components().collect();
+        if components.len() != 4
+            || components.iter().any(|c| !matches!(c, Component::Normal(_)))
+        {
+            return Err(
+                "Codex session file must live under sessions/<year>/<month>/<day>/".to_string(),
+            );
+        }
+        let file_name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| "Invalid session file name".to_string())?;
+        if !file_name.starts_with("rollout-") {
+            return Err("Codex session file name must start with 'rollout-'".to_string());
+        }
+        Ok(())
+    }
+
+    /// Codex 没有项目目录，所有会话的元数据存放在 `~/.codex/` 下的同一个文件里。
+    /// 会话 id 是 UUID，其他数据根目录的会话也记在这里，不写进同步来的副本。
+    fn metadata_path(&self, _project_id: &str) -> Option<PathBuf> {
+        Some(get_codex_home()?.join(crate::metadata::METADATA_FILE_NAME))
+    }
+
+    fn metadata_project_ids(&self) -> Vec<String> {
+        vec![String::new()]
+    }
+
+    fn invalidate_cache(&self) {
+        invalidate_sessions_cache();
+        crate::search::mark_unsynced(self.id());
+    }
+
+    fn invalidate_paths(&self, changed: &[PathBuf]) {
+        invalidate_paths(changed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_count_events_price_the_last_assistant_message_of_their_turn() {
+        let dir = std::env::temp_dir().join(format!("asv-codex-usage-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let rollout = dir.join("rollout.jsonl");
+        let message = |role: &str, text: &str| {
+            format!(
+                r#"{{"type":"response_item","payload":{{"type":"message","role":"{}","content":[{{"type":"output_text","text":"{}"}}]}}}}"#,
+                role, text
+            )
+        };
+        let lines = [
+            r#"{"type":"turn_context","payload":{"model":"gpt-5"}}"#.to_string(),
+            message("user", "u1"),
+            message("assistant", "a1"),
+            message("assistant", "a2"),
+            // Older builds only report cumulative totals.
+            r#"{"type":"event_msg","payload":{"type":"token_count","info":{"total_token_usage":{"input_tokens":100,"output_tokens":10}}}}"#.to_string(),
+            message("user", "u2"),
+            message("assistant", "a3"),
+            r#"{"type":"event_msg","payload":{"type":"token_count","info":{"total_token_usage":{"input_tokens":160,"output_tokens":20}}}}"#.to_string(),
+            message("user", "u3"),
+            message("assistant", "a4"),
+            r#"{"type":"event_msg","payload":{"type":"token_count","info":{"last_token_usage":{"input_tokens":30,"output_tokens":5},"total_token_usage":{"input_tokens":190,"output_tokens":25}}}}"#.to_string(),
+        ];
+        fs::write(&rollout, lines.join("\n")).unwrap();
+
+        // A one-message page warms a 4-message tail window (u2..a4), so the
+        // cumulative totals before it still have to be followed.
+        parse_tail_messages(&rollout, 0, 1).unwrap();
+        let (window, total) = crate::state::get_cached_range(&rollout, 3, 7)
+            .unwrap()
+            .unwrap();
+        assert_eq!(total, 7);
+
+        let full = parse_all_messages(&rollout).unwrap();
+        let usage = |m: &DisplayMessage| m.usage.map(|u| u.total_tokens);
+        assert_eq!(
+            full.iter().map(usage).collect::<Vec<_>>(),
+            [None, None, Some(110), None, Some(70), None, Some(35)]
+        );
+        assert_eq!(full[2].usage.unwrap().input_tokens, 100);
+        assert_eq!(
+            window.iter().map(usage).collect::<Vec<_>>(),
+            full[3..].iter().map(usage).collect::<Vec<_>>()
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
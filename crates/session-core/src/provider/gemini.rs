@@ -0,0 +1,1117 @@
+//! Gemini CLI 数据源。
+//!
+//! Gemini CLI 把每个项目的数据放在 `~/.gemini/tmp/<project-hash>/` 下，
+//! `<project-hash>` 是项目根目录绝对路径的 SHA-256，无法反推：
+//!   - `chats/session-*.json`：自动记录的完整对话（ConversationRecord），
+//!     assistant 消息带 `tokens` 统计，是 token / 花费的唯一来源
+//!   - `checkpoint-<tag>.json`：`/chat save <tag>` 保存的检查点，内容是 Gemini API
+//!     的 `Content[]`（`role` + `parts`），没有时间戳和 token
+//!   - `logs.json`：用户输入日志（只有 user 消息），整体作为一个「输入日志」会话展示
+//!
+//! 项目 id 就是 hash 目录名。展示路径优先读目录下的 `.project_root`，读不到时
+//! 只能显示 hash 前缀。会话文件是整文件 JSON、数量也少，这里不做磁盘列表缓存，
+//! 只复用全局消息 LRU。
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use rayon::prelude::*;
+use serde_json::Value;
+
+use crate::data_roots::{scoped_id, split_scoped_id, DEFAULT_ROOT};
+use crate::models::message::{
+    DisplayContentBlock, DisplayMessage, MessageUsage, PaginatedMessages, RangeMessages,
+};
+use crate::models::pricing;
+use crate::models::project::ProjectEntry;
+use crate::models::session::{SessionIndexEntry, SessionStatus};
+use crate::models::stats::{RequestRecord, TokenUsageSummary};
+use crate::parser::path_encoder::short_name_from_path;
+use crate::state::{clear_message_cache_for_path, get_cached_full_messages, store_full_messages};
+
+use super::claude::{DeleteLevel, DeleteResult};
+use super::{paginate_all, slice_range, SearchTarget, SessionProvider};
+
+const MAX_TEXT_BLOCK_SIZE: usize = 20_000;
+const MAX_OUTPUT_BLOCK_SIZE: usize = 30_000;
+const MAX_ARGS_SIZE: usize = 10_000;
+
+/// Gemini CLI 在检查点开头注入的环境上下文（目录结构、日期等）和模型的固定应答。
+/// 不是用户写的内容，展示时跳过。
+const SETUP_CONTEXT_PREFIX: &str = "This is the Gemini CLI. We are setting up the context";
+const SETUP_CONTEXT_ACK: &str = "Got it. Thanks for the context!";
+
+const LOGS_SESSION_ID: &str = "logs";
+
+// ── Directory scanning ──
+
+fn get_gemini_home() -> Option<PathBuf> {
+    dirs::home_dir().map(|h| h.join(".gemini"))
+}
+
+/// `~/.gemini/tmp` — 所有项目 hash 目录的父目录。
+pub fn get_tmp_dir() -> Option<PathBuf> {
+    get_gemini_home().map(|h| h.join("tmp"))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileKind {
+    Chat,
+    Checkpoint,
+    Logs,
+}
+
+fn file_kind(path: &Path) -> Option<FileKind> {
+    let name = path.file_name()?.to_str()?;
+    if !name.ends_with(".json") {
+        return None;
+    }
+    let parent = path.parent()?.file_name()?.to_str()?;
+    if parent == "chats" {
+        return name.starts_with("session-").then_some(FileKind::Chat);
+    }
+    if name == "logs.json" {
+        return Some(FileKind::Logs);
+    }
+    name.starts_with("checkpoint").then_some(FileKind::Checkpoint)
+}
+
+/// `tmp/` 目录：本机 `~/.gemini`（根目录 `local`）在前，其后是配置的数据根目录。
+fn tmp_dirs() -> Vec<(String, PathBuf)> {
+    crate::data_roots::resolve_roots("gemini", get_gemini_home())
+        .into_iter()
+        .map(|root| (root.name, root.path.join("tmp")))
+        .collect()
+}
+
+/// 所有根目录下的项目 hash 目录，带上（按根目录加前缀的）项目 id。
+fn project_dirs() -> Vec<(String, PathBuf)> {
+    let mut dirs = Vec::new();
+    for (root, tmp_dir) in tmp_dirs() {
+        let Ok(entries) = fs::read_dir(&tmp_dir) else {
+            continue;
+        };
+        for path in entries.flatten().map(|e| e.path()) {
+            if !path.is_dir() {
+                continue;
+            }
+            if let Some(hash) = path.file_name().and_then(|n| n.to_str()) {
+                dirs.push((scoped_id(&root, hash), path.clone()));
+            }
+        }
+    }
+    dirs
+}
+
+/// 项目目录下的全部会话文件：顶层的 checkpoint / logs.json，以及 `chats/` 下的对话记录。
+fn session_files(project_dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for dir in [project_dir.to_path_buf(), project_dir.join("chats")] {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_file() && file_kind(&path).is_some() {
+                files.push(path);
+            }
+        }
+    }
+    files
+}
+
+/// Resolve a (possibly `@root:`-scoped) project hash to its canonical
+/// directory under that root's `tmp/`.
+pub fn resolve_project_dir(project_id: &str) -> Result<PathBuf, String> {
+    let (root, hash) = split_scoped_id(project_id);
+    if !crate::metadata::is_single_normal_component(hash) {
+        return Err(format!("Invalid project id: {}", project_id));
+    }
+    let base = tmp_dirs()
+        .into_iter()
+        .find(|(name, _)| name == root)
+        .map(|(_, dir)| dir)
+        .ok_or_else(|| format!("Unknown Gemini data root: {}", root))?
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve Gemini tmp directory: {}", e))?;
+    let canonical = base
+        .join(hash)
+        .canonicalize()
+        .map_err(|_| format!("Project directory not found: {}", project_id))?;
+    if !canonical.is_dir() || canonical.parent() != Some(base.as_path()) {
+        return Err(format!("Invalid project id: {}", project_id));
+    }
+    Ok(canonical)
+}
+
+/// 项目展示路径：`.project_root` 里记录的真实路径，否则退回 hash 前缀。
+fn project_display_path(project_dir: &Path) -> (String, bool) {
+    let root = fs::read_to_string(project_dir.join(".project_root"))
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+    match root {
+        Some(root) => {
+            let exists = Path::new(&root).exists();
+            (root, exists)
+        }
+        None => {
+            let hash = project_dir
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or_default();
+            let short: String = hash.chars().take(12).collect();
+            (format!("gemini:{}", short), false)
+        }
+    }
+}
+
+fn systemtime_to_rfc3339(t: std::time::SystemTime) -> String {
+    let d = t.duration_since(std::time::UNIX_EPOCH).unwrap_or_default();
+    chrono::DateTime::from_timestamp(d.as_secs() as i64, d.subsec_nanos())
+        .map(|dt| dt.to_rfc3339())
+        .unwrap_or_default()
+}
+
+fn file_times(path: &Path) -> (Option<String>, Option<String>) {
+    let meta = fs::metadata(path).ok();
+    let created = meta
+        .as_ref()
+        .and_then(|m| m.created().ok())
+        .map(systemtime_to_rfc3339);
+    let modified = meta
+        .as_ref()
+        .and_then(|m| m.modified().ok())
+        .map(systemtime_to_rfc3339);
+    (created, modified)
+}
+
+// ── Parsing ──
+
+/// Per-assistant-turn token usage from a ConversationRecord `tokens` object.
+struct TokenUsage {
+    timestamp: String,
+    model: String,
+    message_id: Option<String>,
+    input: u64,
+    output: u64,
+    cached: u64,
+    thoughts: u64,
+    tool: u64,
+}
+
+#[derive(Default)]
+struct ParsedSession {
+    session_id: String,
+    start_time: Option<String>,
+    last_updated: Option<String>,
+    messages: Vec<DisplayMessage>,
+    usage: Vec<TokenUsage>,
+}
+
+fn truncate_string(s: &str, max_len: usize) -> String {
+    if s.len() <= max_len {
+        return s.to_string();
+    }
+    let mut end = max_len;
+    while end > 0 && !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}...", &s[..end])
+}
+
+fn str_field<'a>(v: &'a Value, key: &str) -> Option<&'a str> {
+    v.get(key).and_then(|v| v.as_str())
+}
+
+fn pretty_json(v: &Value) -> String {
+    if let Some(s) = v.as_str() {
+        return s.to_string();
+    }
+    serde_json::to_string_pretty(v).unwrap_or_else(|_| v.to_string())
+}
+
+/// `content` 既可能是纯字符串，也可能是 `Part[]`（新版 CLI）。
+fn content_text(v: &Value) -> String {
+    match v {
+        Value::String(s) => s.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .filter(|p| !p.get("thought").and_then(|t| t.as_bool()).unwrap_or(false))
+            .filter_map(|p| str_field(p, "text"))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Value::Object(_) => str_field(v, "text").unwrap_or("").to_string(),
+        _ => String::new(),
+    }
+}
+
+/// 工具调用结果：`functionResponse.response` 里的 `output` / `error`，否则整体转 JSON。
+fn function_response_text(response: &Value) -> (String, bool) {
+    if let Some(err) = response.get("error") {
+        return (pretty_json(err), true);
+    }
+    match response.get("output") {
+        Some(out) => (pretty_json(out), false),
+        None => (pretty_json(response), false),
+    }
+}
+
+fn tool_call_result(call: &Value) -> (String, bool) {
+    let status_error = str_field(call, "status") == Some("error");
+    let from_parts = call.get("result").and_then(|r| r.as_array()).and_then(|parts| {
+        parts.iter().find_map(|p| {
+            p.get("functionResponse")
+                .and_then(|fr| fr.get("response"))
+                .map(function_response_text)
+        })
+    });
+    let (text, is_error) = from_parts
+        .or_else(|| {
+            call.get("result")
+                .and_then(|r| r.as_str())
+                .map(|s| (s.to_string(), false))
+        })
+        .or_else(|| {
+            str_field(call, "resultDisplay").map(|s| (s.to_string(), false))
+        })
+        .unwrap_or_default();
+    (
+        truncate_string(&text, MAX_OUTPUT_BLOCK_SIZE),
+        is_error || status_error,
+    )
+}
+
+fn parse_chat_record(root: &Value, path: &Path) -> ParsedSession {
+    let mut parsed = ParsedSession {
+        session_id: str_field(root, "sessionId")
+            .map(str::to_string)
+            .or_else(|| path.file_stem().and_then(|s| s.to_str()).map(str::to_string))
+            .unwrap_or_default(),
+        start_time: str_field(root, "startTime").map(str::to_string),
+        last_updated: str_field(root, "lastUpdated").map(str::to_string),
+        ..Default::default()
+    };
+
+    let Some(records) = root.get("messages").and_then(|m| m.as_array()) else {
+        return parsed;
+    };
+
+    let mut prev_uuid: Option<String> = None;
+    for msg in records {
+        let id = str_field(msg, "id").map(str::to_string);
+        let timestamp = str_field(msg, "timestamp").map(str::to_string);
+        match str_field(msg, "type").unwrap_or("") {
+            "user" => {
+                let text = content_text(msg.get("content").unwrap_or(&Value::Null));
+                if text.trim().is_empty() {
+                    continue;
+                }
+                parsed.messages.push(DisplayMessage {
+                    uuid: id.clone(),
+                    parent_uuid: prev_uuid.clone(),
+                    role: "user".to_string(),
+                    timestamp,
+                    model: None,
+                    content: vec![DisplayContentBlock::Text {
+                        text: truncate_string(&text, MAX_TEXT_BLOCK_SIZE),
+                    }],
+                    usage: None,
+                });
+                prev_uuid = id;
+            }
+            "gemini" => {
+                let model = str_field(msg, "model").map(str::to_string);
+                let mut blocks = Vec::new();
+
+                if let Some(thoughts) = msg.get("thoughts").and_then(|t| t.as_array()) {
+                    for thought in thoughts {
+                        let subject = str_field(thought, "subject").unwrap_or("").trim();
+                        let description = str_field(thought, "description").unwrap_or("").trim();
+                        let thinking = match (subject.is_empty(), description.is_empty()) {
+                            (true, true) => continue,
+                            (false, true) => format!("**{}**", subject),
+                            (true, false) => description.to_string(),
+                            (false, false) => format!("**{}**\n\n{}", subject, description),
+                        };
+                        blocks.push(DisplayContentBlock::Thinking { thinking });
+                    }
+                }
+
+                let text = content_text(msg.get("content").unwrap_or(&Value::Null));
+                if !text.trim().is_empty() {
+                    blocks.push(DisplayContentBlock::Text {
+                        text: truncate_string(&text, MAX_TEXT_BLOCK_SIZE),
+                    });
+                }
+
+                let calls = msg
+                    .get("toolCalls")
+                    .and_then(|c| c.as_array())
+                    .cloned()
+                    .unwrap_or_default();
+                let mut results = Vec::new();
+                for (i, call) in calls.iter().enumerate() {
+                    let call_id = str_field(call, "id")
+                        .map(str::to_string)
+                        .unwrap_or_else(|| format!("{}-{}", id.as_deref().unwrap_or(""), i));
+                    let name = str_field(call, "name").unwrap_or("unknown").to_string();
+                    let args = call.get("args").map(pretty_json).unwrap_or_default();
+                    blocks.push(DisplayContentBlock::ToolUse {
+                        id: call_id.clone(),
+                        name,
+                        input: truncate_string(&args, MAX_ARGS_SIZE),
+                        subagent: None,
+                    });
+                    if call.get("result").is_some() || call.get("resultDisplay").is_some() {
+                        let (content, is_error) = tool_call_result(call);
+                        results.push(DisplayContentBlock::ToolResult {
+                            tool_use_id: call_id,
+                            content,
+                            is_error,
+                        });
+                    }
+                }
+
+                let mut message_usage = None;
+                if let (Some(tokens), Some(ts)) = (msg.get("tokens"), timestamp.as_ref()) {
+                    let n = |k: &str| tokens.get(k).and_then(|v| v.as_u64()).unwrap_or(0);
+                    let usage = TokenUsage {
+                        timestamp: ts.clone(),
+                        model: model.clone().unwrap_or_else(|| "unknown".to_string()),
+                        message_id: id.clone(),
+                        input: n("input"),
+                        output: n("output"),
+                        cached: n("cached"),
+                        thoughts: n("thoughts"),
+                        tool: n("tool"),
+                    };
+                    let (input, output, cache_read) = usage.billed();
+                    message_usage = Some(MessageUsage::priced(&usage.model, input, output, cache_read, 0));
+                    parsed.usage.push(usage);
+                }
+
+                if blocks.is_empty() {
+                    continue;
+                }
+                parsed.messages.push(DisplayMessage {
+                    uuid: id.clone(),
+                    parent_uuid: prev_uuid.clone(),
+                    role: "assistant".to_string(),
+                    timestamp: timestamp.clone(),
+                    model,
+                    content: blocks,
+                    usage: message_usage,
+                });
+                prev_uuid = id.clone();
+
+                // 工具结果和 Claude 一样单独成一条 role = "tool" 的消息。
+                if !results.is_empty() {
+                    let result_id = id.map(|id| format!("{}-tool", id));
+                    parsed.messages.push(DisplayMessage {
+                        uuid: result_id.clone(),
+                        parent_uuid: prev_uuid.clone(),
+                        role: "tool".to_string(),
+                        timestamp,
+                        model: None,
+                        content: results,
+                        usage: None,
+                    });
+                    prev_uuid = result_id;
+                }
+            }
+            // info / error / warning 是 CLI 自身的提示，不属于对话。
+            _ => {}
+        }
+    }
+
+    parsed
+}
+
+fn checkpoint_blocks(parts: &[Value]) -> Vec<DisplayContentBlock> {
+    let mut blocks = Vec::new();
+    for part in parts {
+        if let Some(text) = str_field(part, "text") {
+            if text.trim().is_empty() {
+                continue;
+            }
+            let text = truncate_string(text, MAX_TEXT_BLOCK_SIZE);
+            if part.get("thought").and_then(|t| t.as_bool()).unwrap_or(false) {
+                blocks.push(DisplayContentBlock::Thinking { thinking: text });
+            } else {
+                blocks.push(DisplayContentBlock::Text { text });
+            }
+        } else if let Some(call) = part.get("functionCall") {
+            let name = str_field(call, "name").unwrap_or("unknown").to_string();
+            let id = str_field(call, "id").map(str::to_string).unwrap_or_else(|| name.clone());
+            let args = call.get("args").map(pretty_json).unwrap_or_default();
+            blocks.push(DisplayContentBlock::ToolUse {
+                id,
+                name,
+                input: truncate_string(&args, MAX_ARGS_SIZE),
+                subagent: None,
+            });
+        } else if let Some(resp) = part.get("functionResponse") {
+            let id = str_field(resp, "id")
+                .or_else(|| str_field(resp, "name"))
+                .unwrap_or("")
+                .to_string();
+            let (content, is_error) = resp
+                .get("response")
+                .map(function_response_text)
+                .unwrap_or_default();
+            blocks.push(DisplayContentBlock::ToolResult {
+                tool_use_id: id,
+                content: truncate_string(&content, MAX_OUTPUT_BLOCK_SIZE),
+                is_error,
+            });
+        }
+    }
+    blocks
+}
+
+fn parse_checkpoint(root: &Value, path: &Path) -> ParsedSession {
+    let mut parsed = ParsedSession {
+        session_id: path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("checkpoint")
+            .to_string(),
+        ..Default::default()
+    };
+    let Some(contents) = root.as_array() else {
+        return parsed;
+    };
+
+    for content in contents {
+        let parts = content
+            .get("parts")
+            .and_then(|p| p.as_array())
+            .map(Vec::as_slice)
+            .unwrap_or(&[]);
+        let first_text = parts.first().and_then(|p| str_field(p, "text")).unwrap_or("");
+        if first_text.starts_with(SETUP_CONTEXT_PREFIX) || first_text.trim() == SETUP_CONTEXT_ACK {
+            continue;
+        }
+
+        let blocks = checkpoint_blocks(parts);
+        if blocks.is_empty() {
+            continue;
+        }
+        let role = match str_field(content, "role") {
+            Some("model") => "assistant",
+            _ if blocks
+                .iter()
+                .all(|b| matches!(b, DisplayContentBlock::ToolResult { .. })) =>
+            {
+                "tool"
+            }
+            _ => "user",
+        };
+        parsed.messages.push(DisplayMessage {
+            uuid: None,
+            parent_uuid: None,
+            role: role.to_string(),
+            timestamp: None,
+            model: None,
+            content: blocks,
+            usage: None,
+        });
+    }
+
+    parsed
+}
+
+fn parse_logs(root: &Value) -> ParsedSession {
+    let mut parsed = ParsedSession {
+        session_id: LOGS_SESSION_ID.to_string(),
+        ..Default::default()
+    };
+    let Some(entries) = root.as_array() else {
+        return parsed;
+    };
+
+    for entry in entries {
+        if str_field(entry, "type") != Some("user") {
+            continue;
+        }
+        let Some(text) = str_field(entry, "message").filter(|t| !t.trim().is_empty()) else {
+            continue;
+        };
+        let uuid = match (str_field(entry, "sessionId"), entry.get("messageId")) {
+            (Some(sid), Some(mid)) => Some(format!("{}-{}", sid, mid)),
+            _ => None,
+        };
+        let timestamp = str_field(entry, "timestamp").map(str::to_string);
+        if parsed.start_time.is_none() {
+            parsed.start_time = timestamp.clone();
+        }
+        parsed.last_updated = timestamp.clone().or(parsed.last_updated);
+        parsed.messages.push(DisplayMessage {
+            uuid,
+            parent_uuid: None,
+            role: "user".to_string(),
+            timestamp,
+            model: None,
+            content: vec![DisplayContentBlock::Text {
+                text: truncate_string(text, MAX_TEXT_BLOCK_SIZE),
+            }],
+            usage: None,
+        });
+    }
+
+    parsed
+}
+
+fn parse_session_file(path: &Path) -> Result<ParsedSession, String> {
+    let kind = file_kind(path).ok_or_else(|| "Not a Gemini session file".to_string())?;
+    let content = fs::read_to_string(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let root: Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse Gemini session: {}", e))?;
+    Ok(match kind {
+        FileKind::Chat => parse_chat_record(&root, path),
+        FileKind::Checkpoint => parse_checkpoint(&root, path),
+        FileKind::Logs => parse_logs(&root),
+    })
+}
+
+// ── Projects / sessions ──
+
+fn first_prompt(messages: &[DisplayMessage]) -> Option<String> {
+    messages
+        .iter()
+        .filter(|m| m.role == "user")
+        .find_map(|m| {
+            m.content.iter().find_map(|b| match b {
+                DisplayContentBlock::Text { text } if !text.trim().is_empty() => {
+                    Some(truncate_string(text.trim(), 200))
+                }
+                _ => None,
+            })
+        })
+}
+
+fn scan_session(path: &Path, project_path: &str) -> SessionIndexEntry {
+    let (created, modified) = file_times(path);
+    let mut entry = SessionIndexEntry {
+        source: "gemini".to_string(),
+        session_id: path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("")
+            .to_string(),
+        file_path: path.to_string_lossy().to_string(),
+        first_prompt: None,
+        thread_name: None,
+        message_count: 0,
+        created,
+        modified,
+        git_branch: None,
+        project_path: Some(project_path.to_string()),
+        is_sidechain: None,
+        cwd: None,
+        model_provider: None,
+        cli_version: None,
+        alias: None,
+        tags: None,
+        status: SessionStatus::Corrupt,
+    };
+
+    let Ok(parsed) = parse_session_file(path) else {
+        return entry;
+    };
+    entry.session_id = parsed.session_id;
+    entry.first_prompt = first_prompt(&parsed.messages);
+    entry.message_count = parsed.messages.len() as u32;
+    if parsed.start_time.is_some() {
+        entry.created = parsed.start_time;
+    }
+    if parsed.last_updated.is_some() {
+        entry.modified = parsed.last_updated;
+    }
+    if path.file_name().and_then(|n| n.to_str()) == Some("logs.json") {
+        entry.thread_name = Some("Gemini 输入日志".to_string());
+    }
+    entry.status = if parsed.messages.is_empty() {
+        SessionStatus::Empty
+    } else {
+        SessionStatus::Valid
+    };
+    entry
+}
+
+fn scan_project_sessions(project_id: &str) -> Result<Vec<SessionIndexEntry>, String> {
+    let project_dir = resolve_project_dir(project_id)?;
+    let (display_path, _) = project_display_path(&project_dir);
+    let mut entries: Vec<SessionIndexEntry> = session_files(&project_dir)
+        .into_par_iter()
+        .map(|path| scan_session(&path, &display_path))
+        .collect();
+    entries.sort_by(|a, b| b.modified.cmp(&a.modified));
+    Ok(entries)
+}
+
+pub fn get_projects() -> Result<Vec<ProjectEntry>, String> {
+    let mut projects: Vec<ProjectEntry> = project_dirs()
+        .into_par_iter()
+        .filter_map(|(project_id, dir)| {
+            let files = session_files(&dir);
+            if files.is_empty() {
+                return None;
+            }
+            let last_modified = files
+                .iter()
+                .filter_map(|f| fs::metadata(f).and_then(|m| m.modified()).ok())
+                .max()
+                .map(systemtime_to_rfc3339);
+            let (display_path, path_exists) = project_display_path(&dir);
+            let data_root = match split_scoped_id(&project_id).0 {
+                DEFAULT_ROOT => None,
+                root => Some(root.to_string()),
+            };
+            Some(ProjectEntry {
+                source: "gemini".to_string(),
+                short_name: short_name_from_path(&display_path),
+                id: project_id,
+                display_path,
+                session_count: files.len(),
+                last_modified,
+                model_provider: None,
+                alias: None,
+                path_exists,
+                is_virtual: false,
+                data_root,
+            })
+        })
+        .collect();
+    projects.sort_by(|a, b| b.last_modified.cmp(&a.last_modified));
+    Ok(projects)
+}
+
+pub fn get_sessions(project_id: &str) -> Result<Vec<SessionIndexEntry>, String> {
+    Ok(scan_project_sessions(project_id)?
+        .into_iter()
+        .filter(|s| s.status == SessionStatus::Valid)
+        .collect())
+}
+
+pub fn get_invalid_sessions(project_id: &str) -> Result<Vec<SessionIndexEntry>, String> {
+    Ok(scan_project_sessions(project_id)?
+        .into_iter()
+        .filter(|s| s.status != SessionStatus::Valid)
+        .collect())
+}
+
+/// 删除一个 Gemini 项目：把该项目下每个会话文件移入回收站（可还原）。
+/// hash 目录本身保留——Gemini CLI 还在里面放 shell 历史等非会话数据。
+pub fn delete_project(project_id: &str) -> Result<DeleteResult, String> {
+    let project_dir = resolve_project_dir(project_id)?;
+    let (display_path, _) = project_display_path(&project_dir);
+    let project_name = short_name_from_path(&display_path);
+
+    let mut sessions_deleted = 0;
+    for path in session_files(&project_dir) {
+        let session_id = parse_session_file(&path)
+            .map(|p| p.session_id)
+            .unwrap_or_default();
+        match crate::recyclebin::move_to_recyclebin(
+            &path,
+            "project",
+            "ManualDelete",
+            "gemini",
+            project_id,
+            None,
+            Some(project_name.clone()),
+        ) {
+            Ok(_) => {
+                sessions_deleted += 1;
+                clear_message_cache_for_path(&path);
+                if !session_id.is_empty() {
+                    let _ = crate::metadata::remove_session_meta("gemini", project_id, &session_id);
+                }
+            }
+            Err(e) => {
+                eprintln!("[gemini::delete_project] Failed to recycle {:?}: {}", path, e);
+            }
+        }
+    }
+
+    Ok(DeleteResult {
+        sessions_deleted,
+        config_cleaned: false,
+        bookmarks_removed: 0,
+    })
+}
+
+// ── Messages ──
+
+pub fn parse_all_messages(path: &Path) -> Result<Vec<DisplayMessage>, String> {
+    if let Ok(Some(cached)) = get_cached_full_messages(path) {
+        return Ok(cached);
+    }
+    let messages = parse_session_file(path)?.messages;
+    let _ = store_full_messages(path, &messages);
+    Ok(messages)
+}
+
+// ── Tokens / cost ──
+
+impl TokenUsage {
+    /// 按计费口径返回 `(input, output, cache_read)`。
+    ///
+    /// Gemini 的 `tokens.input` 包含命中缓存的 `cached`，这里拆开：`input` 只算未命中部分，
+    /// `cached` 记为 cache_read；`thoughts` 按输出计费并入 output，`tool` 是工具调用的
+    /// 额外 prompt，并入 input。
+    fn billed(&self) -> (u64, u64, u64) {
+        let cache_read = self.cached;
+        let input = (self.input + self.tool).saturating_sub(cache_read);
+        let output = self.output + self.thoughts;
+        (input, output, cache_read)
+    }
+}
+
+fn usage_to_record(
+    usage: &TokenUsage,
+    project_id: &str,
+    session_id: &str,
+    file_path: &str,
+) -> RequestRecord {
+    let (input, output, cache_read) = usage.billed();
+    RequestRecord {
+        timestamp: usage.timestamp.clone(),
+        source: "gemini".to_string(),
+        project_id: project_id.to_string(),
+        session_id: session_id.to_string(),
+        file_path: file_path.to_string(),
+        model: usage.model.clone(),
+        input_tokens: input,
+        output_tokens: output,
+        cache_read_tokens: cache_read,
+        cache_creation_tokens: 0,
+        total_tokens: input + output + cache_read,
+        cost_usd: pricing::compute_cost(&usage.model, input, 0, cache_read, output),
+        duration_ms: None,
+        message_uuid: usage.message_id.clone(),
+    }
+}
+
+/// 所有根目录下的会话文件，带上所属项目 id。
+fn all_session_files() -> Vec<(String, PathBuf)> {
+    project_dirs()
+        .into_iter()
+        .flat_map(|(project_id, dir)| {
+            session_files(&dir)
+                .into_iter()
+                .map(move |path| (project_id.clone(), path))
+        })
+        .collect()
+}
+
+pub fn collect_requests() -> Result<Vec<RequestRecord>, String> {
+    Ok(all_session_files()
+        .into_par_iter()
+        .filter(|(_, path)| file_kind(path) == Some(FileKind::Chat))
+        .flat_map_iter(|(project_id, path)| {
+            let file_path = path.to_string_lossy().into_owned();
+            let parsed = parse_session_file(&path).unwrap_or_default();
+            parsed
+                .usage
+                .iter()
+                .map(|u| usage_to_record(u, &project_id, &parsed.session_id, &file_path))
+                .collect::<Vec<_>>()
+        })
+        .collect())
+}
+
+pub fn get_stats() -> Result<TokenUsageSummary, String> {
+    let files = all_session_files();
+    let scanned: Vec<(usize, Vec<RequestRecord>)> = files
+        .into_par_iter()
+        .filter_map(|(project_id, path)| {
+            let parsed = parse_session_file(&path).ok()?;
+            if parsed.messages.is_empty() {
+                return None;
+            }
+            let file_path = path.to_string_lossy().into_owned();
+            let records = parsed
+                .usage
+                .iter()
+                .map(|u| usage_to_record(u, &project_id, &parsed.session_id, &file_path))
+                .collect();
+            Some((parsed.messages.len(), records))
+        })
+        .collect();
+
+    let session_count = scanned.len() as u64;
+    let message_count = scanned.iter().map(|(n, _)| *n as u64).sum();
+    let records: Vec<RequestRecord> = scanned.into_iter().flat_map(|(_, r)| r).collect();
+    Ok(crate::stats::summary_from_records(
+        &records,
+        session_count,
+        message_count,
+    ))
+}
+
+// ── SessionProvider ──
+
+pub struct GeminiProvider;
+
+impl SessionProvider for GeminiProvider {
+    fn id(&self) -> &'static str {
+        "gemini"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Gemini"
+    }
+
+    fn get_projects(&self) -> Result<Vec<ProjectEntry>, String> {
+        get_projects()
+    }
+
+    fn validate_project_id(&self, project_id: &str) -> Result<(), String> {
+        resolve_project_dir(project_id).map(|_| ())
+    }
+
+    fn project_display_name(&self, project_id: &str) -> String {
+        let display_path = resolve_project_dir(project_id)
+            .map(|dir| project_display_path(&dir).0)
+            .unwrap_or_else(|_| project_id.to_string());
+        short_name_from_path(&display_path)
+    }
+
+    fn delete_project(
+        &self,
+        project_id: &str,
+        _level: DeleteLevel,
+    ) -> Result<DeleteResult, String> {
+        delete_project(project_id)
+    }
+
+    fn get_sessions(&self, project_id: &str) -> Result<Vec<SessionIndexEntry>, String> {
+        get_sessions(project_id)
+    }
+
+    fn refresh_sessions_cache(&self, project_id: &str) -> Result<Vec<SessionIndexEntry>, String> {
+        get_sessions(project_id)
+    }
+
+    fn get_invalid_sessions(&self, project_id: &str) -> Result<Vec<SessionIndexEntry>, String> {
+        get_invalid_sessions(project_id)
+    }
+
+    fn verify_session_owner(
+        &self,
+        path: &Path,
+        project_id: Option<&str>,
+        session_id: Option<&str>,
+    ) -> Result<(), String> {
+        if let Some(pid) = project_id {
+            let project_dir = resolve_project_dir(pid)?;
+            if !path.starts_with(&project_dir) {
+                return Err("Session file does not belong to the requested project".to_string());
+            }
+        }
+        if let Some(sid) = session_id {
+            if parse_session_file(path)?.session_id != sid {
+                return Err(
+                    "Session id does not match the requested Gemini session file".to_string(),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_session_messages(
+        &self,
+        path: &Path,
+        page: usize,
+        page_size: usize,
+        from_end: bool,
+    ) -> Result<PaginatedMessages, String> {
+        let messages = parse_all_messages(path)?;
+        Ok(paginate_all(&messages, page, page_size, from_end))
+    }
+
+    fn parse_messages_range(
+        &self,
+        path: &Path,
+        start: usize,
+        end: usize,
+    ) -> Result<RangeMessages, String> {
+        let messages = parse_all_messages(path)?;
+        Ok(slice_range(&messages, start, end))
+    }
+
+    fn parse_all_messages(&self, path: &Path) -> Result<Vec<DisplayMessage>, String> {
+        parse_all_messages(path)
+    }
+
+    fn search_targets(&self) -> Vec<SearchTarget> {
+        project_dirs()
+            .into_par_iter()
+            .flat_map_iter(|(project_id, dir)| {
+                let (display_path, _) = project_display_path(&dir);
+                let project_name = short_name_from_path(&display_path);
+                let meta = crate::metadata::load_metadata("gemini", &project_id);
+                let metas: HashMap<String, crate::metadata::SessionMeta> = meta.sessions;
+
+                session_files(&dir)
+                    .into_iter()
+                    .map(|file_path| {
+                        let session_id = match file_kind(&file_path) {
+                            Some(FileKind::Logs) => LOGS_SESSION_ID.to_string(),
+                            _ => parse_session_file(&file_path)
+                                .map(|p| p.session_id)
+                                .unwrap_or_default(),
+                        };
+                        let session_meta = metas.get(&session_id);
+                        let alias = session_meta.and_then(|s| s.alias.clone());
+                        let tags = session_meta
+                            .map(|s| s.tags.clone())
+                            .filter(|t| !t.is_empty());
+                        let mut target = SearchTarget {
+                            project_id: project_id.clone(),
+                            project_name: project_name.clone(),
+                            session_id,
+                            file_path,
+                            thread_name: None,
+                            alias: alias.clone(),
+                            search_aliases: Vec::with_capacity(1),
+                            tags,
+                            git_branch: None,
+                        };
+                        target.push_search_alias(alias);
+                        target
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    fn get_stats(&self) -> Result<TokenUsageSummary, String> {
+        get_stats()
+    }
+
+    fn collect_requests(&self) -> Result<Vec<RequestRecord>, String> {
+        collect_requests()
+    }
+
+    fn session_file_extension(&self) -> &'static str {
+        "json"
+    }
+
+    fn session_roots(&self) -> Vec<PathBuf> {
+        tmp_dirs().into_iter().map(|(_, dir)| dir).collect()
+    }
+
+    fn validate_layout(&self, path: &Path, base: &Path) -> Result<(), String> {
+        let relative = path
+            .strip_prefix(base)
+            .map_err(|_| "Session file is outside the Gemini tmp directory".to_string())?;
+        let components: Vec<_> = relative.components().collect();
+        if components.iter().any(|c| !matches!(c, Component::Normal(_))) {
+            return Err("Invalid Gemini session file path".to_string());
+        }
+        let expected = match file_kind(path) {
+            Some(FileKind::Chat) => 3,
+            Some(FileKind::Checkpoint) | Some(FileKind::Logs) => 2,
+            None => return Err("Not a Gemini session file".to_string()),
+        };
+        if components.len() != expected {
+            return Err(
+                "Gemini session file must live under tmp/<project-hash>/".to_string(),
+            );
+        }
+        Ok(())
+    }
+
+    fn metadata_path(&self, project_id: &str) -> Option<PathBuf> {
+        resolve_project_dir(project_id)
+            .ok()
+            .map(|dir| dir.join(crate::metadata::METADATA_FILE_NAME))
+    }
+
+    fn metadata_project_ids(&self) -> Vec<String> {
+        project_dirs().into_iter().map(|(id, _)| id).collect()
+    }
+
+    /// 没有列表缓存；消息缓存按 mtime 自动失效。
+    fn invalidate_cache(&self) {
+        crate::search::mark_unsynced(self.id());
+    }
+
+    fn invalidate_paths(&self, changed: &[PathBuf]) {
+        crate::search::mark_dirty(changed);
+        for path in changed {
+            clear_message_cache_for_path(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chat_record_maps_messages_tools_and_tokens() {
+        let root: Value = serde_json::from_str(
+            r#"{
+                "sessionId": "s-1",
+                "startTime": "2026-01-02T03:04:05Z",
+                "messages": [
+                    {"id": "u1", "timestamp": "2026-01-02T03:04:05Z", "type": "user", "content": "list files"},
+                    {"id": "g1", "timestamp": "2026-01-02T03:04:09Z", "type": "gemini",
+                     "content": "Sure.", "model": "gemini-2.5-pro",
+                     "thoughts": [{"subject": "Plan", "description": "use ls"}],
+                     "tokens": {"input": 1000, "output": 50, "cached": 400, "thoughts": 20, "tool": 0, "total": 1070},
+                     "toolCalls": [{"id": "c1", "name": "list_directory", "args": {"path": "."}, "status": "success",
+                                    "result": [{"functionResponse": {"id": "c1", "name": "list_directory", "response": {"output": "a.rs"}}}]}]},
+                    {"id": "i1", "timestamp": "2026-01-02T03:04:10Z", "type": "info", "content": "noise"}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let parsed = parse_chat_record(&root, Path::new("session-x.json"));
+
+        assert_eq!(parsed.session_id, "s-1");
+        let roles: Vec<&str> = parsed.messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, ["user", "assistant", "tool"]);
+        assert!(matches!(
+            &parsed.messages[2].content[0],
+            DisplayContentBlock::ToolResult { tool_use_id, content, is_error: false }
+                if tool_use_id == "c1" && content == "a.rs"
+        ));
+
+        let record = usage_to_record(&parsed.usage[0], "p", "s-1", "f");
+        assert_eq!(record.input_tokens, 600);
+        assert_eq!(record.cache_read_tokens, 400);
+        assert_eq!(record.output_tokens, 70);
+        assert!(record.cost_usd > 0.0);
+        let usage = parsed.messages[1].usage.unwrap();
+        assert_eq!(usage.total_tokens, record.total_tokens);
+        assert_eq!(usage.cost_usd, record.cost_usd);
+        assert!(parsed.messages[2].usage.is_none());
+    }
+
+    #[test]
+    fn checkpoint_skips_injected_context() {
+        let root: Value = serde_json::from_str(
+            r#"[
+                {"role": "user", "parts": [{"text": "This is the Gemini CLI. We are setting up the context for our chat."}]},
+                {"role": "model", "parts": [{"text": "Got it. Thanks for the context!"}]},
+                {"role": "user", "parts": [{"text": "hi"}]},
+                {"role": "model", "parts": [{"functionCall": {"name": "read_file", "args": {"path": "a"}}}]},
+                {"role": "user", "parts": [{"functionResponse": {"name": "read_file", "response": {"output": "x"}}}]}
+            ]"#,
+        )
+        .unwrap();
+        let parsed = parse_checkpoint(&root, Path::new("checkpoint-demo.json"));
+
+        assert_eq!(parsed.session_id, "checkpoint-demo");
+        let roles: Vec<&str> = parsed.messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, ["user", "assistant", "tool"]);
+        assert_eq!(first_prompt(&parsed.messages).as_deref(), Some("hi"));
+    }
+}
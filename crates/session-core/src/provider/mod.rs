@@ -1,2 +1,423 @@
+//! Session source providers.
+//!
+//! 每个数据源（Claude、Codex …）实现一个 [`SessionProvider`]，上层的
+//! export / search / stats / paths / metadata 以及 web 路由、Tauri 命令都只通过
+//! [`get`] / [`all`] 拿到 provider 再调用，不再各自 `match source { "claude" => … }`。
+//! 新增数据源只需要实现 trait 并在 [`PROVIDERS`] 里注册。
+
+pub mod aider;
 pub mod claude;
 pub mod codex;
+pub mod gemini;
+
+use std::path::{Path, PathBuf};
+
+use crate::models::file_history::{FileContentsAtMessage, FileHistoryIndex, SessionFileChanges};
+use crate::models::message::{
+    BranchMessages, ConversationTree, DisplayMessage, MessageMedia, PaginatedMessages,
+    RangeMessages, SubagentThread,
+};
+use crate::models::plan::SessionPlan;
+use crate::models::project::ProjectEntry;
+use crate::models::session::SessionIndexEntry;
+use crate::models::stats::{
+    ProjectCostEntry, RequestLogPage, RequestRecord, SessionCostSummary, TokenUsageSummary,
+};
+use crate::stats::RequestLogFilter;
+
+use self::claude::{DeleteLevel, DeleteResult};
+
+/// One session file plus the display context search needs to build a
+/// [`crate::search::SearchResult`] without re-reading project metadata.
+#[derive(Debug, Clone)]
+pub struct SearchTarget {
+    pub project_id: String,
+    pub project_name: String,
+    pub session_id: String,
+    pub file_path: PathBuf,
+    /// Codex Desktop thread title; `None` for sources that don't have one.
+    pub thread_name: Option<String>,
+    /// Alias shown in results.
+    pub alias: Option<String>,
+    /// Every alias the query is matched against (e.g. Claude's custom-title
+    /// plus the metadata alias). Trimmed and non-empty.
+    pub search_aliases: Vec<String>,
+    pub tags: Option<Vec<String>>,
+    /// Git branch the session ran on, for `branch:` filters.
+    pub git_branch: Option<String>,
+}
+
+impl SearchTarget {
+    pub fn push_search_alias(&mut self, alias: Option<String>) {
+        if let Some(alias) = alias
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+        {
+            if !self.search_aliases.iter().any(|existing| existing == &alias) {
+                self.search_aliases.push(alias);
+            }
+        }
+    }
+}
+
+/// A pluggable session source.
+///
+/// Required methods cover what every source must be able to do (list, parse,
+/// count tokens, delete). Methods with a default body are either derived from
+/// the required ones or are source-specific features that most providers
+/// don't support.
+pub trait SessionProvider: Send + Sync {
+    /// Wire identifier, e.g. `"claude"`. Matches the `source` string carried
+    /// over IPC / HTTP and stored in recycle-bin / bookmark records.
+    fn id(&self) -> &'static str;
+
+    /// Human-readable name used in error messages.
+    fn display_name(&self) -> &'static str;
+
+    // ── Projects ──
+
+    fn get_projects(&self) -> Result<Vec<ProjectEntry>, String>;
+
+    fn refresh_projects_cache(&self) -> Result<Vec<ProjectEntry>, String> {
+        self.get_projects()
+    }
+
+    /// Reject project ids that don't resolve to a real project before any
+    /// filesystem work happens. Sources whose ids aren't paths accept all.
+    fn validate_project_id(&self, _project_id: &str) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Short project name used by stats / cost breakdowns.
+    fn project_display_name(&self, project_id: &str) -> String {
+        Path::new(project_id)
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or(project_id)
+            .to_string()
+    }
+
+    fn delete_project(&self, project_id: &str, level: DeleteLevel)
+        -> Result<DeleteResult, String>;
+
+    fn set_project_alias(&self, _project_id: &str, _alias: Option<String>) -> Result<(), String> {
+        Err(format!("set_project_alias not supported for source: {}", self.id()))
+    }
+
+    fn cleanup_orphan_dirs(&self) -> Result<usize, String> {
+        Err(format!("Orphan dir cleanup not supported for source: {}", self.id()))
+    }
+
+    // ── Sessions ──
+
+    fn get_sessions(&self, project_id: &str) -> Result<Vec<SessionIndexEntry>, String>;
+
+    fn refresh_sessions_cache(&self, project_id: &str) -> Result<Vec<SessionIndexEntry>, String>;
+
+    fn get_invalid_sessions(&self, project_id: &str) -> Result<Vec<SessionIndexEntry>, String>;
+
+    /// Whether session aliases live inside the session file itself (Claude's
+    /// `custom-title` record) rather than in the viewer metadata file.
+    fn stores_alias_in_session_file(&self) -> bool {
+        false
+    }
+
+    /// Persist an alias into the session file. Only called when
+    /// [`Self::stores_alias_in_session_file`] is true.
+    fn write_session_alias(
+        &self,
+        _path: &Path,
+        _session_id: &str,
+        _alias: Option<&str>,
+    ) -> Result<(), String> {
+        Err(format!("Session file aliases not supported for source: {}", self.id()))
+    }
+
+    /// Set the alias of a session started by the CLI in `project_path` (the
+    /// chat `/rename`), keeping its tags. Returns the session's project id.
+    fn rename_chat_session(
+        &self,
+        _project_path: &str,
+        _session_id: &str,
+        _alias: Option<&str>,
+    ) -> Result<String, String> {
+        Err(format!("Chat rename not supported for source: {}", self.id()))
+    }
+
+    /// Check that an already-validated session file really belongs to the
+    /// given project / session id. Guards destructive APIs against a caller
+    /// pairing one session's path with another session's identifiers.
+    fn verify_session_owner(
+        &self,
+        _path: &Path,
+        _project_id: Option<&str>,
+        _session_id: Option<&str>,
+    ) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Refuse deleting a single session when that can't be done by removing
+    /// its file (e.g. several sessions share one file).
+    fn check_session_deletable(&self, _path: &Path) -> Result<(), String> {
+        Ok(())
+    }
+
+    // ── Messages ──
+
+    fn parse_session_messages(
+        &self,
+        path: &Path,
+        page: usize,
+        page_size: usize,
+        from_end: bool,
+    ) -> Result<PaginatedMessages, String>;
+
+    fn parse_messages_range(
+        &self,
+        path: &Path,
+        start: usize,
+        end: usize,
+    ) -> Result<RangeMessages, String>;
+
+    fn parse_all_messages(&self, path: &Path) -> Result<Vec<DisplayMessage>, String>;
+
+    /// Feed the session's messages to `f` in order, for exports of sessions
+    /// too large to hold in memory. Stops at the first error `f` returns.
+    /// The default parses everything first; streaming providers override it.
+    fn for_each_message(
+        &self,
+        path: &Path,
+        f: &mut dyn FnMut(DisplayMessage) -> Result<(), String>,
+    ) -> Result<(), String> {
+        self.parse_all_messages(path)?.into_iter().try_for_each(f)
+    }
+
+    /// Load the subagent thread a tool call spawned, as referenced by the
+    /// `subagent` summary on its `ToolUse` block.
+    fn parse_subagent_thread(&self, _path: &Path, _agent_id: &str) -> Result<SubagentThread, String> {
+        Err(format!("Subagent threads not supported for source: {}", self.id()))
+    }
+
+    /// Build the message tree of a session whose records link to their
+    /// parent, with every rewind / edit / retry branch kept apart.
+    fn parse_conversation_tree(&self, _path: &Path) -> Result<ConversationTree, String> {
+        Err(format!("Conversation tree not supported for source: {}", self.id()))
+    }
+
+    /// Load the branch through message `uuid` of [`Self::parse_conversation_tree`]
+    /// as a linear message list.
+    fn parse_branch_messages(&self, _path: &Path, _uuid: &str) -> Result<BranchMessages, String> {
+        Err(format!("Conversation tree not supported for source: {}", self.id()))
+    }
+
+    /// Final todo list and proposed plans of a session, taken from its
+    /// TodoWrite / ExitPlanMode / `update_plan` tool calls.
+    fn session_plan(&self, path: &Path) -> Result<SessionPlan, String> {
+        Ok(crate::plans::plan_from_messages(&self.parse_all_messages(path)?))
+    }
+
+    /// Decoded payload of an `Image` / `Document` display block, addressed by
+    /// its message uuid and `block_index`.
+    fn read_message_media(
+        &self,
+        _path: &Path,
+        _uuid: &str,
+        _block_index: usize,
+    ) -> Result<MessageMedia, String> {
+        Err(format!("Message media not supported for source: {}", self.id()))
+    }
+
+    /// Files the session edited, with their pre-edit backups and the tool
+    /// calls that changed them.
+    fn file_history(&self, _path: &Path) -> Result<FileHistoryIndex, String> {
+        Err(format!("File history not supported for source: {}", self.id()))
+    }
+
+    /// Contents of `file` before and after message `uuid`.
+    fn file_contents_at(
+        &self,
+        _path: &Path,
+        _file: &str,
+        _uuid: &str,
+    ) -> Result<FileContentsAtMessage, String> {
+        Err(format!("File history not supported for source: {}", self.id()))
+    }
+
+    /// Every file change the session's edit tool calls made, for patch export.
+    fn file_changes(&self, _path: &Path) -> Result<SessionFileChanges, String> {
+        Err(format!("Patch export not supported for source: {}", self.id()))
+    }
+
+    // ── Search ──
+
+    /// Every session file that global search should scan, with its display
+    /// context already resolved.
+    fn search_targets(&self) -> Vec<SearchTarget>;
+
+    /// Raw text of one session, used by search as a cheap pre-filter before
+    /// parsing.
+    fn read_session_text(&self, path: &Path) -> std::io::Result<String> {
+        std::fs::read_to_string(path)
+    }
+
+    // ── Tokens / cost ──
+
+    fn get_stats(&self) -> Result<TokenUsageSummary, String>;
+
+    /// Every priced request this source knows about, unsorted.
+    fn collect_requests(&self) -> Result<Vec<RequestRecord>, String>;
+
+    fn request_log(
+        &self,
+        filter: &RequestLogFilter,
+        page: usize,
+        page_size: usize,
+    ) -> Result<RequestLogPage, String> {
+        let records = self.collect_requests()?;
+        Ok(crate::stats::paginate_records(records, filter, page, page_size))
+    }
+
+    fn project_costs(&self) -> Result<Vec<ProjectCostEntry>, String> {
+        let records = self.collect_requests()?;
+        Ok(crate::stats::project_costs_from_records(
+            self.id(),
+            records,
+            |project_id| self.project_display_name(project_id),
+        ))
+    }
+
+    /// Whether [`Self::session_cost`] is cheap enough to call once per
+    /// session. Otherwise callers pricing many sessions read
+    /// [`Self::collect_requests`] once and group it themselves.
+    fn session_cost_is_cached(&self) -> bool {
+        false
+    }
+
+    fn session_cost(&self, file_path: &str) -> Result<SessionCostSummary, String> {
+        let records = self.collect_requests()?;
+        Ok(crate::stats::build_session_summary_from_records(
+            self.id(),
+            file_path,
+            records,
+        ))
+    }
+
+    // ── Files on disk ──
+
+    /// Extension (without the dot) every session file of this source has.
+    fn session_file_extension(&self) -> &'static str {
+        "jsonl"
+    }
+
+    /// Sources that keep several sessions in one file address each of them as
+    /// `<file>#<selector>`. Splits off the selector so the file part can be
+    /// validated on its own; single-session sources never have one.
+    fn split_session_selector<'a>(&self, file_path: &'a str) -> (&'a str, Option<&'a str>) {
+        (file_path, None)
+    }
+
+    /// Root directories session files of this source live under. Used to
+    /// validate user-supplied paths and as the file watcher targets.
+    fn session_roots(&self) -> Vec<PathBuf>;
+
+    /// Check the layout of a canonical session file path relative to the
+    /// canonical [`Self::session_roots`] entry that contains it.
+    fn validate_layout(&self, path: &Path, base: &Path) -> Result<(), String>;
+
+    /// Whether a change to this file (under one of [`Self::session_roots`])
+    /// should refresh caches and notify the frontend.
+    fn is_watched_file(&self, path: &Path) -> bool {
+        path.extension()
+            .map(|e| e == "jsonl" || e == "json")
+            .unwrap_or(false)
+    }
+
+    /// Location of the viewer's alias/tag metadata file for a project.
+    fn metadata_path(&self, project_id: &str) -> Option<PathBuf>;
+
+    /// Project ids that may own a metadata file (for cross-project tags).
+    fn metadata_project_ids(&self) -> Vec<String>;
+
+    // ── Cache ──
+
+    fn invalidate_cache(&self);
+
+    /// Incrementally invalidate caches for changed paths under
+    /// [`Self::session_roots`].
+    fn invalidate_paths(&self, changed: &[PathBuf]);
+}
+
+static PROVIDERS: &[&dyn SessionProvider] = &[
+    &claude::ClaudeProvider,
+    &codex::CodexProvider,
+    &gemini::GeminiProvider,
+    &aider::AiderProvider,
+];
+
+/// All registered providers, in display order.
+pub fn all() -> &'static [&'static dyn SessionProvider] {
+    PROVIDERS
+}
+
+/// Page through a fully parsed message list. For sources whose session file
+/// is a single document (no line-oriented tail read), so the whole file is
+/// parsed and memoized anyway.
+pub(crate) fn paginate_all(
+    messages: &[DisplayMessage],
+    page: usize,
+    page_size: usize,
+    from_end: bool,
+) -> PaginatedMessages {
+    let total = messages.len();
+    let bounds = crate::state::page_bounds(total, page, page_size, from_end);
+    let start = bounds.start.min(total);
+    let end = bounds.end.min(total).max(start);
+    PaginatedMessages {
+        messages: messages[start..end].to_vec(),
+        total,
+        page,
+        page_size,
+        has_more: bounds.has_more,
+    }
+}
+
+/// `[start, end)` slice of a fully parsed message list, clamped to its length.
+pub(crate) fn slice_range(messages: &[DisplayMessage], start: usize, end: usize) -> RangeMessages {
+    let total = messages.len();
+    let start = start.min(total);
+    let end = end.min(total).max(start);
+    RangeMessages {
+        messages: messages[start..end].to_vec(),
+        total,
+        start,
+        end,
+    }
+}
+
+/// Look up a provider by its wire id.
+pub fn get(source: &str) -> Result<&'static dyn SessionProvider, String> {
+    PROVIDERS
+        .iter()
+        .copied()
+        .find(|p| p.id() == source)
+        .ok_or_else(|| format!("Unknown source: {}", source))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registry_ids_are_unique_and_resolvable() {
+        let mut seen = std::collections::HashSet::new();
+        for provider in all() {
+            assert!(seen.insert(provider.id()), "duplicate id {}", provider.id());
+            assert_eq!(get(provider.id()).unwrap().id(), provider.id());
+        }
+    }
+
+    #[test]
+    fn unknown_source_is_rejected() {
+        assert_eq!(get("nope").err().unwrap(), "Unknown source: nope");
+    }
+}
@@ -184,10 +184,8 @@ pub fn restore_item(id: &str) -> Result<(), String> {
     save_manifest(&manifest)?;
 
     // 失效 sessions 缓存，让前端列表显示恢复的条目
-    match item.source.as_str() {
-        "claude" => crate::provider::claude::invalidate_cache(),
-        "codex" => crate::provider::codex::invalidate_sessions_cache(),
-        _ => {}
+    if let Ok(provider) = crate::provider::get(&item.source) {
+        provider.invalidate_cache();
     }
 
     Ok(())
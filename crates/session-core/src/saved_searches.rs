@@ -0,0 +1,367 @@
+//! 保存的搜索与智能合集。
+//!
+//! 常用查询（来源、匹配范围、过滤语法、标签）以名字保存在
+//! `~/.session-viewer-saved-searches.json`，与收藏文件放在一起。每条保存的搜索
+//! 都可以当作实时的"智能合集"打开：重新执行查询、列出命中的会话，并与上次查看时
+//! 命中的会话比较，报告新增了多少。
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::search::{self, SearchOptions, SearchResult, SearchScope};
+
+/// Results fetched per collection. Each session lists at most a handful of
+/// matches, so this covers several hundred sessions.
+const MAX_COLLECTION_RESULTS: usize = 2000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedSearchesFile {
+    pub version: u32,
+    pub searches: Vec<SavedSearch>,
+    /// Saved search id -> file paths of the sessions it matched when last
+    /// viewed.
+    #[serde(default)]
+    pub seen: HashMap<String, Vec<String>>,
+}
+
+impl Default for SavedSearchesFile {
+    fn default() -> Self {
+        SavedSearchesFile {
+            version: 1,
+            searches: vec![],
+            seen: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedSearch {
+    pub id: String,
+    pub name: String,
+    /// A source id, or `all`.
+    pub source: String,
+    /// Search text, including any filters (`branch:release/*`, `tool:Bash`).
+    pub query: String,
+    /// `all` | `content` | `session` | `tags`
+    pub scope: String,
+    #[serde(default)]
+    pub regex: bool,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    #[serde(default)]
+    pub whole_word: bool,
+    /// Sessions must carry every one of these tags.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub created_at: String,
+    #[serde(default)]
+    pub last_viewed_at: Option<String>,
+}
+
+/// A session matching a saved search.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionSession {
+    /// The session's best match.
+    #[serde(flatten)]
+    pub result: SearchResult,
+    /// Not matched when the collection was last viewed.
+    pub is_new: bool,
+}
+
+/// A saved search run as a live collection of sessions.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SmartCollection {
+    pub search: SavedSearch,
+    /// New sessions first, then by relevance.
+    pub sessions: Vec<CollectionSession>,
+    /// Among `sessions`.
+    pub new_count: usize,
+    /// Every matching session, including those past the results cap.
+    pub total_sessions: usize,
+    /// `sessions` stops at the results cap and leaves some matches out.
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionCount {
+    pub id: String,
+    pub total_sessions: usize,
+    pub new_count: usize,
+}
+
+fn saved_searches_path() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or("Cannot determine home directory")?;
+    Ok(home.join(".session-viewer-saved-searches.json"))
+}
+
+fn load_file() -> SavedSearchesFile {
+    let Ok(path) = saved_searches_path() else {
+        return SavedSearchesFile::default();
+    };
+    fs::read_to_string(&path)
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+fn save_file(file: &SavedSearchesFile) -> Result<(), String> {
+    let path = saved_searches_path()?;
+    let json = serde_json::to_string_pretty(file)
+        .map_err(|e| format!("Failed to serialize saved searches: {}", e))?;
+
+    // Atomic write: write to tmp then rename
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, &json)
+        .map_err(|e| format!("Failed to write saved searches tmp: {}", e))?;
+    fs::rename(&tmp_path, &path)
+        .map_err(|e| format!("Failed to rename saved searches file: {}", e))?;
+    Ok(())
+}
+
+fn generate_id() -> String {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    let ts = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    format!("{:x}", ts)
+}
+
+/// The query text with the saved tags added as `tag:` filters.
+fn effective_query(search: &SavedSearch) -> String {
+    let mut query = search.query.trim().to_string();
+    for tag in &search.tags {
+        query.push_str(&format!(" tag:\"{}\"", tag.replace('"', "")));
+    }
+    query.trim().to_string()
+}
+
+/// The best match of the sessions `search` matches now, best first, up to
+/// the results cap; and how many sessions match in all.
+fn matching_sessions(search: &SavedSearch) -> Result<(Vec<SearchResult>, usize), String> {
+    let response = search::global_search(
+        &search.source,
+        &effective_query(search),
+        MAX_COLLECTION_RESULTS,
+        SearchScope::from_query(&search.scope),
+        SearchOptions {
+            regex: search.regex,
+            case_sensitive: search.case_sensitive,
+            whole_word: search.whole_word,
+        },
+    )?;
+    if let Some(e) = response.query_error {
+        return Err(format!("Invalid query: {}", e.message));
+    }
+    let mut listed = HashSet::new();
+    let results: Vec<SearchResult> = response
+        .results
+        .into_iter()
+        .filter(|r| listed.insert(r.file_path.clone()))
+        .collect();
+    // Without the index the total only covers the results.
+    let total_sessions = response.total_sessions.max(results.len());
+    Ok((results, total_sessions))
+}
+
+/// Flags the sessions not in `seen`, moving them to the front.
+fn collect_sessions(
+    results: Vec<SearchResult>,
+    seen: &[String],
+) -> (Vec<CollectionSession>, usize) {
+    let seen: HashSet<&str> = seen.iter().map(String::as_str).collect();
+    let mut sessions: Vec<CollectionSession> = results
+        .into_iter()
+        .map(|result| CollectionSession {
+            is_new: !seen.contains(result.file_path.as_str()),
+            result,
+        })
+        .collect();
+    sessions.sort_by_key(|s| !s.is_new);
+    let new_count = sessions.iter().filter(|s| s.is_new).count();
+    (sessions, new_count)
+}
+
+/// Add the listed sessions to `seen`. Sessions seen before but not listed
+/// now (e.g. past the results cap) stay seen.
+fn mark_seen(seen: &mut Vec<String>, sessions: &[CollectionSession]) {
+    let known: HashSet<String> = seen.iter().cloned().collect();
+    seen.extend(
+        sessions
+            .iter()
+            .map(|s| &s.result.file_path)
+            .filter(|path| !known.contains(*path))
+            .cloned(),
+    );
+}
+
+pub fn list_saved_searches() -> Vec<SavedSearch> {
+    load_file().searches
+}
+
+/// Save `search`; the sessions it matches now count as already seen.
+pub fn add_saved_search(search: SavedSearch) -> Result<SavedSearch, String> {
+    let mut file = load_file();
+
+    let mut search = search;
+    search.name = search.name.trim().to_string();
+    if search.name.is_empty() {
+        return Err("Saved search name is required".to_string());
+    }
+    if search.query.trim().is_empty() && search.tags.is_empty() {
+        return Err("Saved search needs a query or tags".to_string());
+    }
+    if file.searches.iter().any(|s| s.name == search.name) {
+        return Err("A saved search with this name already exists".to_string());
+    }
+
+    let (matched, _) = matching_sessions(&search)?;
+    if search.id.is_empty() {
+        search.id = generate_id();
+    }
+    let now = chrono::Utc::now().to_rfc3339();
+    if search.created_at.is_empty() {
+        search.created_at = now.clone();
+    }
+    search.last_viewed_at = Some(now);
+
+    file.seen.insert(
+        search.id.clone(),
+        matched.into_iter().map(|r| r.file_path).collect(),
+    );
+    file.searches.push(search.clone());
+    save_file(&file)?;
+    Ok(search)
+}
+
+pub fn remove_saved_search(id: &str) -> Result<(), String> {
+    let mut file = load_file();
+    let len_before = file.searches.len();
+    file.searches.retain(|s| s.id != id);
+    if file.searches.len() == len_before {
+        return Err("Saved search not found".to_string());
+    }
+    file.seen.remove(id);
+    save_file(&file)?;
+    Ok(())
+}
+
+/// How many sessions each saved search matches now, and how many of those
+/// are new since it was last viewed. Searches that fail are left out.
+pub fn collection_counts() -> Vec<CollectionCount> {
+    let file = load_file();
+    file.searches
+        .iter()
+        .filter_map(|search| match matching_sessions(search) {
+            Ok((results, total_sessions)) => {
+                let seen = file
+                    .seen
+                    .get(&search.id)
+                    .map(Vec::as_slice)
+                    .unwrap_or_default();
+                let (_, new_count) = collect_sessions(results, seen);
+                Some(CollectionCount {
+                    id: search.id.clone(),
+                    total_sessions,
+                    new_count,
+                })
+            }
+            Err(e) => {
+                eprintln!("[saved_searches] {}: {}", search.name, e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Run saved search `id` as a collection. With `mark_viewed`, the sessions
+/// listed now stop counting as new; those seen before stay seen.
+pub fn open_collection(id: &str, mark_viewed: bool) -> Result<SmartCollection, String> {
+    let mut file = load_file();
+    let index = file
+        .searches
+        .iter()
+        .position(|s| s.id == id)
+        .ok_or("Saved search not found")?;
+    let (results, total_sessions) = matching_sessions(&file.searches[index])?;
+    let seen = file.seen.get(id).map(Vec::as_slice).unwrap_or_default();
+    let (sessions, new_count) = collect_sessions(results, seen);
+
+    if mark_viewed {
+        let search = &mut file.searches[index];
+        search.last_viewed_at = Some(chrono::Utc::now().to_rfc3339());
+        mark_seen(file.seen.entry(id.to_string()).or_default(), &sessions);
+        save_file(&file)?;
+    }
+
+    Ok(SmartCollection {
+        search: file.searches.swap_remove(index),
+        truncated: total_sessions > sessions.len(),
+        sessions,
+        new_count,
+        total_sessions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adds_tags_to_query_and_flags_new_sessions() {
+        let search = SavedSearch {
+            id: String::new(),
+            name: "release".to_string(),
+            source: "codex".to_string(),
+            query: "branch:release/* ".to_string(),
+            scope: "all".to_string(),
+            regex: false,
+            case_sensitive: false,
+            whole_word: false,
+            tags: vec!["pay ments".to_string()],
+            created_at: String::new(),
+            last_viewed_at: None,
+        };
+        assert_eq!(
+            effective_query(&search),
+            "branch:release/* tag:\"pay ments\""
+        );
+        assert!(search::Query::parse(&effective_query(&search)).is_ok());
+
+        let result = |path: &str| SearchResult {
+            source: "codex".to_string(),
+            project_id: String::new(),
+            project_name: String::new(),
+            session_id: path.to_string(),
+            first_prompt: None,
+            thread_name: None,
+            alias: None,
+            tags: None,
+            matched_text: String::new(),
+            role: "session".to_string(),
+            timestamp: None,
+            file_path: path.to_string(),
+            total_message_count: 0,
+            matched_message_id: None,
+            match_ranges: Vec::new(),
+            score: 0.0,
+        };
+        let (sessions, new_count) =
+            collect_sessions(vec![result("a"), result("b")], &["a".to_string()]);
+        assert_eq!(new_count, 1);
+        assert_eq!(sessions[0].result.file_path, "b");
+        assert!(sessions[0].is_new && !sessions[1].is_new);
+
+        // "c" fell past the cap this time; it stays seen.
+        let mut seen = vec!["a".to_string(), "c".to_string()];
+        mark_seen(&mut seen, &sessions);
+        assert_eq!(seen, ["a", "c", "b"]);
+    }
+}
@@ -20,6 +20,7 @@ pub enum Phase {
     Projects = 1,
     Sessions = 2,
     Index = 3,
+    Search = 4,
 }
 
 /// 发给前端的进度快照。
@@ -37,6 +38,7 @@ fn phase_label(code: u8) -> String {
         1 => "扫描项目",
         2 => "扫描会话",
         3 => "建立索引",
+        4 => "搜索会话",
         _ => "扫描中",
     }
     .to_string()
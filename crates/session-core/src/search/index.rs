@@ -0,0 +1,936 @@
+//! 全局搜索的持久化全文索引。
+//!
+//! 以前每次搜索都要把所有会话文件读一遍、逐个解析，语料一大一次查询就要几十秒
+//! 并吃满 CPU。这里把每个会话的内容块写进 app dir 下的 SQLite FTS5 表
+//! （`search-index.db`），查询走倒排索引并按 bm25 排序。
+//!
+//! - `blocks` 用 trigram 分词，保持原来"不区分大小写的子串匹配"语义；不足
+//!   3 个字符的词没法用 trigram，退化为对索引表的 `LIKE` 扫描。
+//! - `words` 是同一批块按 [`super::tokenize`] 切出的英文词 / 中文二元组
+//!   （contentless，不存原文），用于 bm25 相关度打分；纯中文词靠它精确命中，
+//!   两个字的中文词也不必 `LIKE`。`vocab` 收集出现过的英文词，单个英文词查询
+//!   时从中找出拼写相近的词一并匹配。`vocab` 只增不删，多出来的词不影响结果。
+//! - 查询语法（[`super::query`]）里作用于消息的过滤条件直接编译成 SQL 条件，
+//!   会话级过滤由调用方按会话筛掉。正则 / 区分大小写 / 整词模式通过注册的
+//!   `regexp()` 函数在 SQL 里判断，trigram 只负责先缩小范围，计数仍然准确。
+//! - 每个文件记下 size / mtime。进程内第一次查询某个来源时逐个 stat 对账，
+//!   之后只检查 watcher 通过 `invalidate_paths` 报告过的文件（[`mark_dirty`]）
+//!   和新出现的会话；已经不在会话列表里的文件顺带删掉。
+//! - 块的 rowid 是 `file_id << BLOCK_BITS | 块序号`，重建一个文件只需按 rowid
+//!   区间删除，不用扫整张表。
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::UNIX_EPOCH;
+
+use parking_lot::Mutex;
+use rayon::prelude::*;
+use regex::Regex;
+use rusqlite::functions::FunctionFlags;
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::app_dir::get_app_dir;
+use crate::models::message::{DisplayContentBlock, DisplayMessage};
+use crate::provider::{SearchTarget, SessionProvider};
+use crate::scan_progress::{self, Phase};
+
+use super::query::{date_bound, Condition, Pattern, Query, Term};
+use super::tokenize;
+use super::{block_text, block_tool, tool_names};
+
+/// Bump when the schema or what gets indexed changes; the index is rebuilt.
+const SCHEMA_VERSION: i64 = 3;
+
+const BLOCK_BITS: u32 = 24;
+const MAX_BLOCKS_PER_FILE: i64 = (1 << BLOCK_BITS) - 1;
+
+/// The first prompt is matched for session-name searches; keep enough of it.
+const FIRST_PROMPT_CHARS: usize = 2000;
+
+/// Files parsed in parallel and written in one transaction.
+const INDEX_CHUNK: usize = 64;
+
+/// Typo corrections tried per query word.
+const MAX_NEAR_WORDS: usize = 16;
+
+/// Longest word kept in `vocab`; longer "words" are hashes and the like.
+const MAX_VOCAB_WORD: usize = 40;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS files (
+    id INTEGER PRIMARY KEY,
+    source TEXT NOT NULL,
+    path TEXT NOT NULL UNIQUE,
+    size INTEGER NOT NULL,
+    mtime INTEGER NOT NULL,
+    message_count INTEGER NOT NULL,
+    first_prompt TEXT,
+    first_prompt_uuid TEXT,
+    first_prompt_timestamp TEXT
+);
+CREATE INDEX IF NOT EXISTS files_source ON files (source);
+CREATE VIRTUAL TABLE IF NOT EXISTS blocks USING fts5 (
+    text,
+    role UNINDEXED,
+    timestamp UNINDEXED,
+    message_uuid UNINDEXED,
+    model UNINDEXED,
+    tool UNINDEXED,
+    tokenize = 'trigram'
+);
+CREATE VIRTUAL TABLE IF NOT EXISTS words USING fts5 (
+    tokens,
+    content = '',
+    contentless_delete = 1,
+    tokenize = 'unicode61 remove_diacritics 0'
+);
+CREATE TABLE IF NOT EXISTS vocab (word TEXT PRIMARY KEY) WITHOUT ROWID;
+";
+
+/// `None` when the index could not be opened; search then scans files.
+static CONNECTION: OnceLock<Mutex<Option<Connection>>> = OnceLock::new();
+/// Sources reconciled against the file system in this process.
+static SYNCED: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+/// Files reported changed since they were last checked.
+static DIRTY: OnceLock<Mutex<HashSet<PathBuf>>> = OnceLock::new();
+
+fn synced() -> &'static Mutex<HashSet<String>> {
+    SYNCED.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+fn dirty() -> &'static Mutex<HashSet<PathBuf>> {
+    DIRTY.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+fn db_path() -> Option<PathBuf> {
+    // Unit tests must not write to the user's index; search scans files instead.
+    if cfg!(test) {
+        return None;
+    }
+    get_app_dir().map(|d| d.join("search-index.db"))
+}
+
+fn open() -> Result<Connection, String> {
+    let path = db_path().ok_or("Cannot determine app directory")?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create app directory: {}", e))?;
+    }
+    let conn =
+        Connection::open(&path).map_err(|e| format!("Failed to open search index: {}", e))?;
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .and_then(|_| conn.pragma_update(None, "synchronous", "NORMAL"))
+        .map_err(|e| format!("Failed to configure search index: {}", e))?;
+    init(&conn)?;
+    Ok(conn)
+}
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// `text REGEXP pattern`, compiling each pattern once per statement.
+fn register_regexp(conn: &Connection) -> rusqlite::Result<()> {
+    conn.create_scalar_function(
+        "regexp",
+        2,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| {
+            let regex: Arc<Regex> = ctx.get_or_create_aux(0, |pattern| -> Result<_, BoxError> {
+                Ok(Regex::new(pattern.as_str()?)?)
+            })?;
+            let text = ctx.get_raw(1).as_str().unwrap_or_default();
+            Ok(regex.is_match(text))
+        },
+    )
+}
+
+fn init(conn: &Connection) -> Result<(), String> {
+    register_regexp(conn).map_err(|e| format!("Failed to configure search index: {}", e))?;
+    let version: i64 = conn
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .map_err(|e| format!("Failed to read search index version: {}", e))?;
+    if version != SCHEMA_VERSION {
+        conn.execute_batch(
+            "DROP TABLE IF EXISTS blocks; DROP TABLE IF EXISTS words; \
+             DROP TABLE IF EXISTS vocab; DROP TABLE IF EXISTS files;",
+        )
+        .map_err(|e| format!("Failed to reset search index: {}", e))?;
+    }
+    conn.execute_batch(SCHEMA)
+        .and_then(|_| conn.pragma_update(None, "user_version", SCHEMA_VERSION))
+        .map_err(|e| format!("Failed to create search index: {}", e))
+}
+
+/// Run `f` on the index, or return `None` when it is unavailable. Other
+/// queries wait while `f` runs, so keep it to one batch of work.
+pub(super) fn with_index<T>(f: impl FnOnce(&Connection) -> T) -> Option<T> {
+    let lock = CONNECTION.get_or_init(|| Mutex::new(open().ok()));
+    let guard = lock.lock();
+    guard.as_ref().map(f)
+}
+
+/// [`with_index`] for fallible work; an unavailable index is an error.
+pub(super) fn locked<T>(f: impl FnOnce(&Connection) -> Result<T, String>) -> Result<T, String> {
+    with_index(f).unwrap_or_else(|| Err("Search index is unavailable".to_string()))
+}
+
+/// Record changed session files (from the fs watcher) so the next query
+/// re-checks them.
+pub fn mark_dirty(paths: &[PathBuf]) {
+    dirty().lock().extend(paths.iter().cloned());
+}
+
+/// Re-check every file of `source` against the file system on its next query.
+pub fn mark_unsynced(source: &str) {
+    synced().lock().remove(source);
+}
+
+fn file_stamp(path: &Path) -> Option<(i64, i64)> {
+    let meta = fs::metadata(path).ok()?;
+    let mtime = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0);
+    Some((meta.len() as i64, mtime))
+}
+
+/// The first prompt of a session: text, message uuid, timestamp.
+#[derive(Debug, Clone)]
+pub(super) struct FirstPrompt {
+    pub text: String,
+    pub uuid: Option<String>,
+    pub timestamp: Option<String>,
+}
+
+/// What the index knows about one session file.
+#[derive(Debug, Clone)]
+pub(super) struct IndexedFile {
+    pub id: i64,
+    size: i64,
+    /// Modification time, unix millis.
+    pub mtime: i64,
+    pub message_count: u32,
+    pub first_prompt: Option<FirstPrompt>,
+}
+
+fn first_prompt(messages: &[DisplayMessage]) -> Option<FirstPrompt> {
+    let msg = messages.iter().find(|m| m.role == "user")?;
+    msg.content.iter().find_map(|block| match block {
+        DisplayContentBlock::Text { text } => Some(FirstPrompt {
+            text: text.chars().take(FIRST_PROMPT_CHARS).collect(),
+            uuid: msg.uuid.clone(),
+            timestamp: msg.timestamp.clone(),
+        }),
+        _ => None,
+    })
+}
+
+fn load_files(conn: &Connection, source: &str) -> Result<HashMap<String, IndexedFile>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT path, id, size, mtime, message_count, first_prompt, first_prompt_uuid, \
+             first_prompt_timestamp FROM files WHERE source = ?1",
+        )
+        .map_err(|e| format!("Failed to read search index: {}", e))?;
+    let rows = stmt
+        .query_map([source], |row| {
+            let first_prompt = row.get::<_, Option<String>>(5)?.map(|text| FirstPrompt {
+                text,
+                uuid: row.get(6).ok().flatten(),
+                timestamp: row.get(7).ok().flatten(),
+            });
+            Ok((
+                row.get::<_, String>(0)?,
+                IndexedFile {
+                    id: row.get(1)?,
+                    size: row.get(2)?,
+                    mtime: row.get(3)?,
+                    message_count: row.get(4)?,
+                    first_prompt,
+                },
+            ))
+        })
+        .map_err(|e| format!("Failed to read search index: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read search index: {}", e))
+}
+
+fn delete_blocks(conn: &Connection, file_id: i64) -> rusqlite::Result<()> {
+    let range = params![
+        file_id << BLOCK_BITS,
+        (file_id << BLOCK_BITS) | MAX_BLOCKS_PER_FILE
+    ];
+    conn.execute("DELETE FROM blocks WHERE rowid BETWEEN ?1 AND ?2", range)?;
+    conn.execute("DELETE FROM words WHERE rowid BETWEEN ?1 AND ?2", range)?;
+    Ok(())
+}
+
+fn delete_file(conn: &Connection, file_id: i64) -> rusqlite::Result<()> {
+    delete_blocks(conn, file_id)?;
+    conn.execute("DELETE FROM files WHERE id = ?1", [file_id])?;
+    Ok(())
+}
+
+/// (Re)write one session file's row and blocks.
+fn write_file(
+    conn: &Connection,
+    source: &str,
+    path: &str,
+    (size, mtime): (i64, i64),
+    messages: &[DisplayMessage],
+) -> rusqlite::Result<()> {
+    let first = first_prompt(messages);
+    conn.execute(
+        "INSERT INTO files (source, path, size, mtime, message_count, first_prompt, \
+         first_prompt_uuid, first_prompt_timestamp) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8) \
+         ON CONFLICT (path) DO UPDATE SET source = excluded.source, size = excluded.size, \
+         mtime = excluded.mtime, message_count = excluded.message_count, \
+         first_prompt = excluded.first_prompt, first_prompt_uuid = excluded.first_prompt_uuid, \
+         first_prompt_timestamp = excluded.first_prompt_timestamp",
+        params![
+            source,
+            path,
+            size,
+            mtime,
+            messages.len() as i64,
+            first.as_ref().map(|f| &f.text),
+            first.as_ref().and_then(|f| f.uuid.as_ref()),
+            first.as_ref().and_then(|f| f.timestamp.as_ref()),
+        ],
+    )?;
+    let file_id: i64 = conn.query_row("SELECT id FROM files WHERE path = ?1", [path], |row| {
+        row.get(0)
+    })?;
+    delete_blocks(conn, file_id)?;
+
+    let mut insert = conn.prepare_cached(
+        "INSERT INTO blocks (rowid, text, role, timestamp, message_uuid, model, tool) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+    )?;
+    let mut insert_words =
+        conn.prepare_cached("INSERT INTO words (rowid, tokens) VALUES (?1, ?2)")?;
+    let tools = tool_names(messages);
+    let blocks = messages
+        .iter()
+        .flat_map(|m| m.content.iter().map(move |b| (m, b)))
+        .filter(|(_, block)| !block_text(block).trim().is_empty());
+    let mut vocab: HashSet<String> = HashSet::new();
+    for (ord, (msg, block)) in (0..MAX_BLOCKS_PER_FILE).zip(blocks) {
+        let rowid = (file_id << BLOCK_BITS) | ord;
+        let text = block_text(block);
+        insert.execute(params![
+            rowid,
+            text,
+            msg.role,
+            msg.timestamp,
+            msg.uuid,
+            msg.model,
+            block_tool(block, &tools),
+        ])?;
+        let tokens = tokenize::tokens(text);
+        insert_words.execute(params![rowid, tokens.join(" ")])?;
+        vocab.extend(
+            tokens
+                .into_iter()
+                .filter(|t| t.len() <= MAX_VOCAB_WORD && tokenize::is_fuzzy_word(t)),
+        );
+    }
+    let mut insert_vocab = conn.prepare_cached("INSERT OR IGNORE INTO vocab (word) VALUES (?1)")?;
+    for word in vocab {
+        insert_vocab.execute([word])?;
+    }
+    Ok(())
+}
+
+/// Parse and write `paths` (target path, file part), a chunk at a time.
+/// Files are parsed without the index lock, which is taken only to write each
+/// chunk, so queries of other sources aren't held up by a cold index. Stops
+/// between files once `cancel` is set.
+fn index_files(
+    provider: &dyn SessionProvider,
+    paths: &[(&str, &Path)],
+    cancel: &AtomicBool,
+) -> Result<(), String> {
+    let report = paths.len() >= INDEX_CHUNK;
+    if report {
+        scan_progress::begin(Phase::Index, paths.len() as u64);
+    }
+    let result = paths.chunks(INDEX_CHUNK).try_for_each(|chunk| {
+        let parsed: Vec<_> = chunk
+            .par_iter()
+            .filter(|_| !cancel.load(Ordering::Relaxed))
+            .map(|&(path, file)| {
+                let stamp = file_stamp(file);
+                // An unparsable file is still recorded, so it isn't retried
+                // until it changes.
+                let messages = stamp.map(|_| {
+                    provider
+                        .parse_all_messages(Path::new(path))
+                        .unwrap_or_default()
+                });
+                if report {
+                    scan_progress::inc();
+                }
+                (path, stamp, messages)
+            })
+            .collect();
+
+        locked(|conn| write_chunk(conn, provider, parsed))?;
+        if cancel.load(Ordering::Relaxed) {
+            return Err("Search cancelled".to_string());
+        }
+        Ok(())
+    });
+    if report {
+        scan_progress::finish();
+    }
+    result
+}
+
+type Parsed<'a> = (&'a str, Option<(i64, i64)>, Option<Vec<DisplayMessage>>);
+
+/// Write one chunk of parsed files in a transaction.
+fn write_chunk(
+    conn: &Connection,
+    provider: &dyn SessionProvider,
+    parsed: Vec<Parsed>,
+) -> Result<(), String> {
+    let write = || -> rusqlite::Result<()> {
+        let tx = conn.unchecked_transaction()?;
+        for (path, stamp, messages) in parsed {
+            match (stamp, messages) {
+                (Some(stamp), Some(messages)) => {
+                    write_file(&tx, provider.id(), path, stamp, &messages)?
+                }
+                _ => {
+                    let id: Option<i64> = tx
+                        .query_row("SELECT id FROM files WHERE path = ?1", [path], |row| {
+                            row.get(0)
+                        })
+                        .optional()?;
+                    if let Some(id) = id {
+                        delete_file(&tx, id)?;
+                    }
+                }
+            }
+        }
+        tx.commit()
+    };
+    write().map_err(|e| format!("Failed to update search index: {}", e))
+}
+
+/// Bring the index up to date with `targets` and return the indexed files by
+/// target path. Takes the index lock per batch; fails with "Search cancelled"
+/// once `cancel` is set.
+pub(super) fn sync(
+    provider: &dyn SessionProvider,
+    targets: &[SearchTarget],
+    cancel: &AtomicBool,
+) -> Result<HashMap<String, IndexedFile>, String> {
+    let source = provider.id();
+    let known = locked(|conn| load_files(conn, source))?;
+    // Only marked synced once a full pass finishes, so a cancelled one is redone.
+    let full = !synced().lock().contains(source);
+
+    let wanted: Vec<(String, &Path)> = targets
+        .iter()
+        .filter_map(|t| {
+            let path = t.file_path.to_str()?;
+            let file = Path::new(provider.split_session_selector(path).0);
+            Some((path.to_string(), file))
+        })
+        .collect();
+    let changed: HashSet<PathBuf> = {
+        let mut dirty = dirty().lock();
+        let mine: HashSet<PathBuf> = wanted
+            .iter()
+            .map(|(_, file)| file.to_path_buf())
+            .filter(|file| dirty.contains(file))
+            .collect();
+        dirty.retain(|p| !mine.contains(p));
+        mine
+    };
+
+    let stale: Vec<(&str, &Path)> = wanted
+        .iter()
+        .filter(|(path, file)| match known.get(path) {
+            None => true,
+            Some(row) if full || changed.contains(*file) => {
+                file_stamp(file) != Some((row.size, row.mtime))
+            }
+            Some(_) => false,
+        })
+        .map(|(path, file)| (path.as_str(), *file))
+        .collect();
+
+    let wanted_paths: HashSet<&str> = wanted.iter().map(|(p, _)| p.as_str()).collect();
+    let removed: Vec<i64> = known
+        .iter()
+        .filter(|(path, _)| !wanted_paths.contains(path.as_str()))
+        .map(|(_, row)| row.id)
+        .collect();
+
+    if stale.is_empty() && removed.is_empty() {
+        synced().lock().insert(source.to_string());
+        return Ok(known);
+    }
+    if !removed.is_empty() {
+        locked(|conn| {
+            let tx = conn
+                .unchecked_transaction()
+                .map_err(|e| format!("Failed to update search index: {}", e))?;
+            for id in removed {
+                delete_file(&tx, id)
+                    .map_err(|e| format!("Failed to update search index: {}", e))?;
+            }
+            tx.commit()
+                .map_err(|e| format!("Failed to update search index: {}", e))
+        })?;
+    }
+    index_files(provider, &stale, cancel)?;
+    synced().lock().insert(source.to_string());
+    locked(|conn| load_files(conn, source))
+}
+
+/// Index file id of a block rowid.
+pub(super) fn file_of(rowid: i64) -> i64 {
+    rowid >> BLOCK_BITS
+}
+
+fn like_escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// `LIKE` pattern equivalent to [`Pattern::matches`] (case-insensitive for
+/// ASCII only).
+fn like_pattern(pattern: &Pattern) -> String {
+    let escaped = like_escape(&pattern.value).replace('*', "%");
+    if pattern.substring && !pattern.is_glob() {
+        format!("%{}%", escaped)
+    } else {
+        escaped
+    }
+}
+
+/// Quoted FTS5 string: a phrase of whatever the table's tokenizer makes of
+/// `text`.
+fn phrase(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "\"\""))
+}
+
+/// Words in `vocab` a typo or two away from `word`, closest first.
+fn near_words(conn: &Connection, word: &str) -> Result<Vec<String>, String> {
+    let max = tokenize::typo_budget(word);
+    let mut stmt = conn
+        .prepare_cached("SELECT word FROM vocab WHERE length(word) BETWEEN ?1 AND ?2")
+        .map_err(|e| format!("Failed to query search index: {}", e))?;
+    let rows = stmt
+        .query_map(
+            params![
+                word.len().saturating_sub(max) as i64,
+                (word.len() + max) as i64
+            ],
+            |row| row.get::<_, String>(0),
+        )
+        .map_err(|e| format!("Failed to query search index: {}", e))?;
+    let mut near = Vec::new();
+    for candidate in rows {
+        let candidate = candidate.map_err(|e| format!("Failed to query search index: {}", e))?;
+        if candidate == word {
+            continue;
+        }
+        if let Some(distance) = tokenize::edit_distance(&candidate, word, max) {
+            near.push((distance, candidate));
+        }
+    }
+    near.sort();
+    near.truncate(MAX_NEAR_WORDS);
+    Ok(near.into_iter().map(|(_, w)| w).collect())
+}
+
+/// A content term as queries on `words`: what scores it, and (for typo
+/// tolerance) the corrections it also matches.
+#[derive(Default)]
+struct WordsQuery {
+    score: Option<String>,
+    near: Option<String>,
+}
+
+fn words_query(conn: &Connection, term: &Term) -> Result<WordsQuery, String> {
+    let Some(literal) = term.literal.as_deref() else {
+        return Ok(WordsQuery::default());
+    };
+    if !term.fuzzy {
+        let tokens = tokenize::tokens(literal);
+        return Ok(WordsQuery {
+            score: (!tokens.is_empty()).then(|| phrase(&tokens.join(" "))),
+            near: None,
+        });
+    }
+    let near: Vec<String> = near_words(conn, literal)?
+        .iter()
+        .map(|w| phrase(w))
+        .collect();
+    // The exact word is listed twice (bm25 sums per phrase) so it outweighs
+    // a correction.
+    let mut score = vec![format!("{}*", phrase(literal)), phrase(literal)];
+    score.extend(near.iter().cloned());
+    Ok(WordsQuery {
+        score: Some(format!("({})", score.join(" OR "))),
+        near: (!near.is_empty()).then(|| format!("({})", near.join(" OR "))),
+    })
+}
+
+/// A pure CJK term of two or more characters: its bigram phrase in `words`
+/// matches exactly where the substring occurs.
+fn is_cjk_phrase(literal: &str) -> bool {
+    literal.chars().count() >= 2 && literal.chars().all(tokenize::is_cjk)
+}
+
+/// Blocks matching the message-level conditions of `query`, best match
+/// first, with their relevance (higher is better, 0 when unscored).
+///
+/// Each content term is a set of rowids: trigram `MATCH` for 3+ characters,
+/// the bigram phrase in `words` for CJK, `LIKE` otherwise, plus the typo
+/// corrections from `vocab`. Terms outside plain mode are checked with
+/// `regexp`. bm25 over `words` orders the result.
+pub(super) fn match_blocks(conn: &Connection, query: &Query) -> Result<Vec<(i64, f64)>, String> {
+    fn trigram(term: &Term) -> Option<&str> {
+        term.literal
+            .as_deref()
+            .filter(|literal| literal.chars().count() >= 3)
+    }
+
+    let mut conditions: Vec<String> = Vec::new();
+    let mut scores: Vec<String> = Vec::new();
+    let mut params: Vec<String> = Vec::new();
+    let bind = |params: &mut Vec<String>, value: String| {
+        params.push(value);
+        format!("?{}", params.len())
+    };
+
+    for clause in &query.clauses {
+        let condition = match &clause.condition {
+            Condition::Text(term) => {
+                let words = match clause.negated {
+                    true => WordsQuery::default(),
+                    false => words_query(conn, term)?,
+                };
+                scores.extend(words.score);
+                // Outside plain mode these only narrow, which a negated term
+                // must not do.
+                let narrow = term.plain || !clause.negated;
+                let literal = term.literal.as_deref().unwrap_or_default();
+                let mut sets: Vec<String> = Vec::new();
+                if narrow {
+                    if term.literal.is_some() && is_cjk_phrase(literal) {
+                        let tokens = tokenize::tokens(literal).join(" ");
+                        sets.push(format!(
+                            "SELECT rowid FROM words WHERE words MATCH {}",
+                            bind(&mut params, phrase(&tokens))
+                        ));
+                    } else if let Some(literal) = trigram(term) {
+                        sets.push(format!(
+                            "SELECT rowid FROM blocks WHERE blocks MATCH {}",
+                            bind(&mut params, phrase(literal))
+                        ));
+                    } else if term.plain {
+                        sets.push(format!(
+                            "SELECT rowid FROM blocks WHERE text LIKE {} ESCAPE '\\'",
+                            bind(&mut params, format!("%{}%", like_escape(literal)))
+                        ));
+                    }
+                }
+                if let Some(near) = words.near {
+                    sets.push(format!(
+                        "SELECT rowid FROM words WHERE words MATCH {}",
+                        bind(&mut params, near)
+                    ));
+                }
+
+                let mut parts = Vec::new();
+                if !sets.is_empty() {
+                    parts.push(format!("b.rowid IN ({})", sets.join(" UNION ")));
+                }
+                if !term.plain {
+                    parts.push(format!(
+                        "b.text REGEXP {}",
+                        bind(&mut params, term.regex.as_str().to_string())
+                    ));
+                }
+                parts.join(" AND ")
+            }
+            Condition::Role(p) => format!(
+                "b.role LIKE {} ESCAPE '\\'",
+                bind(&mut params, like_pattern(p))
+            ),
+            Condition::Tool(p) => format!(
+                "IFNULL(b.tool, '') LIKE {} ESCAPE '\\'",
+                bind(&mut params, like_pattern(p))
+            ),
+            Condition::Model(p) => format!(
+                "IFNULL(b.model, '') LIKE {} ESCAPE '\\'",
+                bind(&mut params, like_pattern(p))
+            ),
+            Condition::After(d) => format!(
+                "IFNULL(b.timestamp, '') >= {}",
+                bind(&mut params, date_bound(d))
+            ),
+            Condition::Before(d) => format!(
+                "IFNULL(b.timestamp, '') != '' AND b.timestamp < {}",
+                bind(&mut params, date_bound(d))
+            ),
+            _ => continue,
+        };
+        conditions.push(if clause.negated {
+            format!("NOT ({})", condition)
+        } else {
+            condition
+        });
+    }
+
+    let mut sql = if scores.is_empty() {
+        "SELECT b.rowid, NULL FROM blocks b WHERE 1".to_string()
+    } else {
+        format!(
+            "SELECT b.rowid, w.score FROM blocks b LEFT JOIN \
+             (SELECT rowid, bm25(words) AS score FROM words WHERE words MATCH {}) w \
+             ON w.rowid = b.rowid WHERE 1",
+            bind(&mut params, scores.join(" OR "))
+        )
+    };
+    for condition in &conditions {
+        sql.push_str(" AND ");
+        sql.push_str(condition);
+    }
+    // Equal scores (or no scored terms at all) fall back to newest first.
+    sql.push_str(if scores.is_empty() {
+        " ORDER BY b.timestamp IS NULL, b.timestamp DESC, b.rowid"
+    } else {
+        " ORDER BY w.score IS NULL, w.score, b.timestamp IS NULL, b.timestamp DESC, b.rowid"
+    });
+
+    let mut stmt = conn
+        .prepare(&sql)
+        .map_err(|e| format!("Failed to query search index: {}", e))?;
+    let rows = stmt
+        .query_map(rusqlite::params_from_iter(params), |row| {
+            // bm25() is lower-is-better.
+            let score: Option<f64> = row.get(1)?;
+            Ok((row.get(0)?, -score.unwrap_or(0.0)))
+        })
+        .map_err(|e| format!("Failed to query search index: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to query search index: {}", e))
+}
+
+/// A stored block: text, role, timestamp, message uuid.
+pub(super) struct Block {
+    pub text: String,
+    pub role: String,
+    pub timestamp: Option<String>,
+    pub message_uuid: Option<String>,
+}
+
+pub(super) fn block(conn: &Connection, rowid: i64) -> Result<Block, String> {
+    conn.query_row(
+        "SELECT text, role, timestamp, message_uuid FROM blocks WHERE rowid = ?1",
+        [rowid],
+        |row| {
+            Ok(Block {
+                text: row.get(0)?,
+                role: row.get(1)?,
+                timestamp: row.get(2)?,
+                message_uuid: row.get(3)?,
+            })
+        },
+    )
+    .map_err(|e| format!("Failed to query search index: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::SearchOptions;
+
+    fn q(query: &str) -> Query {
+        Query::parse(query).unwrap()
+    }
+
+    fn message(role: &str, uuid: &str, text: &str) -> DisplayMessage {
+        DisplayMessage {
+            uuid: Some(uuid.to_string()),
+            parent_uuid: None,
+            role: role.to_string(),
+            timestamp: None,
+            model: None,
+            content: vec![DisplayContentBlock::Text {
+                text: text.to_string(),
+            }],
+            usage: None,
+        }
+    }
+
+    #[test]
+    fn matches_substrings_and_replaces_rewritten_files() {
+        let conn = Connection::open_in_memory().unwrap();
+        init(&conn).unwrap();
+        write_file(
+            &conn,
+            "claude",
+            "/a.jsonl",
+            (1, 1),
+            &[
+                message("user", "u1", "修复登录页面的 Bug"),
+                message("assistant", "a1", "Migrating the database schema"),
+            ],
+        )
+        .unwrap();
+        write_file(
+            &conn,
+            "claude",
+            "/b.jsonl",
+            (1, 1),
+            &[message("user", "u2", "登录")],
+        )
+        .unwrap();
+
+        let hits = match_blocks(&conn, &q("grating the")).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(
+            block(&conn, hits[0].0).unwrap().message_uuid.as_deref(),
+            Some("a1")
+        );
+        assert_eq!(match_blocks(&conn, &q("bug")).unwrap().len(), 1);
+        // Two-character CJK terms match through their bigram.
+        assert_eq!(match_blocks(&conn, &q("登录")).unwrap().len(), 2);
+        assert_eq!(match_blocks(&conn, &q("登")).unwrap().len(), 2);
+        assert_eq!(match_blocks(&conn, &q("登录 -bug")).unwrap().len(), 1);
+        assert_eq!(
+            match_blocks(&conn, &q("database -role:user"))
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(match_blocks(&conn, &q("role:assistant")).unwrap().len(), 1);
+        assert!(match_blocks(&conn, &q("database before:2020-01-01"))
+            .unwrap()
+            .is_empty());
+        let regex = SearchOptions {
+            regex: true,
+            ..SearchOptions::default()
+        };
+        let hits = |query: &str, options| {
+            match_blocks(&conn, &Query::parse_with(query, options).unwrap())
+                .unwrap()
+                .len()
+        };
+        assert_eq!(hits(r"migrat\w+ -bug", regex), 1);
+        let exact = SearchOptions {
+            case_sensitive: true,
+            whole_word: true,
+            ..SearchOptions::default()
+        };
+        assert_eq!(hits("Bug", exact), 1);
+        assert_eq!(hits("bug", exact), 0);
+        assert_eq!(hits("Migrat", exact), 0);
+
+        let files = load_files(&conn, "claude").unwrap();
+        assert_eq!(files["/a.jsonl"].message_count, 2);
+        assert_eq!(
+            files["/a.jsonl"]
+                .first_prompt
+                .as_ref()
+                .map(|f| f.text.as_str()),
+            Some("修复登录页面的 Bug")
+        );
+
+        write_file(
+            &conn,
+            "claude",
+            "/a.jsonl",
+            (2, 2),
+            &[message("user", "u3", "hello")],
+        )
+        .unwrap();
+        assert!(match_blocks(&conn, &q("grating")).unwrap().is_empty());
+        let files = load_files(&conn, "claude").unwrap();
+        let a = &files["/a.jsonl"];
+        assert_eq!(
+            file_of(match_blocks(&conn, &q("hello")).unwrap()[0].0),
+            a.id
+        );
+
+        delete_file(&conn, a.id).unwrap();
+        assert!(match_blocks(&conn, &q("hello")).unwrap().is_empty());
+        assert_eq!(load_files(&conn, "claude").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn ranks_by_relevance_and_corrects_typos() {
+        let conn = Connection::open_in_memory().unwrap();
+        init(&conn).unwrap();
+        write_file(
+            &conn,
+            "claude",
+            "/a.jsonl",
+            (1, 1),
+            &[
+                message(
+                    "user",
+                    "u1",
+                    "Please look at the failing test and the build logs later",
+                ),
+                message(
+                    "assistant",
+                    "a1",
+                    "The database migration: the migration adds the column",
+                ),
+                message("user", "u2", "登录页面报错，登录接口超时"),
+            ],
+        )
+        .unwrap();
+
+        let hits = match_blocks(&conn, &q("migration")).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert!(hits[0].1 > 0.0);
+        // A typo still finds the word.
+        let hits = match_blocks(&conn, &q("migraiton")).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(match_blocks(&conn, &q("databse tables")).unwrap().len(), 0);
+        assert_eq!(match_blocks(&conn, &q("databse column")).unwrap().len(), 1);
+        // Negated terms stay exact.
+        assert_eq!(match_blocks(&conn, &q("the -migraiton")).unwrap().len(), 2);
+
+        let hits = match_blocks(&conn, &q("登录 the")).unwrap();
+        assert!(hits.is_empty());
+        let hits = match_blocks(&conn, &q("the")).unwrap();
+        assert_eq!(hits.len(), 2);
+        let first = block(&conn, hits[0].0).unwrap();
+        // The shorter block with the term repeated outranks the longer one.
+        assert_eq!(first.message_uuid.as_deref(), Some("a1"));
+        assert!(hits[0].1 > hits[1].1);
+
+        // The exact spelling outranks a correction, even in a longer block.
+        write_file(
+            &conn,
+            "claude",
+            "/b.jsonl",
+            (1, 1),
+            &[message("user", "u3", "run the databse")],
+        )
+        .unwrap();
+        let hits = match_blocks(&conn, &q("database")).unwrap();
+        assert_eq!(hits.len(), 2);
+        assert_eq!(
+            block(&conn, hits[0].0).unwrap().message_uuid.as_deref(),
+            Some("a1")
+        );
+    }
+}
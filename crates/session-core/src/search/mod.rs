@@ -0,0 +1,981 @@
+use rayon::prelude::*;
+use rusqlite::Connection;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::models::message::{DisplayContentBlock, DisplayMessage};
+use crate::provider::{self, SearchTarget, SessionProvider};
+use crate::scan_progress::{self, Phase, ScanProgress};
+
+mod index;
+mod query;
+mod tokenize;
+
+pub use index::{mark_dirty, mark_unsynced};
+pub use query::{Query, QueryError, SearchOptions};
+
+use query::{BlockFields, SessionFields};
+
+/// Content matches listed per session; the rest are only counted.
+const MAX_MATCHES_PER_SESSION: usize = 5;
+
+/// `source` value of [`global_search`] that searches every registered source.
+pub const ALL_SOURCES: &str = "all";
+
+/// Indexed content matches handed to a [`SearchSink`] at a time.
+const STREAM_BATCH: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchScope {
+    All,
+    Session,
+    Content,
+    Tags,
+}
+
+impl SearchScope {
+    pub fn from_query(value: &str) -> Self {
+        match value {
+            "session" => Self::Session,
+            "content" => Self::Content,
+            "tags" => Self::Tags,
+            _ => Self::All,
+        }
+    }
+
+    fn includes_session(self) -> bool {
+        matches!(self, Self::All | Self::Session)
+    }
+
+    fn includes_content(self) -> bool {
+        matches!(self, Self::All | Self::Content)
+    }
+
+    fn includes_tags(self) -> bool {
+        matches!(self, Self::All | Self::Tags)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResult {
+    pub source: String,
+    pub project_id: String,
+    pub project_name: String,
+    pub session_id: String,
+    pub first_prompt: Option<String>,
+    /// Codex only: Codex Desktop's thread title (session_index.jsonl).
+    /// Preferred over first_prompt for display. `None` for Claude.
+    pub thread_name: Option<String>,
+    pub alias: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub matched_text: String,
+    pub role: String,
+    pub timestamp: Option<String>,
+    pub file_path: String,
+    pub total_message_count: u32,
+    pub matched_message_id: Option<String>,
+    /// Where the content terms matched within `matched_text`.
+    pub match_ranges: Vec<MatchRange>,
+    /// Relevance (BM25-style); higher is better, 0 when unscored.
+    pub score: f64,
+}
+
+/// A match inside `SearchResult::matched_text`, as byte offsets and as
+/// character (Unicode scalar) offsets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchRange {
+    pub start: usize,
+    pub end: usize,
+    pub char_start: usize,
+    pub char_end: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResponse {
+    pub results: Vec<SearchResult>,
+    /// Every match, including those cut by `max_results` or the per-session
+    /// limit.
+    pub total_matches: usize,
+    /// Sessions with at least one match.
+    pub total_sessions: usize,
+    /// `false` when the index was unavailable and the files were scanned
+    /// instead; the totals then only cover `results`.
+    pub indexed: bool,
+    /// Set, with no results, when the query does not parse.
+    pub query_error: Option<QueryError>,
+    /// Plain text terms of the query (filters and exclusions removed), for
+    /// searching within a session; empty in regex mode.
+    pub highlight_terms: Vec<String>,
+}
+
+/// Results a streamed search has found so far, for the client that asked
+/// with `id`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchBatch {
+    pub id: u64,
+    pub results: Vec<SearchResult>,
+    pub progress: ScanProgress,
+}
+
+/// Where [`global_search_stream`] hands results over as it finds them, and
+/// how it learns that a newer query has superseded it.
+#[derive(Clone, Copy)]
+pub struct SearchSink<'a> {
+    pub cancel: &'a AtomicBool,
+    pub on_batch: &'a (dyn Fn(&[SearchResult]) + Sync),
+}
+
+impl SearchSink<'_> {
+    fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+
+    fn send(&self, results: &[SearchResult]) {
+        if !results.is_empty() && !self.is_cancelled() {
+            (self.on_batch)(results);
+        }
+    }
+}
+
+#[derive(Clone)]
+struct SearchSessionContext {
+    source: String,
+    project_id: String,
+    project_name: String,
+    session_id: String,
+    thread_name: Option<String>,
+    alias: Option<String>,
+    search_aliases: Vec<String>,
+    tags: Option<Vec<String>>,
+    git_branch: Option<String>,
+    file_path: String,
+}
+
+impl SearchSessionContext {
+    fn new(provider: &dyn SessionProvider, target: SearchTarget) -> Self {
+        SearchSessionContext {
+            source: provider.id().to_string(),
+            project_id: target.project_id,
+            project_name: target.project_name,
+            session_id: target.session_id,
+            thread_name: target.thread_name,
+            alias: target.alias,
+            search_aliases: target.search_aliases,
+            tags: target.tags,
+            git_branch: target.git_branch,
+            file_path: target.file_path.to_string_lossy().to_string(),
+        }
+    }
+
+    fn fields(&self) -> SessionFields<'_> {
+        SessionFields {
+            project_id: &self.project_id,
+            project_name: &self.project_name,
+            git_branch: self.git_branch.as_deref(),
+            tags: self.tags.as_deref().unwrap_or_default(),
+        }
+    }
+
+    /// A result in this session; message-specific fields are left empty.
+    fn result(
+        &self,
+        role: &str,
+        (matched_text, match_ranges): (String, Vec<MatchRange>),
+        total_message_count: u32,
+    ) -> SearchResult {
+        SearchResult {
+            source: self.source.clone(),
+            project_id: self.project_id.clone(),
+            project_name: self.project_name.clone(),
+            session_id: self.session_id.clone(),
+            first_prompt: None,
+            thread_name: self.thread_name.clone(),
+            alias: self.alias.clone(),
+            tags: self.tags.clone(),
+            matched_text,
+            role: role.to_string(),
+            timestamp: None,
+            file_path: self.file_path.clone(),
+            total_message_count,
+            matched_message_id: None,
+            match_ranges,
+            score: 0.0,
+        }
+    }
+}
+
+/// Safely truncate a string to approximately `max_chars` characters
+fn safe_truncate(s: &str, max_chars: usize) -> String {
+    let truncated: String = s.chars().take(max_chars).collect();
+    if truncated.len() < s.len() {
+        format!("{}...", truncated)
+    } else {
+        truncated
+    }
+}
+
+/// Byte offset `chars` characters before `from`.
+fn chars_before(text: &str, from: usize, chars: usize) -> usize {
+    text[..from]
+        .char_indices()
+        .rev()
+        .nth(chars.saturating_sub(1))
+        .map(|(i, _)| if chars == 0 { from } else { i })
+        .unwrap_or(0)
+}
+
+/// Byte offset `chars` characters after `from`.
+fn chars_after(text: &str, from: usize, chars: usize) -> usize {
+    text[from..]
+        .char_indices()
+        .nth(chars)
+        .map(|(i, _)| from + i)
+        .unwrap_or(text.len())
+}
+
+/// `text[window]` with the match `ranges` (byte offsets into `text`) that
+/// fall inside it, clipped and rebased onto the excerpt.
+fn excerpt(text: &str, ranges: &[Range<usize>], window: Range<usize>) -> (String, Vec<MatchRange>) {
+    let part = &text[window.clone()];
+    let mut char_pos = 0;
+    let mut last_byte = 0;
+    let mut char_at = |byte: usize| {
+        char_pos += part[last_byte..byte].chars().count();
+        last_byte = byte;
+        char_pos
+    };
+    let matches = ranges
+        .iter()
+        .filter(|r| r.start < window.end && r.end > window.start)
+        .map(|r| {
+            let start = r.start.max(window.start) - window.start;
+            let end = r.end.min(window.end) - window.start;
+            MatchRange {
+                start,
+                end,
+                char_start: char_at(start),
+                char_end: char_at(end),
+            }
+        })
+        .collect();
+    (part.to_string(), matches)
+}
+
+/// The first `max_chars` characters of `text` (with `...` when cut) and
+/// the query's matches in them. Matching runs on the whole text, so a cut
+/// never creates or breaks a match.
+fn leading(text: &str, query: &Query, max_chars: usize) -> (String, Vec<MatchRange>) {
+    let end = chars_after(text, 0, max_chars);
+    let (mut part, ranges) = excerpt(text, &query.match_ranges(text), 0..end);
+    if end < text.len() {
+        part.push_str("...");
+    }
+    (part, ranges)
+}
+
+/// Extract searchable text from a DisplayContentBlock
+fn block_text(block: &DisplayContentBlock) -> &str {
+    match block {
+        DisplayContentBlock::Text { text } => text,
+        DisplayContentBlock::Thinking { thinking } => thinking,
+        DisplayContentBlock::ToolUse { input, .. } => input,
+        DisplayContentBlock::ToolResult { content, .. } => content,
+        DisplayContentBlock::Reasoning { text } => text,
+        DisplayContentBlock::FunctionCall { arguments, .. } => arguments,
+        DisplayContentBlock::FunctionCallOutput { output, .. } => output,
+        DisplayContentBlock::Document { title, .. } => title.as_deref().unwrap_or(""),
+        DisplayContentBlock::Image { .. } => "",
+    }
+}
+
+/// Tool name per call id, to tag tool results with the tool they answer.
+fn tool_names(messages: &[DisplayMessage]) -> HashMap<&str, &str> {
+    messages
+        .iter()
+        .flat_map(|m| &m.content)
+        .filter_map(|block| match block {
+            DisplayContentBlock::ToolUse { id, name, .. } => Some((id.as_str(), name.as_str())),
+            DisplayContentBlock::FunctionCall { call_id, name, .. } => {
+                Some((call_id.as_str(), name.as_str()))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Tool a block calls or returns the result of.
+fn block_tool<'a>(block: &'a DisplayContentBlock, names: &HashMap<&str, &'a str>) -> Option<&'a str> {
+    match block {
+        DisplayContentBlock::ToolUse { name, .. } | DisplayContentBlock::FunctionCall { name, .. } => {
+            Some(name)
+        }
+        DisplayContentBlock::ToolResult { tool_use_id: id, .. }
+        | DisplayContentBlock::FunctionCallOutput { call_id: id, .. } => {
+            names.get(id.as_str()).copied()
+        }
+        _ => None,
+    }
+}
+
+/// Matched text shown for a block: 50 characters either side of the first
+/// match, or the start of the block for filter-only queries.
+fn snippet(text: &str, query: &Query) -> (String, Vec<MatchRange>) {
+    let ranges = query.match_ranges(text);
+    match ranges.first() {
+        Some(first) => {
+            let window = chars_before(text, first.start, 50)..chars_after(text, first.end, 50);
+            excerpt(text, &ranges, window)
+        }
+        None => (safe_truncate(text, 100), Vec::new()),
+    }
+}
+
+/// A session's first prompt: text, message uuid, timestamp.
+type FirstPrompt<'a> = (&'a str, Option<&'a str>, Option<&'a str>);
+
+/// Matches decided by the session alone: its tags, name or first prompt, or
+/// the session itself when the query only has session filters.
+fn session_matches(
+    ctx: &SearchSessionContext,
+    query: &Query,
+    scope: SearchScope,
+    first_prompt: Option<FirstPrompt>,
+    total_message_count: u32,
+) -> Vec<SearchResult> {
+    let prompt_result = |(text, uuid, timestamp): FirstPrompt| SearchResult {
+        first_prompt: Some(safe_truncate(text, 100)),
+        score: query.relevance(text),
+        timestamp: timestamp.map(str::to_string),
+        matched_message_id: uuid.map(str::to_string),
+        ..ctx.result("session", leading(text, query, 100), total_message_count)
+    };
+
+    if query.is_session_listing() {
+        let result = match (ctx.alias.as_ref(), first_prompt) {
+            (None, Some(prompt)) => prompt_result(prompt),
+            (alias, _) => {
+                let name = alias.unwrap_or(&ctx.session_id).clone();
+                ctx.result("session", (name, Vec::new()), total_message_count)
+            }
+        };
+        return vec![result];
+    }
+    if query.has_message_filters() {
+        return Vec::new();
+    }
+
+    let mut results = Vec::new();
+    if scope.includes_tags() {
+        if let Some(tag) = ctx.tags.iter().flatten().find(|t| query.matches_text(t)) {
+            results.push(SearchResult {
+                score: query.relevance(tag),
+                ..ctx.result("tag", leading(tag, query, usize::MAX), total_message_count)
+            });
+        }
+    }
+    if scope.includes_session() {
+        if let Some(alias) = ctx.search_aliases.iter().find(|a| query.matches_text(a)) {
+            let matched = leading(alias, query, usize::MAX);
+            results.push(SearchResult {
+                score: query.relevance(alias),
+                ..ctx.result("session", matched, total_message_count)
+            });
+        } else if let Some(prompt) = first_prompt.filter(|(text, ..)| query.matches_text(text)) {
+            results.push(prompt_result(prompt));
+        }
+    }
+    results
+}
+
+fn search_messages_for_session(
+    ctx: &SearchSessionContext,
+    messages: &[DisplayMessage],
+    query: &Query,
+    scope: SearchScope,
+) -> Vec<SearchResult> {
+    let total_message_count = messages.len() as u32;
+    let mut results = Vec::new();
+    let first_prompt = messages.iter().find(|m| m.role == "user").and_then(|msg| {
+        msg.content.iter().find_map(|block| match block {
+            DisplayContentBlock::Text { text } => Some((
+                text.as_str(),
+                msg.uuid.as_deref(),
+                msg.timestamp.as_deref(),
+            )),
+            _ => None,
+        })
+    });
+
+    results.extend(session_matches(
+        ctx,
+        query,
+        scope,
+        first_prompt,
+        total_message_count,
+    ));
+    if !scope.includes_content() || query.is_session_listing() {
+        return results;
+    }
+
+    let first_prompt = first_prompt.map(|(text, ..)| safe_truncate(text, 100));
+    let tools = tool_names(messages);
+    let mut message_match_count = 0usize;
+    for msg in messages {
+        for block in &msg.content {
+            let text = block_text(block);
+            let fields = BlockFields {
+                text,
+                role: &msg.role,
+                model: msg.model.as_deref(),
+                tool: block_tool(block, &tools),
+                timestamp: msg.timestamp.as_deref(),
+            };
+            if text.trim().is_empty() || !query.matches_block(&fields) {
+                continue;
+            }
+            let result = SearchResult {
+                first_prompt: first_prompt.clone(),
+                timestamp: msg.timestamp.clone(),
+                matched_message_id: msg.uuid.clone(),
+                score: query.relevance(text),
+                ..ctx.result(&msg.role, snippet(text, query), total_message_count)
+            };
+            results.push(result);
+
+            message_match_count += 1;
+            if message_match_count >= MAX_MATCHES_PER_SESSION {
+                return results;
+            }
+        }
+    }
+
+    results
+}
+
+/// Name, tag and prompt matches first, then the rest; each by relevance,
+/// then newest first.
+fn by_relevance(a: &SearchResult, b: &SearchResult) -> std::cmp::Ordering {
+    let is_message = |r: &SearchResult| !matches!(r.role.as_str(), "session" | "tag");
+    is_message(a)
+        .cmp(&is_message(b))
+        .then(b.score.total_cmp(&a.score))
+        .then_with(|| b.timestamp.cmp(&a.timestamp))
+}
+
+/// Search `source`, or with [`ALL_SOURCES`] every registered source, merging
+/// the results by relevance and keeping the best `max_results` overall.
+pub fn global_search(
+    source: &str,
+    query: &str,
+    max_results: usize,
+    scope: SearchScope,
+    options: SearchOptions,
+) -> Result<SearchResponse, String> {
+    let sink = SearchSink {
+        cancel: &AtomicBool::new(false),
+        on_batch: &|_| {},
+    };
+    global_search_stream(source, query, max_results, scope, options, sink)
+}
+
+/// [`global_search`], handing each batch of results to `sink` as it is found
+/// and reporting progress through [`scan_progress`]. Batches arrive in
+/// discovery order and, across sources, may hold more than `max_results`; the
+/// returned response is the ranked, truncated list. Once `sink.cancel` is set
+/// the search stops early and its (partial) response should be dropped.
+pub fn global_search_stream(
+    source: &str,
+    query: &str,
+    max_results: usize,
+    scope: SearchScope,
+    options: SearchOptions,
+    sink: SearchSink,
+) -> Result<SearchResponse, String> {
+    let providers: Vec<&dyn SessionProvider> = if source == ALL_SOURCES {
+        provider::all().to_vec()
+    } else {
+        vec![provider::get(source)?]
+    };
+    search_providers(&providers, query, max_results, scope, options, sink)
+}
+
+fn search_providers(
+    providers: &[&dyn SessionProvider],
+    query: &str,
+    max_results: usize,
+    scope: SearchScope,
+    options: SearchOptions,
+    sink: SearchSink,
+) -> Result<SearchResponse, String> {
+    let query = match Query::parse_with(query, options) {
+        Ok(query) => query,
+        Err(e) => {
+            return Ok(SearchResponse {
+                query_error: Some(e),
+                ..SearchResponse::default()
+            })
+        }
+    };
+    let mut response = SearchResponse {
+        indexed: true,
+        ..SearchResponse::default()
+    };
+    if query.is_empty() || max_results == 0 {
+        return Ok(response);
+    }
+
+    for &provider in providers {
+        if sink.is_cancelled() {
+            break;
+        }
+        let part = search_source(provider, &query, max_results, scope, sink);
+        response.total_matches += part.total_matches;
+        response.total_sessions += part.total_sessions;
+        response.indexed &= part.indexed;
+        response.results.extend(part.results);
+    }
+    // Stable, so a single source keeps its own order.
+    response.results.sort_by(by_relevance);
+    response.results.truncate(max_results);
+    response.highlight_terms = query
+        .text_terms(false)
+        .filter(|t| t.literal.is_some())
+        .map(|t| t.text.clone())
+        .collect();
+    Ok(response)
+}
+
+/// One source: through the index, or scanning its files when the index is
+/// unavailable.
+fn search_source(
+    provider: &dyn SessionProvider,
+    query: &Query,
+    max_results: usize,
+    scope: SearchScope,
+    sink: SearchSink,
+) -> SearchResponse {
+    let response = match search_index(provider, query, max_results, scope, sink) {
+        Ok(response) => response,
+        // Dropped by the caller; don't start scanning files for it.
+        Err(_) if sink.is_cancelled() => SearchResponse::default(),
+        // Scan the files instead; `indexed: false` tells the caller.
+        Err(_) => {
+            let results = search_provider(provider, query, max_results, scope, sink);
+            let total_sessions = results
+                .iter()
+                .map(|r| &r.file_path)
+                .collect::<HashSet<_>>()
+                .len();
+            SearchResponse {
+                total_matches: results.len(),
+                total_sessions,
+                results,
+                ..SearchResponse::default()
+            }
+        }
+    };
+    // A superseding search has taken the progress over by now.
+    if !sink.is_cancelled() {
+        scan_progress::finish();
+    }
+    response
+}
+
+fn search_index(
+    provider: &dyn SessionProvider,
+    query: &Query,
+    max_results: usize,
+    scope: SearchScope,
+    sink: SearchSink,
+) -> Result<SearchResponse, String> {
+    let targets = provider.search_targets();
+    let files = index::sync(provider, &targets, sink.cancel)?;
+
+    // Sessions passing the session filters, with what the index knows of them.
+    let contexts: Vec<(SearchSessionContext, Option<&index::IndexedFile>)> = targets
+        .into_iter()
+        .map(|target| SearchSessionContext::new(provider, target))
+        .filter(|ctx| query.matches_session(&ctx.fields()))
+        .map(|ctx| {
+            let file = files.get(&ctx.file_path);
+            (ctx, file)
+        })
+        .collect();
+    index::locked(|conn| query_index(conn, query, &contexts, max_results, scope, sink))
+}
+
+/// Match `contexts` (sessions passing the session filters) against the index:
+/// name, tag and prompt matches first, then blocks by relevance.
+fn query_index(
+    conn: &Connection,
+    query: &Query,
+    contexts: &[(SearchSessionContext, Option<&index::IndexedFile>)],
+    max_results: usize,
+    scope: SearchScope,
+    sink: SearchSink,
+) -> Result<SearchResponse, String> {
+    let by_file_id: HashMap<i64, usize> = contexts
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (_, file))| Some((file.as_ref()?.id, i)))
+        .collect();
+
+    let mut response = SearchResponse {
+        indexed: true,
+        ..SearchResponse::default()
+    };
+    let mut matched_sessions: HashSet<usize> = HashSet::new();
+    // Counts a match; `result` is listed while there is room.
+    let mut push = |response: &mut SearchResponse, session: usize, result: Option<SearchResult>| {
+        response.total_matches += 1;
+        matched_sessions.insert(session);
+        if response.results.len() < max_results {
+            response.results.extend(result);
+        }
+    };
+
+    // Name, tag and prompt matches lead, best first; ties go to the most
+    // recently modified session.
+    let mut session_results: Vec<(usize, SearchResult)> = Vec::new();
+    for (i, (ctx, file)) in contexts.iter().enumerate() {
+        let first_prompt = file.and_then(|f| f.first_prompt.as_ref()).map(|p| {
+            (p.text.as_str(), p.uuid.as_deref(), p.timestamp.as_deref())
+        });
+        let total_message_count = file.map(|f| f.message_count).unwrap_or(0);
+        for result in session_matches(ctx, query, scope, first_prompt, total_message_count) {
+            session_results.push((i, result));
+        }
+    }
+    let mtime = |i: usize| contexts[i].1.map(|f| f.mtime).unwrap_or(0);
+    session_results.sort_by(|a, b| {
+        b.1.score
+            .total_cmp(&a.1.score)
+            .then_with(|| mtime(b.0).cmp(&mtime(a.0)))
+    });
+    for (i, result) in session_results {
+        push(&mut response, i, Some(result));
+    }
+    sink.send(&response.results);
+    let mut sent = response.results.len();
+
+    if scope.includes_content() && !query.is_session_listing() {
+        let hits = index::match_blocks(conn, query)?;
+        scan_progress::begin(Phase::Search, hits.len() as u64);
+        let mut listed: HashMap<usize, usize> = HashMap::new();
+        for (rowid, score) in hits {
+            if sink.is_cancelled() {
+                break;
+            }
+            scan_progress::inc();
+            if response.results.len() >= sent + STREAM_BATCH {
+                sink.send(&response.results[sent..]);
+                sent = response.results.len();
+            }
+            let Some(&i) = by_file_id.get(&index::file_of(rowid)) else {
+                continue;
+            };
+            let listed = listed.entry(i).or_default();
+            if response.results.len() >= max_results || *listed >= MAX_MATCHES_PER_SESSION {
+                push(&mut response, i, None);
+                continue;
+            }
+            *listed += 1;
+
+            let (ctx, file) = &contexts[i];
+            let block = index::block(conn, rowid)?;
+            let result = SearchResult {
+                first_prompt: file
+                    .and_then(|f| f.first_prompt.as_ref())
+                    .map(|p| safe_truncate(&p.text, 100)),
+                timestamp: block.timestamp,
+                matched_message_id: block.message_uuid,
+                score,
+                ..ctx.result(
+                    &block.role,
+                    snippet(&block.text, query),
+                    file.map(|f| f.message_count).unwrap_or(0),
+                )
+            };
+            push(&mut response, i, Some(result));
+        }
+        sink.send(&response.results[sent..]);
+    }
+
+    response.total_sessions = matched_sessions.len();
+    Ok(response)
+}
+
+fn search_provider(
+    provider: &dyn SessionProvider,
+    query: &Query,
+    max_results: usize,
+    scope: SearchScope,
+    sink: SearchSink,
+) -> Vec<SearchResult> {
+    if max_results == 0 {
+        return Vec::new();
+    }
+
+    let targets = provider.search_targets();
+    // Regex terms cannot be checked against the raw (JSON-escaped) file.
+    let positive: Vec<&query::Term> = query
+        .text_terms(false)
+        .filter(|t| t.literal.is_some())
+        .collect();
+
+    scan_progress::begin(Phase::Search, targets.len() as u64);
+    let search_target = |target: SearchTarget| {
+        if sink.is_cancelled() {
+            return Vec::new();
+        }
+        let ctx = SearchSessionContext::new(provider, target);
+        if !query.matches_session(&ctx.fields()) {
+            return Vec::new();
+        }
+
+        // Cheap pre-filter on the raw file before parsing it.
+        if !positive.is_empty() {
+            let content = match provider.read_session_text(Path::new(&ctx.file_path)) {
+                Ok(c) => c.to_lowercase(),
+                Err(_) => return Vec::new(),
+            };
+            let content_has_query = positive.iter().all(|t| {
+                let literal = t.literal.as_deref().unwrap_or_default();
+                content.contains(literal)
+                    || t.fuzzy && !tokenize::near_matches(&content, literal).is_empty()
+            });
+            let name_has_query = ctx
+                .search_aliases
+                .iter()
+                .chain(ctx.tags.iter().flatten())
+                .any(|candidate| query.matches_text(candidate));
+            if !content_has_query && !name_has_query {
+                return Vec::new();
+            }
+        }
+
+        if let Ok(messages) = provider.parse_all_messages(Path::new(&ctx.file_path)) {
+            return search_messages_for_session(&ctx, &messages, query, scope);
+        }
+
+        Vec::new()
+    };
+    let results: Vec<SearchResult> = targets
+        .into_par_iter()
+        .flat_map(|target: SearchTarget| {
+            let results = search_target(target);
+            scan_progress::inc();
+            sink.send(&results);
+            results
+        })
+        .collect();
+
+    // Every session is searched and ranked before cutting, so a strong hit in
+    // a later target isn't dropped for a weaker one that came back first.
+    let mut results = results;
+    results.sort_by(by_relevance);
+    results.truncate(max_results);
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::atomic::AtomicUsize;
+
+    use super::*;
+    use crate::models::message::{PaginatedMessages, RangeMessages};
+    use crate::models::project::ProjectEntry;
+    use crate::models::session::SessionIndexEntry;
+    use crate::models::stats::{RequestRecord, TokenUsageSummary};
+    use crate::provider::claude::{DeleteLevel, DeleteResult};
+
+    /// One session per text, each a single user message; counts how many
+    /// get parsed.
+    struct Fixture {
+        source: &'static str,
+        texts: Vec<String>,
+        parsed: AtomicUsize,
+    }
+
+    impl Fixture {
+        fn new(source: &'static str, texts: Vec<String>) -> Self {
+            Fixture {
+                source,
+                texts,
+                parsed: AtomicUsize::new(0),
+            }
+        }
+
+        fn text(&self, path: &Path) -> &str {
+            let stem = path.file_stem().unwrap().to_str().unwrap();
+            &self.texts[stem[1..].parse::<usize>().unwrap()]
+        }
+    }
+
+    impl SessionProvider for Fixture {
+        fn id(&self) -> &'static str {
+            self.source
+        }
+
+        fn display_name(&self) -> &'static str {
+            "Fixture"
+        }
+
+        fn search_targets(&self) -> Vec<SearchTarget> {
+            (0..self.texts.len())
+                .map(|i| SearchTarget {
+                    project_id: "p".to_string(),
+                    project_name: "p".to_string(),
+                    session_id: format!("s{}", i),
+                    file_path: PathBuf::from(format!("/{}/s{}.jsonl", self.source, i)),
+                    thread_name: None,
+                    alias: None,
+                    search_aliases: vec![],
+                    tags: None,
+                    git_branch: None,
+                })
+                .collect()
+        }
+
+        fn read_session_text(&self, path: &Path) -> std::io::Result<String> {
+            Ok(self.text(path).to_string())
+        }
+
+        fn parse_all_messages(&self, path: &Path) -> Result<Vec<DisplayMessage>, String> {
+            self.parsed.fetch_add(1, Ordering::Relaxed);
+            Ok(vec![DisplayMessage {
+                uuid: Some("m1".to_string()),
+                parent_uuid: None,
+                role: "user".to_string(),
+                timestamp: None,
+                model: None,
+                content: vec![DisplayContentBlock::Text {
+                    text: self.text(path).to_string(),
+                }],
+                usage: None,
+            }])
+        }
+
+        // Not used by search.
+        fn get_projects(&self) -> Result<Vec<ProjectEntry>, String> {
+            unreachable!()
+        }
+        fn delete_project(&self, _: &str, _: DeleteLevel) -> Result<DeleteResult, String> {
+            unreachable!()
+        }
+        fn get_sessions(&self, _: &str) -> Result<Vec<SessionIndexEntry>, String> {
+            unreachable!()
+        }
+        fn refresh_sessions_cache(&self, _: &str) -> Result<Vec<SessionIndexEntry>, String> {
+            unreachable!()
+        }
+        fn get_invalid_sessions(&self, _: &str) -> Result<Vec<SessionIndexEntry>, String> {
+            unreachable!()
+        }
+        fn parse_session_messages(
+            &self,
+            _: &Path,
+            _: usize,
+            _: usize,
+            _: bool,
+        ) -> Result<PaginatedMessages, String> {
+            unreachable!()
+        }
+        fn parse_messages_range(
+            &self,
+            _: &Path,
+            _: usize,
+            _: usize,
+        ) -> Result<RangeMessages, String> {
+            unreachable!()
+        }
+        fn get_stats(&self) -> Result<TokenUsageSummary, String> {
+            unreachable!()
+        }
+        fn collect_requests(&self) -> Result<Vec<RequestRecord>, String> {
+            unreachable!()
+        }
+        fn session_roots(&self) -> Vec<PathBuf> {
+            unreachable!()
+        }
+        fn validate_layout(&self, _: &Path, _: &Path) -> Result<(), String> {
+            unreachable!()
+        }
+        fn metadata_path(&self, _: &str) -> Option<PathBuf> {
+            unreachable!()
+        }
+        fn metadata_project_ids(&self) -> Vec<String> {
+            unreachable!()
+        }
+        fn invalidate_cache(&self) {}
+        fn invalidate_paths(&self, _: &[PathBuf]) {}
+    }
+
+    #[test]
+    fn stream_stops_once_cancelled() {
+        let fixture = Fixture::new("fixture", vec!["find the needle".to_string(); 8]);
+        let cancel = AtomicBool::new(false);
+        let batches = AtomicUsize::new(0);
+        // The receiver supersedes the search as soon as the first batch lands.
+        let on_batch = |_: &[SearchResult]| {
+            batches.fetch_add(1, Ordering::Relaxed);
+            cancel.store(true, Ordering::Relaxed);
+        };
+        let sink = SearchSink {
+            cancel: &cancel,
+            on_batch: &on_batch,
+        };
+        // One worker, so sessions are searched one at a time.
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap();
+        pool.install(|| {
+            let providers: [&dyn SessionProvider; 2] = [&fixture, &fixture];
+            let options = SearchOptions::default();
+            search_providers(&providers, "needle", 100, SearchScope::All, options, sink)
+        })
+        .unwrap();
+
+        assert_eq!(batches.load(Ordering::Relaxed), 1);
+        // Neither the rest of the sessions nor the second source were searched.
+        assert_eq!(fixture.parsed.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn sources_merge_by_relevance() {
+        // Fewer other words around the match score higher.
+        let padded = |n: usize| format!("needle{}", " filler".repeat(n));
+        let a = Fixture::new("a", vec![padded(0), padded(4), padded(8)]);
+        let b = Fixture::new("b", vec![padded(2), padded(6), padded(10)]);
+        let cancel = AtomicBool::new(false);
+        let sink = SearchSink {
+            cancel: &cancel,
+            on_batch: &|_: &[SearchResult]| {},
+        };
+        let providers: [&dyn SessionProvider; 2] = [&a, &b];
+        let options = SearchOptions::default();
+        let response =
+            search_providers(&providers, "needle", 4, SearchScope::Content, options, sink).unwrap();
+
+        let order: Vec<(&str, &str)> = response
+            .results
+            .iter()
+            .map(|r| (r.source.as_str(), r.session_id.as_str()))
+            .collect();
+        assert_eq!(order, [("a", "s0"), ("b", "s0"), ("a", "s1"), ("b", "s1")]);
+        assert!(response.results.windows(2).all(|w| w[0].score > w[1].score));
+        assert_eq!(response.results.len(), 4);
+        // Both sources' matches, not just those listed.
+        assert_eq!(response.total_matches, 6);
+        assert_eq!(response.total_sessions, 6);
+    }
+}
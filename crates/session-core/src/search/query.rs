@@ -0,0 +1,588 @@
+//! 全局搜索的查询语法。
+//!
+//! 空格分隔的若干条件，全部满足才算命中：
+//!
+//! - `word`、`"exact phrase"`：内容子串，默认不区分大小写；[`SearchOptions`]
+//!   可切换为正则、区分大小写、整词匹配（只影响内容词，不影响过滤器）。默认
+//!   模式下单个英文词还容忍拼写错误（见 [`super::tokenize`]），取反的词除外
+//! - `key:value` 过滤，value 可加引号，`*` 为通配符：
+//!   - 作用于消息：`role:` `tool:` `model:`（子串）`after:` `before:`（`YYYY-MM-DD`）
+//!   - 作用于会话：`project:`（子串）`branch:` `tag:`
+//! - 前缀 `-` 取反，如 `-excluded`、`-role:user`
+//!
+//! 不认识的 `key:` 按普通文本处理（`http://…` 这类不会报错）。解析失败返回
+//! 带字符位置的 [`QueryError`]，前端据此标出出错的部分。
+
+use chrono::NaiveDate;
+use regex::Regex;
+use serde::Serialize;
+use std::ops::Range;
+
+use super::tokenize;
+
+/// How content terms match.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchOptions {
+    /// Each term is a regular expression (quote one containing spaces).
+    pub regex: bool,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+}
+
+/// A query that failed to parse. `start..end` are character (not byte)
+/// offsets into the query.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryError {
+    pub message: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl QueryError {
+    fn new(message: impl Into<String>, start: usize, end: usize) -> Self {
+        QueryError {
+            message: message.into(),
+            start,
+            end,
+        }
+    }
+}
+
+/// A content term compiled for the active [`SearchOptions`].
+#[derive(Debug, Clone)]
+pub struct Term {
+    /// The term as typed.
+    pub text: String,
+    /// Lowercased literal text; `None` in regex mode.
+    pub literal: Option<String>,
+    /// Matches the term under the options; used for ranges and for whatever
+    /// the literal can only approximate.
+    pub regex: Regex,
+    /// Whether a case-insensitive substring search on `literal` is exact,
+    /// i.e. the regex adds nothing.
+    pub plain: bool,
+    /// A plain single Latin word that also matches words a typo or two away.
+    pub fuzzy: bool,
+}
+
+impl PartialEq for Term {
+    fn eq(&self, other: &Self) -> bool {
+        self.text == other.text && self.regex.as_str() == other.regex.as_str()
+    }
+}
+
+impl Term {
+    fn new(text: &str, options: SearchOptions) -> Result<Self, String> {
+        let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+        let mut pattern = if options.regex {
+            format!("(?:{})", text)
+        } else {
+            regex::escape(text)
+        };
+        if options.whole_word {
+            // `\b` next to a non-word character would demand a word there.
+            let (head, tail) = if options.regex {
+                (true, true)
+            } else {
+                (is_word(text.chars().next()), is_word(text.chars().last()))
+            };
+            pattern = format!(
+                "{}{}{}",
+                if head { "\\b" } else { "" },
+                pattern,
+                if tail { "\\b" } else { "" }
+            );
+        }
+        if !options.case_sensitive {
+            pattern = format!("(?i){}", pattern);
+        }
+        let regex = Regex::new(&pattern).map_err(|e| match e {
+            regex::Error::Syntax(message) => {
+                let reason = message
+                    .lines()
+                    .rev()
+                    .find_map(|l| l.strip_prefix("error: "));
+                format!("Invalid regex: {}", reason.unwrap_or("syntax error"))
+            }
+            _ => "Regex is too large".to_string(),
+        })?;
+        let plain = !options.regex && !options.case_sensitive && !options.whole_word;
+        let literal = (!options.regex).then(|| text.to_lowercase());
+        Ok(Term {
+            text: text.to_string(),
+            fuzzy: plain && literal.as_deref().is_some_and(tokenize::is_fuzzy_word),
+            literal,
+            regex,
+            plain,
+        })
+    }
+
+    /// Exact match under the options.
+    pub fn is_match(&self, text: &str) -> bool {
+        self.regex.is_match(text)
+    }
+
+    /// Exact match, or for a fuzzy term a word within the typo budget.
+    pub fn is_near_match(&self, text: &str) -> bool {
+        self.is_match(text)
+            || self.fuzzy
+                && self
+                    .literal
+                    .as_deref()
+                    .is_some_and(|term| !tokenize::near_matches(text, term).is_empty())
+    }
+
+    fn ranges<'a>(&'a self, text: &'a str) -> impl Iterator<Item = Range<usize>> + 'a {
+        let near = match (&self.literal, self.fuzzy) {
+            (Some(term), true) => tokenize::near_matches(text, term),
+            _ => Vec::new(),
+        };
+        self.regex.find_iter(text).map(|m| m.range()).chain(near)
+    }
+}
+
+/// A string filter value, lowercased. Matches case-insensitively: whole
+/// value, substring for `project:` / `model:`, or a `*` glob.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern {
+    pub value: String,
+    pub substring: bool,
+}
+
+impl Pattern {
+    fn new(value: &str, substring: bool) -> Self {
+        Pattern {
+            value: value.to_lowercase(),
+            substring,
+        }
+    }
+
+    pub fn is_glob(&self) -> bool {
+        self.value.contains('*')
+    }
+
+    pub fn matches(&self, candidate: &str) -> bool {
+        let candidate = candidate.to_lowercase();
+        if self.is_glob() {
+            glob_match(&self.value, &candidate)
+        } else if self.substring {
+            candidate.contains(&self.value)
+        } else {
+            candidate == self.value
+        }
+    }
+}
+
+/// `*`-only glob over the whole string.
+fn glob_match(pattern: &str, candidate: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if !candidate.starts_with(first) {
+        return false;
+    }
+    let mut rest = &candidate[first.len()..];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    Text(Term),
+    Role(Pattern),
+    Tool(Pattern),
+    Model(Pattern),
+    /// Message on or after the day (UTC).
+    After(NaiveDate),
+    /// Message before the day (UTC).
+    Before(NaiveDate),
+    Project(Pattern),
+    Branch(Pattern),
+    Tag(Pattern),
+}
+
+impl Condition {
+    /// Decided by the session alone rather than per message.
+    pub fn is_session_filter(&self) -> bool {
+        matches!(self, Self::Project(_) | Self::Branch(_) | Self::Tag(_))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clause {
+    pub negated: bool,
+    pub condition: Condition,
+    /// Character offsets of the clause in the query.
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Query {
+    pub clauses: Vec<Clause>,
+}
+
+/// What a session-level filter is checked against.
+pub struct SessionFields<'a> {
+    pub project_id: &'a str,
+    pub project_name: &'a str,
+    pub git_branch: Option<&'a str>,
+    pub tags: &'a [String],
+}
+
+/// What a message-level condition is checked against.
+pub struct BlockFields<'a> {
+    pub text: &'a str,
+    pub role: &'a str,
+    pub model: Option<&'a str>,
+    /// Tool called, or whose result this is.
+    pub tool: Option<&'a str>,
+    pub timestamp: Option<&'a str>,
+}
+
+/// `after:` / `before:` bound as the RFC 3339 prefix timestamps are
+/// compared against.
+pub fn date_bound(date: &NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Result<Query, QueryError> {
+        Self::parse_with(input, SearchOptions::default())
+    }
+
+    pub fn parse_with(input: &str, options: SearchOptions) -> Result<Query, QueryError> {
+        let term = |text: &str, start: usize, end: usize| {
+            Term::new(text, options)
+                .map(Condition::Text)
+                .map_err(|message| QueryError::new(message, start, end))
+        };
+        let chars: Vec<char> = input.chars().collect();
+        let mut clauses = Vec::new();
+        let mut i = 0;
+        while i < chars.len() {
+            if chars[i].is_whitespace() {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            let negated = chars[i] == '-' && chars.get(i + 1).is_some_and(|c| !c.is_whitespace());
+            if negated {
+                i += 1;
+            }
+
+            let condition = if chars[i] == '"' {
+                let (text, end) = read_quoted(&chars, i)?;
+                i = end;
+                if text.trim().is_empty() {
+                    return Err(QueryError::new("Empty phrase", start, i));
+                }
+                term(&text, start, i)?
+            } else {
+                let word_start = i;
+                while i < chars.len() && !chars[i].is_whitespace() && chars[i] != ':' {
+                    i += 1;
+                }
+                let key: String = chars[word_start..i].iter().collect();
+                let filter = chars.get(i) == Some(&':') && is_filter_key(&key.to_lowercase());
+                if filter {
+                    i += 1;
+                    let value_start = i;
+                    let value = if chars.get(i) == Some(&'"') {
+                        let (value, end) = read_quoted(&chars, i)?;
+                        i = end;
+                        value
+                    } else {
+                        while i < chars.len() && !chars[i].is_whitespace() {
+                            i += 1;
+                        }
+                        chars[value_start..i].iter().collect()
+                    };
+                    if value.trim().is_empty() {
+                        return Err(QueryError::new(
+                            format!("Missing value for `{}:`", key),
+                            start,
+                            i,
+                        ));
+                    }
+                    filter_condition(&key.to_lowercase(), value.trim(), value_start, i)?
+                } else {
+                    while i < chars.len() && !chars[i].is_whitespace() {
+                        i += 1;
+                    }
+                    let text: String = chars[word_start..i].iter().collect();
+                    term(&text, start, i)?
+                }
+            };
+            clauses.push(Clause {
+                negated,
+                condition,
+                start,
+                end: i,
+            });
+        }
+
+        if !clauses.is_empty() && clauses.iter().all(|c| c.negated) {
+            let first = &clauses[0];
+            return Err(QueryError::new(
+                "Add at least one term or filter that is not negated",
+                first.start,
+                first.end,
+            ));
+        }
+        Ok(Query { clauses })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clauses.is_empty()
+    }
+
+    /// Content terms that must (`negated == false`) or must not appear.
+    pub fn text_terms(&self, negated: bool) -> impl Iterator<Item = &Term> {
+        self.clauses.iter().filter_map(move |c| match &c.condition {
+            Condition::Text(term) if c.negated == negated => Some(term),
+            _ => None,
+        })
+    }
+
+    /// Byte ranges of the positive terms (near matches included) in `text`,
+    /// sorted, overlaps merged.
+    pub fn match_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = self
+            .text_terms(false)
+            .flat_map(|t| t.ranges(text))
+            .filter(|r| !r.is_empty())
+            .collect();
+        ranges.sort_by_key(|r| (r.start, r.end));
+        let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+        merged
+    }
+
+    /// BM25-style relevance of `text` where the index's corpus statistics
+    /// are unavailable: saturating term frequency with length normalisation,
+    /// every term weighted alike. Higher is better.
+    pub fn relevance(&self, text: &str) -> f64 {
+        const K1: f64 = 1.2;
+        const B: f64 = 0.75;
+        /// Tokens in a typical message block.
+        const AVG_TOKENS: f64 = 60.0;
+
+        let norm = 1.0 - B + B * tokenize::token_count(text) as f64 / AVG_TOKENS;
+        self.text_terms(false)
+            .map(|term| {
+                let exact = term.regex.find_iter(text).count() as f64;
+                // Corrections count half.
+                let tf = match (&term.literal, term.fuzzy) {
+                    (Some(literal), true) => {
+                        let corrected = tokenize::near_matches(text, literal)
+                            .into_iter()
+                            .filter(|r| text[r.clone()].to_lowercase() != *literal)
+                            .count();
+                        exact + corrected as f64 * 0.5
+                    }
+                    _ => exact,
+                };
+                tf * (K1 + 1.0) / (tf + K1 * norm)
+            })
+            .sum()
+    }
+
+    pub fn has_message_filters(&self) -> bool {
+        self.clauses
+            .iter()
+            .any(|c| !c.condition.is_session_filter() && !matches!(c.condition, Condition::Text(_)))
+    }
+
+    /// Only session filters: list the matching sessions themselves.
+    pub fn is_session_listing(&self) -> bool {
+        self.clauses.iter().all(|c| c.condition.is_session_filter())
+    }
+
+    /// `text` (a session name, tag, prompt, …) contains every positive term
+    /// and no negated one. False without positive terms.
+    pub fn matches_text(&self, text: &str) -> bool {
+        let mut positive = self.text_terms(false).peekable();
+        positive.peek().is_some()
+            && positive.all(|t| t.is_near_match(text))
+            && !self.text_terms(true).any(|t| t.is_match(text))
+    }
+
+    pub fn matches_session(&self, session: &SessionFields) -> bool {
+        self.clauses.iter().all(|clause| {
+            let matched = match &clause.condition {
+                Condition::Project(p) => {
+                    p.matches(session.project_name) || p.matches(session.project_id)
+                }
+                Condition::Branch(p) => session.git_branch.is_some_and(|b| p.matches(b)),
+                Condition::Tag(p) => session.tags.iter().any(|t| p.matches(t)),
+                _ => return true,
+            };
+            matched != clause.negated
+        })
+    }
+
+    /// Message-level conditions (content terms included); session filters
+    /// are ignored.
+    pub fn matches_block(&self, block: &BlockFields) -> bool {
+        self.clauses.iter().all(|clause| {
+            let matched = match &clause.condition {
+                Condition::Text(term) if clause.negated => term.is_match(block.text),
+                Condition::Text(term) => term.is_near_match(block.text),
+                Condition::Role(p) => p.matches(block.role),
+                Condition::Tool(p) => block.tool.is_some_and(|t| p.matches(t)),
+                Condition::Model(p) => block.model.is_some_and(|m| p.matches(m)),
+                Condition::After(d) => block.timestamp.is_some_and(|t| t >= date_bound(d).as_str()),
+                Condition::Before(d) => block
+                    .timestamp
+                    .is_some_and(|t| !t.is_empty() && t < date_bound(d).as_str()),
+                _ => return true,
+            };
+            matched != clause.negated
+        })
+    }
+}
+
+fn is_filter_key(key: &str) -> bool {
+    matches!(
+        key,
+        "role" | "tool" | "model" | "after" | "before" | "project" | "branch" | "tag"
+    )
+}
+
+fn filter_condition(
+    key: &str,
+    value: &str,
+    start: usize,
+    end: usize,
+) -> Result<Condition, QueryError> {
+    let date = || {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .map_err(|_| QueryError::new("Expected a date like 2026-09-01", start, end))
+    };
+    Ok(match key {
+        "role" => Condition::Role(Pattern::new(value, false)),
+        "tool" => Condition::Tool(Pattern::new(value, false)),
+        "model" => Condition::Model(Pattern::new(value, true)),
+        "after" => Condition::After(date()?),
+        "before" => Condition::Before(date()?),
+        "project" => Condition::Project(Pattern::new(value, true)),
+        "branch" => Condition::Branch(Pattern::new(value, false)),
+        _ => Condition::Tag(Pattern::new(value, false)),
+    })
+}
+
+/// Text between the quote at `open` and the next one, and the offset after
+/// the closing quote.
+fn read_quoted(chars: &[char], open: usize) -> Result<(String, usize), QueryError> {
+    let close = chars[open + 1..]
+        .iter()
+        .position(|&c| c == '"')
+        .map(|p| open + 1 + p)
+        .ok_or_else(|| QueryError::new("Unterminated quote", open, chars.len()))?;
+    Ok((chars[open + 1..close].iter().collect(), close + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_terms_filters_and_negation() {
+        let query = Query::parse(
+            r#"tool:Bash role:assistant model:opus project:api after:2026-09-01 branch:release/* "Exact Phrase" -excluded http://x"#,
+        )
+        .unwrap();
+        let conditions: Vec<_> = query.clauses.iter().map(|c| &c.condition).collect();
+        assert_eq!(conditions[0], &Condition::Tool(Pattern::new("bash", false)));
+        assert_eq!(
+            conditions[4],
+            &Condition::After(NaiveDate::from_ymd_opt(2026, 9, 1).unwrap())
+        );
+        let texts: Vec<_> = query
+            .text_terms(false)
+            .map(|t| t.literal.as_deref())
+            .collect();
+        assert_eq!(texts, [Some("exact phrase"), Some("http://x")]);
+        assert!(query.clauses[7].negated);
+
+        let session = SessionFields {
+            project_id: "-home-me-api",
+            project_name: "api",
+            git_branch: Some("release/2.0"),
+            tags: &[],
+        };
+        assert!(query.matches_session(&session));
+        let block = BlockFields {
+            text: "ran the exact phrase on http://x",
+            role: "assistant",
+            model: Some("claude-opus-4"),
+            tool: Some("Bash"),
+            timestamp: Some("2026-09-02T10:00:00Z"),
+        };
+        assert!(query.matches_block(&block));
+        assert!(!query.matches_block(&BlockFields {
+            text: "exact phrase, http://x excluded",
+            ..block
+        }));
+    }
+
+    #[test]
+    fn reports_error_positions() {
+        let err = Query::parse("foo after:yesterday").unwrap_err();
+        assert_eq!((err.start, err.end), (10, 19));
+        let err = Query::parse("登录 \"unterminated").unwrap_err();
+        assert_eq!((err.start, err.end), (3, 16));
+        assert_eq!(Query::parse("tag: x").unwrap_err().start, 0);
+        assert!(Query::parse("-foo").is_err());
+        let regex = SearchOptions {
+            regex: true,
+            ..SearchOptions::default()
+        };
+        let err = Query::parse_with("tool:Bash (unclosed", regex).unwrap_err();
+        assert_eq!((err.start, err.end), (10, 19));
+        assert!(err.message.starts_with("Invalid regex"));
+    }
+
+    #[test]
+    fn regex_whole_word_and_case_modes() {
+        let ranges = |query: &str, options: SearchOptions, text: &str| {
+            Query::parse_with(query, options)
+                .unwrap()
+                .match_ranges(text)
+        };
+        let text = "Box in a Sandbox, box";
+        assert_eq!(
+            ranges("box", SearchOptions::default(), text),
+            [0..3, 13..16, 18..21]
+        );
+        let whole_word = SearchOptions {
+            whole_word: true,
+            ..SearchOptions::default()
+        };
+        assert_eq!(ranges("box", whole_word, text), [0..3, 18..21]);
+        let exact = SearchOptions {
+            case_sensitive: true,
+            ..whole_word
+        };
+        assert_eq!(ranges("Box", exact, text), vec![(0..3)]);
+
+        let regex = SearchOptions {
+            regex: true,
+            ..SearchOptions::default()
+        };
+        let text = "key sk-abcdefghijklmnopqrstuv; rm -rf /tmp/x";
+        assert_eq!(ranges(r"sk-[A-Za-z0-9]{20,}", regex, text), vec![(4..29)]);
+        assert_eq!(ranges(r#""rm -rf \S+""#, regex, text), vec![(31..44)]);
+    }
+}
@@ -0,0 +1,165 @@
+//! 搜索打分用的分词与拼写容错。
+//!
+//! 中英混排：连续的拉丁字母 / 数字算一个词（小写），连续的中日韩字符切成
+//! 重叠的二元组（`修复登录` → `修复 复登 登录`），单个汉字保留原样。这样两个
+//! 字的中文词也能走倒排索引并参与 BM25 打分，而不必退化成全表 `LIKE`。
+//!
+//! 拼写容错只针对拉丁词：4 个字符以上允许 1 处编辑，8 个以上允许 2 处
+//! （增删改、相邻互换）。
+
+use std::ops::Range;
+
+/// Han, kana and Hangul: scripts written without spaces between words.
+pub fn is_cjk(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x3040..=0x30FF      // Hiragana, Katakana
+            | 0x3400..=0x4DBF // CJK Extension A
+            | 0x4E00..=0x9FFF // CJK Unified Ideographs
+            | 0xAC00..=0xD7AF // Hangul syllables
+            | 0xF900..=0xFAFF // CJK Compatibility Ideographs
+            | 0x20000..=0x2FA1F
+    )
+}
+
+/// Latin (non-CJK alphanumeric) words in `text`, with their byte ranges.
+pub fn words(text: &str) -> impl Iterator<Item = (Range<usize>, &str)> {
+    let mut chars = text.char_indices().peekable();
+    std::iter::from_fn(move || {
+        let is_word = |c: char| c.is_alphanumeric() && !is_cjk(c);
+        let (start, _) = chars.find(|&(_, c)| is_word(c))?;
+        let mut end = text.len();
+        while let Some(&(i, c)) = chars.peek() {
+            if !is_word(c) {
+                end = i;
+                break;
+            }
+            chars.next();
+        }
+        Some((start..end, &text[start..end]))
+    })
+}
+
+/// Lowercased search tokens of `text`: Latin words and CJK bigrams, in order.
+pub fn tokens(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let mut run: Vec<char> = Vec::new();
+    let flush_run = |run: &mut Vec<char>, tokens: &mut Vec<String>| {
+        match run.len() {
+            0 => {}
+            1 => tokens.push(run[0].to_string()),
+            _ => tokens.extend(run.windows(2).map(|w| w.iter().collect::<String>())),
+        }
+        run.clear();
+    };
+    for c in text.chars() {
+        if is_cjk(c) {
+            if !word.is_empty() {
+                tokens.push(std::mem::take(&mut word));
+            }
+            run.push(c);
+        } else {
+            flush_run(&mut run, &mut tokens);
+            if c.is_alphanumeric() {
+                word.extend(c.to_lowercase());
+            } else if !word.is_empty() {
+                tokens.push(std::mem::take(&mut word));
+            }
+        }
+    }
+    flush_run(&mut run, &mut tokens);
+    if !word.is_empty() {
+        tokens.push(word);
+    }
+    tokens
+}
+
+/// Number of [`tokens`] in `text`, without building them.
+pub fn token_count(text: &str) -> usize {
+    words(text).count() + text.chars().filter(|&c| is_cjk(c)).count()
+}
+
+/// Edits allowed when matching a Latin word of `word`'s length.
+pub fn typo_budget(word: &str) -> usize {
+    match word.chars().count() {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+/// A single lowercase ASCII word long enough to correct typos in.
+pub fn is_fuzzy_word(term: &str) -> bool {
+    term.bytes()
+        .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit())
+        && term.bytes().any(|b| b.is_ascii_lowercase())
+        && typo_budget(term) > 0
+}
+
+/// Optimal string alignment distance between `a` and `b`, or `None` when it
+/// exceeds `max`.
+pub fn edit_distance(a: &str, b: &str, max: usize) -> Option<usize> {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+    let width = b.len() + 1;
+    let mut prev2 = vec![0; width];
+    let mut prev: Vec<usize> = (0..width).collect();
+    let mut cur = vec![0; width];
+    for i in 1..=a.len() {
+        cur[0] = i;
+        let mut row_min = i;
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut d = (prev[j] + 1).min(cur[j - 1] + 1).min(prev[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d = d.min(prev2[j - 2] + 1);
+            }
+            cur[j] = d;
+            row_min = row_min.min(d);
+        }
+        if row_min > max {
+            return None;
+        }
+        std::mem::swap(&mut prev2, &mut prev);
+        std::mem::swap(&mut prev, &mut cur);
+    }
+    Some(prev[b.len()]).filter(|&d| d <= max)
+}
+
+/// Byte ranges of the Latin words in `text` within the typo budget of
+/// `term` (a lowercase word, see [`is_fuzzy_word`]).
+pub fn near_matches(text: &str, term: &str) -> Vec<Range<usize>> {
+    let max = typo_budget(term);
+    words(text)
+        .filter(|(_, word)| word.chars().count().abs_diff(term.len()) <= max)
+        .filter(|(_, word)| edit_distance(&word.to_lowercase(), term, max).is_some())
+        .map(|(range, _)| range)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_mixed_text_and_tolerates_typos() {
+        assert_eq!(
+            tokens("修复登录页面的 Bug，见 API_v2 文档"),
+            [
+                "修复", "复登", "登录", "录页", "页面", "面的", "bug", "见", "api", "v2", "文档"
+            ]
+        );
+        assert_eq!(edit_distance("migration", "migraiton", 2), Some(1));
+        assert_eq!(edit_distance("databse", "database", 1), Some(1));
+        assert_eq!(edit_distance("box", "fox", 0), None);
+        assert!(is_fuzzy_word("databse") && !is_fuzzy_word("api") && !is_fuzzy_word("登录"));
+        assert_eq!(
+            near_matches("run the Databse migration", "database"),
+            vec![(8..15)]
+        );
+    }
+}
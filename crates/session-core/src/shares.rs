@@ -0,0 +1,323 @@
+//! 只读分享链接：把单个会话（可选消息区间与脱敏配置）分享给没有 token 的人。
+//!
+//! 分享记录和签名密钥保存在 `~/.session-viewer-shares.json`，与收藏、保存的搜索
+//! 放在一起。链接里的 token 为 `base64url(id:过期时间).base64url(HMAC-SHA256)`：
+//! 签名挡住伪造和篡改，过期时间写进签名里；会话、区间、脱敏等范围以服务端记录为准，
+//! 撤销即删除记录，已发出的链接随之失效。
+
+use std::fs;
+use std::path::PathBuf;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine as _;
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::Mutex;
+use ring::hmac;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+
+use crate::export::RedactionProfile;
+use crate::paths::validate_session_file;
+
+/// Lifetime of a share when none is given.
+pub const DEFAULT_TTL_HOURS: u64 = 72;
+/// Longest lifetime a share can be given.
+pub const MAX_TTL_HOURS: u64 = 30 * 24;
+
+/// Serializes load-modify-save of the shares file, so two requests can't
+/// both mint a signing key or drop each other's shares.
+static LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Share {
+    pub id: String,
+    pub source: String,
+    pub file_path: String,
+    /// Label for the share list; not shown to the recipient.
+    #[serde(default)]
+    pub title: Option<String>,
+    /// First message shared, 0-based.
+    #[serde(default)]
+    pub message_start: Option<usize>,
+    /// Message to stop before (exclusive).
+    #[serde(default)]
+    pub message_end: Option<usize>,
+    /// Redaction profile, as accepted by [`RedactionProfile::parse`].
+    #[serde(default)]
+    pub redact: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// What the caller asks to share.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewShare {
+    pub source: String,
+    pub file_path: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub message_start: Option<usize>,
+    #[serde(default)]
+    pub message_end: Option<usize>,
+    #[serde(default)]
+    pub redact: Option<String>,
+    /// Defaults to [`DEFAULT_TTL_HOURS`].
+    #[serde(default)]
+    pub expires_in_hours: Option<u64>,
+}
+
+/// A share with the token that opens it.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareLink {
+    #[serde(flatten)]
+    pub share: Share,
+    pub token: String,
+}
+
+/// Why a token doesn't open a share.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShareError {
+    /// Malformed, forged or revoked.
+    NotFound,
+    Expired,
+}
+
+impl Share {
+    /// The share's redaction profile; invalid values were rejected on create.
+    pub fn redaction(&self) -> RedactionProfile {
+        self.redact
+            .as_deref()
+            .and_then(|r| RedactionProfile::parse(r).ok())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SharesFile {
+    version: u32,
+    /// HMAC-SHA256 key, base64. Generated on first use.
+    #[serde(default)]
+    secret: String,
+    #[serde(default)]
+    shares: Vec<Share>,
+}
+
+impl Default for SharesFile {
+    fn default() -> Self {
+        SharesFile {
+            version: 1,
+            secret: String::new(),
+            shares: vec![],
+        }
+    }
+}
+
+fn shares_path() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or("Cannot determine home directory")?;
+    Ok(home.join(".session-viewer-shares.json"))
+}
+
+fn load_file() -> SharesFile {
+    let Ok(path) = shares_path() else {
+        return SharesFile::default();
+    };
+    fs::read_to_string(&path)
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+fn save_file(file: &SharesFile) -> Result<(), String> {
+    let path = shares_path()?;
+    let json = serde_json::to_string_pretty(file)
+        .map_err(|e| format!("Failed to serialize shares: {}", e))?;
+
+    // Atomic write: write to tmp then rename. The file holds the signing key,
+    // so keep it private to the user.
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, &json).map_err(|e| format!("Failed to write shares tmp: {}", e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&tmp_path, fs::Permissions::from_mode(0o600))
+            .map_err(|e| format!("Failed to protect shares file: {}", e))?;
+    }
+    fs::rename(&tmp_path, &path).map_err(|e| format!("Failed to rename shares file: {}", e))?;
+    Ok(())
+}
+
+/// The signing key, minting one into `file` if it has none.
+fn signing_key(file: &mut SharesFile) -> Result<hmac::Key, String> {
+    if let Ok(secret) = URL_SAFE_NO_PAD.decode(&file.secret) {
+        if secret.len() >= 32 {
+            return Ok(hmac::Key::new(hmac::HMAC_SHA256, &secret));
+        }
+    }
+    let mut secret = [0u8; 32];
+    SystemRandom::new()
+        .fill(&mut secret)
+        .map_err(|_| "Failed to generate share signing key".to_string())?;
+    file.secret = URL_SAFE_NO_PAD.encode(secret);
+    Ok(hmac::Key::new(hmac::HMAC_SHA256, &secret))
+}
+
+fn sign(key: &hmac::Key, id: &str, expires_at: DateTime<Utc>) -> String {
+    let payload = format!("{}:{}", id, expires_at.timestamp());
+    let tag = hmac::sign(key, payload.as_bytes());
+    format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(payload),
+        URL_SAFE_NO_PAD.encode(tag.as_ref())
+    )
+}
+
+/// The share id and expiry (unix seconds) `token` was signed for.
+fn verify(key: &hmac::Key, token: &str) -> Option<(String, i64)> {
+    let (payload, tag) = token.split_once('.')?;
+    let payload = URL_SAFE_NO_PAD.decode(payload).ok()?;
+    let tag = URL_SAFE_NO_PAD.decode(tag).ok()?;
+    hmac::verify(key, &payload, &tag).ok()?;
+    let payload = String::from_utf8(payload).ok()?;
+    let (id, expires_at) = payload.rsplit_once(':')?;
+    Some((id.to_string(), expires_at.parse().ok()?))
+}
+
+fn generate_id() -> Result<String, String> {
+    let mut bytes = [0u8; 12];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .map_err(|_| "Failed to generate share id".to_string())?;
+    Ok(URL_SAFE_NO_PAD.encode(bytes))
+}
+
+/// Drop expired shares; returns whether any were dropped.
+fn prune(file: &mut SharesFile, now: DateTime<Utc>) -> bool {
+    let before = file.shares.len();
+    file.shares.retain(|s| s.expires_at > now);
+    file.shares.len() != before
+}
+
+pub fn create_share(new: NewShare) -> Result<ShareLink, String> {
+    validate_session_file(&new.source, &new.file_path)?;
+    if let Some(redact) = new.redact.as_deref() {
+        RedactionProfile::parse(redact)?;
+    }
+    if let (Some(start), Some(end)) = (new.message_start, new.message_end) {
+        if start >= end {
+            return Err("Message range is empty".to_string());
+        }
+    }
+    let hours = new.expires_in_hours.unwrap_or(DEFAULT_TTL_HOURS);
+    if hours == 0 || hours > MAX_TTL_HOURS {
+        return Err(format!(
+            "Share lifetime must be 1 to {} hours",
+            MAX_TTL_HOURS
+        ));
+    }
+
+    let _guard = LOCK.lock();
+    let mut file = load_file();
+    let key = signing_key(&mut file)?;
+    // Whole seconds, so the stored expiry matches the one in the token.
+    let now = DateTime::from_timestamp(Utc::now().timestamp(), 0).unwrap_or_else(Utc::now);
+    prune(&mut file, now);
+
+    let share = Share {
+        id: generate_id()?,
+        source: new.source,
+        file_path: new.file_path,
+        title: new.title.filter(|t| !t.trim().is_empty()),
+        message_start: new.message_start,
+        message_end: new.message_end,
+        redact: new
+            .redact
+            .filter(|r| !RedactionProfile::parse(r).unwrap_or_default().is_none()),
+        created_at: now,
+        expires_at: now + Duration::hours(hours as i64),
+    };
+    file.shares.push(share.clone());
+    save_file(&file)?;
+
+    let token = sign(&key, &share.id, share.expires_at);
+    Ok(ShareLink { share, token })
+}
+
+/// Active shares, newest first.
+pub fn list_shares() -> Result<Vec<ShareLink>, String> {
+    let _guard = LOCK.lock();
+    let mut file = load_file();
+    let had_key = !file.secret.is_empty();
+    let key = signing_key(&mut file)?;
+    if prune(&mut file, Utc::now()) || !had_key {
+        save_file(&file)?;
+    }
+
+    let mut links: Vec<ShareLink> = file
+        .shares
+        .into_iter()
+        .map(|share| ShareLink {
+            token: sign(&key, &share.id, share.expires_at),
+            share,
+        })
+        .collect();
+    links.sort_by_key(|l| std::cmp::Reverse(l.share.created_at));
+    Ok(links)
+}
+
+pub fn revoke_share(id: &str) -> Result<(), String> {
+    let _guard = LOCK.lock();
+    let mut file = load_file();
+    let before = file.shares.len();
+    file.shares.retain(|s| s.id != id);
+    if file.shares.len() == before {
+        return Err("Share not found".to_string());
+    }
+    save_file(&file)
+}
+
+/// The share `token` opens.
+pub fn resolve_share(token: &str) -> Result<Share, ShareError> {
+    let file = load_file();
+    let secret = URL_SAFE_NO_PAD
+        .decode(&file.secret)
+        .map_err(|_| ShareError::NotFound)?;
+    let key = hmac::Key::new(hmac::HMAC_SHA256, &secret);
+    let (id, expires_at) = verify(&key, token).ok_or(ShareError::NotFound)?;
+    if expires_at <= Utc::now().timestamp() {
+        return Err(ShareError::Expired);
+    }
+    file.shares
+        .into_iter()
+        .find(|s| s.id == id && s.expires_at.timestamp() == expires_at)
+        .ok_or(ShareError::NotFound)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_reject_tampering() {
+        let key = hmac::Key::new(hmac::HMAC_SHA256, &[7u8; 32]);
+        let expires_at = DateTime::from_timestamp(1_900_000_000, 0).unwrap();
+        let token = sign(&key, "abc", expires_at);
+        assert_eq!(
+            verify(&key, &token),
+            Some(("abc".to_string(), 1_900_000_000))
+        );
+
+        // Pushing the expiry out invalidates the signature.
+        let (_, tag) = token.split_once('.').unwrap();
+        let forged = format!("{}.{}", URL_SAFE_NO_PAD.encode("abc:1999999999"), tag);
+        assert_eq!(verify(&key, &forged), None);
+
+        let other = hmac::Key::new(hmac::HMAC_SHA256, &[8u8; 32]);
+        assert_eq!(verify(&other, &token), None);
+        assert_eq!(verify(&key, "garbage"), None);
+    }
+}
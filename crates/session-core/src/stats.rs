@@ -14,17 +14,13 @@ use crate::models::stats::{
     DailyTokenEntry, ProjectCostEntry, RequestLogPage, RequestRecord, SessionCostSummary,
     TokenUsageSummary,
 };
-use crate::parser::path_encoder::{decode_project_path_validated, get_projects_dir};
-use crate::provider::codex;
+use crate::data_roots::scoped_id;
+use crate::provider;
 
 // ── Public entry point ──────────────────────────────────────────────────────
 
 pub fn get_stats(source: &str) -> Result<TokenUsageSummary, String> {
-    match source {
-        "claude" => get_claude_stats(),
-        "codex" => codex::get_stats(),
-        _ => Err(format!("Unknown source: {}", source)),
-    }
+    provider::get(source)?.get_stats()
 }
 
 /// Filter parameters for the paginated request log.
@@ -45,16 +41,29 @@ pub fn get_request_log(
     page: usize,
     page_size: usize,
 ) -> Result<RequestLogPage, String> {
-    // Codex still goes through the legacy path — its cache layout is
-    // session-shaped, not record-shaped, and the volume is tiny.
-    if filter.source == "codex" {
-        let records = codex::collect_requests()?;
-        return Ok(paginate_records(records, &filter, page, page_size));
-    }
-    if filter.source != "claude" {
-        return Err(format!("Unknown source: {}", filter.source));
-    }
+    provider::get(&filter.source)?.request_log(&filter, page, page_size)
+}
 
+pub fn get_project_costs(source: &str) -> Result<Vec<ProjectCostEntry>, String> {
+    provider::get(source)?.project_costs()
+}
+
+pub fn get_session_cost(source: &str, file_path: &str) -> Result<SessionCostSummary, String> {
+    provider::get(source)?.session_cost(file_path)
+}
+
+// ── Claude: record-shaped stats cache ───────────────────────────────────────
+//
+// Other providers go through the generic `collect_requests` path in
+// `SessionProvider` — their cache layout is session-shaped, not
+// record-shaped, and the volume is tiny. Claude keeps a per-file compact
+// record cache so these queries never materialise the full list.
+
+pub(crate) fn claude_request_log(
+    filter: &RequestLogFilter,
+    page: usize,
+    page_size: usize,
+) -> Result<RequestLogPage, String> {
     ensure_claude_cache_fresh()?;
     with_claude_cache(|cache| {
         // First pass: count + accumulate totals over the filtered set, so we
@@ -136,8 +145,8 @@ pub fn get_request_log(
     })
 }
 
-/// Codex still returns the full record list — pagination/sort happens here.
-fn paginate_records(
+/// Non-cached providers return the full record list — pagination/sort happens here.
+pub(crate) fn paginate_records(
     mut records: Vec<RequestRecord>,
     filter: &RequestLogFilter,
     page: usize,
@@ -202,14 +211,7 @@ fn paginate_records(
     }
 }
 
-pub fn get_project_costs(source: &str) -> Result<Vec<ProjectCostEntry>, String> {
-    if source == "codex" {
-        return codex_project_costs();
-    }
-    if source != "claude" {
-        return Err(format!("Unknown source: {}", source));
-    }
-
+pub(crate) fn claude_project_costs() -> Result<Vec<ProjectCostEntry>, String> {
     ensure_claude_cache_fresh()?;
     with_claude_cache(|cache| {
         let mut by_project: HashMap<String, ProjectCostEntry> = HashMap::new();
@@ -219,7 +221,7 @@ pub fn get_project_costs(source: &str) -> Result<Vec<ProjectCostEntry>, String>
                 .or_insert_with(|| ProjectCostEntry {
                     source: "claude".to_string(),
                     project_id: fs.project_id.clone(),
-                    display_name: project_display_name("claude", &fs.project_id),
+                    display_name: crate::provider::claude::project_display_path(&fs.project_id),
                     request_count: 0,
                     total_tokens: 0,
                     cache_read_tokens: 0,
@@ -241,16 +243,19 @@ pub fn get_project_costs(source: &str) -> Result<Vec<ProjectCostEntry>, String>
     })
 }
 
-fn codex_project_costs() -> Result<Vec<ProjectCostEntry>, String> {
-    let records = codex::collect_requests()?;
+pub(crate) fn project_costs_from_records(
+    source: &str,
+    records: Vec<RequestRecord>,
+    display_name: impl Fn(&str) -> String,
+) -> Vec<ProjectCostEntry> {
     let mut by_project: HashMap<String, ProjectCostEntry> = HashMap::new();
     for r in records {
         let entry = by_project
             .entry(r.project_id.clone())
             .or_insert_with(|| ProjectCostEntry {
-                source: "codex".to_string(),
+                source: source.to_string(),
                 project_id: r.project_id.clone(),
-                display_name: project_display_name("codex", &r.project_id),
+                display_name: display_name(&r.project_id),
                 request_count: 0,
                 total_tokens: 0,
                 cache_read_tokens: 0,
@@ -267,22 +272,14 @@ fn codex_project_costs() -> Result<Vec<ProjectCostEntry>, String> {
             .partial_cmp(&a.cost_usd)
             .unwrap_or(std::cmp::Ordering::Equal)
     });
-    Ok(list)
+    list
 }
 
-pub fn get_session_cost(source: &str, file_path: &str) -> Result<SessionCostSummary, String> {
-    if source == "codex" {
-        let records = codex::collect_requests()?;
-        return Ok(build_session_summary_from_records(source, file_path, records));
-    }
-    if source != "claude" {
-        return Err(format!("Unknown source: {}", source));
-    }
-
+pub(crate) fn claude_session_cost(file_path: &str) -> Result<SessionCostSummary, String> {
     ensure_claude_cache_fresh()?;
     with_claude_cache(|cache| {
         let Some(fs_entry) = cache.files.get(file_path) else {
-            return Ok(empty_session_summary(source, file_path));
+            return Ok(empty_session_summary("claude", file_path));
         };
         let mut records: Vec<RequestRecord> = fs_entry
             .requests
@@ -290,11 +287,29 @@ pub fn get_session_cost(source: &str, file_path: &str) -> Result<SessionCostSumm
             .map(|rec| compact_to_record(file_path, rec, fs_entry, "claude"))
             .collect();
         records.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
-        Ok(materialise_session_summary(source, file_path, records))
+        Ok(materialise_session_summary("claude", file_path, records))
     })
 }
 
-fn build_session_summary_from_records(
+/// Every cached Claude request, flattened. Used by callers that need the
+/// raw records rather than one of the aggregated views above.
+pub(crate) fn claude_collect_requests() -> Result<Vec<RequestRecord>, String> {
+    ensure_claude_cache_fresh()?;
+    Ok(with_claude_cache(|cache| {
+        cache
+            .files
+            .iter()
+            .flat_map(|(key, fs_entry)| {
+                fs_entry
+                    .requests
+                    .iter()
+                    .map(move |rec| compact_to_record(key, rec, fs_entry, "claude"))
+            })
+            .collect()
+    }))
+}
+
+pub(crate) fn build_session_summary_from_records(
     source: &str,
     file_path: &str,
     records: Vec<RequestRecord>,
@@ -365,21 +380,6 @@ fn empty_session_summary(source: &str, file_path: &str) -> SessionCostSummary {
     }
 }
 
-/// Project display name resolution. For Claude the project id is the encoded
-/// directory name and `decode_project_path_validated` already returns a nice
-/// short name. For Codex the project id is the cwd path, so we just take the
-/// last segment.
-fn project_display_name(source: &str, project_id: &str) -> String {
-    match source {
-        "claude" => decode_project_path_validated(project_id).display_path,
-        _ => Path::new(project_id)
-            .file_name()
-            .and_then(|s| s.to_str())
-            .unwrap_or(project_id)
-            .to_string(),
-    }
-}
-
 // ── Minimal parse structs (only fields needed for stats) ────────────────────
 
 #[derive(Deserialize)]
@@ -649,11 +649,11 @@ fn cache_state() -> &'static Mutex<CacheState> {
 /// in parallel each doing the same scan. Holding the lock means the second
 /// caller waits, then sees a fresh throttle and exits in microseconds.
 fn ensure_claude_cache_fresh() -> Result<(), String> {
-    let projects_dir = match get_projects_dir() {
-        Some(d) => d,
-        None => return Err("Could not find projects dir".to_string()),
-    };
-    if !projects_dir.exists() {
+    let roots = crate::provider::claude::projects_dirs();
+    if roots.is_empty() {
+        return Err("Could not find projects dir".to_string());
+    }
+    if !roots.iter().any(|(_, dir)| dir.exists()) {
         // Reset cache to empty rather than confuse the user with stale
         // numbers from a previous projects dir.
         let mut guard = cache_state().lock();
@@ -673,16 +673,17 @@ fn ensure_claude_cache_fresh() -> Result<(), String> {
         }
     }
 
-    let all_paths = collect_jsonl_paths(&projects_dir);
+    let all_paths = collect_jsonl_paths(&roots);
 
-    // Build the mtime diff inside the locked region.
-    let stale: Vec<PathBuf> = all_paths
+    // Build the mtime diff inside the locked region. A file whose data root
+    // was renamed keeps its mtime but needs its scoped project id refreshed.
+    let stale: Vec<(PathBuf, String)> = all_paths
         .iter()
-        .filter(|p| {
+        .filter(|(p, project_id)| {
             let key = p.to_string_lossy();
             let mtime = file_mtime(p);
             match guard.cache.files.get(key.as_ref()) {
-                Some(entry) => entry.mtime != mtime,
+                Some(entry) => entry.mtime != mtime || &entry.project_id != project_id,
                 None => true,
             }
         })
@@ -696,8 +697,8 @@ fn ensure_claude_cache_fresh() -> Result<(), String> {
         // threads operate on the `stale` Vec only.
         let new_stats: Vec<(String, FileStat)> = stale
             .par_iter()
-            .filter_map(|path| {
-                let stat = scan_file(path)?;
+            .filter_map(|(path, project_id)| {
+                let stat = scan_file(path, project_id.clone())?;
                 Some((path.to_string_lossy().into_owned(), stat))
             })
             .collect();
@@ -711,7 +712,7 @@ fn ensure_claude_cache_fresh() -> Result<(), String> {
     // Drop entries for files that no longer exist on disk.
     let existing_keys: HashSet<String> = all_paths
         .iter()
-        .map(|p| p.to_string_lossy().into_owned())
+        .map(|(p, _)| p.to_string_lossy().into_owned())
         .collect();
     let before = guard.cache.files.len();
     guard.cache.files.retain(|k, _| existing_keys.contains(k));
@@ -744,7 +745,7 @@ fn with_claude_cache<T>(f: impl FnOnce(&AsvStatsCache) -> T) -> T {
     f(&guard.cache)
 }
 
-fn get_claude_stats() -> Result<TokenUsageSummary, String> {
+pub(crate) fn claude_stats() -> Result<TokenUsageSummary, String> {
     ensure_claude_cache_fresh()?;
     let mut guard = cache_state().lock();
     let summary = merge_into_summary(&guard.cache);
@@ -758,23 +759,33 @@ fn get_claude_stats() -> Result<TokenUsageSummary, String> {
 }
 
 /// Collect all *.jsonl paths under the projects directory.
-fn collect_jsonl_paths(projects_dir: &Path) -> Vec<PathBuf> {
+fn collect_jsonl_paths(roots: &[(String, PathBuf)]) -> Vec<(PathBuf, String)> {
     let mut paths = Vec::new();
-    let Ok(project_entries) = fs::read_dir(projects_dir) else {
-        return paths;
-    };
-    for project in project_entries.flatten() {
-        let project_path = project.path();
-        if !project_path.is_dir() {
-            continue;
-        }
-        let Ok(files) = fs::read_dir(&project_path) else {
+    for (root, projects_dir) in roots {
+        let Ok(project_entries) = fs::read_dir(projects_dir) else {
             continue;
         };
-        for file in files.flatten() {
-            let p = file.path();
-            if p.extension().map(|e| e == "jsonl").unwrap_or(false) {
-                paths.push(p);
+        for project in project_entries.flatten() {
+            let project_path = project.path();
+            if !project_path.is_dir() {
+                continue;
+            }
+            // Encoded project directory name, scoped by its data root.
+            let Some(project_id) = project_path
+                .file_name()
+                .and_then(|s| s.to_str())
+                .map(|name| scoped_id(root, name))
+            else {
+                continue;
+            };
+            let Ok(files) = fs::read_dir(&project_path) else {
+                continue;
+            };
+            for file in files.flatten() {
+                let p = file.path();
+                if p.extension().map(|e| e == "jsonl").unwrap_or(false) {
+                    paths.push((p, project_id.clone()));
+                }
             }
         }
     }
@@ -783,19 +794,11 @@ fn collect_jsonl_paths(projects_dir: &Path) -> Vec<PathBuf> {
 
 /// Scan a single JSONL file and return its aggregated stats.
 /// Returns None only if the file cannot be opened at all.
-fn scan_file(path: &Path) -> Option<FileStat> {
+fn scan_file(path: &Path, project_id: String) -> Option<FileStat> {
     let mtime = file_mtime(path);
     let file = fs::File::open(path).ok()?;
     let reader = BufReader::with_capacity(64 * 1024, file);
 
-    // Encoded project directory is the parent dir name.
-    let project_id = path
-        .parent()
-        .and_then(|p| p.file_name())
-        .and_then(|s| s.to_str())
-        .unwrap_or("")
-        .to_string();
-
     let mut stat = FileStat {
         mtime,
         project_id,
@@ -1034,6 +1037,82 @@ fn merge_into_summary(cache: &AsvStatsCache) -> TokenUsageSummary {
     }
 }
 
+/// Build a summary from flat request records. For providers without a
+/// dedicated stats cache; `session_count` / `message_count` come from the
+/// caller's own scan since records only cover priced assistant turns.
+pub(crate) fn summary_from_records(
+    records: &[RequestRecord],
+    session_count: u64,
+    message_count: u64,
+) -> TokenUsageSummary {
+    let mut summary = empty_summary();
+    let mut daily_map: HashMap<String, DailyBuckets> = HashMap::new();
+
+    for r in records {
+        summary.total_input_tokens += r.input_tokens;
+        summary.total_output_tokens += r.output_tokens;
+        summary.total_cache_read_tokens += r.cache_read_tokens;
+        summary.total_cache_creation_tokens += r.cache_creation_tokens;
+        summary.total_tokens += r.total_tokens;
+        summary.total_cost_usd += r.cost_usd;
+        *summary.tokens_by_model.entry(r.model.clone()).or_insert(0) += r.total_tokens;
+        *summary.cost_by_model.entry(r.model.clone()).or_insert(0.0) += r.cost_usd;
+
+        let Some(date) = r.timestamp.get(..10) else {
+            continue;
+        };
+        let bucket = daily_map.entry(date.to_string()).or_default();
+        bucket.input += r.input_tokens;
+        bucket.output += r.output_tokens;
+        bucket.cache_read += r.cache_read_tokens;
+        bucket.cache_creation += r.cache_creation_tokens;
+        bucket.cost += r.cost_usd;
+        bucket.messages += 1;
+        *bucket
+            .per_model_cache_ratio_num
+            .entry(r.model.clone())
+            .or_insert(0) += r.cache_read_tokens;
+        *bucket
+            .per_model_cache_ratio_den
+            .entry(r.model.clone())
+            .or_insert(0) += r.input_tokens + r.cache_read_tokens + r.cache_creation_tokens;
+    }
+
+    let mut daily_tokens: Vec<DailyTokenEntry> = daily_map
+        .into_iter()
+        .map(|(date, bucket)| {
+            let cache_hit_ratio_by_model = bucket
+                .per_model_cache_ratio_num
+                .iter()
+                .filter_map(|(model, num)| {
+                    let den = bucket.per_model_cache_ratio_den.get(model).copied()?;
+                    (den > 0).then(|| (model.clone(), *num as f64 / den as f64))
+                })
+                .collect();
+            DailyTokenEntry {
+                date,
+                input_tokens: bucket.input,
+                output_tokens: bucket.output,
+                cache_read_tokens: bucket.cache_read,
+                cache_creation_tokens: bucket.cache_creation,
+                total_tokens: bucket.input
+                    + bucket.output
+                    + bucket.cache_read
+                    + bucket.cache_creation,
+                cost_usd: bucket.cost,
+                message_count: bucket.messages,
+                cache_hit_ratio_by_model,
+            }
+        })
+        .collect();
+    daily_tokens.sort_by(|a, b| a.date.cmp(&b.date));
+
+    summary.daily_tokens = daily_tokens;
+    summary.session_count = session_count;
+    summary.message_count = message_count;
+    summary
+}
+
 pub(crate) fn empty_summary() -> TokenUsageSummary {
     TokenUsageSummary {
         total_input_tokens: 0,
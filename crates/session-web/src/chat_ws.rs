@@ -58,6 +58,11 @@ fn allowed_project_roots(source: &str) -> Result<Vec<PathBuf>, String> {
 
     let mut roots = Vec::new();
     for project in projects {
+        // Projects from synced data roots belong to other machines; never let
+        // them authorize a local working directory.
+        if project.data_root.is_some() {
+            continue;
+        }
         let display_path = project.display_path;
         if display_path.trim().is_empty() {
             continue;
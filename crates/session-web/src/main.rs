@@ -15,7 +15,7 @@ use axum::{
 use clap::Parser;
 use config::Config;
 use std::collections::HashMap;
-use std::path::{Component, Path, PathBuf};
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use tower_http::cors::CorsLayer;
@@ -67,22 +67,6 @@ impl WsTicketStore {
     }
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-pub(crate) enum SessionSource {
-    Claude,
-    Codex,
-}
-
-impl SessionSource {
-    pub(crate) fn parse(source: &str) -> Result<Self, String> {
-        match source {
-            "claude" => Ok(Self::Claude),
-            "codex" => Ok(Self::Codex),
-            _ => Err(format!("Unknown source: {}", source)),
-        }
-    }
-}
-
 fn bearer_token(headers: &HeaderMap) -> Option<&str> {
     headers
         .get("authorization")
@@ -128,43 +112,6 @@ pub(crate) fn require_ws_auth(
     Err(StatusCode::UNAUTHORIZED)
 }
 
-fn is_single_normal_component(value: &str) -> bool {
-    let mut components = Path::new(value).components();
-    matches!(components.next(), Some(Component::Normal(_))) && components.next().is_none()
-}
-
-pub(crate) fn resolve_claude_project_dir(project_id: &str) -> Result<PathBuf, String> {
-    if !is_single_normal_component(project_id) {
-        return Err(format!("Invalid project id: {}", project_id));
-    }
-
-    let base = session_core::parser::path_encoder::get_projects_dir()
-        .ok_or_else(|| "Could not find Claude projects directory".to_string())?
-        .canonicalize()
-        .map_err(|e| format!("Failed to resolve Claude projects directory: {}", e))?;
-    let candidate = base.join(project_id);
-
-    if !candidate.exists() {
-        return Err(format!("Project directory not found: {}", project_id));
-    }
-
-    let canonical = candidate
-        .canonicalize()
-        .map_err(|e| format!("Failed to resolve project directory: {}", e))?;
-    if !canonical.is_dir() {
-        return Err(format!("Project directory not found: {}", project_id));
-    }
-    let relative = canonical
-        .strip_prefix(&base)
-        .map_err(|_| format!("Invalid project id: {}", project_id))?;
-
-    if relative.components().count() != 1 {
-        return Err(format!("Invalid project id: {}", project_id));
-    }
-
-    Ok(canonical)
-}
-
 /// Validate a session file path. Delegates to `session_core::paths` so the
 /// Tauri and web codepaths share a single source of truth.
 pub(crate) fn resolve_session_file_path(
@@ -291,7 +238,35 @@ async fn main() {
             "/api/messages/range",
             get(routes::messages::get_messages_range),
         )
+        .route(
+            "/api/messages/subagent",
+            get(routes::messages::get_subagent_thread),
+        )
+        .route(
+            "/api/messages/tree",
+            get(routes::messages::get_conversation_tree),
+        )
+        .route(
+            "/api/messages/branch",
+            get(routes::messages::get_branch_messages),
+        )
+        .route(
+            "/api/messages/plan",
+            get(routes::messages::get_session_plan),
+        )
+        .route(
+            "/api/messages/media",
+            get(routes::messages::get_message_media),
+        )
         .route("/api/export", get(routes::export::export_session))
+        .route("/api/export/archive", post(routes::export::export_archive))
+        .route("/api/export/dataset", post(routes::export::export_dataset))
+        .route("/api/file-history", get(routes::file_history::get_file_history))
+        .route(
+            "/api/file-history/contents",
+            get(routes::file_history::get_file_contents_at),
+        )
+        .route("/api/compare", get(routes::compare::compare_sessions))
         .route("/api/scan-progress", get(routes::progress::get_scan_progress))
         .route("/api/search", get(routes::search::global_search))
         .route("/api/skills", get(routes::skills::list_skills))
@@ -302,9 +277,32 @@ async fn main() {
         .route("/api/stats/requests", get(routes::stats::get_request_log))
         .route("/api/stats/projects", get(routes::stats::get_project_costs))
         .route("/api/stats/session", get(routes::stats::get_session_cost))
+        .route(
+            "/api/prompt-history",
+            get(routes::prompt_history::get_prompt_history),
+        )
         .route("/api/bookmarks", get(routes::bookmarks::list_bookmarks))
         .route("/api/bookmarks", post(routes::bookmarks::add_bookmark))
         .route("/api/bookmarks/{id}", delete(routes::bookmarks::remove_bookmark))
+        .route("/api/saved-searches", get(routes::saved_searches::list_saved_searches))
+        .route("/api/saved-searches", post(routes::saved_searches::add_saved_search))
+        .route(
+            "/api/saved-searches/counts",
+            get(routes::saved_searches::collection_counts),
+        )
+        .route(
+            "/api/saved-searches/{id}",
+            delete(routes::saved_searches::remove_saved_search),
+        )
+        .route(
+            "/api/saved-searches/{id}/collection",
+            get(routes::saved_searches::open_collection),
+        )
+        .route("/api/shares", get(routes::shares::list_shares))
+        .route("/api/shares", post(routes::shares::create_share))
+        .route("/api/shares/{id}", delete(routes::shares::revoke_share))
+        .route("/api/data-roots", get(routes::data_roots::get_data_roots))
+        .route("/api/data-roots", put(routes::data_roots::set_data_roots))
         .route("/api/recyclebin", get(routes::recyclebin::list_items))
         .route(
             "/api/recyclebin/{id}/restore",
@@ -370,6 +368,9 @@ async fn main() {
         .route("/api/models", post(list_models_handler))
         .layer(middleware::from_fn(check_auth));
 
+    // Public share links (no auth: the signed token is the credential)
+    let share_routes = Router::new().route("/share/{token}", get(routes::shares::view_share));
+
     // Static file fallback (no auth needed)
     let static_routes = Router::new().fallback(static_files::static_handler);
 
@@ -378,6 +379,7 @@ async fn main() {
         .merge(cli_routes)
         .merge(ws_routes)
         .merge(chat_ws_routes)
+        .merge(share_routes)
         .merge(static_routes)
         .layer(CorsLayer::permissive())
         .layer(axum::Extension(app_token))
@@ -0,0 +1,43 @@
+use axum::extract::Query;
+use axum::http::StatusCode;
+use axum::response::Json;
+use serde::Deserialize;
+use session_core::compare;
+use session_core::models::compare::SessionComparison;
+
+use crate::resolve_session_file_path;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompareQuery {
+    pub source_a: String,
+    pub file_path_a: String,
+    pub source_b: String,
+    pub file_path_b: String,
+}
+
+/// Two sessions side by side, with their turns aligned.
+pub async fn compare_sessions(
+    Query(params): Query<CompareQuery>,
+) -> Result<Json<SessionComparison>, (StatusCode, String)> {
+    for (source, file_path) in [
+        (&params.source_a, &params.file_path_a),
+        (&params.source_b, &params.file_path_b),
+    ] {
+        resolve_session_file_path(source, file_path).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    }
+
+    let result = tokio::task::spawn_blocking(move || {
+        compare::compare_sessions(
+            &params.source_a,
+            &params.file_path_a,
+            &params.source_b,
+            &params.file_path_b,
+        )
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    Ok(Json(result))
+}
@@ -0,0 +1,30 @@
+use axum::extract::Query;
+use axum::http::StatusCode;
+use axum::response::Json;
+use serde::Deserialize;
+use session_core::data_roots::{self, DataRoot};
+
+#[derive(Deserialize)]
+pub struct DataRootsQuery {
+    pub source: String,
+}
+
+pub async fn get_data_roots(Query(params): Query<DataRootsQuery>) -> Json<Vec<DataRoot>> {
+    Json(data_roots::get_roots(&params.source))
+}
+
+#[derive(Deserialize)]
+pub struct SetDataRootsBody {
+    pub source: String,
+    pub roots: Vec<DataRoot>,
+}
+
+pub async fn set_data_roots(
+    Json(body): Json<SetDataRootsBody>,
+) -> Result<Json<Vec<DataRoot>>, (StatusCode, String)> {
+    tokio::task::spawn_blocking(move || data_roots::set_roots(&body.source, body.roots))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map(Json)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))
+}
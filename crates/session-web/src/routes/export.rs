@@ -1,7 +1,13 @@
+use axum::body::Body;
 use axum::extract::Query;
-use axum::http::StatusCode;
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Json, Response};
 use serde::Deserialize;
-use session_core::export::{render_session, ExportFormat};
+use session_core::export::archive::{collect_archive, stream_archive, ArchiveSelection};
+use session_core::export::dataset::{self, DatasetExport};
+use session_core::export::{
+    write_session, ExportFormat, ExportOptions, PatchGrouping, RedactionProfile,
+};
 
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -9,19 +15,259 @@ pub struct ExportQuery {
     pub source: String,
     pub file_path: String,
     pub format: String,
+    /// patch / mbox：`call`（默认）或 `turn`。
+    #[serde(default)]
+    pub patch_grouping: Option<String>,
+    #[serde(default)]
+    pub include_thinking: Option<bool>,
+    #[serde(default)]
+    pub include_tool_calls: Option<bool>,
+    #[serde(default)]
+    pub include_tool_results: Option<bool>,
+    #[serde(default)]
+    pub max_tool_output_lines: Option<usize>,
+    #[serde(default)]
+    pub message_start: Option<usize>,
+    #[serde(default)]
+    pub message_end: Option<usize>,
+    /// `none` / `standard`，或 `home,email,secrets` 的任意组合。
+    #[serde(default)]
+    pub redact: Option<String>,
+    /// 下载文件名（不含后缀），默认取会话文件名。
+    #[serde(default)]
+    pub filename: Option<String>,
 }
 
-/// 渲染单个会话为 JSON / Markdown / HTML，正文即导出内容（text/plain）。
-/// 文件名由前端决定，浏览器侧用 Blob 触发下载。
+impl ExportQuery {
+    /// Unset fields keep [`ExportOptions::default`].
+    pub fn options(&self) -> Result<ExportOptions, String> {
+        let defaults = ExportOptions::default();
+        Ok(ExportOptions {
+            patch_grouping: self
+                .patch_grouping
+                .as_deref()
+                .map(PatchGrouping::parse)
+                .transpose()?
+                .unwrap_or_default(),
+            include_thinking: self.include_thinking.unwrap_or(defaults.include_thinking),
+            include_tool_calls: self.include_tool_calls.unwrap_or(defaults.include_tool_calls),
+            include_tool_results: self
+                .include_tool_results
+                .unwrap_or(defaults.include_tool_results),
+            max_tool_output_lines: self.max_tool_output_lines,
+            message_start: self.message_start,
+            message_end: self.message_end,
+            redact: self
+                .redact
+                .as_deref()
+                .map(RedactionProfile::parse)
+                .transpose()?
+                .unwrap_or_default(),
+        })
+    }
+}
+
+/// JSON body of the archive route; export options sit at the top level next
+/// to the selection.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveRequest {
+    pub source: String,
+    pub selection: ArchiveSelection,
+    pub format: String,
+    #[serde(flatten)]
+    pub options: ExportOptions,
+}
+
+/// 渲染单个会话并流式下发，正文即导出内容，带 `Content-Disposition` 文件名。
+/// JSON / Markdown / HTML 边解析边写，大会话也不会整个堆在内存里。
+///
+/// 出第一块之前的错误（路径无效、没有文件改动等）照常返回错误状态码；之后的
+/// 错误只能中断响应体。
 pub async fn export_session(
     Query(params): Query<ExportQuery>,
-) -> Result<String, (StatusCode, String)> {
+) -> Result<Response, (StatusCode, String)> {
+    let fmt = ExportFormat::parse(&params.format).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let options = params.options().map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let filename = format!(
+        "{}.{}",
+        params.filename.as_deref().unwrap_or_else(|| {
+            let name = params.file_path.rsplit(['/', '\\']).next().unwrap_or("");
+            name.rsplit_once('.').map_or(name, |(stem, _)| stem)
+        }),
+        fmt.extension()
+    );
+    let body = stream_session(params.source, params.file_path, fmt, options).await?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type(fmt).to_string()),
+            (header::CONTENT_DISPOSITION, content_disposition(&filename)),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+/// Render a session into a streamed body. Waits for the first chunk, so
+/// errors raised before any output become an error response.
+pub(crate) async fn stream_session(
+    source: String,
+    file_path: String,
+    fmt: ExportFormat,
+    options: ExportOptions,
+) -> Result<Body, (StatusCode, String)> {
+    // Bounded like the archive route; each chunk is one BufWriter flush.
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<std::io::Result<Vec<u8>>>(4);
+    tokio::task::spawn_blocking(move || {
+        let writer = std::io::BufWriter::with_capacity(
+            64 * 1024,
+            ChannelWriter { tx: tx.clone() },
+        );
+        if let Err(e) = write_session(&source, &file_path, fmt, &options, writer) {
+            let _ = tx.blocking_send(Err(std::io::Error::other(e)));
+        }
+    });
+
+    let first = match rx.recv().await {
+        Some(Err(e)) => return Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+        first => first,
+    };
+    let body = futures_util::stream::unfold((first, rx), |(pending, mut rx)| async move {
+        let chunk = match pending {
+            Some(chunk) => chunk,
+            None => rx.recv().await?,
+        };
+        Some((chunk, (None, rx)))
+    });
+    Ok(Body::from_stream(body))
+}
+
+/// `io::Write` end of the export channel.
+struct ChannelWriter {
+    tx: tokio::sync::mpsc::Sender<std::io::Result<Vec<u8>>>,
+}
+
+impl std::io::Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.tx.blocking_send(Ok(buf.to_vec())).map_err(|_| {
+            std::io::Error::new(std::io::ErrorKind::BrokenPipe, "Download cancelled")
+        })?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn content_type(fmt: ExportFormat) -> &'static str {
+    match fmt {
+        ExportFormat::Json => "application/json; charset=utf-8",
+        ExportFormat::Markdown => "text/markdown; charset=utf-8",
+        ExportFormat::Html => "text/html; charset=utf-8",
+        ExportFormat::Patch => "text/x-diff; charset=utf-8",
+        ExportFormat::Mbox => "application/mbox",
+        ExportFormat::OpenAi | ExportFormat::ShareGpt => "application/jsonl; charset=utf-8",
+    }
+}
+
+/// `attachment` with an ASCII fallback name plus the UTF-8 name (RFC 6266),
+/// so Chinese session titles survive.
+fn content_disposition(filename: &str) -> String {
+    let fallback: String = filename
+        .chars()
+        .map(|c| match c {
+            ' '..='~' if !matches!(c, '"' | '\\' | '%' | ';') => c,
+            _ => '_',
+        })
+        .collect();
+    let encoded: String = filename
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'.' | b'-' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect();
+    format!(
+        "attachment; filename=\"{}\"; filename*=UTF-8''{}",
+        fallback, encoded
+    )
+}
+
+/// 把一批会话打成 zip 流式下发：选中的会话先解析好（选择无效时直接返回 4xx），
+/// 之后每写完一个条目就发出一块，浏览器边收边存。
+pub async fn export_archive(
+    Json(params): Json<ArchiveRequest>,
+) -> Result<Response, (StatusCode, String)> {
+    let fmt = ExportFormat::parse(&params.format).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let options = params.options;
+    let source = params.source;
+    let selection = params.selection;
+
+    let lookup_source = source.clone();
+    let sessions =
+        tokio::task::spawn_blocking(move || collect_archive(&lookup_source, &selection))
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+            .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    // Bounded, so a slow download holds back the renderer instead of
+    // buffering the whole archive.
+    let (tx, rx) = tokio::sync::mpsc::channel::<std::io::Result<Vec<u8>>>(4);
+    tokio::task::spawn_blocking(move || {
+        let result = stream_archive(&source, &sessions, fmt, &options, |chunk| {
+            tx.blocking_send(Ok(chunk)).map_err(|_| {
+                std::io::Error::new(std::io::ErrorKind::BrokenPipe, "Download cancelled")
+            })
+        });
+        if let Err(e) = result {
+            // Headers are already out; failing the body is the only way to
+            // tell the client the zip is incomplete.
+            let _ = tx.blocking_send(Err(std::io::Error::other(e)));
+        }
+    });
+    let body = futures_util::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    });
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"sessions.zip\"".to_string(),
+            ),
+        ],
+        Body::from_stream(body),
+    )
+        .into_response())
+}
+
+/// 把一批会话导出成一个微调数据集（openai / sharegpt JSONL），连同去重和跳过统计
+/// 一起以 JSON 返回。请求体与打包导出相同。
+pub async fn export_dataset(
+    Json(params): Json<ArchiveRequest>,
+) -> Result<Json<DatasetExport>, (StatusCode, String)> {
     let fmt = ExportFormat::parse(&params.format).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    if !fmt.is_dataset() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Not a dataset format: {}", params.format),
+        ));
+    }
+    let options = params.options;
     let source = params.source;
-    let file_path = params.file_path;
+    let selection = params.selection;
 
-    tokio::task::spawn_blocking(move || render_session(&source, &file_path, fmt))
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))
+    tokio::task::spawn_blocking(move || {
+        let sessions = collect_archive(&source, &selection)?;
+        dataset::export_dataset(&source, &sessions, fmt, &options)
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .map(Json)
+    .map_err(|e| (StatusCode::BAD_REQUEST, e))
 }
@@ -0,0 +1,60 @@
+use axum::extract::Query;
+use axum::http::StatusCode;
+use axum::response::Json;
+use serde::Deserialize;
+use session_core::models::file_history::{FileContentsAtMessage, FileHistoryIndex};
+use session_core::provider;
+
+use crate::resolve_session_file_path;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileHistoryQuery {
+    pub source: String,
+    pub file_path: String,
+}
+
+/// Files a session edited, with their file-history backups and edit tool calls.
+pub async fn get_file_history(
+    Query(params): Query<FileHistoryQuery>,
+) -> Result<Json<FileHistoryIndex>, (StatusCode, String)> {
+    let provider = provider::get(&params.source).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let resolved_path = resolve_session_file_path(&params.source, &params.file_path)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    let result = tokio::task::spawn_blocking(move || provider.file_history(&resolved_path))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    Ok(Json(result))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileContentsQuery {
+    pub source: String,
+    pub file_path: String,
+    /// Edited file, as listed by `get_file_history`.
+    pub file: String,
+    pub uuid: String,
+}
+
+/// Contents of an edited file before and after one message. Only the
+/// session's backups are read, never the file on disk.
+pub async fn get_file_contents_at(
+    Query(params): Query<FileContentsQuery>,
+) -> Result<Json<FileContentsAtMessage>, (StatusCode, String)> {
+    let provider = provider::get(&params.source).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let resolved_path = resolve_session_file_path(&params.source, &params.file_path)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    let result = tokio::task::spawn_blocking(move || {
+        provider.file_contents_at(&resolved_path, &params.file, &params.uuid)
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    Ok(Json(result))
+}
@@ -1,9 +1,12 @@
 use axum::extract::Query;
-use axum::http::StatusCode;
-use axum::response::Json;
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Json, Response};
 use serde::Deserialize;
-use session_core::models::message::{PaginatedMessages, RangeMessages};
-use session_core::provider::{claude, codex};
+use session_core::models::message::{
+    BranchMessages, ConversationTree, PaginatedMessages, RangeMessages, SubagentThread,
+};
+use session_core::models::plan::SessionPlan;
+use session_core::provider;
 
 use crate::resolve_session_file_path;
 
@@ -27,21 +30,15 @@ fn default_page_size() -> usize {
 pub async fn get_messages(
     Query(params): Query<MessagesQuery>,
 ) -> Result<Json<PaginatedMessages>, (StatusCode, String)> {
-    let source = params.source;
-    let resolved_path = resolve_session_file_path(&source, &params.file_path)
+    let provider = provider::get(&params.source).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let resolved_path = resolve_session_file_path(&params.source, &params.file_path)
         .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
     let page = params.page;
     let page_size = params.page_size;
     let from_end = params.from_end;
 
     let result = tokio::task::spawn_blocking(move || {
-        match source.as_str() {
-            "claude" => {
-                claude::parse_session_messages(&resolved_path, page, page_size, from_end)
-            }
-            "codex" => codex::parse_session_messages(&resolved_path, page, page_size, from_end),
-            _ => Err(format!("Unknown source: {}", source)),
-        }
+        provider.parse_session_messages(&resolved_path, page, page_size, from_end)
     })
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
@@ -63,16 +60,107 @@ pub struct MessagesRangeQuery {
 pub async fn get_messages_range(
     Query(params): Query<MessagesRangeQuery>,
 ) -> Result<Json<RangeMessages>, (StatusCode, String)> {
-    let source = params.source;
-    let resolved_path = resolve_session_file_path(&source, &params.file_path)
+    let provider = provider::get(&params.source).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let resolved_path = resolve_session_file_path(&params.source, &params.file_path)
         .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
     let start = params.start;
     let end = params.end;
 
-    let result = tokio::task::spawn_blocking(move || match source.as_str() {
-        "claude" => claude::parse_messages_range(&resolved_path, start, end),
-        "codex" => codex::parse_messages_range(&resolved_path, start, end),
-        _ => Err(format!("Unknown source: {}", source)),
+    let result = tokio::task::spawn_blocking(move || {
+        provider.parse_messages_range(&resolved_path, start, end)
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    Ok(Json(result))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubagentQuery {
+    pub source: String,
+    pub file_path: String,
+    pub agent_id: String,
+}
+
+/// Load the subagent (sidechain) thread spawned by a Task tool call.
+pub async fn get_subagent_thread(
+    Query(params): Query<SubagentQuery>,
+) -> Result<Json<SubagentThread>, (StatusCode, String)> {
+    let provider = provider::get(&params.source).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let resolved_path = resolve_session_file_path(&params.source, &params.file_path)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let agent_id = params.agent_id;
+
+    let result = tokio::task::spawn_blocking(move || {
+        provider.parse_subagent_thread(&resolved_path, &agent_id)
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    Ok(Json(result))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TreeQuery {
+    pub source: String,
+    pub file_path: String,
+}
+
+/// Build the uuid / parentUuid message tree of a session.
+pub async fn get_conversation_tree(
+    Query(params): Query<TreeQuery>,
+) -> Result<Json<ConversationTree>, (StatusCode, String)> {
+    let provider = provider::get(&params.source).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let resolved_path = resolve_session_file_path(&params.source, &params.file_path)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    let result = tokio::task::spawn_blocking(move || provider.parse_conversation_tree(&resolved_path))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    Ok(Json(result))
+}
+
+/// Final todo list and plan documents of a session.
+pub async fn get_session_plan(
+    Query(params): Query<TreeQuery>,
+) -> Result<Json<SessionPlan>, (StatusCode, String)> {
+    let provider = provider::get(&params.source).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let resolved_path = resolve_session_file_path(&params.source, &params.file_path)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    let result = tokio::task::spawn_blocking(move || provider.session_plan(&resolved_path))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    Ok(Json(result))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchQuery {
+    pub source: String,
+    pub file_path: String,
+    pub uuid: String,
+}
+
+/// Load the branch through one message as a linear message list.
+pub async fn get_branch_messages(
+    Query(params): Query<BranchQuery>,
+) -> Result<Json<BranchMessages>, (StatusCode, String)> {
+    let provider = provider::get(&params.source).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let resolved_path = resolve_session_file_path(&params.source, &params.file_path)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let uuid = params.uuid;
+
+    let result = tokio::task::spawn_blocking(move || {
+        provider.parse_branch_messages(&resolved_path, &uuid)
     })
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
@@ -80,3 +168,59 @@ pub async fn get_messages_range(
 
     Ok(Json(result))
 }
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaQuery {
+    pub source: String,
+    pub file_path: String,
+    pub uuid: String,
+    pub block_index: usize,
+}
+
+/// Media types served as-is; anything else goes out as an attachment so a
+/// crafted session can't get HTML or SVG rendered on this origin.
+const INLINE_MEDIA_TYPES: &[&str] = &[
+    "image/png",
+    "image/jpeg",
+    "image/gif",
+    "image/webp",
+    "application/pdf",
+    "text/plain",
+];
+
+/// Stream the decoded payload of an image / document block.
+pub async fn get_message_media(
+    Query(params): Query<MediaQuery>,
+) -> Result<Response, (StatusCode, String)> {
+    let provider = provider::get(&params.source).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let resolved_path = resolve_session_file_path(&params.source, &params.file_path)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let uuid = params.uuid;
+    let block_index = params.block_index;
+
+    let media = tokio::task::spawn_blocking(move || {
+        provider.read_message_media(&resolved_path, &uuid, block_index)
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .map_err(|e| (StatusCode::NOT_FOUND, e))?;
+
+    let inline = INLINE_MEDIA_TYPES.contains(&media.media_type.as_str());
+    let content_type = if inline {
+        media.media_type
+    } else {
+        "application/octet-stream".to_string()
+    };
+    let disposition = if inline { "inline" } else { "attachment" };
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::CONTENT_DISPOSITION, disposition.to_string()),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+            (header::CACHE_CONTROL, "private, max-age=3600".to_string()),
+        ],
+        media.data,
+    )
+        .into_response())
+}
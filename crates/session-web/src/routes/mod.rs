@@ -1,11 +1,17 @@
 pub mod bookmarks;
+pub mod compare;
+pub mod data_roots;
 pub mod export;
+pub mod file_history;
 pub mod messages;
 pub mod progress;
 pub mod projects;
+pub mod prompt_history;
 pub mod provider_sync;
 pub mod recyclebin;
+pub mod saved_searches;
 pub mod search;
+pub mod shares;
 pub mod sessions;
 pub mod skills;
 pub mod stats;
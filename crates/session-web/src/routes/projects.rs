@@ -3,7 +3,7 @@ use axum::response::Json;
 use axum::http::StatusCode;
 use serde::Deserialize;
 use session_core::models::project::ProjectEntry;
-use session_core::provider::{claude, codex};
+use session_core::provider;
 use session_core::provider::claude::{DeleteLevel, DeleteResult};
 
 #[derive(Deserialize)]
@@ -15,11 +15,7 @@ pub async fn get_projects(
     Query(params): Query<ProjectsQuery>,
 ) -> Result<Json<Vec<ProjectEntry>>, (StatusCode, String)> {
     let source = params.source;
-    let result = tokio::task::spawn_blocking(move || match source.as_str() {
-        "claude" => claude::get_projects(),
-        "codex" => codex::get_projects(),
-        _ => Err(format!("Unknown source: {}", source)),
-    })
+    let result = tokio::task::spawn_blocking(move || provider::get(&source)?.get_projects())
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
@@ -46,10 +42,8 @@ pub async fn delete_project(
     let source = params.source;
     let project_id = params.project_id;
     let level = params.level;
-    let res = tokio::task::spawn_blocking(move || match source.as_str() {
-        "claude" => claude::delete_project(&project_id, level),
-        "codex" => codex::delete_project(&project_id),
-        _ => Err(format!("Delete project not supported for source: {}", source)),
+    let res = tokio::task::spawn_blocking(move || {
+        provider::get(&source)?.delete_project(&project_id, level)
     })
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
@@ -73,10 +67,7 @@ pub async fn set_project_alias(
     axum::Json(body): axum::Json<SetAliasBody>,
 ) -> Result<axum::Json<()>, (StatusCode, String)> {
     let res = tokio::task::spawn_blocking(move || {
-        match body.source.as_str() {
-            "claude" => claude::set_project_alias(&body.project_id, body.alias),
-            _ => Err(format!("set_project_alias not supported for source: {}", body.source)),
-        }
+        provider::get(&body.source)?.set_project_alias(&body.project_id, body.alias)
     })
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
@@ -0,0 +1,39 @@
+use axum::extract::Query;
+use axum::http::StatusCode;
+use axum::response::Json;
+use serde::Deserialize;
+use session_core::models::prompt_history::PromptHistoryPage;
+use session_core::prompt_history::{self, PromptHistoryFilter};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptHistoryQuery {
+    #[serde(default)]
+    pub query: Option<String>,
+    #[serde(default)]
+    pub project: Option<String>,
+    #[serde(default)]
+    pub page: Option<usize>,
+    #[serde(default)]
+    pub page_size: Option<usize>,
+}
+
+/// Claude Code 输入历史（history.jsonl），按时间倒序分页。
+pub async fn get_prompt_history(
+    Query(params): Query<PromptHistoryQuery>,
+) -> Result<Json<PromptHistoryPage>, (StatusCode, String)> {
+    let filter = PromptHistoryFilter {
+        query: params.query.filter(|s| !s.trim().is_empty()),
+        project: params.project.filter(|s| !s.is_empty()),
+    };
+    let page = params.page.unwrap_or(0);
+    let page_size = params.page_size.unwrap_or(100);
+
+    let result = tokio::task::spawn_blocking(move || {
+        prompt_history::get_prompt_history(filter, page, page_size)
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    Ok(Json(result))
+}
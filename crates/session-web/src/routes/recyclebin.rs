@@ -53,12 +53,8 @@ pub async fn cleanup_orphan_dirs(
     Query(params): Query<CleanupQuery>,
 ) -> Result<Json<EmptyResult>, (StatusCode, String)> {
     let source = params.source;
-    let count = tokio::task::spawn_blocking(move || match source.as_str() {
-        "claude" => session_core::provider::claude::cleanup_all_orphan_dirs(),
-        _ => Err(format!(
-            "Orphan dir cleanup not supported for source: {}",
-            source
-        )),
+    let count = tokio::task::spawn_blocking(move || {
+        session_core::provider::get(&source)?.cleanup_orphan_dirs()
     })
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
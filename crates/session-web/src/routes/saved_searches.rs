@@ -0,0 +1,63 @@
+use axum::extract::{Path, Query};
+use axum::http::StatusCode;
+use axum::response::Json;
+use serde::Deserialize;
+use session_core::saved_searches::{self, CollectionCount, SavedSearch, SmartCollection};
+
+pub async fn list_saved_searches() -> Json<Vec<SavedSearch>> {
+    Json(saved_searches::list_saved_searches())
+}
+
+pub async fn add_saved_search(
+    Json(search): Json<SavedSearch>,
+) -> Result<Json<SavedSearch>, (StatusCode, String)> {
+    let result = tokio::task::spawn_blocking(move || saved_searches::add_saved_search(search))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    Ok(Json(result))
+}
+
+pub async fn remove_saved_search(
+    Path(id): Path<String>,
+) -> Result<Json<()>, (StatusCode, String)> {
+    saved_searches::remove_saved_search(&id)
+        .map(Json)
+        .map_err(|e| (StatusCode::NOT_FOUND, e))
+}
+
+pub async fn collection_counts() -> Result<Json<Vec<CollectionCount>>, (StatusCode, String)> {
+    let result = tokio::task::spawn_blocking(saved_searches::collection_counts)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(result))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionQuery {
+    #[serde(default)]
+    pub mark_viewed: bool,
+}
+
+pub async fn open_collection(
+    Path(id): Path<String>,
+    Query(params): Query<CollectionQuery>,
+) -> Result<Json<SmartCollection>, (StatusCode, String)> {
+    if !saved_searches::list_saved_searches()
+        .iter()
+        .any(|s| s.id == id)
+    {
+        return Err((StatusCode::NOT_FOUND, "Saved search not found".to_string()));
+    }
+    let result = tokio::task::spawn_blocking(move || {
+        saved_searches::open_collection(&id, params.mark_viewed)
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    Ok(Json(result))
+}
@@ -2,37 +2,54 @@ use axum::extract::Query;
 use axum::response::Json;
 use axum::http::StatusCode;
 use serde::Deserialize;
-use session_core::search::SearchResult;
+use session_core::search::{SearchOptions, SearchResponse, SearchScope};
 
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SearchQuery {
+    /// A source id, or `all` to search every source.
     pub source: String,
     pub query: String,
     #[serde(default = "default_max_results")]
     pub max_results: usize,
     #[serde(default)]
     pub scope: Option<String>,
+    #[serde(default)]
+    pub regex: bool,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    #[serde(default)]
+    pub whole_word: bool,
 }
 
 fn default_max_results() -> usize {
     50
 }
 
+impl SearchQuery {
+    pub fn scope(&self) -> SearchScope {
+        SearchScope::from_query(self.scope.as_deref().unwrap_or("all"))
+    }
+
+    pub fn options(&self) -> SearchOptions {
+        SearchOptions {
+            regex: self.regex,
+            case_sensitive: self.case_sensitive,
+            whole_word: self.whole_word,
+        }
+    }
+}
+
 pub async fn global_search(
     Query(params): Query<SearchQuery>,
-) -> Result<Json<Vec<SearchResult>>, (StatusCode, String)> {
-    let source = params.source;
-    let query = params.query;
-    let max_results = params.max_results;
-    let scope = params.scope.unwrap_or_else(|| "all".to_string());
-
+) -> Result<Json<SearchResponse>, (StatusCode, String)> {
     let result = tokio::task::spawn_blocking(move || {
         session_core::search::global_search(
-            &source,
-            &query,
-            max_results,
-            session_core::search::SearchScope::from_query(&scope),
+            &params.source,
+            &params.query,
+            params.max_results,
+            params.scope(),
+            params.options(),
         )
     })
     .await
@@ -4,9 +4,9 @@ use axum::response::Json;
 use serde::Deserialize;
 use session_core::metadata;
 use session_core::models::session::SessionIndexEntry;
-use session_core::provider::{claude, codex};
+use session_core::provider::{self, SessionProvider};
 
-use crate::{resolve_claude_project_dir, resolve_session_file_path, SessionSource};
+use crate::resolve_session_file_path;
 
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -16,22 +16,18 @@ pub struct SessionsQuery {
 }
 
 fn merge_session_metadata(
-    source: &str,
+    provider: &dyn SessionProvider,
     project_id: &str,
     sessions: &mut [SessionIndexEntry],
 ) {
-    let meta = metadata::load_metadata(source, project_id);
+    let meta = metadata::load_metadata(provider.id(), project_id);
     for session in sessions {
         if let Some(sm) = meta.sessions.get(&session.session_id) {
-            if source == "claude" {
-                if !sm.tags.is_empty() {
-                    session.tags = Some(sm.tags.clone());
-                }
-            } else {
+            if !provider.stores_alias_in_session_file() {
                 session.alias = sm.alias.clone();
-                if !sm.tags.is_empty() {
-                    session.tags = Some(sm.tags.clone());
-                }
+            }
+            if !sm.tags.is_empty() {
+                session.tags = Some(sm.tags.clone());
             }
         }
     }
@@ -40,26 +36,19 @@ fn merge_session_metadata(
 pub async fn get_sessions(
     Query(params): Query<SessionsQuery>,
 ) -> Result<Json<Vec<SessionIndexEntry>>, (StatusCode, String)> {
-    let source = params.source;
     let project_id = params.project_id;
-    let source_kind = SessionSource::parse(&source)
+    let provider = provider::get(&params.source)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    provider
+        .validate_project_id(&project_id)
         .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
-
-    if source_kind == SessionSource::Claude {
-        resolve_claude_project_dir(&project_id)
-            .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
-    }
 
     let result = tokio::task::spawn_blocking(move || {
-        let mut sessions = match source.as_str() {
-            "claude" => claude::get_sessions(&project_id)?,
-            "codex" => codex::get_sessions(&project_id)?,
-            _ => return Err(format!("Unknown source: {}", source)),
-        };
+        let mut sessions = provider.get_sessions(&project_id)?;
 
-        merge_session_metadata(&source, &project_id, &mut sessions);
+        merge_session_metadata(provider, &project_id, &mut sessions);
 
-        Ok(sessions)
+        Ok::<_, String>(sessions)
     })
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
@@ -71,26 +60,19 @@ pub async fn get_sessions(
 pub async fn get_invalid_sessions(
     Query(params): Query<SessionsQuery>,
 ) -> Result<Json<Vec<SessionIndexEntry>>, (StatusCode, String)> {
-    let source = params.source;
     let project_id = params.project_id;
-    let source_kind = SessionSource::parse(&source)
+    let provider = provider::get(&params.source)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    provider
+        .validate_project_id(&project_id)
         .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
-
-    if source_kind == SessionSource::Claude {
-        resolve_claude_project_dir(&project_id)
-            .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
-    }
 
     let result = tokio::task::spawn_blocking(move || {
-        let mut sessions = match source.as_str() {
-            "claude" => claude::get_invalid_sessions(&project_id)?,
-            "codex" => codex::get_invalid_sessions(&project_id)?,
-            _ => return Err(format!("Unknown source: {}", source)),
-        };
+        let mut sessions = provider.get_invalid_sessions(&project_id)?;
 
-        merge_session_metadata(&source, &project_id, &mut sessions);
+        merge_session_metadata(provider, &project_id, &mut sessions);
 
-        Ok(sessions)
+        Ok::<_, String>(sessions)
     })
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
@@ -117,7 +99,7 @@ pub async fn delete_session(
     let source = params
         .source
         .ok_or_else(|| (StatusCode::BAD_REQUEST, "source is required".to_string()))?;
-    let source_kind = SessionSource::parse(&source)
+    let provider = provider::get(&source)
         .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
     let project_id = params.project_id;
     let session_id = params.session_id;
@@ -134,76 +116,18 @@ pub async fn delete_session(
             if let (Some(pid), Some(sid)) = (project_id.as_ref(), session_id.as_ref()) {
                 let _ = metadata::remove_session_meta(&source, pid, sid);
             }
-            match source_kind {
-                SessionSource::Claude => claude::invalidate_cache(),
-                SessionSource::Codex => codex::invalidate_sessions_cache(),
-            }
+            provider.invalidate_cache();
             return Ok(Json(()));
         }
         Err(e) => return Err((StatusCode::BAD_REQUEST, e)),
     };
 
-    match source_kind {
-        SessionSource::Claude => {
-            if let Some(ref pid) = project_id {
-                let project_dir = resolve_claude_project_dir(pid)
-                    .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
-                let parent = resolved_path
-                    .parent()
-                    .ok_or_else(|| {
-                        (StatusCode::BAD_REQUEST, "Invalid session file path".to_string())
-                    })?;
-                if parent != project_dir.as_path() {
-                    return Err((
-                        StatusCode::BAD_REQUEST,
-                        "Session file does not belong to the requested project".to_string(),
-                    ));
-                }
-            }
-
-            if let Some(ref sid) = session_id {
-                let file_stem = resolved_path
-                    .file_stem()
-                    .and_then(|stem| stem.to_str())
-                    .ok_or_else(|| {
-                        (StatusCode::BAD_REQUEST, "Invalid session file name".to_string())
-                    })?;
-                if file_stem != sid {
-                    return Err((
-                        StatusCode::BAD_REQUEST,
-                        "Session id does not match the requested Claude session file".to_string(),
-                    ));
-                }
-            }
-        }
-        SessionSource::Codex => {
-            let session_meta = codex::extract_session_meta(&resolved_path)
-                .ok_or_else(|| {
-                    (
-                        StatusCode::BAD_REQUEST,
-                        "Failed to read Codex session metadata".to_string(),
-                    )
-                })?;
-
-            if let Some(ref pid) = project_id {
-                if session_meta.cwd != *pid {
-                    return Err((
-                        StatusCode::BAD_REQUEST,
-                        "Session file does not belong to the requested Codex project".to_string(),
-                    ));
-                }
-            }
-
-            if let Some(ref sid) = session_id {
-                if session_meta.id != *sid {
-                    return Err((
-                        StatusCode::BAD_REQUEST,
-                        "Session id does not match the requested Codex session file".to_string(),
-                    ));
-                }
-            }
-        }
-    }
+    provider
+        .verify_session_owner(&resolved_path, project_id.as_deref(), session_id.as_deref())
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    provider
+        .check_session_deletable(&resolved_path)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
 
     tokio::task::spawn_blocking(move || {
         std::fs::remove_file(&resolved_path)
@@ -238,40 +162,25 @@ pub struct UpdateMetaBody {
 pub async fn update_session_meta(
     Json(body): Json<UpdateMetaBody>,
 ) -> Result<Json<()>, (StatusCode, String)> {
-    let source_kind = SessionSource::parse(&body.source)
+    let provider = provider::get(&body.source)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    provider
+        .validate_project_id(&body.project_id)
         .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
 
-    if source_kind == SessionSource::Claude {
-        resolve_claude_project_dir(&body.project_id)
-            .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
-    }
-
-    let validated_file_path = if source_kind == SessionSource::Claude {
+    let validated_file_path = if provider.stores_alias_in_session_file() {
         body.file_path
             .as_deref()
             .map(|file_path| {
                 let resolved = resolve_session_file_path(&body.source, file_path)
                     .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
-                let project_dir = resolve_claude_project_dir(&body.project_id)
+                provider
+                    .verify_session_owner(
+                        &resolved,
+                        Some(&body.project_id),
+                        Some(&body.session_id),
+                    )
                     .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
-                let parent = resolved.parent().ok_or_else(|| {
-                    (StatusCode::BAD_REQUEST, "Invalid session file path".to_string())
-                })?;
-                if parent != project_dir.as_path() {
-                    return Err((
-                        StatusCode::BAD_REQUEST,
-                        "Session file does not belong to the requested project".to_string(),
-                    ));
-                }
-                let file_stem = resolved.file_stem().and_then(|stem| stem.to_str()).ok_or_else(
-                    || (StatusCode::BAD_REQUEST, "Invalid session file name".to_string()),
-                )?;
-                if file_stem != body.session_id {
-                    return Err((
-                        StatusCode::BAD_REQUEST,
-                        "Session id does not match the requested Claude session file".to_string(),
-                    ));
-                }
                 Ok(resolved)
             })
             .transpose()?
@@ -280,13 +189,9 @@ pub async fn update_session_meta(
     };
 
     tokio::task::spawn_blocking(move || {
-        if body.source == "claude" {
+        if provider.stores_alias_in_session_file() {
             if let Some(path) = validated_file_path.as_deref() {
-                session_core::parser::jsonl::append_custom_title(
-                    path,
-                    &body.session_id,
-                    body.alias.as_deref(),
-                )?;
+                provider.write_session_alias(path, &body.session_id, body.alias.as_deref())?;
             }
             metadata::update_session_meta(
                 &body.source,
@@ -324,7 +229,7 @@ pub struct RenameChatBody {
 pub async fn rename_chat_session(
     Json(body): Json<RenameChatBody>,
 ) -> Result<Json<()>, (StatusCode, String)> {
-    SessionSource::parse(&body.source).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    provider::get(&body.source).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
 
     tokio::task::spawn_blocking(move || {
         metadata::rename_chat_session(
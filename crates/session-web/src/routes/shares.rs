@@ -0,0 +1,81 @@
+use axum::extract::Path;
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Json, Response};
+use session_core::export::{ExportFormat, ExportOptions};
+use session_core::shares::{self, NewShare, ShareError, ShareLink};
+
+use super::export::stream_session;
+
+pub async fn list_shares() -> Result<Json<Vec<ShareLink>>, (StatusCode, String)> {
+    tokio::task::spawn_blocking(shares::list_shares)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+pub async fn create_share(
+    Json(new): Json<NewShare>,
+) -> Result<Json<ShareLink>, (StatusCode, String)> {
+    tokio::task::spawn_blocking(move || shares::create_share(new))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map(Json)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))
+}
+
+pub async fn revoke_share(Path(id): Path<String>) -> Result<Json<()>, (StatusCode, String)> {
+    tokio::task::spawn_blocking(move || shares::revoke_share(&id))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map(Json)
+        .map_err(|e| (StatusCode::NOT_FOUND, e))
+}
+
+/// 公开只读页面（不经过 token 校验）：按分享记录的消息区间和脱敏配置渲染 HTML 导出。
+/// 只能看到这一个会话，链接过期或被撤销后立即失效。
+pub async fn view_share(Path(token): Path<String>) -> Result<Response, (StatusCode, String)> {
+    let share = tokio::task::spawn_blocking(move || shares::resolve_share(&token))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| match e {
+            ShareError::NotFound => (StatusCode::NOT_FOUND, "Share link not found".to_string()),
+            ShareError::Expired => (StatusCode::GONE, "Share link has expired".to_string()),
+        })?;
+
+    let options = ExportOptions {
+        message_start: share.message_start,
+        message_end: share.message_end,
+        redact: share.redaction(),
+        ..Default::default()
+    };
+    let body = stream_session(share.source, share.file_path, ExportFormat::Html, options)
+        .await
+        // Don't leak server paths or parser errors to anonymous viewers.
+        .map_err(|_| {
+            (
+                StatusCode::NOT_FOUND,
+                "Shared session is no longer available".to_string(),
+            )
+        })?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/html; charset=utf-8"),
+            // The export is self-contained: inline styles, no scripts.
+            (
+                header::CONTENT_SECURITY_POLICY,
+                "default-src 'none'; style-src 'unsafe-inline'; img-src data: https:",
+            ),
+            // The token is in the URL; keep it out of Referer headers.
+            (header::REFERRER_POLICY, "no-referrer"),
+            (header::CACHE_CONTROL, "no-store"),
+            (
+                header::HeaderName::from_static("x-robots-tag"),
+                "noindex, nofollow",
+            ),
+        ],
+        body,
+    )
+        .into_response())
+}
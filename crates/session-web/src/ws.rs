@@ -2,10 +2,13 @@ use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
 use axum::http::StatusCode;
 use axum::response::Response;
 use notify::{Config, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::sync::broadcast;
+use tokio::sync::{broadcast, mpsc};
 
+use crate::routes::search::SearchQuery;
 use crate::{require_ws_auth, AppToken, WsTicketStore};
 
 /// Minimum interval between sending file change events.
@@ -13,8 +16,13 @@ use crate::{require_ws_auth, AppToken, WsTicketStore};
 /// so use a longer debounce to avoid flooding clients.
 const DEBOUNCE_DURATION: Duration = Duration::from_millis(1000);
 
-use session_core::parser::path_encoder::get_projects_dir;
-use session_core::provider::{claude, codex};
+/// How often the watcher checks whether the data roots were reconfigured.
+const ROOTS_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+use session_core::data_roots;
+use session_core::provider;
+use session_core::scan_progress;
+use session_core::search::{SearchBatch, SearchResponse, SearchResult, SearchSink};
 
 /// Shared broadcast sender for file change events
 pub type FsChangeTx = Arc<broadcast::Sender<Vec<String>>>;
@@ -24,7 +32,8 @@ pub type FsChangeTx = Arc<broadcast::Sender<Vec<String>>>;
 /// channel closes for any reason, we sleep briefly and respawn so a
 /// transient failure (network share unmounting, OS resource exhaustion,
 /// etc.) doesn't silently leave the server without change notifications
-/// for the rest of its lifetime.
+/// for the rest of its lifetime. When the data roots change it is rebuilt
+/// right away with the new directories.
 pub fn start_file_watcher() -> FsChangeTx {
     let (tx, _) = broadcast::channel::<Vec<String>>(64);
     let tx = Arc::new(tx);
@@ -32,7 +41,9 @@ pub fn start_file_watcher() -> FsChangeTx {
 
     std::thread::spawn(move || {
         loop {
-            run_file_watcher_once(&tx_clone);
+            if run_file_watcher_once(&tx_clone) {
+                continue;
+            }
             // The inner function only returns when the watcher dies. Pause
             // before respawning so a chronic failure can't pin a CPU core.
             tracing::warn!("file watcher exited; respawning in 5s");
@@ -43,72 +54,66 @@ pub fn start_file_watcher() -> FsChangeTx {
     tx
 }
 
-/// One pass of the file watcher loop. Returns when the watcher fails or its
-/// event stream ends.
-fn run_file_watcher_once(tx_clone: &broadcast::Sender<Vec<String>>) {
+/// One pass of the file watcher loop. Returns `false` when the watcher fails
+/// or its event stream ends, `true` when the data roots were reconfigured.
+fn run_file_watcher_once(tx_clone: &broadcast::Sender<Vec<String>>) -> bool {
+    let generation = data_roots::generation();
     let (notify_tx, notify_rx) = std::sync::mpsc::channel();
 
     let mut watcher = match RecommendedWatcher::new(notify_tx, Config::default()) {
         Ok(w) => w,
         Err(e) => {
             tracing::error!("Failed to create file watcher: {}", e);
-            return;
+            return false;
         }
     };
 
-    // Watch Claude projects directory
-    if let Some(dir) = get_projects_dir() {
+    // Watch every provider's session roots
+    let roots: Vec<(&'static dyn provider::SessionProvider, std::path::PathBuf)> = provider::all()
+        .iter()
+        .flat_map(|p| p.session_roots().into_iter().map(move |dir| (*p, dir)))
+        .collect();
+    for (provider, dir) in &roots {
         if dir.exists() {
-            if let Err(e) = watcher.watch(&dir, RecursiveMode::Recursive) {
-                tracing::warn!("Failed to watch Claude directory: {}", e);
-            }
-        }
-    }
-
-    // Watch Codex sessions directory
-    if let Some(dir) = codex::get_sessions_dir() {
-        if dir.exists() {
-            if let Err(e) = watcher.watch(&dir, RecursiveMode::Recursive) {
-                tracing::warn!("Failed to watch Codex directory: {}", e);
+            if let Err(e) = watcher.watch(dir, RecursiveMode::Recursive) {
+                tracing::warn!("Failed to watch {} directory: {}", provider.display_name(), e);
             }
         }
     }
 
     let mut last_emit = Instant::now() - DEBOUNCE_DURATION;
 
-    for event in notify_rx {
+    loop {
+        if data_roots::generation() != generation {
+            tracing::info!("data roots changed; rebuilding file watcher");
+            return true;
+        }
+        let event = match notify_rx.recv_timeout(ROOTS_POLL_INTERVAL) {
+            Ok(event) => event,
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => continue,
+            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => return false,
+        };
         match event {
             Ok(event) => {
                 let relevant = event.paths.iter().any(|p| {
-                    p.extension()
-                        .map(|e| e == "jsonl" || e == "json")
-                        .unwrap_or(false)
+                    roots
+                        .iter()
+                        .any(|(provider, dir)| p.starts_with(dir) && provider.is_watched_file(p))
                 });
 
                 if relevant && last_emit.elapsed() >= DEBOUNCE_DURATION {
                     // Surgically update only the affected projects/files (same
                     // path-partitioned approach as the Tauri watcher) instead of
                     // wiping the whole cache on every change.
-                    if let Some(dir) = get_projects_dir() {
-                        let claude_paths: Vec<std::path::PathBuf> = event
-                            .paths
-                            .iter()
-                            .filter(|p| p.starts_with(&dir))
-                            .cloned()
-                            .collect();
-                        if !claude_paths.is_empty() {
-                            claude::invalidate_paths(&claude_paths);
-                        }
-                    }
-                    if let Some(dir) = codex::get_sessions_dir() {
-                        let codex_paths: Vec<std::path::PathBuf> = event
+                    for (provider, dir) in &roots {
+                        let provider_paths: Vec<std::path::PathBuf> = event
                             .paths
                             .iter()
-                            .filter(|p| p.starts_with(&dir))
+                            .filter(|p| p.starts_with(dir))
                             .cloned()
                             .collect();
-                        if !codex_paths.is_empty() {
-                            codex::invalidate_paths(&codex_paths);
+                        if !provider_paths.is_empty() {
+                            provider.invalidate_paths(&provider_paths);
                         }
                     }
 
@@ -129,7 +134,7 @@ fn run_file_watcher_once(tx_clone: &broadcast::Sender<Vec<String>>) {
     }
 }
 
-/// WebSocket handler for file change events
+/// WebSocket handler for file change events and streamed searches
 pub async fn ws_handler(
     ws: WebSocketUpgrade,
     axum::extract::State(tx): axum::extract::State<FsChangeTx>,
@@ -148,8 +153,74 @@ pub struct WsAuthQuery {
     pub ticket: Option<String>,
 }
 
+/// Messages a client sends on `/ws`.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum ClientMessage {
+    /// Start a search, cancelling the one still running on this connection.
+    Search {
+        id: u64,
+        #[serde(flatten)]
+        params: SearchQuery,
+    },
+    /// Stop the running search without starting another.
+    SearchCancel,
+}
+
+/// Search messages sent back to the client, tagged with the request `id`.
+/// A cancelled search sends nothing further.
+#[derive(Serialize)]
+#[serde(tag = "type")]
+enum SearchEvent {
+    #[serde(rename = "search-batch")]
+    Batch(SearchBatch),
+    #[serde(rename = "search-done")]
+    Done { id: u64, response: SearchResponse },
+    #[serde(rename = "search-error")]
+    Error { id: u64, error: String },
+}
+
+/// Run a search on the blocking pool, streaming its batches into `events`.
+fn start_search(
+    id: u64,
+    params: SearchQuery,
+    cancel: Arc<AtomicBool>,
+    events: mpsc::UnboundedSender<SearchEvent>,
+) {
+    tokio::task::spawn_blocking(move || {
+        let on_batch = |results: &[SearchResult]| {
+            let _ = events.send(SearchEvent::Batch(SearchBatch {
+                id,
+                results: results.to_vec(),
+                progress: scan_progress::snapshot(),
+            }));
+        };
+        let result = session_core::search::global_search_stream(
+            &params.source,
+            &params.query,
+            params.max_results,
+            params.scope(),
+            params.options(),
+            SearchSink {
+                cancel: &cancel,
+                on_batch: &on_batch,
+            },
+        );
+        if cancel.load(Ordering::Relaxed) {
+            return;
+        }
+        let _ = events.send(match result {
+            Ok(response) => SearchEvent::Done { id, response },
+            Err(error) => SearchEvent::Error { id, error },
+        });
+    });
+}
+
 async fn handle_socket(mut socket: WebSocket, tx: FsChangeTx) {
     let mut rx = tx.subscribe();
+    let (events_tx, mut events_rx) = mpsc::unbounded_channel::<SearchEvent>();
+    // Cancel flag of this connection's running search; a newer one sets it.
+    let mut search: Option<Arc<AtomicBool>> = None;
 
     loop {
         tokio::select! {
@@ -168,12 +239,37 @@ async fn handle_socket(mut socket: WebSocket, tx: FsChangeTx) {
                     Err(_) => break,
                 }
             }
+            Some(event) = events_rx.recv() => {
+                let Ok(json) = serde_json::to_string(&event) else {
+                    continue;
+                };
+                if socket.send(Message::Text(json.into())).await.is_err() {
+                    break;
+                }
+            }
             msg = socket.recv() => {
                 match msg {
                     Some(Ok(Message::Close(_))) | None => break,
+                    Some(Ok(Message::Text(text))) => {
+                        let Ok(message) = serde_json::from_str::<ClientMessage>(&text) else {
+                            continue;
+                        };
+                        if let Some(cancel) = search.take() {
+                            cancel.store(true, Ordering::Relaxed);
+                        }
+                        if let ClientMessage::Search { id, params } = message {
+                            let cancel = Arc::new(AtomicBool::new(false));
+                            start_search(id, params, Arc::clone(&cancel), events_tx.clone());
+                            search = Some(cancel);
+                        }
+                    }
                     _ => {} // ignore other messages
                 }
             }
         }
     }
+
+    if let Some(cancel) = search {
+        cancel.store(true, Ordering::Relaxed);
+    }
 }
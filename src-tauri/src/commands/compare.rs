@@ -0,0 +1,13 @@
+use session_core::compare;
+use session_core::models::compare::SessionComparison;
+
+/// Two sessions side by side, with their turns aligned.
+#[tauri::command]
+pub fn compare_sessions(
+    source_a: String,
+    file_path_a: String,
+    source_b: String,
+    file_path_b: String,
+) -> Result<SessionComparison, String> {
+    compare::compare_sessions(&source_a, &file_path_a, &source_b, &file_path_b)
+}
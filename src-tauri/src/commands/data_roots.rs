@@ -0,0 +1,11 @@
+use session_core::data_roots::{self, DataRoot};
+
+#[tauri::command]
+pub fn get_data_roots(source: String) -> Result<Vec<DataRoot>, String> {
+    Ok(data_roots::get_roots(&source))
+}
+
+#[tauri::command]
+pub fn set_data_roots(source: String, roots: Vec<DataRoot>) -> Result<Vec<DataRoot>, String> {
+    data_roots::set_roots(&source, roots)
+}
@@ -1,6 +1,21 @@
 use std::path::Path;
 
-use session_core::export::{render_session, ExportFormat};
+use session_core::export::archive::{collect_archive, write_archive, ArchiveSelection};
+use session_core::export::dataset::{self, DatasetStats};
+use session_core::export::{render_session, ExportFormat, ExportOptions, PatchGrouping};
+
+/// 过滤 / 截断 / 脱敏等选项整体作为 `options` 传入；`patchGrouping` 作为单独参数
+/// 保留，设置时覆盖 `options` 里的同名字段。
+fn export_options(
+    patch_grouping: Option<String>,
+    options: Option<ExportOptions>,
+) -> Result<ExportOptions, String> {
+    let mut options = options.unwrap_or_default();
+    if let Some(grouping) = patch_grouping.as_deref() {
+        options.patch_grouping = PatchGrouping::parse(grouping)?;
+    }
+    Ok(options)
+}
 
 /// 渲染单个会话为指定格式的字符串。文件名由前端决定，这里只返回内容。
 #[tauri::command]
@@ -8,36 +23,119 @@ pub fn export_session(
     source: String,
     file_path: String,
     format: String,
+    patch_grouping: Option<String>,
+    options: Option<ExportOptions>,
 ) -> Result<String, String> {
     let fmt = ExportFormat::parse(&format)?;
-    render_session(&source, &file_path, fmt)
+    render_session(
+        &source,
+        &file_path,
+        fmt,
+        &export_options(patch_grouping, options)?,
+    )
 }
 
-/// 把导出内容写入用户通过保存/选目录对话框选定的路径。
-///
-/// 轻量守卫：只允许写 `.json` / `.md` / `.html` 后缀（导出场景），且父目录必须
-/// 已存在，避免被当成任意文件写入接口。
-#[tauri::command]
-pub fn write_export_file(path: String, content: String) -> Result<(), String> {
-    let p = Path::new(&path);
-
+/// 导出目标路径守卫：后缀必须在 `extensions` 里，且父目录必须已存在，避免被当成
+/// 任意文件写入接口。
+fn check_export_path(p: &Path, extensions: &[&str]) -> Result<(), String> {
     let ext_ok = p
         .extension()
         .and_then(|e| e.to_str())
-        .map(|e| matches!(e.to_ascii_lowercase().as_str(), "json" | "md" | "html"))
+        .map(|e| extensions.contains(&e.to_ascii_lowercase().as_str()))
         .unwrap_or(false);
     if !ext_ok {
-        return Err("Export file must end with .json, .md or .html".to_string());
+        let list: Vec<String> = extensions.iter().map(|e| format!(".{}", e)).collect();
+        return Err(format!("Export file must end with {}", list.join(", ")));
     }
 
     match p.parent() {
-        Some(parent) if parent.as_os_str().is_empty() || parent.is_dir() => {}
-        Some(parent) => {
-            return Err(format!("Target directory does not exist: {}", parent.display()))
-        }
-        None => return Err("Invalid export path".to_string()),
+        Some(parent) if parent.as_os_str().is_empty() || parent.is_dir() => Ok(()),
+        Some(parent) => Err(format!(
+            "Target directory does not exist: {}",
+            parent.display()
+        )),
+        None => Err("Invalid export path".to_string()),
     }
+}
+
+/// 把导出内容写入用户通过保存/选目录对话框选定的路径。
+///
+/// 轻量守卫：只允许写 `.json` / `.md` / `.html` / `.patch` / `.mbox` / `.jsonl` 后缀（导出场景），
+/// 且父目录必须已存在。
+#[tauri::command]
+pub fn write_export_file(path: String, content: String) -> Result<(), String> {
+    let p = Path::new(&path);
+    check_export_path(p, &["json", "md", "html", "patch", "mbox", "jsonl"])?;
 
     std::fs::write(p, content.as_bytes())
         .map_err(|e| format!("Failed to write export file: {}", e))
 }
+
+/// 把一个项目 / 标签 / 选中的会话打成 zip，写到用户在保存对话框里选的 `.zip`
+/// 路径。先写临时文件再改名，导出失败不会留下半个 zip。返回打包进去的会话数。
+#[tauri::command]
+pub async fn export_archive(
+    source: String,
+    selection: ArchiveSelection,
+    format: String,
+    patch_grouping: Option<String>,
+    options: Option<ExportOptions>,
+    path: String,
+) -> Result<usize, String> {
+    let fmt = ExportFormat::parse(&format)?;
+    let options = export_options(patch_grouping, options)?;
+    check_export_path(Path::new(&path), &["zip"])?;
+
+    tokio::task::spawn_blocking(move || {
+        let sessions = collect_archive(&source, &selection)?;
+        let p = Path::new(&path);
+        let tmp_path = p.with_extension("zip.tmp");
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&tmp_path)
+            .map_err(|e| format!("Failed to create export file: {}", e))?;
+        let manifest = match write_archive(&source, &sessions, fmt, &options, file) {
+            Ok((_, manifest)) => manifest,
+            Err(e) => {
+                let _ = std::fs::remove_file(&tmp_path);
+                return Err(e);
+            }
+        };
+        std::fs::rename(&tmp_path, p)
+            .map_err(|e| format!("Failed to write export file: {}", e))?;
+        Ok(manifest.sessions.iter().filter(|s| s.path.is_some()).count())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// 把一批会话导出成一个微调数据集（openai / sharegpt JSONL），写到用户选的
+/// `.jsonl` 路径。返回写入、去重和跳过的统计。
+#[tauri::command]
+pub async fn export_dataset(
+    source: String,
+    selection: ArchiveSelection,
+    format: String,
+    options: Option<ExportOptions>,
+    path: String,
+) -> Result<DatasetStats, String> {
+    let fmt = ExportFormat::parse(&format)?;
+    if !fmt.is_dataset() {
+        return Err(format!("Not a dataset format: {}", format));
+    }
+    let options = options.unwrap_or_default();
+    check_export_path(Path::new(&path), &["jsonl"])?;
+
+    tokio::task::spawn_blocking(move || {
+        let sessions = collect_archive(&source, &selection)?;
+        let export = dataset::export_dataset(&source, &sessions, fmt, &options)?;
+        std::fs::write(&path, export.content.as_bytes())
+            .map_err(|e| format!("Failed to write export file: {}", e))?;
+        Ok(export.stats)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,22 @@
+use session_core::models::file_history::{FileContentsAtMessage, FileHistoryIndex};
+use session_core::paths::validate_session_file;
+use session_core::provider;
+
+/// Files a session edited, with their file-history backups and edit tool calls.
+#[tauri::command]
+pub fn get_file_history(source: String, file_path: String) -> Result<FileHistoryIndex, String> {
+    let path = validate_session_file(&source, &file_path)?;
+    provider::get(&source)?.file_history(&path)
+}
+
+/// Contents of an edited file before and after one message.
+#[tauri::command]
+pub fn get_file_contents_at(
+    source: String,
+    file_path: String,
+    file: String,
+    uuid: String,
+) -> Result<FileContentsAtMessage, String> {
+    let path = validate_session_file(&source, &file_path)?;
+    provider::get(&source)?.file_contents_at(&path, &file, &uuid)
+}
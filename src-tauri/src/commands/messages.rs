@@ -1,7 +1,9 @@
-use std::path::Path;
-
-use session_core::models::message::{PaginatedMessages, RangeMessages};
-use session_core::provider::{claude, codex};
+use session_core::models::message::{
+    BranchMessages, ConversationTree, PaginatedMessages, RangeMessages, SubagentThread,
+};
+use session_core::models::plan::SessionPlan;
+use session_core::paths::validate_session_file;
+use session_core::provider;
 
 #[tauri::command]
 pub fn get_messages(
@@ -11,16 +13,15 @@ pub fn get_messages(
     page_size: usize,
     from_end: Option<bool>,
 ) -> Result<PaginatedMessages, String> {
-    let path = Path::new(&file_path);
-    if !path.exists() {
-        return Err(format!("Session file not found: {}", file_path));
-    }
-
-    match source.as_str() {
-        "claude" => claude::parse_session_messages(path, page, page_size, from_end.unwrap_or(false)),
-        "codex" => codex::parse_session_messages(path, page, page_size, from_end.unwrap_or(false)),
-        _ => Err(format!("Unknown source: {}", source)),
-    }
+    // Validates the file and keeps an Aider `#<n>` session selector intact.
+    let path = validate_session_file(&source, &file_path)?;
+
+    provider::get(&source)?.parse_session_messages(
+        &path,
+        page,
+        page_size,
+        from_end.unwrap_or(false),
+    )
 }
 
 /// Load `[start, end)` of messages. Used by the progressive (windowed)
@@ -33,14 +34,60 @@ pub fn get_messages_range(
     start: usize,
     end: usize,
 ) -> Result<RangeMessages, String> {
-    let path = Path::new(&file_path);
-    if !path.exists() {
-        return Err(format!("Session file not found: {}", file_path));
-    }
-
-    match source.as_str() {
-        "claude" => claude::parse_messages_range(path, start, end),
-        "codex" => codex::parse_messages_range(path, start, end),
-        _ => Err(format!("Unknown source: {}", source)),
-    }
+    let path = validate_session_file(&source, &file_path)?;
+
+    provider::get(&source)?.parse_messages_range(&path, start, end)
+}
+
+/// Load the subagent (sidechain) thread spawned by a Task tool call.
+#[tauri::command]
+pub fn get_subagent_thread(
+    source: String,
+    file_path: String,
+    agent_id: String,
+) -> Result<SubagentThread, String> {
+    let path = validate_session_file(&source, &file_path)?;
+    provider::get(&source)?.parse_subagent_thread(&path, &agent_id)
+}
+
+/// Build the uuid / parentUuid message tree of a session.
+#[tauri::command]
+pub fn get_conversation_tree(
+    source: String,
+    file_path: String,
+) -> Result<ConversationTree, String> {
+    let path = validate_session_file(&source, &file_path)?;
+    provider::get(&source)?.parse_conversation_tree(&path)
+}
+
+/// Final todo list and plan documents of a session.
+#[tauri::command]
+pub fn get_session_plan(source: String, file_path: String) -> Result<SessionPlan, String> {
+    let path = validate_session_file(&source, &file_path)?;
+    provider::get(&source)?.session_plan(&path)
+}
+
+/// Load the branch through one message as a linear message list.
+#[tauri::command]
+pub fn get_branch_messages(
+    source: String,
+    file_path: String,
+    uuid: String,
+) -> Result<BranchMessages, String> {
+    let path = validate_session_file(&source, &file_path)?;
+    provider::get(&source)?.parse_branch_messages(&path, &uuid)
+}
+
+/// Decoded payload of an image / document block, returned as raw bytes
+/// (an `ArrayBuffer` on the JS side) instead of JSON.
+#[tauri::command]
+pub fn get_message_media(
+    source: String,
+    file_path: String,
+    uuid: String,
+    block_index: usize,
+) -> Result<tauri::ipc::Response, String> {
+    let path = validate_session_file(&source, &file_path)?;
+    let media = provider::get(&source)?.read_message_media(&path, &uuid, block_index)?;
+    Ok(tauri::ipc::Response::new(media.data))
 }
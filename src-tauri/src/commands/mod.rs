@@ -1,11 +1,16 @@
 pub mod bookmarks;
 pub mod chat;
+pub mod compare;
+pub mod data_roots;
 pub mod export;
+pub mod file_history;
 pub mod messages;
 pub mod progress;
 pub mod projects;
+pub mod prompt_history;
 pub mod provider_sync;
 pub mod recyclebin;
+pub mod saved_searches;
 pub mod search;
 pub mod sessions;
 pub mod skills;
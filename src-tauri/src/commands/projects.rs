@@ -1,23 +1,15 @@
 use session_core::models::project::ProjectEntry;
-use session_core::provider::{claude, codex};
+use session_core::provider;
 use session_core::provider::claude::{DeleteLevel, DeleteResult};
 
 #[tauri::command]
 pub fn get_projects(source: String) -> Result<Vec<ProjectEntry>, String> {
-    match source.as_str() {
-        "claude" => claude::get_projects(),
-        "codex" => codex::get_projects(),
-        _ => Err(format!("Unknown source: {}", source)),
-    }
+    provider::get(&source)?.get_projects()
 }
 
 #[tauri::command]
 pub fn refresh_projects_cache(source: String) -> Result<Vec<ProjectEntry>, String> {
-    match source.as_str() {
-        "claude" => claude::refresh_projects_cache(),
-        "codex" => codex::get_projects(),
-        _ => Err(format!("Unknown source: {}", source)),
-    }
+    provider::get(&source)?.refresh_projects_cache()
 }
 
 #[tauri::command]
@@ -26,11 +18,7 @@ pub fn delete_project(
     project_id: String,
     level: DeleteLevel,
 ) -> Result<DeleteResult, String> {
-    match source.as_str() {
-        "claude" => claude::delete_project(&project_id, level),
-        "codex" => codex::delete_project(&project_id),
-        _ => Err(format!("Delete project not supported for source: {}", source)),
-    }
+    provider::get(&source)?.delete_project(&project_id, level)
 }
 
 #[tauri::command]
@@ -39,8 +27,5 @@ pub fn set_project_alias(
     project_id: String,
     alias: Option<String>,
 ) -> Result<(), String> {
-    match source.as_str() {
-        "claude" => claude::set_project_alias(&project_id, alias),
-        _ => Err(format!("set_project_alias not supported for source: {}", source)),
-    }
+    provider::get(&source)?.set_project_alias(&project_id, alias)
 }
@@ -0,0 +1,17 @@
+use session_core::models::prompt_history::PromptHistoryPage;
+use session_core::prompt_history::{self, PromptHistoryFilter};
+
+/// Claude Code 输入历史（history.jsonl），按时间倒序分页。
+#[tauri::command]
+pub fn get_prompt_history(
+    query: Option<String>,
+    project: Option<String>,
+    page: Option<usize>,
+    page_size: Option<usize>,
+) -> Result<PromptHistoryPage, String> {
+    let filter = PromptHistoryFilter {
+        query: query.filter(|s| !s.trim().is_empty()),
+        project: project.filter(|s| !s.is_empty()),
+    };
+    prompt_history::get_prompt_history(filter, page.unwrap_or(0), page_size.unwrap_or(100))
+}
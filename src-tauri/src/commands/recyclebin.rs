@@ -1,4 +1,4 @@
-use session_core::provider::claude;
+use session_core::provider;
 use session_core::recyclebin::{self, RecycledItem};
 
 #[tauri::command]
@@ -23,8 +23,5 @@ pub fn empty_recyclebin() -> Result<usize, String> {
 
 #[tauri::command]
 pub fn cleanup_orphan_dirs(source: String) -> Result<usize, String> {
-    match source.as_str() {
-        "claude" => claude::cleanup_all_orphan_dirs(),
-        _ => Err(format!("Orphan dir cleanup not supported for source: {}", source)),
-    }
+    provider::get(&source)?.cleanup_orphan_dirs()
 }
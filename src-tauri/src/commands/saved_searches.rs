@@ -0,0 +1,37 @@
+use session_core::saved_searches::{self, CollectionCount, SavedSearch, SmartCollection};
+
+#[tauri::command]
+pub fn list_saved_searches() -> Result<Vec<SavedSearch>, String> {
+    Ok(saved_searches::list_saved_searches())
+}
+
+#[tauri::command]
+pub async fn add_saved_search(search: SavedSearch) -> Result<SavedSearch, String> {
+    tokio::task::spawn_blocking(move || saved_searches::add_saved_search(search))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub fn remove_saved_search(id: String) -> Result<(), String> {
+    saved_searches::remove_saved_search(&id)
+}
+
+#[tauri::command]
+pub async fn get_collection_counts() -> Result<Vec<CollectionCount>, String> {
+    tokio::task::spawn_blocking(saved_searches::collection_counts)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn open_smart_collection(
+    id: String,
+    mark_viewed: Option<bool>,
+) -> Result<SmartCollection, String> {
+    tokio::task::spawn_blocking(move || {
+        saved_searches::open_collection(&id, mark_viewed.unwrap_or(false))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
@@ -1,4 +1,37 @@
-use session_core::search::{SearchResult, SearchScope};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use session_core::scan_progress;
+use session_core::search::{
+    SearchBatch, SearchOptions, SearchResponse, SearchResult, SearchScope, SearchSink,
+};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Cancel flag of the running streamed search; a newer one sets it.
+pub struct SearchStreamState {
+    pub current: Mutex<Option<Arc<AtomicBool>>>,
+}
+
+impl SearchStreamState {
+    pub fn new() -> Self {
+        Self {
+            current: Mutex::new(None),
+        }
+    }
+}
+
+fn search_options(
+    regex: Option<bool>,
+    case_sensitive: Option<bool>,
+    whole_word: Option<bool>,
+) -> SearchOptions {
+    SearchOptions {
+        regex: regex.unwrap_or(false),
+        case_sensitive: case_sensitive.unwrap_or(false),
+        whole_word: whole_word.unwrap_or(false),
+    }
+}
 
 #[tauri::command]
 pub fn global_search(
@@ -6,11 +39,81 @@ pub fn global_search(
     query: String,
     max_results: usize,
     scope: Option<String>,
-) -> Result<Vec<SearchResult>, String> {
+    regex: Option<bool>,
+    case_sensitive: Option<bool>,
+    whole_word: Option<bool>,
+) -> Result<SearchResponse, String> {
     session_core::search::global_search(
         &source,
         &query,
         max_results,
         SearchScope::from_query(scope.as_deref().unwrap_or("all")),
+        search_options(regex, case_sensitive, whole_word),
     )
 }
+
+/// Like `global_search`, but emits `search-batch` events (tagged with
+/// `search_id`) as results are found, and cancels the previous streamed
+/// search. Returns `None` when this search was itself superseded.
+#[tauri::command]
+pub async fn search_stream(
+    app: AppHandle,
+    search_id: u64,
+    source: String,
+    query: String,
+    max_results: usize,
+    scope: Option<String>,
+    regex: Option<bool>,
+    case_sensitive: Option<bool>,
+    whole_word: Option<bool>,
+) -> Result<Option<SearchResponse>, String> {
+    let cancel = Arc::new(AtomicBool::new(false));
+    let previous = app
+        .state::<SearchStreamState>()
+        .current
+        .lock()
+        .replace(Arc::clone(&cancel));
+    if let Some(previous) = previous {
+        previous.store(true, Ordering::Relaxed);
+    }
+
+    let flag = Arc::clone(&cancel);
+    let result = tokio::task::spawn_blocking(move || {
+        let on_batch = |results: &[SearchResult]| {
+            let _ = app.emit(
+                "search-batch",
+                SearchBatch {
+                    id: search_id,
+                    results: results.to_vec(),
+                    progress: scan_progress::snapshot(),
+                },
+            );
+        };
+        session_core::search::global_search_stream(
+            &source,
+            &query,
+            max_results,
+            SearchScope::from_query(scope.as_deref().unwrap_or("all")),
+            search_options(regex, case_sensitive, whole_word),
+            SearchSink {
+                cancel: &flag,
+                on_batch: &on_batch,
+            },
+        )
+    })
+    .await
+    .map_err(|e| format!("search_stream task failed: {}", e))?;
+
+    if cancel.load(Ordering::Relaxed) {
+        return Ok(None);
+    }
+    result.map(Some)
+}
+
+/// Stop the running streamed search, if any.
+#[tauri::command]
+pub fn cancel_search(app: AppHandle) {
+    if let Some(cancel) = app.state::<SearchStreamState>().current.lock().take() {
+        cancel.store(true, Ordering::Relaxed);
+    }
+}
@@ -2,22 +2,22 @@ use session_core::metadata;
 use session_core::metadata::validate_session_id;
 use session_core::models::session::SessionIndexEntry;
 use session_core::paths::validate_session_file;
-use session_core::provider::{claude, codex};
+use session_core::provider::{self, SessionProvider};
 use session_core::recyclebin;
 
-fn merge_session_metadata(source: &str, project_id: &str, sessions: &mut [SessionIndexEntry]) {
-    let meta = metadata::load_metadata(source, project_id);
+fn merge_session_metadata(
+    provider: &dyn SessionProvider,
+    project_id: &str,
+    sessions: &mut [SessionIndexEntry],
+) {
+    let meta = metadata::load_metadata(provider.id(), project_id);
     for session in sessions {
         if let Some(sm) = meta.sessions.get(&session.session_id) {
-            if source == "claude" {
-                if !sm.tags.is_empty() {
-                    session.tags = Some(sm.tags.clone());
-                }
-            } else {
+            if !provider.stores_alias_in_session_file() {
                 session.alias = sm.alias.clone();
-                if !sm.tags.is_empty() {
-                    session.tags = Some(sm.tags.clone());
-                }
+            }
+            if !sm.tags.is_empty() {
+                session.tags = Some(sm.tags.clone());
             }
         }
     }
@@ -25,13 +25,10 @@ fn merge_session_metadata(source: &str, project_id: &str, sessions: &mut [Sessio
 
 #[tauri::command]
 pub fn get_sessions(source: String, project_id: String) -> Result<Vec<SessionIndexEntry>, String> {
-    let mut sessions = match source.as_str() {
-        "claude" => claude::get_sessions(&project_id)?,
-        "codex" => codex::get_sessions(&project_id)?,
-        _ => return Err(format!("Unknown source: {}", source)),
-    };
+    let provider = provider::get(&source)?;
+    let mut sessions = provider.get_sessions(&project_id)?;
 
-    merge_session_metadata(&source, &project_id, &mut sessions);
+    merge_session_metadata(provider, &project_id, &mut sessions);
 
     Ok(sessions)
 }
@@ -41,13 +38,10 @@ pub fn refresh_sessions_cache(
     source: String,
     project_id: String,
 ) -> Result<Vec<SessionIndexEntry>, String> {
-    let mut sessions = match source.as_str() {
-        "claude" => claude::refresh_sessions_cache(&project_id)?,
-        "codex" => codex::refresh_sessions_cache(&project_id)?,
-        _ => return Err(format!("Unknown source: {}", source)),
-    };
+    let provider = provider::get(&source)?;
+    let mut sessions = provider.refresh_sessions_cache(&project_id)?;
 
-    merge_session_metadata(&source, &project_id, &mut sessions);
+    merge_session_metadata(provider, &project_id, &mut sessions);
 
     Ok(sessions)
 }
@@ -57,13 +51,10 @@ pub fn get_invalid_sessions(
     source: String,
     project_id: String,
 ) -> Result<Vec<SessionIndexEntry>, String> {
-    let mut sessions = match source.as_str() {
-        "claude" => claude::get_invalid_sessions(&project_id)?,
-        "codex" => codex::get_invalid_sessions(&project_id)?,
-        _ => return Err(format!("Unknown source: {}", source)),
-    };
+    let provider = provider::get(&source)?;
+    let mut sessions = provider.get_invalid_sessions(&project_id)?;
 
-    merge_session_metadata(&source, &project_id, &mut sessions);
+    merge_session_metadata(provider, &project_id, &mut sessions);
 
     Ok(sessions)
 }
@@ -85,6 +76,7 @@ pub fn delete_session(
     // to the recycle bin.
     match validate_session_file(&source, &file_path) {
         Ok(path) => {
+            provider::get(&source)?.check_session_deletable(&path)?;
             // File is present → move it to the recycle bin (restorable).
             recyclebin::move_to_recyclebin(
                 &path,
@@ -109,11 +101,7 @@ pub fn delete_session(
 
     // Clean up metadata
     let _ = metadata::remove_session_meta(&source, &project_id, &session_id);
-    if source == "claude" {
-        claude::invalidate_cache();
-    } else if source == "codex" {
-        codex::invalidate_sessions_cache();
-    }
+    provider::get(&source)?.invalidate_cache();
 
     Ok(())
 }
@@ -128,26 +116,23 @@ pub fn update_session_meta(
     file_path: Option<String>,
 ) -> Result<(), String> {
     validate_session_id(&session_id)?;
-    if source == "claude" {
+    let provider = provider::get(&source)?;
+    if provider.stores_alias_in_session_file() {
         // Write alias to JSONL (same format as CC /rename). Only honor the
-        // path if it resolves into the Claude projects directory, so a
+        // path if it resolves into the source's session directory, so a
         // misbehaving caller can't trick us into appending lines to e.g.
         // `~/.bashrc` via the alias write.
         if let Some(ref fp) = file_path {
             let path = validate_session_file(&source, fp)?;
-            session_core::parser::jsonl::append_custom_title(
-                &path,
-                &session_id,
-                alias.as_deref(),
-            )?;
+            provider.write_session_alias(&path, &session_id, alias.as_deref())?;
         }
-        // Only persist tags to metadata (alias is now in JSONL for Claude)
+        // Only persist tags to metadata (alias lives in the session file)
         let result = metadata::update_session_meta(&source, &project_id, &session_id, None, tags);
-        claude::invalidate_cache();
+        provider.invalidate_cache();
         result
     } else {
         let result = metadata::update_session_meta(&source, &project_id, &session_id, alias, tags);
-        codex::invalidate_sessions_cache();
+        provider.invalidate_cache();
         result
     }
 }
@@ -160,11 +145,7 @@ pub fn rename_chat_session(
     alias: Option<String>,
 ) -> Result<(), String> {
     metadata::rename_chat_session(&source, &project_path, &session_id, alias.as_deref())?;
-    if source == "claude" {
-        claude::invalidate_cache();
-    } else if source == "codex" {
-        codex::invalidate_sessions_cache();
-    }
+    provider::get(&source)?.invalidate_cache();
     Ok(())
 }
 
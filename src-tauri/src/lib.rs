@@ -2,6 +2,7 @@ mod commands;
 mod watcher;
 
 use commands::chat::ChatProcessState;
+use commands::search::SearchStreamState;
 use session_core::state::AppState;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -16,6 +17,7 @@ pub fn run() {
         .plugin(tauri_plugin_dialog::init())
         .manage(AppState::new())
         .manage(ChatProcessState::new())
+        .manage(SearchStreamState::new())
         .invoke_handler(tauri::generate_handler![
             commands::projects::get_projects,
             commands::projects::refresh_projects_cache,
@@ -31,10 +33,22 @@ pub fn run() {
             commands::sessions::get_cross_project_tags,
             commands::messages::get_messages,
             commands::messages::get_messages_range,
+            commands::messages::get_subagent_thread,
+            commands::messages::get_conversation_tree,
+            commands::messages::get_branch_messages,
+            commands::messages::get_session_plan,
+            commands::messages::get_message_media,
             commands::export::export_session,
             commands::export::write_export_file,
+            commands::export::export_archive,
+            commands::export::export_dataset,
+            commands::file_history::get_file_history,
+            commands::file_history::get_file_contents_at,
+            commands::compare::compare_sessions,
             commands::progress::get_scan_progress,
             commands::search::global_search,
+            commands::search::search_stream,
+            commands::search::cancel_search,
             commands::skills::list_skills,
             commands::skills::get_skill_content,
             commands::skills::delete_skill,
@@ -43,6 +57,7 @@ pub fn run() {
             commands::stats::get_request_log,
             commands::stats::get_project_costs,
             commands::stats::get_session_cost,
+            commands::prompt_history::get_prompt_history,
             commands::terminal::resume_session,
             commands::terminal::fork_and_resume,
             commands::updater::get_install_type,
@@ -55,6 +70,13 @@ pub fn run() {
             commands::bookmarks::list_bookmarks,
             commands::bookmarks::add_bookmark,
             commands::bookmarks::remove_bookmark,
+            commands::saved_searches::list_saved_searches,
+            commands::saved_searches::add_saved_search,
+            commands::saved_searches::remove_saved_search,
+            commands::saved_searches::get_collection_counts,
+            commands::saved_searches::open_smart_collection,
+            commands::data_roots::get_data_roots,
+            commands::data_roots::set_data_roots,
             commands::recyclebin::list_recycled_items,
             commands::recyclebin::restore_recycled_item,
             commands::recyclebin::permanently_delete_recycled_item,
@@ -72,10 +94,7 @@ pub fn run() {
             app.handle()
                 .plugin(tauri_plugin_updater::Builder::new().build())?;
 
-            let handle = app.handle().clone();
-            if let Err(e) = watcher::fs_watcher::start_watcher(handle) {
-                eprintln!("Warning: Failed to start file watcher: {}", e);
-            }
+            watcher::fs_watcher::start_watcher(app.handle().clone());
             Ok(())
         })
         .run(tauri::generate_context!())
@@ -1,130 +1,110 @@
 use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
 use std::path::PathBuf;
-use std::sync::mpsc;
+use std::sync::mpsc::{self, RecvTimeoutError};
 use std::time::{Duration, Instant};
 use tauri::{AppHandle, Emitter};
 
-use session_core::parser::path_encoder::get_projects_dir;
-use session_core::provider::{claude, codex};
+use session_core::{data_roots, provider};
 
 /// Minimum interval between emitting fs-change events to the frontend.
 const DEBOUNCE_DURATION: Duration = Duration::from_millis(300);
 
-/// Start watching both Claude and Codex directories for changes.
+/// How often the watcher checks whether the data roots were reconfigured.
+const ROOTS_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Every registered provider's session directories.
+fn watch_roots() -> Vec<(&'static dyn provider::SessionProvider, PathBuf)> {
+    provider::all()
+        .iter()
+        .flat_map(|p| p.session_roots().into_iter().map(move |dir| (*p, dir)))
+        .collect()
+}
+
+/// Start watching every registered provider's session directories for changes.
 /// Emits "fs-change" events to the frontend when files are modified.
 /// Events are debounced to avoid flooding the frontend during batch operations.
-pub fn start_watcher(app_handle: AppHandle) -> Result<(), String> {
-    let claude_dir = get_projects_dir();
-    let codex_dir = codex::get_sessions_dir();
-
-    // At least one directory must exist
-    if claude_dir.as_ref().map(|d| d.exists()).unwrap_or(false)
-        || codex_dir.as_ref().map(|d| d.exists()).unwrap_or(false)
-    {
-        // ok, proceed
-    } else {
-        return Err("Neither Claude nor Codex directory exists".to_string());
-    }
+/// The watched directories are rebuilt whenever the data roots change, so
+/// this starts even when none of them exist yet.
+pub fn start_watcher(app_handle: AppHandle) {
+    std::thread::spawn(move || while run_watcher(&app_handle) {});
+}
 
-    std::thread::spawn(move || {
-        let (tx, rx) = mpsc::channel::<notify::Result<Event>>();
+/// Watch the current roots until the watcher fails (`false`) or the data
+/// roots are reconfigured (`true`).
+fn run_watcher(app_handle: &AppHandle) -> bool {
+    let generation = data_roots::generation();
+    let roots = watch_roots();
+    let (tx, rx) = mpsc::channel::<notify::Result<Event>>();
 
-        let mut watcher = match RecommendedWatcher::new(tx, Config::default()) {
-            Ok(w) => w,
-            Err(e) => {
-                eprintln!("Failed to create watcher: {}", e);
-                return;
-            }
-        };
-
-        // Watch Claude projects directory
-        if let Some(ref dir) = claude_dir {
-            if dir.exists() {
-                if let Err(e) = watcher.watch(dir, RecursiveMode::Recursive) {
-                    eprintln!("Failed to watch Claude directory: {}", e);
-                }
-            }
+    let mut watcher = match RecommendedWatcher::new(tx, Config::default()) {
+        Ok(w) => w,
+        Err(e) => {
+            eprintln!("Failed to create watcher: {}", e);
+            return false;
         }
+    };
 
-        // Watch Codex sessions directory
-        if let Some(ref dir) = codex_dir {
-            if dir.exists() {
-                if let Err(e) = watcher.watch(dir, RecursiveMode::Recursive) {
-                    eprintln!("Failed to watch Codex directory: {}", e);
-                }
+    for (provider, dir) in &roots {
+        if dir.exists() {
+            if let Err(e) = watcher.watch(dir, RecursiveMode::Recursive) {
+                eprintln!("Failed to watch {} directory: {}", provider.display_name(), e);
             }
         }
+    }
 
-        let mut last_emit = Instant::now() - DEBOUNCE_DURATION;
-
-        for event in rx {
-            match event {
-                Ok(event) => {
-                    let relevant = event.paths.iter().any(|p| {
-                        let is_meta = p
-                            .file_name()
-                            .map(|n| n == ".session-viewer-meta.json")
-                            .unwrap_or(false);
-                        !is_meta
-                            && p.extension()
-                                .map(|e| e == "jsonl" || e == "json")
-                                .unwrap_or(false)
-                    });
-
-                    if relevant && last_emit.elapsed() >= DEBOUNCE_DURATION {
-                        let is_claude_change = claude_dir.as_ref().map(|dir| {
-                            event.paths.iter().any(|path| path.starts_with(dir))
-                        }).unwrap_or(false);
-                        let is_codex_change = codex_dir.as_ref().map(|dir| {
-                            event.paths.iter().any(|path| path.starts_with(dir))
-                        }).unwrap_or(false);
+    let mut last_emit = Instant::now() - DEBOUNCE_DURATION;
 
-                        // Hand each provider only the paths under its own
-                        // directory, so it can surgically update just the
-                        // affected projects/files instead of wiping everything.
-                        if is_claude_change {
-                            if let Some(ref dir) = claude_dir {
-                                let paths: Vec<PathBuf> = event
-                                    .paths
-                                    .iter()
-                                    .filter(|p| p.starts_with(dir))
-                                    .cloned()
-                                    .collect();
-                                if !paths.is_empty() {
-                                    claude::invalidate_paths(&paths);
-                                }
-                            }
-                        }
-                        if is_codex_change {
-                            if let Some(ref dir) = codex_dir {
-                                let paths: Vec<PathBuf> = event
-                                    .paths
-                                    .iter()
-                                    .filter(|p| p.starts_with(dir))
-                                    .cloned()
-                                    .collect();
-                                if !paths.is_empty() {
-                                    codex::invalidate_paths(&paths);
-                                }
-                            }
-                        }
+    loop {
+        if data_roots::generation() != generation {
+            return true;
+        }
+        let event = match rx.recv_timeout(ROOTS_POLL_INTERVAL) {
+            Ok(event) => event,
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => return false,
+        };
+        match event {
+            Ok(event) => {
+                let relevant = event.paths.iter().any(|p| {
+                    let is_meta = p
+                        .file_name()
+                        .map(|n| n == ".session-viewer-meta.json")
+                        .unwrap_or(false);
+                    !is_meta
+                        && roots.iter().any(|(provider, dir)| {
+                            p.starts_with(dir) && provider.is_watched_file(p)
+                        })
+                });
 
-                        let paths: Vec<String> = event
+                if relevant && last_emit.elapsed() >= DEBOUNCE_DURATION {
+                    // Hand each provider only the paths under its own
+                    // directory, so it can surgically update just the
+                    // affected projects/files instead of wiping everything.
+                    for (provider, dir) in &roots {
+                        let paths: Vec<PathBuf> = event
                             .paths
                             .iter()
-                            .map(|p| p.to_string_lossy().to_string())
+                            .filter(|p| p.starts_with(dir))
+                            .cloned()
                             .collect();
-
-                        let _ = app_handle.emit("fs-change", paths);
-                        last_emit = Instant::now();
+                        if !paths.is_empty() {
+                            provider.invalidate_paths(&paths);
+                        }
                     }
-                }
-                Err(e) => {
-                    eprintln!("Watch error: {}", e);
+
+                    let paths: Vec<String> = event
+                        .paths
+                        .iter()
+                        .map(|p| p.to_string_lossy().to_string())
+                        .collect();
+
+                    let _ = app_handle.emit("fs-change", paths);
+                    last_emit = Instant::now();
                 }
             }
+            Err(e) => {
+                eprintln!("Watch error: {}", e);
+            }
         }
-    });
-
-    Ok(())
+    }
 }
@@ -39,6 +39,10 @@ const BookmarksPage = lazy(async () => {
   const module = await import("./components/bookmark/BookmarksPage");
   return { default: module.BookmarksPage };
 });
+const CollectionsPage = lazy(async () => {
+  const module = await import("./components/collection/CollectionsPage");
+  return { default: module.CollectionsPage };
+});
 const RecyclebinPage = lazy(async () => {
   const module = await import("./components/recyclebin/RecyclebinPage");
   return { default: module.RecyclebinPage };
@@ -139,6 +143,14 @@ function App() {
             </LazyRoute>
           }
         />
+        <Route
+          path="/collections"
+          element={
+            <LazyRoute>
+              <CollectionsPage />
+            </LazyRoute>
+          }
+        />
         <Route
           path="/cleanup"
           element={
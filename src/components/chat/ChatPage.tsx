@@ -351,9 +351,10 @@ export function ChatPage({ paneId = DEFAULT_CHAT_PANE_ID }: ChatPageProps) {
     setActivePane(paneId);
   }, [paneId, setActivePane]);
 
-  // Sync source from appStore into the target pane
+  // Sync source from appStore into the target pane. Only Claude and Codex have
+  // a chat CLI; other sources leave the pane on its last CLI.
   useEffect(() => {
-    setPaneSource(paneId, appSource);
+    if (appSource === "claude" || appSource === "codex") setPaneSource(paneId, appSource);
   }, [appSource, paneId, setPaneSource]);
 
   // Detect CLI on mount + fetch config & model list
@@ -0,0 +1,207 @@
+import { useCallback, useEffect, useState } from "react";
+import { useNavigate } from "react-router-dom";
+import { useAppStore } from "../../stores/appStore";
+import { api } from "../../services/api";
+import type { CollectionCount, CollectionSession, SavedSearch, SmartCollection } from "../../types";
+import { isSessionSource } from "../../utils/sessionSource";
+import { Layers, Trash2, MessageSquare, Loader2 } from "lucide-react";
+
+const SCOPE_LABELS: Record<SavedSearch["scope"], string> = {
+  all: "所有",
+  content: "session 内容",
+  session: "会话名称",
+  tags: "标签",
+};
+
+const getSourceLabel = (s: string) =>
+  ({ all: "所有来源", claude: "Claude", codex: "Codex", gemini: "Gemini", aider: "Aider" })[s] ?? s;
+
+export function CollectionsPage() {
+  const navigate = useNavigate();
+  const { source, setSource } = useAppStore();
+  const [searches, setSearches] = useState<SavedSearch[]>([]);
+  const [loading, setLoading] = useState(true);
+  const [counts, setCounts] = useState<Record<string, CollectionCount>>({});
+  const [selectedId, setSelectedId] = useState<string | null>(null);
+  const [collection, setCollection] = useState<SmartCollection | null>(null);
+  const [collectionLoading, setCollectionLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    let cancelled = false;
+    api
+      .listSavedSearches()
+      .then((list) => {
+        if (!cancelled) setSearches(list);
+      })
+      .catch((e) => console.error("Failed to load saved searches:", e))
+      .finally(() => {
+        if (!cancelled) setLoading(false);
+      });
+    // Counts re-run every saved search, so they fill in after the list.
+    api
+      .getCollectionCounts()
+      .then((list) => {
+        if (!cancelled) setCounts(Object.fromEntries(list.map((c) => [c.id, c])));
+      })
+      .catch((e) => console.error("Failed to load collection counts:", e));
+    return () => {
+      cancelled = true;
+    };
+  }, []);
+
+  const openCollection = useCallback(async (id: string) => {
+    setSelectedId(id);
+    setCollectionLoading(true);
+    setError(null);
+    try {
+      const opened = await api.openSmartCollection(id, true);
+      setCollection(opened);
+      // Viewing it marks everything listed as seen.
+      setCounts((prev) => ({
+        ...prev,
+        [id]: { id, totalSessions: opened.totalSessions, newCount: 0 },
+      }));
+    } catch (e) {
+      setCollection(null);
+      setError(e instanceof Error ? e.message : String(e));
+    } finally {
+      setCollectionLoading(false);
+    }
+  }, []);
+
+  const handleRemove = async (id: string) => {
+    try {
+      await api.removeSavedSearch(id);
+      setSearches((prev) => prev.filter((s) => s.id !== id));
+      if (selectedId === id) {
+        setSelectedId(null);
+        setCollection(null);
+      }
+    } catch (e) {
+      console.error("Failed to remove saved search:", e);
+    }
+  };
+
+  const openSession = (session: CollectionSession) => {
+    const params = new URLSearchParams();
+    if (session.matchedMessageId) params.set("scrollTo", session.matchedMessageId);
+    const suffix = params.toString() ? `?${params.toString()}` : "";
+    if (session.source !== source && isSessionSource(session.source)) {
+      setSource(session.source);
+    }
+    navigate(
+      `/projects/${encodeURIComponent(session.projectId)}/session/${encodeURIComponent(session.filePath)}${suffix}`,
+    );
+  };
+
+  return (
+    <div className="p-6">
+      <div className="flex items-center gap-2 mb-6">
+        <Layers className="w-5 h-5 text-primary" />
+        <h1 className="text-2xl font-bold">智能合集</h1>
+        <span className="text-sm text-muted-foreground">({searches.length})</span>
+      </div>
+
+      {loading ? (
+        <div className="text-muted-foreground">加载保存的搜索...</div>
+      ) : searches.length === 0 ? (
+        <div className="text-muted-foreground">
+          暂无保存的搜索。在全局搜索页设置好查询、范围和标签后点击「保存搜索」即可创建。
+        </div>
+      ) : (
+        <div className="flex gap-6 items-start">
+          <div className="w-72 shrink-0 space-y-1.5">
+            {searches.map((s) => {
+              const count = counts[s.id];
+              return (
+                <div
+                  key={s.id}
+                  onClick={() => openCollection(s.id)}
+                  className={`bg-card border rounded-lg p-3 transition-all cursor-pointer group ${
+                    selectedId === s.id
+                      ? "border-primary"
+                      : "border-border hover:border-primary/50 hover:bg-accent/30"
+                  }`}
+                >
+                  <div className="flex items-center gap-2">
+                    <span className="text-sm font-medium truncate flex-1">{s.name}</span>
+                    {count && count.newCount > 0 && (
+                      <span className="text-xs bg-primary/15 text-primary px-1.5 py-0.5 rounded-full shrink-0">
+                        +{count.newCount} 新
+                      </span>
+                    )}
+                    <button
+                      onClick={(e) => {
+                        e.stopPropagation();
+                        handleRemove(s.id);
+                      }}
+                      className="p-1 rounded-md text-transparent group-hover:text-muted-foreground hover:!text-destructive transition-colors shrink-0"
+                      title="删除保存的搜索"
+                    >
+                      <Trash2 className="w-3.5 h-3.5" />
+                    </button>
+                  </div>
+                  <p className="mt-1 text-xs font-mono text-muted-foreground truncate">
+                    {s.query || s.tags.map((t) => `tag:${t}`).join(" ")}
+                  </p>
+                  <div className="flex items-center gap-2 mt-1 text-xs text-muted-foreground">
+                    <span>{getSourceLabel(s.source)}</span>
+                    <span>{SCOPE_LABELS[s.scope] ?? s.scope}</span>
+                    {count && <span className="ml-auto">{count.totalSessions} 个会话</span>}
+                  </div>
+                </div>
+              );
+            })}
+          </div>
+
+          <div className="flex-1 min-w-0">
+            {collectionLoading ? (
+              <div className="flex items-center gap-2 text-muted-foreground">
+                <Loader2 className="w-4 h-4 animate-spin" />
+                正在执行搜索...
+              </div>
+            ) : error ? (
+              <div className="text-sm text-destructive">{error}</div>
+            ) : collection ? (
+              <div className="space-y-1.5">
+                <p className="text-sm text-muted-foreground mb-3">
+                  {collection.totalSessions} 个会话
+                  {collection.truncated && <span>，仅列出最相关的 {collection.sessions.length} 个</span>}
+                  {collection.newCount > 0 && <span>，其中 {collection.newCount} 个是上次查看后新增的</span>}
+                </p>
+                {collection.sessions.map((session) => (
+                  <div
+                    key={session.filePath}
+                    onClick={() => openSession(session)}
+                    className="bg-card border border-border rounded-lg p-3 hover:border-primary/50 hover:bg-accent/30 transition-all cursor-pointer"
+                  >
+                    <div className="flex items-center gap-2">
+                      <MessageSquare className="w-3.5 h-3.5 text-muted-foreground shrink-0" />
+                      <span className="text-sm font-medium truncate flex-1">
+                        {session.alias || session.threadName || session.firstPrompt || "（无标题）"}
+                      </span>
+                      {session.isNew && (
+                        <span className="text-xs bg-primary/15 text-primary px-1.5 py-0.5 rounded shrink-0">新</span>
+                      )}
+                    </div>
+                    <div className="flex items-center gap-2 mt-1 text-xs text-muted-foreground">
+                      <span className="px-2 py-0.5 bg-muted rounded">{session.projectName}</span>
+                      {collection.search.source === "all" && <span>{getSourceLabel(session.source)}</span>}
+                      <span className="truncate flex-1">{session.matchedText}</span>
+                      {session.timestamp && (
+                        <span className="shrink-0">{new Date(session.timestamp).toLocaleDateString()}</span>
+                      )}
+                    </div>
+                  </div>
+                ))}
+              </div>
+            ) : (
+              <div className="text-muted-foreground">选择左侧保存的搜索，查看当前命中的会话。</div>
+            )}
+          </div>
+        </div>
+      )}
+    </div>
+  );
+}
@@ -11,7 +11,7 @@ import { useFileWatcher } from "../../hooks/useFileWatcher";
 import { UpdateIndicator } from "./UpdateIndicator";
 import { ProjectActionsMenu } from "../project/ProjectActionsMenu";
 import { DeleteProjectDialog } from "../project/DeleteProjectDialog";
-import type { ProjectEntry } from "../../types";
+import type { ProjectEntry, SessionSource } from "../../types";
 import { collapseDirectBuckets, DIRECT_GROUP_ID } from "../../utils/directChat";
 import { open as openFileDialog } from "@tauri-apps/plugin-dialog";
 import {
@@ -40,9 +40,12 @@ import {
   Loader2,
   AlertCircle,
   Star,
+  Layers,
   FolderX,
   Repeat,
   Sparkles,
+  Gem,
+  Wrench,
 } from "lucide-react";
 
 declare const __IS_TAURI__: boolean;
@@ -109,7 +112,7 @@ export function Sidebar() {
     location.pathname === "/direct-chat" ||
     location.pathname.startsWith(`/projects/${encodeURIComponent("<codex-direct>/")}`);
 
-  const handleSourceChange = (s: "claude" | "codex") => {
+  const handleSourceChange = (s: SessionSource) => {
     if (s !== source) {
       setSource(s);
       navigate("/projects");
@@ -124,10 +127,10 @@ export function Sidebar() {
           AI Session Viewer
         </h1>
         {/* Source Tabs */}
-        <div className="flex rounded-lg bg-muted p-0.5">
+        <div className="grid grid-cols-2 gap-0.5 rounded-lg bg-muted p-0.5">
           <button
             onClick={() => handleSourceChange("claude")}
-            className={`flex-1 flex items-center justify-center gap-1.5 px-3 py-1.5 rounded-md text-xs font-medium transition-all ${
+            className={`flex-1 flex items-center justify-center gap-1 px-2 py-1.5 rounded-md text-xs font-medium transition-all ${
               source === "claude"
                 ? "bg-orange-500/20 text-orange-400 shadow-sm"
                 : "text-muted-foreground hover:text-foreground"
@@ -138,7 +141,7 @@ export function Sidebar() {
           </button>
           <button
             onClick={() => handleSourceChange("codex")}
-            className={`flex-1 flex items-center justify-center gap-1.5 px-3 py-1.5 rounded-md text-xs font-medium transition-all ${
+            className={`flex-1 flex items-center justify-center gap-1 px-2 py-1.5 rounded-md text-xs font-medium transition-all ${
               source === "codex"
                 ? "bg-green-500/20 text-green-400 shadow-sm"
                 : "text-muted-foreground hover:text-foreground"
@@ -147,6 +150,28 @@ export function Sidebar() {
             <Terminal className="w-3.5 h-3.5" />
             Codex
           </button>
+          <button
+            onClick={() => handleSourceChange("gemini")}
+            className={`flex-1 flex items-center justify-center gap-1 px-2 py-1.5 rounded-md text-xs font-medium transition-all ${
+              source === "gemini"
+                ? "bg-blue-500/20 text-blue-400 shadow-sm"
+                : "text-muted-foreground hover:text-foreground"
+            }`}
+          >
+            <Gem className="w-3.5 h-3.5" />
+            Gemini
+          </button>
+          <button
+            onClick={() => handleSourceChange("aider")}
+            className={`flex-1 flex items-center justify-center gap-1 px-2 py-1.5 rounded-md text-xs font-medium transition-all ${
+              source === "aider"
+                ? "bg-purple-500/20 text-purple-400 shadow-sm"
+                : "text-muted-foreground hover:text-foreground"
+            }`}
+          >
+            <Wrench className="w-3.5 h-3.5" />
+            Aider
+          </button>
         </div>
       </div>
 
@@ -217,6 +242,17 @@ export function Sidebar() {
               </span>
             )}
           </button>
+          <button
+            onClick={() => navigate("/collections")}
+            className={`w-full flex items-center gap-2 px-3 py-2 rounded-md text-sm transition-colors ${
+              isActive("/collections")
+                ? "bg-accent text-accent-foreground"
+                : "text-muted-foreground hover:bg-accent/50 hover:text-foreground"
+            }`}
+          >
+            <Layers className="w-4 h-4" />
+            智能合集
+          </button>
           <button
             onClick={() => navigate("/cleanup")}
             className={`w-full flex items-center gap-2 px-3 py-2 rounded-md text-sm transition-colors ${
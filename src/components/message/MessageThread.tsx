@@ -6,6 +6,7 @@ import { ToolOutputMessage } from "./ToolOutputMessage";
 import { useAppStore } from "../../stores/appStore";
 import { Star, GitFork, Play, Loader2, ChevronDown, ChevronRight } from "lucide-react";
 import { api } from "../../services/api";
+import { sourceLabel } from "../../utils/sessionSource";
 import { useExpandAllControl } from "../common/ExpandAllContext";
 import {
   buildMessageTree,
@@ -206,7 +207,7 @@ function getThreadLineText(node: ThreadDisplayNode, source: string): string {
   const title = node.threadTitle.trim();
 
   if (node.message.role === "assistant") {
-    const assistantName = source === "claude" ? "Claude" : "Codex";
+    const assistantName = sourceLabel(source);
     return title ? `${assistantName} · ${title}` : assistantName;
   }
 
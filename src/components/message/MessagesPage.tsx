@@ -18,14 +18,14 @@ import { api } from "../../services/api";
 import { subscribeToChatWebSocketMessages } from "../../services/webApi";
 import { SessionMetaEditor } from "../session/SessionMetaEditor";
 import { ScrollArea } from "../ScrollArea";
-import type { DisplayMessage, SessionIndexEntry } from "../../types";
+import type { DisplayMessage, SessionIndexEntry, SessionSource } from "../../types";
 import type { ChatMessage } from "../../types/chat";
 import { ExpandAllProvider } from "../common/ExpandAllContext";
 import { useReplyNotification } from "../../hooks/useReplyNotification";
 import { SessionCostBadge } from "./SessionCostBadge";
+import { sourceLabel } from "../../utils/sessionSource";
 
 declare const __IS_TAURI__: boolean;
-type MessageSource = "claude" | "codex";
 type SplitDirection = "horizontal" | "vertical";
 
 const SPLIT_PANE_MESSAGES_PAGE_SIZE = 50;
@@ -450,7 +450,7 @@ export function MessagesPage() {
   // Sync source from appStore into chatStore, then refresh model list
   useEffect(() => {
     setActivePane(mainPaneId);
-    setPaneSource(mainPaneId, source);
+    if (source === "claude" || source === "codex") setPaneSource(mainPaneId, source);
     fetchChatModelList(mainPaneId);
   }, [fetchChatModelList, mainPaneId, setActivePane, setPaneSource, source]);
 
@@ -1556,7 +1556,7 @@ function SplitSessionPane({
   splitDirection,
   onClose,
 }: {
-  source: MessageSource;
+  source: SessionSource;
   filePath: string;
   showTimestamp: boolean;
   showModel: boolean;
@@ -1661,7 +1661,7 @@ function SplitSessionPane({
   }, [loadMessages]);
 
   useEffect(() => {
-    setPaneSource(paneId, source);
+    if (source === "claude" || source === "codex") setPaneSource(paneId, source);
   }, [paneId, setPaneSource, source]);
 
   useEffect(() => {
@@ -1929,8 +1929,8 @@ const ChatMessagesBlock = memo(function ChatMessagesBlock({
   prevProps.onSubmitAnswers === nextProps.onSubmitAnswers
 ));
 
-function assistantNameFromSource(source: MessageSource) {
-  return source === "codex" ? "Codex" : "Claude";
+function assistantNameFromSource(source: SessionSource) {
+  return sourceLabel(source);
 }
 
 function extractUserQuestionPreview(message: DisplayMessage) {
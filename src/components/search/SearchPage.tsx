@@ -1,9 +1,13 @@
 import { useState, useCallback, useRef, useEffect, useMemo } from "react";
 import { useNavigate } from "react-router-dom";
 import { useAppStore } from "../../stores/appStore";
+import { api } from "../../services/api";
+import type { MatchRange, SearchOptions } from "../../types";
+import { isSessionSource } from "../../utils/sessionSource";
 import {
   Search,
   Loader2,
+  BookmarkPlus,
   MessageSquare,
   MessagesSquare,
   Tag,
@@ -15,6 +19,12 @@ import {
 type SearchMode = "messages" | "sessions";
 type SearchScope = "all" | "content" | "session" | "tags";
 
+const SEARCH_MATCH_OPTIONS: Array<{ key: keyof SearchOptions; label: string; title: string }> = [
+  { key: "caseSensitive", label: "Aa", title: "区分大小写" },
+  { key: "wholeWord", label: "ab", title: "全字匹配" },
+  { key: "regex", label: ".*", title: "正则表达式（含空格的表达式请加引号）" },
+];
+
 const SEARCH_SCOPE_OPTIONS: Array<{ key: SearchScope; label: string }> = [
   { key: "all", label: "所有" },
   { key: "content", label: "session 内容" },
@@ -27,10 +37,21 @@ export function SearchPage() {
   const {
     source,
     searchResults,
+    searchTotalMatches,
+    searchTotalSessions,
+    searchQuery,
+    searchQueryError,
+    searchHighlightTerms,
     searchLoading,
+    searchProgress,
     search,
     searchScope,
     setSearchScope,
+    searchOptions,
+    setSearchOptions,
+    searchAllSources,
+    setSearchAllSources,
+    setSource,
     crossProjectTags,
     globalTagFilter,
     loadCrossProjectTags,
@@ -40,6 +61,10 @@ export function SearchPage() {
   const [searchMode, setSearchMode] = useState<SearchMode>("messages");
   const debounceRef = useRef<ReturnType<typeof setTimeout>>(null);
   const [copiedFilePath, setCopiedFilePath] = useState<string | null>(null);
+  /** Name being typed for "保存搜索"; null while the form is closed. */
+  const [saveName, setSaveName] = useState<string | null>(null);
+  const [saveError, setSaveError] = useState<string | null>(null);
+  const [savedName, setSavedName] = useState<string | null>(null);
   const copyTimerRef = useRef<ReturnType<typeof setTimeout> | null>(null);
 
   const handleCopySessionName = (e: React.MouseEvent, filePath: string, name: string) => {
@@ -63,6 +88,28 @@ export function SearchPage() {
     }
   }, [source, crossProjectTags, loadCrossProjectTags]);
 
+  const handleSaveSearch = async () => {
+    const name = saveName?.trim();
+    if (!name) return;
+    try {
+      await api.addSavedSearch({
+        name,
+        source: searchAllSources ? "all" : source,
+        query: searchQuery,
+        scope: searchScope,
+        regex: !!searchOptions.regex,
+        caseSensitive: !!searchOptions.caseSensitive,
+        wholeWord: !!searchOptions.wholeWord,
+        tags: globalTagFilter,
+      });
+      setSaveName(null);
+      setSaveError(null);
+      setSavedName(name);
+    } catch (e) {
+      setSaveError(e instanceof Error ? e.message : String(e));
+    }
+  };
+
   const handleSearch = useCallback(
     (value: string) => {
       setQuery(value);
@@ -74,19 +121,24 @@ export function SearchPage() {
     [search]
   );
 
-  const highlightMatch = (text: string, q: string) => {
-    if (!q) return text;
-    const idx = text.toLowerCase().indexOf(q.toLowerCase());
-    if (idx === -1) return text;
-    return (
-      <>
-        {text.slice(0, idx)}
-        <mark className="bg-yellow-500/30 text-foreground rounded px-0.5">
-          {text.slice(idx, idx + q.length)}
-        </mark>
-        {text.slice(idx + q.length)}
-      </>
-    );
+  // Ranges come from the server, computed on the full message, so regex and
+  // whole-word matches are marked exactly as they matched.
+  const highlightMatch = (text: string, ranges: MatchRange[]) => {
+    if (ranges.length === 0) return text;
+    const chars = Array.from(text);
+    const parts: React.ReactNode[] = [];
+    let pos = 0;
+    ranges.forEach((range, i) => {
+      parts.push(chars.slice(pos, range.charStart).join(""));
+      parts.push(
+        <mark key={i} className="bg-yellow-500/30 text-foreground rounded px-0.5">
+          {chars.slice(range.charStart, range.charEnd).join("")}
+        </mark>,
+      );
+      pos = range.charEnd;
+    });
+    parts.push(chars.slice(pos).join(""));
+    return <>{parts}</>;
   };
 
   const buildSessionLink = (
@@ -101,23 +153,43 @@ export function SearchPage() {
       params.set("scrollTo", matchedMessageId);
       params.set("matchedOnly", "1");
     }
-    if (query.trim()) {
-      params.set("searchQuery", query.trim());
+    // The session view searches plain text, so hand it the longest term
+    // rather than the filter syntax.
+    const term = searchHighlightTerms.reduce((a, b) => (b.length > a.length ? b : a), "");
+    if (term) {
+      params.set("searchQuery", term);
     }
     const suffix = params.toString() ? `?${params.toString()}` : "";
     return `/projects/${encodedProjectId}/session/${encodedFilePath}${suffix}`;
   };
 
+  // Results from another source open under that source.
+  const openResult = (
+    resultSource: string,
+    projectId: string,
+    filePath: string,
+    matchedMessageId?: string | null,
+  ) => {
+    const link = buildSessionLink(projectId, filePath, matchedMessageId);
+    if (resultSource !== source && isSessionSource(resultSource)) {
+      setSource(resultSource);
+    }
+    navigate(link);
+  };
+
   const handleResultClick = (result: (typeof searchResults)[0]) => {
-    navigate(buildSessionLink(result.projectId, result.filePath, result.matchedMessageId));
+    openResult(result.source, result.projectId, result.filePath, result.matchedMessageId);
   };
 
-  const getRoleLabel = (role: string) => {
+  const getSourceLabel = (s: string) =>
+    ({ claude: "Claude", codex: "Codex", gemini: "Gemini", aider: "Aider" })[s] ?? s;
+
+  const getRoleLabel = (role: string, resultSource: string) => {
     if (role === "user") return "用户";
     if (role === "tool") return "Tool";
     if (role === "session") return "会话名";
     if (role === "tag") return "标签";
-    return source === "codex" ? "Codex" : "Claude";
+    return getSourceLabel(resultSource);
   };
 
   const allGlobalTags = useMemo(() => {
@@ -148,6 +220,7 @@ export function SearchPage() {
   const groupedSessions = useMemo(() => {
     if (searchMode !== "sessions") return [];
     const groups = new Map<string, {
+      source: string;
       projectId: string;
       projectName: string;
       alias: string | null;
@@ -156,8 +229,9 @@ export function SearchPage() {
       tags: string[] | null;
       filePath: string;
       matchCount: number;
+      score: number;
       latestTimestamp: string;
-      matchedTexts: string[];
+      matchedTexts: Array<{ text: string; ranges: MatchRange[] }>;
       totalMessageCount: number;
       firstMatchedMessageId: string | null;
     }>();
@@ -166,17 +240,19 @@ export function SearchPage() {
       const existing = groups.get(r.filePath);
       if (existing) {
         existing.matchCount++;
+        existing.score = Math.max(existing.score, r.score);
         if (r.timestamp && r.timestamp > existing.latestTimestamp) {
           existing.latestTimestamp = r.timestamp;
         }
         if (existing.matchedTexts.length < 3) {
-          existing.matchedTexts.push(r.matchedText);
+          existing.matchedTexts.push({ text: r.matchedText, ranges: r.matchRanges });
         }
         if (!existing.firstMatchedMessageId && r.matchedMessageId) {
           existing.firstMatchedMessageId = r.matchedMessageId;
         }
       } else {
         groups.set(r.filePath, {
+          source: r.source,
           projectId: r.projectId,
           projectName: r.projectName,
           alias: r.alias,
@@ -185,16 +261,18 @@ export function SearchPage() {
           tags: r.tags,
           filePath: r.filePath,
           matchCount: 1,
+          score: r.score,
           latestTimestamp: r.timestamp || "",
-          matchedTexts: [r.matchedText],
+          matchedTexts: [{ text: r.matchedText, ranges: r.matchRanges }],
           totalMessageCount: r.totalMessageCount,
           firstMatchedMessageId: r.matchedMessageId,
         });
       }
     }
 
+    // Most relevant session first (its best match), then most recent.
     return Array.from(groups.values()).sort(
-      (a, b) => b.latestTimestamp.localeCompare(a.latestTimestamp)
+      (a, b) => b.score - a.score || b.latestTimestamp.localeCompare(a.latestTimestamp)
     );
   }, [filteredResults, searchMode]);
 
@@ -208,15 +286,37 @@ export function SearchPage() {
           type="text"
           value={query}
           onChange={(e) => handleSearch(e.target.value)}
-          placeholder="搜索所有会话内容..."
+          placeholder="搜索所有会话内容，支持 tool:Bash role:user branch:main after:2026-01-01 -排除 &quot;短语&quot;"
           className="w-full pl-10 pr-4 py-2.5 bg-card border border-border rounded-lg text-sm focus:outline-none focus:ring-2 focus:ring-ring placeholder:text-muted-foreground"
           autoFocus
         />
         {searchLoading && (
-          <Loader2 className="absolute right-3 top-1/2 -translate-y-1/2 w-4 h-4 animate-spin text-muted-foreground" />
+          <div className="absolute right-3 top-1/2 -translate-y-1/2 flex items-center gap-1.5 text-xs text-muted-foreground tabular-nums">
+            {searchProgress?.active && searchProgress.total > 0 && (
+              <span>
+                {searchProgress.phase} {searchProgress.scanned} / {searchProgress.total}
+              </span>
+            )}
+            <Loader2 className="w-4 h-4 animate-spin" />
+          </div>
         )}
       </div>
 
+      {searchQueryError && (
+        <div className="mb-4 -mt-2 text-xs text-destructive">
+          <span>查询语法错误：{searchQueryError.message}</span>
+          {searchQueryError.end > searchQueryError.start && (
+            <span className="ml-2 font-mono">
+              {Array.from(searchQuery).slice(0, searchQueryError.start).join("")}
+              <mark className="bg-destructive/20 text-destructive rounded px-0.5">
+                {Array.from(searchQuery).slice(searchQueryError.start, searchQueryError.end).join("")}
+              </mark>
+              {Array.from(searchQuery).slice(searchQueryError.end).join("")}
+            </span>
+          )}
+        </div>
+      )}
+
       <div className="flex flex-wrap items-center gap-2 mb-4">
         <div className="flex items-center gap-1 rounded-lg bg-muted p-0.5">
           <button
@@ -262,6 +362,93 @@ export function SearchPage() {
             </button>
           ))}
         </div>
+
+        <button
+          onClick={() => setSearchAllSources(!searchAllSources)}
+          title="同时搜索所有来源（Claude、Codex 等）"
+          className={`px-3 py-1.5 text-xs rounded-lg border border-border transition-colors ${
+            searchAllSources
+              ? "bg-primary text-primary-foreground"
+              : "bg-card text-muted-foreground hover:text-foreground hover:bg-accent"
+          }`}
+        >
+          所有来源
+        </button>
+
+        <div className="flex items-center gap-1 rounded-lg border border-border bg-card px-1 py-1">
+          {SEARCH_MATCH_OPTIONS.map((option) => (
+            <button
+              key={option.key}
+              title={option.title}
+              onClick={() => setSearchOptions({ ...searchOptions, [option.key]: !searchOptions[option.key] })}
+              className={`px-2 py-1 text-xs font-mono rounded-md transition-colors ${
+                option.key === "wholeWord" ? "underline underline-offset-2" : ""
+              } ${
+                searchOptions[option.key]
+                  ? "bg-primary text-primary-foreground"
+                  : "text-muted-foreground hover:text-foreground hover:bg-accent"
+              }`}
+            >
+              {option.label}
+            </button>
+          ))}
+        </div>
+
+        {(searchQuery.trim() || globalTagFilter.length > 0) &&
+          (saveName === null ? (
+            <button
+              onClick={() => {
+                setSaveName("");
+                setSaveError(null);
+                setSavedName(null);
+              }}
+              title="保存当前查询、范围、来源和标签，之后可在「智能合集」中查看新增的匹配会话"
+              className="inline-flex items-center gap-1 px-3 py-1.5 text-xs rounded-lg border border-border bg-card text-muted-foreground hover:text-foreground hover:bg-accent transition-colors"
+            >
+              <BookmarkPlus className="w-3.5 h-3.5" />
+              保存搜索
+            </button>
+          ) : (
+            <form
+              onSubmit={(e) => {
+                e.preventDefault();
+                void handleSaveSearch();
+              }}
+              className="flex items-center gap-1"
+            >
+              <input
+                type="text"
+                value={saveName}
+                onChange={(e) => setSaveName(e.target.value)}
+                placeholder="合集名称"
+                className="w-36 px-2 py-1 bg-card border border-border rounded-md text-xs focus:outline-none focus:ring-2 focus:ring-ring"
+                autoFocus
+              />
+              <button
+                type="submit"
+                disabled={!saveName.trim()}
+                className="px-2 py-1 text-xs rounded-md bg-primary text-primary-foreground disabled:opacity-50"
+              >
+                保存
+              </button>
+              <button
+                type="button"
+                onClick={() => setSaveName(null)}
+                className="px-2 py-1 text-xs rounded-md text-muted-foreground hover:text-foreground hover:bg-accent"
+              >
+                取消
+              </button>
+            </form>
+          ))}
+        {saveError && <span className="text-xs text-destructive">{saveError}</span>}
+        {savedName && (
+          <button
+            onClick={() => navigate("/collections")}
+            className="text-xs text-primary hover:underline"
+          >
+            已保存「{savedName}」，查看智能合集
+          </button>
+        )}
       </div>
 
       {allGlobalTags.length > 0 && (
@@ -299,6 +486,9 @@ export function SearchPage() {
               {globalTagFilter.length > 0 && searchResults.length !== filteredResults.length && (
                 <span>（共 {searchResults.length} 条，已按标签筛选）</span>
               )}
+              {globalTagFilter.length === 0 && searchTotalMatches > searchResults.length && (
+                <span>（共 {searchTotalMatches} 条匹配，仅显示前 {searchResults.length} 条）</span>
+              )}
             </p>
             {filteredResults.map((result, i) => {
               const sessionTitle = result.alias || result.threadName || result.firstPrompt || "（无标题）";
@@ -313,8 +503,13 @@ export function SearchPage() {
                       {result.projectName}
                     </span>
                     <span className="text-xs text-muted-foreground">
-                      {getRoleLabel(result.role)}
+                      {getRoleLabel(result.role, result.source)}
                     </span>
+                    {searchAllSources && (
+                      <span className="text-xs px-2 py-0.5 border border-border rounded text-muted-foreground">
+                        {getSourceLabel(result.source)}
+                      </span>
+                    )}
                     <span className="text-xs px-2 py-0.5 bg-primary/15 text-primary rounded font-medium">
                       共 {result.totalMessageCount} 条消息
                     </span>
@@ -372,7 +567,7 @@ export function SearchPage() {
                   )}
 
                   <p className="text-sm font-mono whitespace-pre-wrap break-all">
-                    {highlightMatch(result.matchedText, query)}
+                    {highlightMatch(result.matchedText, result.matchRanges)}
                   </p>
                 </div>
               );
@@ -385,6 +580,9 @@ export function SearchPage() {
               {globalTagFilter.length > 0 && searchResults.length !== filteredResults.length && (
                 <span>（已按标签筛选）</span>
               )}
+              {globalTagFilter.length === 0 && searchTotalSessions > groupedSessions.length && (
+                <span>，全部匹配共 {searchTotalSessions} 个会话</span>
+              )}
             </p>
             {groupedSessions.map((session) => {
               const title = session.alias || session.threadName || session.firstPrompt || "（无标题）";
@@ -392,12 +590,11 @@ export function SearchPage() {
                 <div
                   key={session.filePath}
                   onClick={() => {
-                    navigate(
-                      buildSessionLink(
-                        session.projectId,
-                        session.filePath,
-                        session.firstMatchedMessageId
-                      )
+                    openResult(
+                      session.source,
+                      session.projectId,
+                      session.filePath,
+                      session.firstMatchedMessageId
                     );
                   }}
                   className="bg-card border border-border rounded-lg p-4 hover:border-primary/50 hover:bg-accent/30 transition-all cursor-pointer"
@@ -406,6 +603,11 @@ export function SearchPage() {
                     <span className="text-xs px-2 py-0.5 bg-muted rounded font-medium">
                       {session.projectName}
                     </span>
+                    {searchAllSources && (
+                      <span className="text-xs px-2 py-0.5 border border-border rounded text-muted-foreground">
+                        {getSourceLabel(session.source)}
+                      </span>
+                    )}
                     <span className="text-xs px-2 py-0.5 bg-primary/15 text-primary rounded font-medium">
                       {session.matchCount} 条匹配
                     </span>
@@ -466,9 +668,9 @@ export function SearchPage() {
                   )}
 
                   <div className="space-y-1">
-                    {session.matchedTexts.map((text, i) => (
+                    {session.matchedTexts.map(({ text, ranges }, i) => (
                       <p key={i} className="text-xs font-mono text-muted-foreground whitespace-pre-wrap break-all line-clamp-1">
-                        {highlightMatch(text, query)}
+                        {highlightMatch(text, ranges)}
                       </p>
                     ))}
                     {session.matchCount > 3 && (
@@ -482,7 +684,7 @@ export function SearchPage() {
             })}
           </div>
         )
-      ) : query && !searchLoading ? (
+      ) : query && !searchLoading && !searchQueryError ? (
         <div className="text-center text-muted-foreground py-12">
           {globalTagFilter.length > 0 && searchResults.length > 0
             ? "没有匹配标签筛选条件的搜索结果"
@@ -1,31 +1,81 @@
-import { useEffect, useRef } from "react";
+import { useEffect, useRef, useState } from "react";
 import ReactDOM from "react-dom";
-import { FileJson, FileText, FileCode } from "lucide-react";
-import type { ExportFormat } from "../../types";
+import { FileJson, FileText, FileCode, FileDiff, Database } from "lucide-react";
+import type { ExportFormat, ExportOptions } from "../../types";
 import { FORMAT_LABEL } from "../../services/exportHelpers";
 
 interface ExportFormatMenuProps {
   anchorRect: DOMRect;
   /** 标题，默认「导出为」。 */
   title?: string;
-  onPick: (format: ExportFormat) => void;
+  /** 显示消息范围输入（单会话导出时有意义）。 */
+  showRange?: boolean;
+  onPick: (format: ExportFormat, options: ExportOptions) => void;
   onClose: () => void;
 }
 
+// 过滤 / 截断 / 脱敏选项跨次导出记住；消息范围只对当前会话有意义，不保存。
+const OPTIONS_KEY = "export_options";
+
+function loadOptions(): ExportOptions {
+  try {
+    return JSON.parse(localStorage.getItem(OPTIONS_KEY) || "{}");
+  } catch {
+    return {};
+  }
+}
+
+const TOGGLES: { key: "includeThinking" | "includeToolCalls" | "includeToolResults"; label: string }[] = [
+  { key: "includeThinking", label: "思考过程" },
+  { key: "includeToolCalls", label: "工具调用" },
+  { key: "includeToolResults", label: "工具结果" },
+];
+
+const inputClass =
+  "w-14 px-1 py-0.5 rounded bg-background border border-border text-foreground text-xs";
+
 const FORMATS: { format: ExportFormat; icon: typeof FileJson }[] = [
   { format: "json", icon: FileJson },
   { format: "markdown", icon: FileText },
   { format: "html", icon: FileCode },
+  { format: "patch", icon: FileDiff },
+  { format: "mbox", icon: FileDiff },
+  { format: "openai", icon: Database },
+  { format: "sharegpt", icon: Database },
 ];
 
 /** 选择导出格式的小浮层（portal 定位，自动翻转防溢出）。 */
 export function ExportFormatMenu({
   anchorRect,
   title = "导出为",
+  showRange = false,
   onPick,
   onClose,
 }: ExportFormatMenuProps) {
   const menuRef = useRef<HTMLDivElement>(null);
+  const [options, setOptions] = useState<ExportOptions>(loadOptions);
+  // 1-based，含两端；留空表示不限
+  const [rangeFrom, setRangeFrom] = useState("");
+  const [rangeTo, setRangeTo] = useState("");
+
+  const updateOptions = (patch: ExportOptions) => {
+    setOptions((prev) => {
+      const next = { ...prev, ...patch };
+      localStorage.setItem(OPTIONS_KEY, JSON.stringify(next));
+      return next;
+    });
+  };
+
+  const pick = (format: ExportFormat) => {
+    const from = parseInt(rangeFrom, 10);
+    const to = parseInt(rangeTo, 10);
+    onPick(format, {
+      ...options,
+      messageStart: from > 0 ? from - 1 : undefined,
+      messageEnd: to > 0 ? to : undefined,
+    });
+    onClose();
+  };
 
   useEffect(() => {
     const handleMouseDown = (e: MouseEvent) => {
@@ -44,8 +94,8 @@ export function ExportFormatMenu({
     };
   }, [onClose]);
 
-  const menuWidth = 160;
-  const menuHeight = 150;
+  const menuWidth = 220;
+  const menuHeight = showRange ? 420 : 390;
   let left = anchorRect.right - menuWidth;
   let top = anchorRect.bottom + 4;
   if (left < 8) left = 8;
@@ -62,13 +112,71 @@ export function ExportFormatMenu({
       <div className="px-3 py-1.5 text-xs text-muted-foreground border-b border-border">
         {title}
       </div>
+      <div className="px-3 py-2 border-b border-border space-y-1.5 text-xs text-muted-foreground">
+        {TOGGLES.map(({ key, label }) => (
+          <label key={key} className="flex items-center gap-2 cursor-pointer">
+            <input
+              type="checkbox"
+              checked={options[key] !== false}
+              onChange={(e) => {
+                const patch: ExportOptions = {};
+                patch[key] = e.target.checked;
+                updateOptions(patch);
+              }}
+            />
+            {label}
+          </label>
+        ))}
+        <label className="flex items-center gap-1.5">
+          工具输出最多
+          <input
+            type="number"
+            min={1}
+            placeholder="不限"
+            value={options.maxToolOutputLines ?? ""}
+            onChange={(e) => {
+              const n = parseInt(e.target.value, 10);
+              updateOptions({ maxToolOutputLines: n > 0 ? n : undefined });
+            }}
+            className={inputClass}
+          />
+          行
+        </label>
+        <label className="flex items-center gap-2 cursor-pointer" title="遮掉家目录、邮箱和形似 API key 的字符串">
+          <input
+            type="checkbox"
+            checked={options.redact === "standard"}
+            onChange={(e) => updateOptions({ redact: e.target.checked ? "standard" : undefined })}
+          />
+          脱敏（路径 / 邮箱 / 密钥）
+        </label>
+        {showRange && (
+          <div className="flex items-center gap-1.5">
+            消息
+            <input
+              type="number"
+              min={1}
+              placeholder="起"
+              value={rangeFrom}
+              onChange={(e) => setRangeFrom(e.target.value)}
+              className={inputClass}
+            />
+            –
+            <input
+              type="number"
+              min={1}
+              placeholder="止"
+              value={rangeTo}
+              onChange={(e) => setRangeTo(e.target.value)}
+              className={inputClass}
+            />
+          </div>
+        )}
+      </div>
       {FORMATS.map(({ format, icon: Icon }) => (
         <button
           key={format}
-          onClick={() => {
-            onPick(format);
-            onClose();
-          }}
+          onClick={() => pick(format)}
           className="w-full text-left px-3 py-2 text-sm text-foreground hover:bg-accent/50 transition-colors flex items-center gap-2"
         >
           <Icon className="w-3.5 h-3.5" />
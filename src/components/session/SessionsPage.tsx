@@ -18,17 +18,20 @@ import {
   Download,
   CheckSquare,
   X,
+  Package,
+  Share2,
 } from "lucide-react";
 import { formatDistanceToNow, format } from "date-fns";
 import { zhCN } from "date-fns/locale";
 import { api } from "../../services/api";
 import { SessionMetaEditor } from "./SessionMetaEditor";
 import { CloneToProviderDialog } from "./CloneToProviderDialog";
+import { ShareDialog } from "./ShareDialog";
 import { ExportFormatMenu } from "./ExportFormatMenu";
 import { ScanProgressView } from "../common/ScanProgressView";
 import { ProjectSkillsPanel } from "../skills/ProjectSkillsPanel";
-import { saveExport, saveExportMany } from "../../services/exportHelpers";
-import type { ExportFormat, SessionIndexEntry } from "../../types";
+import { DATASET_FORMATS, saveArchive, saveDataset, saveExport } from "../../services/exportHelpers";
+import type { ArchiveSelection, ExportFormat, ExportOptions, SessionIndexEntry } from "../../types";
 
 declare const __IS_TAURI__: boolean;
 
@@ -63,6 +66,7 @@ export function SessionsPage() {
   const [deleting, setDeleting] = useState(false);
   const [editingSession, setEditingSession] = useState<string | null>(null);
   const [cloningSession, setCloningSession] = useState<SessionIndexEntry | null>(null);
+  const [sharingSession, setSharingSession] = useState<SessionIndexEntry | null>(null);
 
   const [showCleanDialog, setShowCleanDialog] = useState(false);
   const [cleanSelected, setCleanSelected] = useState<Set<string>>(new Set());
@@ -75,9 +79,15 @@ export function SessionsPage() {
   const [batchDeleteOpen, setBatchDeleteOpen] = useState(false);
   // 单会话导出格式浮层
   const [exportMenu, setExportMenu] = useState<{ session: SessionIndexEntry; rect: DOMRect } | null>(null);
-  // 批量导出格式浮层
-  const [batchExportRect, setBatchExportRect] = useState<DOMRect | null>(null);
+  // 打包导出（zip）格式浮层：选中的会话 / 整个项目 / 某个标签
+  const [archiveMenu, setArchiveMenu] = useState<{
+    rect: DOMRect;
+    title: string;
+    selection: ArchiveSelection;
+    filenameBase: string;
+  } | null>(null);
   const [exportError, setExportError] = useState<string | null>(null);
+  const [exportNotice, setExportNotice] = useState<string | null>(null);
 
   const toggleSelected = (filePath: string) => {
     setSelected((prev) => {
@@ -123,6 +133,8 @@ export function SessionsPage() {
 
   const [copiedId, setCopiedId] = useState<string | null>(null);
 
+  // Only Claude and Codex sessions can be resumed from a CLI.
+  const canResume = source === "claude" || source === "codex";
   const getResumeCommand = (sessionId: string) =>
     source === "claude"
       ? `claude --resume ${sessionId}`
@@ -212,10 +224,14 @@ export function SessionsPage() {
   });
 
   // 单会话导出
-  const handleExportSingle = async (s: SessionIndexEntry, fmt: ExportFormat) => {
+  const handleExportSingle = async (
+    s: SessionIndexEntry,
+    fmt: ExportFormat,
+    options: ExportOptions,
+  ) => {
     setExportError(null);
     try {
-      const content = await api.exportSession(source, s.filePath, fmt);
+      const content = await api.exportSession(source, s.filePath, fmt, undefined, options);
       await saveExport(content, sessionFilenameBase(s), fmt);
     } catch (err) {
       setExportError(typeof err === "string" ? err : String(err));
@@ -223,18 +239,34 @@ export function SessionsPage() {
     }
   };
 
-  // 批量导出：逐个取内容再一次性保存到目录（Tauri）或逐个下载（Web）
-  const handleBatchExport = async (fmt: ExportFormat) => {
-    if (selectedSessions.length === 0) return;
+  // 打包导出：后端把会话渲染后连同 index.html / manifest.json 打成一个 zip；
+  // 数据集格式则合并成一个去重后的 JSONL，并提示写入 / 跳过了多少条
+  const handleArchiveExport = async (
+    selection: ArchiveSelection,
+    filenameBase: string,
+    fmt: ExportFormat,
+    options: ExportOptions,
+  ) => {
     setBatchBusy(true);
     setExportError(null);
     try {
-      const items = [];
-      for (const s of selectedSessions) {
-        const content = await api.exportSession(source, s.filePath, fmt);
-        items.push({ content, filenameBase: sessionFilenameBase(s) });
+      if (DATASET_FORMATS.includes(fmt)) {
+        const stats = await saveDataset(source, selection, fmt, filenameBase, options);
+        if (stats) {
+          const skipped = [
+            stats.duplicates > 0 && `${stats.duplicates} 条重复`,
+            stats.empty > 0 && `${stats.empty} 条无完整问答`,
+            stats.failed > 0 && `${stats.failed} 条读取失败`,
+          ].filter(Boolean);
+          setExportNotice(
+            `已写入 ${stats.written} / ${stats.sessions} 个会话` +
+              (skipped.length > 0 ? `，跳过 ${skipped.join("、")}` : ""),
+          );
+          setTimeout(() => setExportNotice(null), 5000);
+        }
+      } else {
+        await saveArchive(source, selection, fmt, filenameBase, options);
       }
-      await saveExportMany(items, fmt);
     } catch (err) {
       setExportError(typeof err === "string" ? err : String(err));
       setTimeout(() => setExportError(null), 5000);
@@ -296,6 +328,24 @@ export function SessionsPage() {
               清理空会话 ({emptySessions.length})
             </button>
           )}
+          {sessions.length > 0 && (
+            <button
+              onClick={(e) =>
+                setArchiveMenu({
+                  rect: e.currentTarget.getBoundingClientRect(),
+                  title: "打包导出整个项目",
+                  selection: { kind: "project", projectId },
+                  filenameBase: project?.shortName || "project",
+                })
+              }
+              disabled={batchBusy}
+              className="text-xs px-3 py-1.5 rounded-md border border-border text-muted-foreground hover:text-foreground hover:border-primary/50 transition-colors flex items-center gap-1.5 disabled:opacity-50"
+              title="把项目的全部会话连同索引页打成一个 zip"
+            >
+              {batchBusy ? <Loader2 className="w-3.5 h-3.5 animate-spin" /> : <Package className="w-3.5 h-3.5" />}
+              打包导出
+            </button>
+          )}
           {sessions.length > 0 && (
             selectMode ? (
               <button
@@ -362,6 +412,24 @@ export function SessionsPage() {
               清除筛选
             </button>
           )}
+          {tagFilter.length === 1 && (
+            <button
+              onClick={(e) =>
+                setArchiveMenu({
+                  rect: e.currentTarget.getBoundingClientRect(),
+                  title: `打包导出标签「${tagFilter[0]}」`,
+                  selection: { kind: "tag", tag: tagFilter[0] },
+                  filenameBase: `tag-${tagFilter[0]}`,
+                })
+              }
+              disabled={batchBusy}
+              className="px-2 py-1 text-xs text-muted-foreground hover:text-foreground transition-colors flex items-center gap-1 disabled:opacity-50"
+              title="导出所有项目中带此标签的会话"
+            >
+              <Package className="w-3 h-3" />
+              打包导出此标签
+            </button>
+          )}
         </div>
       )}
 
@@ -547,38 +615,42 @@ export function SessionsPage() {
                       <CopyPlus className="w-3.5 h-3.5" />
                     </button>
                   )}
-                  <button
-                    onClick={(e) =>
-                      handleResume(
-                        e,
-                        session.sessionId,
-                        session.projectPath || session.cwd || project?.displayPath || null,
-                        session.filePath
-                      )
-                    }
-                    className="px-3 py-1.5 text-xs bg-primary text-primary-foreground rounded-md hover:bg-primary/90 flex items-center gap-1"
-                    title={__IS_TAURI__ ? "在终端中恢复此会话" : "复制恢复命令"}
-                  >
-                    {__IS_TAURI__ ? (
-                      <><Play className="w-3 h-3" />Resume</>
-                    ) : (
-                      <>
-                        {copiedId === session.sessionId ? "已复制" : <><Copy className="w-3 h-3" />复制命令</>}
-                      </>
-                    )}
-                  </button>
-                  {__IS_TAURI__ && (
-                    <button
-                      onClick={(e) => handleCopyCommand(e, session.sessionId)}
-                      className="px-3 py-1.5 text-xs border border-border text-muted-foreground rounded-md hover:bg-accent hover:text-foreground flex items-center gap-1"
-                      title="复制恢复命令"
-                    >
-                      {copiedId === session.sessionId ? (
-                        <>已复制</>
-                      ) : (
-                        <><Copy className="w-3 h-3" />复制命令</>
+                  {canResume && (
+                    <>
+                      <button
+                        onClick={(e) =>
+                          handleResume(
+                            e,
+                            session.sessionId,
+                            session.projectPath || session.cwd || project?.displayPath || null,
+                            session.filePath
+                          )
+                        }
+                        className="px-3 py-1.5 text-xs bg-primary text-primary-foreground rounded-md hover:bg-primary/90 flex items-center gap-1"
+                        title={__IS_TAURI__ ? "在终端中恢复此会话" : "复制恢复命令"}
+                      >
+                        {__IS_TAURI__ ? (
+                          <><Play className="w-3 h-3" />Resume</>
+                        ) : (
+                          <>
+                            {copiedId === session.sessionId ? "已复制" : <><Copy className="w-3 h-3" />复制命令</>}
+                          </>
+                        )}
+                      </button>
+                      {__IS_TAURI__ && (
+                        <button
+                          onClick={(e) => handleCopyCommand(e, session.sessionId)}
+                          className="px-3 py-1.5 text-xs border border-border text-muted-foreground rounded-md hover:bg-accent hover:text-foreground flex items-center gap-1"
+                          title="复制恢复命令"
+                        >
+                          {copiedId === session.sessionId ? (
+                            <>已复制</>
+                          ) : (
+                            <><Copy className="w-3 h-3" />复制命令</>
+                          )}
+                        </button>
                       )}
-                    </button>
+                    </>
                   )}
                   <button
                     onClick={(e) => {
@@ -590,6 +662,18 @@ export function SessionsPage() {
                   >
                     <Download className="w-3.5 h-3.5" />
                   </button>
+                  {!__IS_TAURI__ && (
+                    <button
+                      onClick={(e) => {
+                        e.stopPropagation();
+                        setSharingSession(session);
+                      }}
+                      className="p-1.5 text-xs text-muted-foreground rounded-md hover:bg-accent hover:text-foreground transition-colors"
+                      title="分享只读链接"
+                    >
+                      <Share2 className="w-3.5 h-3.5" />
+                    </button>
+                  )}
                   <button
                     onClick={(e) => {
                       e.stopPropagation();
@@ -664,6 +748,15 @@ export function SessionsPage() {
         />
       )}
 
+      {/* 只读分享链接（Web 模式） */}
+      {sharingSession && (
+        <ShareDialog
+          source={source}
+          session={sharingSession}
+          onClose={() => setSharingSession(null)}
+        />
+      )}
+
       {/* 清理空会话对话框 */}
       {showCleanDialog && (
         <div className="fixed inset-0 z-50 flex items-center justify-center bg-black/50">
@@ -768,7 +861,14 @@ export function SessionsPage() {
           </button>
           <span className="text-sm text-foreground">已选 {selectedSessions.length}</span>
           <button
-            onClick={(e) => setBatchExportRect(e.currentTarget.getBoundingClientRect())}
+            onClick={(e) =>
+              setArchiveMenu({
+                rect: e.currentTarget.getBoundingClientRect(),
+                title: `导出选中 ${selectedSessions.length} 个`,
+                selection: { kind: "sessions", filePaths: selectedSessions.map((s) => s.filePath) },
+                filenameBase: `${project?.shortName || "sessions"}-selected`,
+              })
+            }
             disabled={batchBusy || selectedSessions.length === 0}
             className="text-xs px-3 py-1.5 rounded-md border border-border text-foreground hover:bg-accent transition-colors flex items-center gap-1.5 disabled:opacity-50"
           >
@@ -796,18 +896,21 @@ export function SessionsPage() {
       {exportMenu && (
         <ExportFormatMenu
           anchorRect={exportMenu.rect}
+          showRange
           onClose={() => setExportMenu(null)}
-          onPick={(fmt) => handleExportSingle(exportMenu.session, fmt)}
+          onPick={(fmt, options) => handleExportSingle(exportMenu.session, fmt, options)}
         />
       )}
 
-      {/* 批量导出格式浮层 */}
-      {batchExportRect && (
+      {/* 打包导出格式浮层 */}
+      {archiveMenu && (
         <ExportFormatMenu
-          anchorRect={batchExportRect}
-          title={`导出选中 ${selectedSessions.length} 个`}
-          onClose={() => setBatchExportRect(null)}
-          onPick={(fmt) => handleBatchExport(fmt)}
+          anchorRect={archiveMenu.rect}
+          title={archiveMenu.title}
+          onClose={() => setArchiveMenu(null)}
+          onPick={(fmt, options) =>
+            handleArchiveExport(archiveMenu.selection, archiveMenu.filenameBase, fmt, options)
+          }
         />
       )}
 
@@ -840,6 +943,13 @@ export function SessionsPage() {
         </div>
       )}
 
+      {/* 数据集导出统计 */}
+      {exportNotice && !exportError && (
+        <div className="fixed bottom-20 left-1/2 -translate-x-1/2 z-50 px-4 py-2 bg-card border border-border rounded-lg text-sm text-foreground shadow-lg max-w-md">
+          {exportNotice}
+        </div>
+      )}
+
       {/* 导出错误提示 */}
       {exportError && (
         <div className="fixed bottom-20 left-1/2 -translate-x-1/2 z-50 px-4 py-2 bg-destructive/10 border border-destructive/30 rounded-lg text-sm text-destructive shadow-lg max-w-md">
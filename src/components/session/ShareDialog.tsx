@@ -0,0 +1,247 @@
+import { useEffect, useState } from "react";
+import { X, Share2, Loader2, Copy, Check, Trash2 } from "lucide-react";
+import { format } from "date-fns";
+import { api } from "../../services/api";
+import type { SessionIndexEntry, Share } from "../../types";
+
+interface Props {
+  source: string;
+  session: SessionIndexEntry;
+  onClose: () => void;
+}
+
+const EXPIRY_OPTIONS: { hours: number; label: string }[] = [
+  { hours: 1, label: "1 小时" },
+  { hours: 24, label: "1 天" },
+  { hours: 72, label: "3 天" },
+  { hours: 24 * 7, label: "7 天" },
+  { hours: 24 * 30, label: "30 天" },
+];
+
+function shareUrl(share: Share): string {
+  return `${window.location.origin}/share/${share.token}`;
+}
+
+const inputClass =
+  "w-16 px-2 py-1 rounded-md bg-background border border-border text-foreground text-sm";
+
+/**
+ * 只读分享链接（仅 Web 模式）：为单个会话生成签名、会过期的公开链接，对方无需
+ * token 即可查看 HTML 导出；可限定消息区间并脱敏。下方列出所有有效分享，可随时撤销。
+ */
+export function ShareDialog({ source, session, onClose }: Props) {
+  const [expiresInHours, setExpiresInHours] = useState(72);
+  const [redact, setRedact] = useState(true);
+  const [rangeFrom, setRangeFrom] = useState("");
+  const [rangeTo, setRangeTo] = useState("");
+  const [creating, setCreating] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+  const [shares, setShares] = useState<Share[]>([]);
+  const [copiedId, setCopiedId] = useState<string | null>(null);
+
+  const title =
+    session.alias || session.threadName || session.firstPrompt || session.sessionId;
+
+  const loadShares = async () => {
+    try {
+      setShares(await api.listShares());
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+    }
+  };
+
+  useEffect(() => {
+    loadShares();
+  }, []);
+
+  const copyLink = (share: Share) => {
+    navigator.clipboard.writeText(shareUrl(share));
+    setCopiedId(share.id);
+    setTimeout(() => setCopiedId(null), 2000);
+  };
+
+  const handleCreate = async () => {
+    setCreating(true);
+    setError(null);
+    try {
+      // 输入框为 1 起、两端都含的消息序号；后端为 0 起、左闭右开。
+      const from = parseInt(rangeFrom, 10);
+      const to = parseInt(rangeTo, 10);
+      const share = await api.createShare({
+        source,
+        filePath: session.filePath,
+        title: title.slice(0, 80),
+        messageStart: from > 0 ? from - 1 : undefined,
+        messageEnd: to > 0 ? to : undefined,
+        redact: redact ? "standard" : undefined,
+        expiresInHours,
+      });
+      copyLink(share);
+      await loadShares();
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+    } finally {
+      setCreating(false);
+    }
+  };
+
+  const handleRevoke = async (id: string) => {
+    setError(null);
+    try {
+      await api.revokeShare(id);
+      setShares((prev) => prev.filter((s) => s.id !== id));
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+    }
+  };
+
+  return (
+    <div
+      className="fixed inset-0 z-50 flex items-center justify-center bg-black/50"
+      onClick={(e) => {
+        if (e.target === e.currentTarget) onClose();
+      }}
+    >
+      <div className="bg-card border border-border rounded-lg p-6 max-w-lg w-full mx-4 shadow-lg">
+        <div className="flex items-center justify-between mb-4">
+          <h3 className="text-lg font-semibold flex items-center gap-2">
+            <Share2 className="w-4 h-4" />
+            分享只读链接
+          </h3>
+          <button
+            onClick={onClose}
+            className="p-1 rounded hover:bg-accent transition-colors"
+          >
+            <X className="w-4 h-4" />
+          </button>
+        </div>
+
+        <div className="mb-4 text-sm">
+          <p className="text-muted-foreground mb-1">会话</p>
+          <p className="font-medium line-clamp-2">{title}</p>
+        </div>
+
+        <div className="mb-4 space-y-3 text-sm">
+          <label className="flex items-center gap-2">
+            <span className="w-20 text-muted-foreground">有效期</span>
+            <select
+              value={expiresInHours}
+              onChange={(e) => setExpiresInHours(Number(e.target.value))}
+              className="px-2 py-1 bg-background border border-border rounded-md text-sm"
+            >
+              {EXPIRY_OPTIONS.map((o) => (
+                <option key={o.hours} value={o.hours}>
+                  {o.label}
+                </option>
+              ))}
+            </select>
+          </label>
+          <div className="flex items-center gap-2">
+            <span className="w-20 text-muted-foreground">消息范围</span>
+            <input
+              type="number"
+              min={1}
+              placeholder="起"
+              value={rangeFrom}
+              onChange={(e) => setRangeFrom(e.target.value)}
+              className={inputClass}
+            />
+            <span className="text-muted-foreground">–</span>
+            <input
+              type="number"
+              min={1}
+              placeholder="止"
+              value={rangeTo}
+              onChange={(e) => setRangeTo(e.target.value)}
+              className={inputClass}
+            />
+            <span className="text-xs text-muted-foreground">留空为全部</span>
+          </div>
+          <label className="flex items-center gap-2">
+            <input
+              type="checkbox"
+              checked={redact}
+              onChange={(e) => setRedact(e.target.checked)}
+              className="accent-primary"
+            />
+            脱敏（家目录、邮箱、密钥）
+          </label>
+          <p className="text-xs text-muted-foreground">
+            拿到链接的人无需登录即可查看这个会话，过期或撤销后立即失效。
+          </p>
+        </div>
+
+        {error && (
+          <div className="mb-4 text-xs text-destructive bg-destructive/10 rounded-md p-2">
+            {error}
+          </div>
+        )}
+
+        <div className="flex justify-end gap-2 mb-4">
+          <button
+            onClick={onClose}
+            className="px-4 py-2 text-sm rounded-md border border-border hover:bg-accent transition-colors"
+          >
+            关闭
+          </button>
+          <button
+            onClick={handleCreate}
+            disabled={creating}
+            className="px-4 py-2 text-sm rounded-md bg-primary text-primary-foreground hover:bg-primary/90 transition-colors flex items-center gap-1.5 disabled:opacity-50"
+          >
+            {creating && <Loader2 className="w-3.5 h-3.5 animate-spin" />}
+            {creating ? "生成中..." : "生成并复制链接"}
+          </button>
+        </div>
+
+        {/* 所有有效分享 */}
+        <div className="border-t border-border pt-3">
+          <p className="text-sm font-medium mb-2">有效的分享（{shares.length}）</p>
+          {shares.length === 0 ? (
+            <p className="text-xs text-muted-foreground">暂无</p>
+          ) : (
+            <div className="max-h-48 overflow-y-auto space-y-1.5">
+              {shares.map((share) => (
+                <div
+                  key={share.id}
+                  className={`flex items-center gap-2 text-xs rounded-md px-2 py-1.5 ${
+                    share.filePath === session.filePath ? "bg-primary/5" : "bg-muted/40"
+                  }`}
+                >
+                  <div className="min-w-0 flex-1">
+                    <p className="truncate text-foreground">{share.title || share.filePath}</p>
+                    <p className="text-muted-foreground">
+                      {format(new Date(share.expiresAt), "yyyy-MM-dd HH:mm")} 过期
+                      {share.messageStart != null || share.messageEnd != null
+                        ? ` · 消息 ${(share.messageStart ?? 0) + 1}–${share.messageEnd ?? "末尾"}`
+                        : ""}
+                      {share.redact ? " · 已脱敏" : ""}
+                    </p>
+                  </div>
+                  <button
+                    onClick={() => copyLink(share)}
+                    className="p-1 rounded hover:bg-accent transition-colors"
+                    title="复制链接"
+                  >
+                    {copiedId === share.id ? (
+                      <Check className="w-3.5 h-3.5" />
+                    ) : (
+                      <Copy className="w-3.5 h-3.5" />
+                    )}
+                  </button>
+                  <button
+                    onClick={() => handleRevoke(share.id)}
+                    className="p-1 rounded hover:bg-destructive/10 hover:text-destructive transition-colors"
+                    title="撤销"
+                  >
+                    <Trash2 className="w-3.5 h-3.5" />
+                  </button>
+                </div>
+              ))}
+            </div>
+          )}
+        </div>
+      </div>
+    </div>
+  );
+}
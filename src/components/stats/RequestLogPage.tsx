@@ -11,6 +11,7 @@ import {
 } from "lucide-react";
 import { useAppStore } from "../../stores/appStore";
 import type { RequestLogFilter, RequestRecord } from "../../types";
+import { sourceLabel } from "../../utils/sessionSource";
 
 function formatTokens(n: number): string {
   if (n >= 1_000_000) return `${(n / 1_000_000).toFixed(1)}M`;
@@ -164,7 +165,7 @@ export function RequestLogPage() {
         <Receipt className="w-5 h-5 text-muted-foreground" />
         <h1 className="text-lg font-semibold">逐请求账单</h1>
         <span className="text-xs text-muted-foreground">
-          ({source === "claude" ? "Claude" : "Codex"})
+          ({sourceLabel(source)})
         </span>
         <div className="ml-auto flex items-center gap-4 text-xs text-muted-foreground">
           <span>
@@ -3,6 +3,7 @@ import { useNavigate } from "react-router-dom";
 import { useAppStore } from "../../stores/appStore";
 import { api } from "../../services/api";
 import type { RequestRecord } from "../../types";
+import { sourceLabel } from "../../utils/sessionSource";
 import {
   BarChart,
   Bar,
@@ -377,10 +378,12 @@ export function StatsPage() {
   }
 
   if (!tokenSummary) {
-    const hint =
-      source === "claude"
-        ? "请确认 ~/.claude/ 目录下存在统计数据。"
-        : "请确认 ~/.codex/sessions/ 目录下存在会话数据。";
+    const hint = {
+      claude: "请确认 ~/.claude/ 目录下存在统计数据。",
+      codex: "请确认 ~/.codex/sessions/ 目录下存在会话数据。",
+      gemini: "请确认 ~/.gemini/tmp/ 目录下存在会话数据。",
+      aider: "请确认已在数据根目录中添加含 .aider.chat.history.md 的项目。",
+    }[source];
     return (
       <div className="p-6 text-muted-foreground">
         未找到统计数据。{hint}
@@ -438,7 +441,7 @@ export function StatsPage() {
         <h1 className="text-2xl font-bold">
           使用统计
           <span className="text-sm font-normal text-muted-foreground ml-2">
-            ({source === "claude" ? "Claude" : "Codex"})
+            ({sourceLabel(source)})
           </span>
         </h1>
         <button
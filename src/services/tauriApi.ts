@@ -1,19 +1,42 @@
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import type {
   ProjectEntry,
   SessionIndexEntry,
   PaginatedMessages,
   RangeMessages,
-  SearchResult,
+  SubagentThread,
+  ConversationTree,
+  BranchMessages,
+  SessionPlan,
+  FileHistoryIndex,
+  FileContentsAtMessage,
+  SessionComparison,
+  SearchBatch,
+  SearchOptions,
+  SearchResponse,
   TokenUsageSummary,
   RequestLogPage,
   RequestLogFilter,
+  PromptHistoryPage,
+  PromptHistoryFilter,
   ProjectCostEntry,
   SessionCostSummary,
   Bookmark,
+  CollectionCount,
+  NewSavedSearch,
+  NewShare,
+  SavedSearch,
+  Share,
+  SmartCollection,
   DeleteLevel,
   DeleteResult,
   ExportFormat,
+  PatchGrouping,
+  ArchiveSelection,
+  DatasetExport,
+  DatasetStats,
+  ExportOptions,
   ScanProgress,
   RecycledItem,
   SkillsResult,
@@ -89,13 +112,133 @@ export async function getMessagesRange(
   });
 }
 
+/** Load the subagent (sidechain) thread spawned by a Task tool call. */
+export async function getSubagentThread(
+  source: string,
+  filePath: string,
+  agentId: string,
+): Promise<SubagentThread> {
+  return invoke<SubagentThread>("get_subagent_thread", { source, filePath, agentId });
+}
+
+/** Build the uuid / parentUuid message tree of a session. */
+export async function getConversationTree(
+  source: string,
+  filePath: string,
+): Promise<ConversationTree> {
+  return invoke<ConversationTree>("get_conversation_tree", { source, filePath });
+}
+
+/** Final todo list and plan documents of a session. */
+export async function getSessionPlan(
+  source: string,
+  filePath: string,
+): Promise<SessionPlan> {
+  return invoke<SessionPlan>("get_session_plan", { source, filePath });
+}
+
+/** Files a session edited, with their file-history backups and edit tool calls. */
+export async function getFileHistory(
+  source: string,
+  filePath: string,
+): Promise<FileHistoryIndex> {
+  return invoke<FileHistoryIndex>("get_file_history", { source, filePath });
+}
+
+/** Contents of edited `file` before and after message `uuid`. */
+export async function getFileContentsAt(
+  source: string,
+  filePath: string,
+  file: string,
+  uuid: string,
+): Promise<FileContentsAtMessage> {
+  return invoke<FileContentsAtMessage>("get_file_contents_at", { source, filePath, file, uuid });
+}
+
+/** Two sessions, possibly from different sources, with their turns aligned. */
+export async function compareSessions(
+  sourceA: string,
+  filePathA: string,
+  sourceB: string,
+  filePathB: string,
+): Promise<SessionComparison> {
+  return invoke<SessionComparison>("compare_sessions", { sourceA, filePathA, sourceB, filePathB });
+}
+
+/** Load the branch through message `uuid` as a linear message list. */
+export async function getBranchMessages(
+  source: string,
+  filePath: string,
+  uuid: string,
+): Promise<BranchMessages> {
+  return invoke<BranchMessages>("get_branch_messages", { source, filePath, uuid });
+}
+
+/**
+ * Load an image / document block's payload and return an object URL for it.
+ * Callers should `URL.revokeObjectURL` it when done.
+ */
+export async function getMessageMediaUrl(
+  source: string,
+  filePath: string,
+  uuid: string,
+  blockIndex: number,
+  mediaType: string,
+): Promise<string> {
+  const data = await invoke<ArrayBuffer>("get_message_media", {
+    source,
+    filePath,
+    uuid,
+    blockIndex,
+  });
+  return URL.createObjectURL(new Blob([data], { type: mediaType }));
+}
+
 export async function globalSearch(
   source: string,
   query: string,
   maxResults: number = 50,
   scope: string = "all",
-): Promise<SearchResult[]> {
-  return invoke<SearchResult[]>("global_search", { source, query, maxResults, scope });
+  options: SearchOptions = {},
+): Promise<SearchResponse> {
+  return invoke<SearchResponse>("global_search", { source, query, maxResults, scope, ...options });
+}
+
+let searchSeq = 0;
+
+/**
+ * Search, calling `onBatch` as results are found. Starting another search
+ * cancels this one, which then resolves to null.
+ */
+export async function streamSearch(
+  source: string,
+  query: string,
+  maxResults: number = 50,
+  scope: string = "all",
+  options: SearchOptions = {},
+  onBatch: (batch: SearchBatch) => void = () => {},
+): Promise<SearchResponse | null> {
+  const searchId = ++searchSeq;
+  const unlisten = await listen<SearchBatch>("search-batch", (event) => {
+    if (event.payload.id === searchId) onBatch(event.payload);
+  });
+  try {
+    return await invoke<SearchResponse | null>("search_stream", {
+      searchId,
+      source,
+      query,
+      maxResults,
+      scope,
+      ...options,
+    });
+  } finally {
+    unlisten();
+  }
+}
+
+export async function cancelSearch(): Promise<void> {
+  searchSeq++;
+  return invoke("cancel_search");
 }
 
 export async function getStats(source: string): Promise<TokenUsageSummary> {
@@ -118,6 +261,17 @@ export async function getRequestLog(
   });
 }
 
+export async function getPromptHistory(
+  filter: PromptHistoryFilter = {},
+): Promise<PromptHistoryPage> {
+  return invoke<PromptHistoryPage>("get_prompt_history", {
+    query: filter.query ?? null,
+    project: filter.project ?? null,
+    page: filter.page ?? 0,
+    pageSize: filter.pageSize ?? 100,
+  });
+}
+
 export async function getProjectCosts(source: string): Promise<ProjectCostEntry[]> {
   return invoke<ProjectCostEntry[]>("get_project_costs", { source });
 }
@@ -154,9 +308,11 @@ export async function deleteProject(
 export async function exportSession(
   source: string,
   filePath: string,
-  format: ExportFormat
+  format: ExportFormat,
+  patchGrouping?: PatchGrouping,
+  options?: ExportOptions,
 ): Promise<string> {
-  return invoke<string>("export_session", { source, filePath, format });
+  return invoke<string>("export_session", { source, filePath, format, patchGrouping, options });
 }
 
 export async function writeExportFile(
@@ -166,6 +322,50 @@ export async function writeExportFile(
   return invoke<void>("write_export_file", { path, content });
 }
 
+/** 打包导出到 `path`（.zip），返回写入的会话数。 */
+export async function exportArchive(
+  source: string,
+  selection: ArchiveSelection,
+  format: ExportFormat,
+  path: string,
+  patchGrouping?: PatchGrouping,
+  options?: ExportOptions,
+): Promise<number> {
+  return invoke<number>("export_archive", { source, selection, format, patchGrouping, options, path });
+}
+
+// 桌面端直接写文件，不走下载。保留此导出仅为与 webApi 的类型对齐；不应被调用。
+export async function fetchExportArchive(
+  _source: string,
+  _selection: ArchiveSelection,
+  _format: ExportFormat,
+  _patchGrouping?: PatchGrouping,
+  _options?: ExportOptions,
+): Promise<Blob> {
+  throw new Error("fetchExportArchive is not supported in desktop mode");
+}
+
+/** 数据集导出到 `path`（.jsonl），返回写入 / 去重 / 跳过统计。 */
+export async function exportDataset(
+  source: string,
+  selection: ArchiveSelection,
+  format: ExportFormat,
+  path: string,
+  options?: ExportOptions,
+): Promise<DatasetStats> {
+  return invoke<DatasetStats>("export_dataset", { source, selection, format, options, path });
+}
+
+// 桌面端直接写文件。保留此导出仅为与 webApi 的类型对齐；不应被调用。
+export async function fetchExportDataset(
+  _source: string,
+  _selection: ArchiveSelection,
+  _format: ExportFormat,
+  _options?: ExportOptions,
+): Promise<DatasetExport> {
+  throw new Error("fetchExportDataset is not supported in desktop mode");
+}
+
 export async function getScanProgress(): Promise<ScanProgress> {
   return invoke<ScanProgress>("get_scan_progress");
 }
@@ -349,6 +549,46 @@ export async function removeBookmark(id: string): Promise<void> {
   return invoke<void>("remove_bookmark", { id });
 }
 
+// Saved searches / smart collections API
+export async function listSavedSearches(): Promise<SavedSearch[]> {
+  return invoke<SavedSearch[]>("list_saved_searches");
+}
+
+export async function addSavedSearch(search: NewSavedSearch): Promise<SavedSearch> {
+  return invoke<SavedSearch>("add_saved_search", {
+    search: { id: "", createdAt: "", lastViewedAt: null, ...search },
+  });
+}
+
+export async function removeSavedSearch(id: string): Promise<void> {
+  return invoke<void>("remove_saved_search", { id });
+}
+
+export async function getCollectionCounts(): Promise<CollectionCount[]> {
+  return invoke<CollectionCount[]>("get_collection_counts");
+}
+
+// 分享链接由 session-web 对外提供，桌面端没有可分享的地址。保留这些导出仅为
+// 与 webApi 的类型对齐；不应被调用。
+export async function listShares(): Promise<Share[]> {
+  throw new Error("listShares is not supported in desktop mode");
+}
+
+export async function createShare(_share: NewShare): Promise<Share> {
+  throw new Error("createShare is not supported in desktop mode");
+}
+
+export async function revokeShare(_id: string): Promise<void> {
+  throw new Error("revokeShare is not supported in desktop mode");
+}
+
+export async function openSmartCollection(
+  id: string,
+  markViewed: boolean = false,
+): Promise<SmartCollection> {
+  return invoke<SmartCollection>("open_smart_collection", { id, markViewed });
+}
+
 export async function setProjectAlias(
   source: string,
   projectId: string,
@@ -3,16 +3,38 @@ import type {
   SessionIndexEntry,
   PaginatedMessages,
   RangeMessages,
-  SearchResult,
+  SubagentThread,
+  ConversationTree,
+  BranchMessages,
+  SessionPlan,
+  FileHistoryIndex,
+  FileContentsAtMessage,
+  SessionComparison,
+  SearchBatch,
+  SearchOptions,
+  SearchResponse,
   TokenUsageSummary,
   RequestLogPage,
   RequestLogFilter,
+  PromptHistoryPage,
+  PromptHistoryFilter,
   ProjectCostEntry,
   SessionCostSummary,
   Bookmark,
+  CollectionCount,
+  NewSavedSearch,
+  NewShare,
+  SavedSearch,
+  Share,
+  SmartCollection,
   DeleteLevel,
   DeleteResult,
   ExportFormat,
+  PatchGrouping,
+  ArchiveSelection,
+  DatasetExport,
+  DatasetStats,
+  ExportOptions,
   ScanProgress,
   RecycledItem,
   SkillsResult,
@@ -264,13 +286,226 @@ export async function getMessagesRange(
   });
 }
 
+/** Load the subagent (sidechain) thread spawned by a Task tool call. */
+export async function getSubagentThread(
+  source: string,
+  filePath: string,
+  agentId: string,
+): Promise<SubagentThread> {
+  return apiFetch("/api/messages/subagent", { source, filePath, agentId });
+}
+
+/** Build the uuid / parentUuid message tree of a session. */
+export async function getConversationTree(
+  source: string,
+  filePath: string,
+): Promise<ConversationTree> {
+  return apiFetch("/api/messages/tree", { source, filePath });
+}
+
+/** Final todo list and plan documents of a session. */
+export async function getSessionPlan(
+  source: string,
+  filePath: string,
+): Promise<SessionPlan> {
+  return apiFetch("/api/messages/plan", { source, filePath });
+}
+
+/** Files a session edited, with their file-history backups and edit tool calls. */
+export async function getFileHistory(
+  source: string,
+  filePath: string,
+): Promise<FileHistoryIndex> {
+  return apiFetch("/api/file-history", { source, filePath });
+}
+
+/** Contents of edited `file` before and after message `uuid`. */
+export async function getFileContentsAt(
+  source: string,
+  filePath: string,
+  file: string,
+  uuid: string,
+): Promise<FileContentsAtMessage> {
+  return apiFetch("/api/file-history/contents", { source, filePath, file, uuid });
+}
+
+/** Two sessions, possibly from different sources, with their turns aligned. */
+export async function compareSessions(
+  sourceA: string,
+  filePathA: string,
+  sourceB: string,
+  filePathB: string,
+): Promise<SessionComparison> {
+  return apiFetch("/api/compare", { sourceA, filePathA, sourceB, filePathB });
+}
+
+/** Load the branch through message `uuid` as a linear message list. */
+export async function getBranchMessages(
+  source: string,
+  filePath: string,
+  uuid: string,
+): Promise<BranchMessages> {
+  return apiFetch("/api/messages/branch", { source, filePath, uuid });
+}
+
+/**
+ * Load an image / document block's payload and return an object URL for it.
+ * The route needs the auth header, so it can't be used as `<img src>` directly.
+ * Callers should `URL.revokeObjectURL` it when done.
+ */
+export async function getMessageMediaUrl(
+  source: string,
+  filePath: string,
+  uuid: string,
+  blockIndex: number,
+  mediaType: string,
+): Promise<string> {
+  const url = new URL("/api/messages/media", window.location.origin);
+  url.searchParams.set("source", source);
+  url.searchParams.set("filePath", filePath);
+  url.searchParams.set("uuid", uuid);
+  url.searchParams.set("blockIndex", String(blockIndex));
+
+  const resp = await withAuthRetry(() =>
+    fetch(url.toString(), { headers: applyAuthHeader({}) }),
+  );
+
+  if (resp.status === 401) {
+    throw new Error("Authentication required");
+  }
+  if (!resp.ok) {
+    const text = await resp.text();
+    throw new Error(text || resp.statusText);
+  }
+  const data = await resp.arrayBuffer();
+  return URL.createObjectURL(new Blob([data], { type: mediaType }));
+}
+
 export async function globalSearch(
   source: string,
   query: string,
   maxResults: number = 50,
   scope: string = "all",
-): Promise<SearchResult[]> {
-  return apiFetch("/api/search", { source, query, maxResults: String(maxResults), scope });
+  options: SearchOptions = {},
+): Promise<SearchResponse> {
+  const params: Record<string, string> = { source, query, maxResults: String(maxResults), scope };
+  if (options.regex) params.regex = "true";
+  if (options.caseSensitive) params.caseSensitive = "true";
+  if (options.wholeWord) params.wholeWord = "true";
+  return apiFetch("/api/search", params);
+}
+
+// Search over the /ws socket. One search runs at a time: sending another
+// makes the server cancel the previous one, which resolves to null here.
+type PendingSearch = {
+  id: number;
+  onBatch: (batch: SearchBatch) => void;
+  resolve: (response: SearchResponse | null) => void;
+  reject: (error: Error) => void;
+};
+let searchWs: WebSocket | null = null;
+let searchWsOpenPromise: Promise<WebSocket> | null = null;
+let pendingSearch: PendingSearch | null = null;
+let searchSeq = 0;
+
+function settlePendingSearch(error?: Error): void {
+  const pending = pendingSearch;
+  pendingSearch = null;
+  if (!pending) return;
+  if (error) pending.reject(error);
+  else pending.resolve(null);
+}
+
+function dispatchSearchWsMessage(rawMessage: string): void {
+  const pending = pendingSearch;
+  if (!pending) return;
+  try {
+    const data = JSON.parse(rawMessage);
+    // fs-change frames share the socket and carry no id.
+    if (data?.id !== pending.id) return;
+    if (data.type === "search-batch") {
+      pending.onBatch(data as SearchBatch);
+    } else if (data.type === "search-done") {
+      pendingSearch = null;
+      pending.resolve(data.response as SearchResponse);
+    } else if (data.type === "search-error") {
+      pendingSearch = null;
+      pending.reject(new Error(typeof data.error === "string" ? data.error : "Search failed"));
+    }
+  } catch {
+    // not JSON; ignore
+  }
+}
+
+async function openSearchWebSocket(): Promise<WebSocket> {
+  if (searchWs?.readyState === WebSocket.OPEN) {
+    return searchWs;
+  }
+  if (!searchWsOpenPromise) {
+    searchWsOpenPromise = connectFileWatcherWebSocket().then(
+      (ws) =>
+        new Promise<WebSocket>((resolve, reject) => {
+          ws.addEventListener("open", () => {
+            searchWs = ws;
+            resolve(ws);
+          });
+          ws.addEventListener("message", (event) => {
+            if (typeof event.data === "string") {
+              dispatchSearchWsMessage(event.data);
+            }
+          });
+          ws.addEventListener("close", () => {
+            if (searchWs === ws) searchWs = null;
+            searchWsOpenPromise = null;
+            settlePendingSearch(new Error("Search WebSocket closed"));
+            reject(new Error("Search WebSocket closed"));
+          });
+        }),
+    );
+    searchWsOpenPromise.catch(() => {
+      searchWsOpenPromise = null;
+    });
+  }
+  return searchWsOpenPromise;
+}
+
+/**
+ * Search, calling `onBatch` as results are found. Starting another search
+ * cancels this one, which then resolves to null. Falls back to a one-shot
+ * /api/search request when the socket can't be opened.
+ */
+export async function streamSearch(
+  source: string,
+  query: string,
+  maxResults: number = 50,
+  scope: string = "all",
+  options: SearchOptions = {},
+  onBatch: (batch: SearchBatch) => void = () => {},
+): Promise<SearchResponse | null> {
+  const id = ++searchSeq;
+  settlePendingSearch();
+
+  let ws: WebSocket;
+  try {
+    ws = await openSearchWebSocket();
+  } catch {
+    const response = await globalSearch(source, query, maxResults, scope, options);
+    return id === searchSeq ? response : null;
+  }
+  if (id !== searchSeq) return null;
+
+  return new Promise<SearchResponse | null>((resolve, reject) => {
+    pendingSearch = { id, onBatch, resolve, reject };
+    ws.send(JSON.stringify({ type: "search", id, source, query, maxResults, scope, ...options }));
+  });
+}
+
+export async function cancelSearch(): Promise<void> {
+  searchSeq++;
+  settlePendingSearch();
+  if (searchWs?.readyState === WebSocket.OPEN) {
+    searchWs.send(JSON.stringify({ type: "search-cancel" }));
+  }
 }
 
 export async function getStats(source: string): Promise<TokenUsageSummary> {
@@ -292,6 +527,17 @@ export async function getRequestLog(
   return apiFetch("/api/stats/requests", params);
 }
 
+export async function getPromptHistory(
+  filter: PromptHistoryFilter = {},
+): Promise<PromptHistoryPage> {
+  const params: Record<string, string> = {};
+  if (filter.query) params.query = filter.query;
+  if (filter.project) params.project = filter.project;
+  if (filter.page !== undefined) params.page = String(filter.page);
+  if (filter.pageSize !== undefined) params.pageSize = String(filter.pageSize);
+  return apiFetch("/api/prompt-history", params);
+}
+
 export async function getProjectCosts(source: string): Promise<ProjectCostEntry[]> {
   return apiFetch("/api/stats/projects", { source });
 }
@@ -327,13 +573,19 @@ export async function deleteProject(
 export async function exportSession(
   source: string,
   filePath: string,
-  format: ExportFormat
+  format: ExportFormat,
+  patchGrouping?: PatchGrouping,
+  options: ExportOptions = {},
 ): Promise<string> {
-  // 导出端点返回 text/plain 正文（非 JSON），单独处理。
+  // 导出端点流式返回导出内容本身（非 JSON 包装），单独处理。
   const url = new URL("/api/export", window.location.origin);
   url.searchParams.set("source", source);
   url.searchParams.set("filePath", filePath);
   url.searchParams.set("format", format);
+  if (patchGrouping) url.searchParams.set("patchGrouping", patchGrouping);
+  for (const [key, value] of Object.entries(options)) {
+    if (value !== undefined) url.searchParams.set(key, String(value));
+  }
 
   const resp = await withAuthRetry(() =>
     fetch(url.toString(), { headers: applyAuthHeader({}) }),
@@ -355,6 +607,67 @@ export async function writeExportFile(_path: string, _content: string): Promise<
   throw new Error("writeExportFile is not supported in web mode");
 }
 
+/** 打包导出：服务端边打包边流式下发 zip，这里收完整个响应后交给浏览器下载。 */
+export async function fetchExportArchive(
+  source: string,
+  selection: ArchiveSelection,
+  format: ExportFormat,
+  patchGrouping?: PatchGrouping,
+  options: ExportOptions = {},
+): Promise<Blob> {
+  const url = new URL("/api/export/archive", window.location.origin).toString();
+  const payload = JSON.stringify({ source, selection, format, patchGrouping, ...options });
+  const resp = await withAuthRetry(() =>
+    fetch(url, {
+      method: "POST",
+      headers: applyAuthHeader({ "Content-Type": "application/json" }),
+      body: payload,
+    }),
+  );
+
+  if (resp.status === 401) {
+    throw new Error("Authentication required");
+  }
+  if (!resp.ok) {
+    const text = await resp.text();
+    throw new Error(text || resp.statusText);
+  }
+  return resp.blob();
+}
+
+// 同 writeExportFile：Web 模式没有本地路径，打包导出走 fetchExportArchive。
+export async function exportArchive(
+  _source: string,
+  _selection: ArchiveSelection,
+  _format: ExportFormat,
+  _path: string,
+  _patchGrouping?: PatchGrouping,
+  _options?: ExportOptions,
+): Promise<number> {
+  throw new Error("exportArchive is not supported in web mode");
+}
+
+/** 数据集导出：返回合并后的 JSONL 和去重 / 跳过统计，由前端触发下载。 */
+export async function fetchExportDataset(
+  source: string,
+  selection: ArchiveSelection,
+  format: ExportFormat,
+  options: ExportOptions = {},
+): Promise<DatasetExport> {
+  return apiPost<DatasetExport>("/api/export/dataset", { source, selection, format, ...options });
+}
+
+// 同 exportArchive：Web 模式走 fetchExportDataset。
+export async function exportDataset(
+  _source: string,
+  _selection: ArchiveSelection,
+  _format: ExportFormat,
+  _path: string,
+  _options?: ExportOptions,
+): Promise<DatasetStats> {
+  throw new Error("exportDataset is not supported in web mode");
+}
+
 export async function getScanProgress(): Promise<ScanProgress> {
   return apiFetch<ScanProgress>("/api/scan-progress");
 }
@@ -875,6 +1188,45 @@ export async function removeBookmark(id: string): Promise<void> {
   await apiDelete(`/api/bookmarks/${encodeURIComponent(id)}`);
 }
 
+// Saved searches / smart collections API
+export async function listSavedSearches(): Promise<SavedSearch[]> {
+  return apiFetch("/api/saved-searches");
+}
+
+export async function addSavedSearch(search: NewSavedSearch): Promise<SavedSearch> {
+  return apiPost("/api/saved-searches", { id: "", createdAt: "", lastViewedAt: null, ...search });
+}
+
+export async function removeSavedSearch(id: string): Promise<void> {
+  await apiDelete(`/api/saved-searches/${encodeURIComponent(id)}`);
+}
+
+export async function getCollectionCounts(): Promise<CollectionCount[]> {
+  return apiFetch("/api/saved-searches/counts");
+}
+
+// Share links API
+export async function listShares(): Promise<Share[]> {
+  return apiFetch("/api/shares");
+}
+
+export async function createShare(share: NewShare): Promise<Share> {
+  return apiPost("/api/shares", share);
+}
+
+export async function revokeShare(id: string): Promise<void> {
+  await apiDelete(`/api/shares/${encodeURIComponent(id)}`);
+}
+
+export async function openSmartCollection(
+  id: string,
+  markViewed: boolean = false,
+): Promise<SmartCollection> {
+  const params: Record<string, string> = {};
+  if (markViewed) params.markViewed = "true";
+  return apiFetch(`/api/saved-searches/${encodeURIComponent(id)}/collection`, params);
+}
+
 export async function setProjectAlias(
   source: string,
   projectId: string,
@@ -8,10 +8,14 @@ import type {
   ProjectCostEntry,
   SessionCostSummary,
   RequestLogFilter,
+  QueryError,
+  ScanProgress,
+  SearchOptions,
   SearchResult,
   Bookmark,
   DeleteLevel,
   RecycledItem,
+  SessionSource,
 } from "../types";
 import { api } from "../services/api";
 
@@ -36,8 +40,8 @@ const projectsInFlight = new Map<string, Promise<void>>();
 
 interface AppState {
   // Source
-  source: "claude" | "codex";
-  setSource: (s: "claude" | "codex") => void;
+  source: SessionSource;
+  setSource: (s: SessionSource) => void;
 
   // Display settings
   showTimestamp: boolean;
@@ -79,8 +83,18 @@ interface AppState {
   // Search
   searchQuery: string;
   searchScope: "all" | "content" | "session" | "tags";
+  searchOptions: SearchOptions;
+  /** Search every source instead of the current one. */
+  searchAllSources: boolean;
   searchResults: SearchResult[];
+  /** Matches / sessions found in total, beyond the listed results. */
+  searchTotalMatches: number;
+  searchTotalSessions: number;
+  searchQueryError: QueryError | null;
+  searchHighlightTerms: string[];
   searchLoading: boolean;
+  /** Progress of the running search, from its latest batch. */
+  searchProgress: ScanProgress | null;
 
   // Stats
   tokenSummary: TokenUsageSummary | null;
@@ -137,6 +151,8 @@ interface AppState {
   jumpToMessageIndex: (targetIndex: number) => Promise<void>;
   search: (query: string) => Promise<void>;
   setSearchScope: (scope: "all" | "content" | "session" | "tags") => void;
+  setSearchOptions: (options: SearchOptions) => void;
+  setSearchAllSources: (all: boolean) => void;
   loadStats: () => Promise<void>;
   /** Load (or reload) the per-request log with the current filter. */
   loadRequestLog: (filter?: RequestLogFilter) => Promise<void>;
@@ -173,6 +189,7 @@ interface AppState {
 export const useAppStore = create<AppState>((set, get) => ({
   source: "claude",
   setSource: (s) => {
+    void api.cancelSearch();
     set({
       source: s,
       projects: [],
@@ -185,9 +202,14 @@ export const useAppStore = create<AppState>((set, get) => ({
       selectedProject: null,
       selectedFilePath: null,
       searchResults: [],
+      searchTotalMatches: 0,
+      searchTotalSessions: 0,
+      searchQueryError: null,
+      searchHighlightTerms: [],
       searchQuery: "",
       searchScope: "all",
       searchLoading: false,
+      searchProgress: null,
       tokenSummary: null,
       statsLoading: false,
       statsIsFirstBuild: null,
@@ -243,8 +265,15 @@ export const useAppStore = create<AppState>((set, get) => ({
 
   searchQuery: "",
   searchScope: "all",
+  searchOptions: {},
+  searchAllSources: false,
   searchResults: [],
+  searchTotalMatches: 0,
+  searchTotalSessions: 0,
+  searchQueryError: null,
+  searchHighlightTerms: [],
   searchLoading: false,
+  searchProgress: null,
 
   tokenSummary: null,
   statsLoading: false,
@@ -642,15 +671,48 @@ export const useAppStore = create<AppState>((set, get) => ({
     const scope = get().searchScope;
     set({ searchQuery: query, searchLoading: true });
     if (!query.trim()) {
-      set({ searchResults: [], searchLoading: false });
+      void api.cancelSearch();
+      set({
+        searchResults: [],
+        searchTotalMatches: 0,
+        searchTotalSessions: 0,
+        searchQueryError: null,
+        searchHighlightTerms: [],
+        searchLoading: false,
+        searchProgress: null,
+      });
       return;
     }
     try {
-      const results = await api.globalSearch(get().source, query, 50, scope);
-      set({ searchResults: results, searchLoading: false });
+      const { source, searchAllSources, searchOptions } = get();
+      // Batches arrive in discovery order; the first one replaces the
+      // previous query's results, the final response ranks them.
+      let streamed: SearchResult[] = [];
+      const response = await api.streamSearch(
+        searchAllSources ? "all" : source,
+        query,
+        50,
+        scope,
+        searchOptions,
+        (batch) => {
+          streamed = [...streamed, ...batch.results];
+          set({ searchResults: streamed, searchProgress: batch.progress });
+        },
+      );
+      // Superseded by a newer search, which owns the state now.
+      if (!response) return;
+      set({
+        searchResults: response.results,
+        searchTotalMatches: response.totalMatches,
+        searchTotalSessions: response.totalSessions,
+        searchQueryError: response.queryError,
+        searchHighlightTerms: response.highlightTerms,
+        searchLoading: false,
+        searchProgress: null,
+      });
     } catch (e) {
       console.error("Failed to search:", e);
-      set({ searchLoading: false });
+      set({ searchLoading: false, searchProgress: null });
     }
   },
 
@@ -662,6 +724,22 @@ export const useAppStore = create<AppState>((set, get) => ({
     }
   },
 
+  setSearchOptions: (options) => {
+    set({ searchOptions: options });
+    const query = get().searchQuery;
+    if (query.trim()) {
+      void get().search(query);
+    }
+  },
+
+  setSearchAllSources: (all) => {
+    set({ searchAllSources: all });
+    const query = get().searchQuery;
+    if (query.trim()) {
+      void get().search(query);
+    }
+  },
+
   loadStats: async () => {
     set({ statsLoading: true, statsIsFirstBuild: null });
     try {
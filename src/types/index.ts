@@ -1,3 +1,6 @@
+/** Session source ids; mirrors the provider registry in session-core. */
+export type SessionSource = "claude" | "codex" | "gemini" | "aider";
+
 export interface ProjectEntry {
   source: string;
   id: string;
@@ -11,6 +14,9 @@ export interface ProjectEntry {
   /** Codex only: synthetic "unrooted" project that buckets sessions with no
    *  cwd by date. Real cwd projects keep this falsy. */
   isVirtual?: boolean;
+  /** Named data root (synced copy of another machine's home) the project
+   *  comes from; absent for the local default home. */
+  dataRoot?: string | null;
 }
 
 /** A discovered skill (one `SKILL.md` directory). Mirrors the Rust
@@ -81,11 +87,44 @@ export interface SessionIndexEntry {
   status?: SessionStatus;
 }
 
+/** Token / cost of one model request, or summed over a thread. */
+export interface MessageUsage {
+  inputTokens: number;
+  outputTokens: number;
+  cacheReadTokens: number;
+  cacheCreationTokens: number;
+  totalTokens: number;
+  costUsd: number;
+}
+
+/** Subagent (sidechain) transcript spawned by a Task tool call; its messages
+ *  are loaded on demand via `getSubagentThread`. */
+export interface SubagentSummary {
+  agentId: string;
+  messageCount: number;
+  usage: MessageUsage;
+}
+
+export interface SubagentThread {
+  agentId: string;
+  messages: DisplayMessage[];
+  usage: MessageUsage;
+}
+
 export type DisplayContentBlock =
   | { type: "text"; text: string }
   | { type: "thinking"; thinking: string }
-  | { type: "tool_use"; id: string; name: string; input: string }
+  | { type: "tool_use"; id: string; name: string; input: string; subagent?: SubagentSummary }
   | { type: "tool_result"; toolUseId: string; content: string; isError: boolean }
+  /** Payload loaded on demand via `getMessageMediaUrl`; `url` only for remote images. */
+  | { type: "image"; mediaType: string; blockIndex: number; url?: string }
+  | {
+      type: "document";
+      mediaType: string;
+      title: string | null;
+      blockIndex: number;
+      url?: string;
+    }
   | { type: "reasoning"; text: string }
   | { type: "function_call"; name: string; arguments: string; callId: string }
   | { type: "function_call_output"; callId: string; output: string };
@@ -97,6 +136,108 @@ export interface DisplayMessage {
   timestamp: string | null;
   model: string | null;
   content: DisplayContentBlock[];
+  /** Tokens / cost of the model request behind this message; set on one message per request. */
+  usage?: MessageUsage;
+}
+
+/** One displayed message of a session's uuid / parentUuid tree. */
+export interface TreeNode {
+  uuid: string;
+  parentUuid: string | null;
+  /** Child uuids, oldest attempt first. */
+  children: string[];
+  role: string;
+  timestamp: string | null;
+  preview: string;
+  /** On the path to the active leaf. */
+  active: boolean;
+}
+
+export interface ConversationTree {
+  nodes: TreeNode[];
+  roots: string[];
+  activeLeaf: string | null;
+  leafCount: number;
+}
+
+export interface BranchMessages {
+  leafUuid: string;
+  active: boolean;
+  messages: DisplayMessage[];
+}
+
+/** One todo of a TodoWrite / update_plan list. */
+export interface TodoItem {
+  content: string;
+  status: "pending" | "in_progress" | "completed" | string;
+  activeForm: string | null;
+}
+
+export interface TodoList {
+  /** Subagent id; null for the session's own agent. */
+  agentId: string | null;
+  items: TodoItem[];
+  completed: number;
+  updatedAt: string | null;
+}
+
+/** A plan presented through ExitPlanMode. */
+export interface PlanDocument {
+  toolUseId: string;
+  messageUuid: string | null;
+  timestamp: string | null;
+  /** Markdown. */
+  plan: string;
+  /** null while unanswered. */
+  approved: boolean | null;
+}
+
+export interface SessionPlan {
+  todos: TodoList | null;
+  agentTodos: TodoList[];
+  plans: PlanDocument[];
+}
+
+/** A pre-edit backup under ~/.claude/file-history/<session>/. */
+export interface FileBackup {
+  version: number;
+  /** Prompt whose snapshot first recorded this version. */
+  messageUuid: string;
+  backupTime: string | null;
+  /** false when the file did not exist yet. */
+  exists: boolean;
+}
+
+/** An Edit / MultiEdit / Write tool call on a tracked file. */
+export interface FileEdit {
+  toolUseId: string;
+  toolName: string;
+  messageUuid: string;
+  timestamp: string | null;
+  /** Backup version the edit was applied on top of. */
+  baseVersion: number | null;
+  isError: boolean;
+}
+
+export interface TrackedFile {
+  filePath: string;
+  backups: FileBackup[];
+  edits: FileEdit[];
+}
+
+export interface FileHistoryIndex {
+  sessionId: string;
+  files: TrackedFile[];
+}
+
+export interface FileContentsAtMessage {
+  filePath: string;
+  messageUuid: string;
+  /** null when the file did not exist. */
+  before: string | null;
+  after: string | null;
+  /** false when an edit could not be replayed and was skipped. */
+  exact: boolean;
 }
 
 export interface PaginatedMessages {
@@ -200,6 +341,69 @@ export interface SessionCostSummary {
   requests: RequestRecord[];
 }
 
+export interface ToolCount {
+  name: string;
+  count: number;
+}
+
+/** One user prompt and everything up to the next one. */
+export interface TurnStats {
+  index: number;
+  prompt: string;
+  messageUuid: string | null;
+  startedAt: string | null;
+  durationMs: number | null;
+  requestCount: number;
+  inputTokens: number;
+  outputTokens: number;
+  cacheReadTokens: number;
+  cacheCreationTokens: number;
+  totalTokens: number;
+  costUsd: number;
+  /** Most used first. */
+  tools: ToolCount[];
+  /** Relative to the session's working directory. */
+  files: string[];
+}
+
+/** b − a of an aligned turn pair. */
+export interface TurnDelta {
+  totalTokens: number;
+  costUsd: number;
+  durationMs: number | null;
+}
+
+/** A side is null when the other session has no matching turn. */
+export interface TurnPair {
+  a: TurnStats | null;
+  b: TurnStats | null;
+  /** 0–1 word overlap of the two prompts. */
+  promptSimilarity: number | null;
+  delta: TurnDelta | null;
+}
+
+export interface CompareSide {
+  source: string;
+  filePath: string;
+  models: string[];
+  turnCount: number;
+  requestCount: number;
+  totalTokens: number;
+  costUsd: number;
+  durationMs: number | null;
+  tools: ToolCount[];
+  files: string[];
+}
+
+export interface SessionComparison {
+  a: CompareSide;
+  b: CompareSide;
+  turns: TurnPair[];
+  filesOnlyA: string[];
+  filesOnlyB: string[];
+  filesBoth: string[];
+}
+
 export interface RequestLogFilter {
   projectId?: string | null;
   sessionId?: string | null;
@@ -210,6 +414,44 @@ export interface RequestLogFilter {
   pageSize?: number;
 }
 
+/** One prompt from Claude Code's `history.jsonl`. */
+export interface PromptHistoryEntry {
+  /** As typed, with markers such as `[Pasted text #1 +12 lines]` / `[Image #1]`. */
+  display: string;
+  timestamp: string | null;
+  project: string | null;
+  dataRoot: string | null;
+  pasted: PastedContentRef[];
+  /** Session containing the prompt, when it could be resolved. */
+  sessionId: string | null;
+  projectId: string | null;
+  filePath: string | null;
+}
+
+export interface PastedContentRef {
+  id: number;
+  kind: "text" | "image" | string;
+  lineCount: number | null;
+  preview: string | null;
+}
+
+export interface PromptHistoryPage {
+  entries: PromptHistoryEntry[];
+  total: number;
+  page: number;
+  pageSize: number;
+  hasMore: boolean;
+  /** Every project in the history with its prompt count. */
+  projects: { project: string; count: number }[];
+}
+
+export interface PromptHistoryFilter {
+  query?: string | null;
+  project?: string | null;
+  page?: number;
+  pageSize?: number;
+}
+
 export interface SearchResult {
   source: string;
   projectId: string;
@@ -226,6 +468,53 @@ export interface SearchResult {
   filePath: string;
   totalMessageCount: number;
   matchedMessageId: string | null;
+  /** Where the content terms matched within matchedText. */
+  matchRanges: MatchRange[];
+  /** Relevance (BM25-style); higher is better, 0 when unscored. */
+  score: number;
+}
+
+/** A match in matchedText: byte offsets and character (code point) offsets. */
+export interface MatchRange {
+  start: number;
+  end: number;
+  charStart: number;
+  charEnd: number;
+}
+
+/** How content terms match; filters are unaffected. */
+export interface SearchOptions {
+  regex?: boolean;
+  caseSensitive?: boolean;
+  wholeWord?: boolean;
+}
+
+export interface SearchResponse {
+  results: SearchResult[];
+  /** Every match, including those past maxResults or the per-session limit. */
+  totalMatches: number;
+  /** Sessions with at least one match. */
+  totalSessions: number;
+  /** false when the index was unavailable and totals only cover results. */
+  indexed: boolean;
+  /** Set, with no results, when the query does not parse. */
+  queryError: QueryError | null;
+  /** Plain text terms of the query, for searching within a session; empty in regex mode. */
+  highlightTerms: string[];
+}
+
+/** Results a streamed search has found so far, tagged with its search id. */
+export interface SearchBatch {
+  id: number;
+  results: SearchResult[];
+  progress: ScanProgress;
+}
+
+/** Query syntax error; start/end are character offsets into the query. */
+export interface QueryError {
+  message: string;
+  start: number;
+  end: number;
 }
 
 export interface Bookmark {
@@ -241,10 +530,131 @@ export interface Bookmark {
   createdAt: string;
 }
 
+/** 保存的搜索：来源、匹配范围、查询（含过滤语法）与标签。 */
+export interface SavedSearch {
+  id: string;
+  name: string;
+  /** A source id, or "all". */
+  source: string;
+  query: string;
+  scope: "all" | "content" | "session" | "tags";
+  regex: boolean;
+  caseSensitive: boolean;
+  wholeWord: boolean;
+  /** Sessions must carry every one of these tags. */
+  tags: string[];
+  createdAt: string;
+  lastViewedAt: string | null;
+}
+
+/** A session in a smart collection: its best match. */
+export interface CollectionSession extends SearchResult {
+  /** Not matched when the collection was last viewed. */
+  isNew: boolean;
+}
+
+/** 智能合集：保存的搜索实时执行的结果，新会话排在前面。 */
+export interface SmartCollection {
+  search: SavedSearch;
+  sessions: CollectionSession[];
+  /** Among `sessions`. */
+  newCount: number;
+  /** Every matching session, including those past the results cap. */
+  totalSessions: number;
+  /** `sessions` stops at the results cap and leaves some matches out. */
+  truncated: boolean;
+}
+
+export interface CollectionCount {
+  id: string;
+  totalSessions: number;
+  newCount: number;
+}
+
+export type NewSavedSearch = Omit<SavedSearch, "id" | "createdAt" | "lastViewedAt">;
+
 export type DeleteLevel = "sessionOnly" | "withCcConfig";
 
 /** 会话导出格式。 */
-export type ExportFormat = "json" | "markdown" | "html";
+export type ExportFormat =
+  | "json"
+  | "markdown"
+  | "html"
+  | "patch"
+  | "mbox"
+  | "openai"
+  | "sharegpt";
+
+/** patch / mbox export: one patch per tool call, or per user turn. */
+export type PatchGrouping = "call" | "turn";
+
+/** 导出时的过滤 / 截断 / 脱敏选项；未设置的字段用后端默认值（全部包含、不截断、不脱敏）。 */
+export interface ExportOptions {
+  includeThinking?: boolean;
+  includeToolCalls?: boolean;
+  includeToolResults?: boolean;
+  /** 每段工具输出最多保留的行数 */
+  maxToolOutputLines?: number;
+  /** 起始消息下标（0-based，含） */
+  messageStart?: number;
+  /** 结束消息下标（不含） */
+  messageEnd?: number;
+  /** `none` / `standard`，或 `home,email,secrets` 的任意组合 */
+  redact?: string;
+}
+
+/** 打包导出（zip）的会话范围。 */
+export type ArchiveSelection =
+  | { kind: "project"; projectId: string }
+  | { kind: "tag"; tag: string }
+  | { kind: "sessions"; filePaths: string[] };
+
+/** 数据集导出统计：写入条数与各类跳过原因。 */
+export interface DatasetStats {
+  sessions: number;
+  written: number;
+  /** 与已写入的对话完全相同 */
+  duplicates: number;
+  /** 没有得到助手回答的用户提问 */
+  empty: number;
+  /** 会话读取失败 */
+  failed: number;
+}
+
+export interface DatasetExport {
+  /** JSONL，每行一个对话 */
+  content: string;
+  stats: DatasetStats;
+}
+
+/** 只读分享链接（仅 Web 模式）：单个会话，可限定消息区间与脱敏。 */
+export interface Share {
+  id: string;
+  source: string;
+  filePath: string;
+  /** 分享列表里显示的名字，对方看不到 */
+  title?: string | null;
+  /** 起始消息下标（0-based，含） */
+  messageStart?: number | null;
+  /** 结束消息下标（不含） */
+  messageEnd?: number | null;
+  redact?: string | null;
+  createdAt: string;
+  expiresAt: string;
+  /** 打开链接用的签名 token：`/share/<token>` */
+  token: string;
+}
+
+export interface NewShare {
+  source: string;
+  filePath: string;
+  title?: string;
+  messageStart?: number;
+  messageEnd?: number;
+  redact?: string;
+  /** 默认 72 小时，最长 720 小时 */
+  expiresInHours?: number;
+}
 
 /** 冷启动扫描进度快照。 */
 export interface ScanProgress {
@@ -0,0 +1,22 @@
+import type { SessionSource } from "../types";
+
+/** Session sources in sidebar order. Mirrors `PROVIDERS` in
+ *  crates/session-core/src/provider/mod.rs. */
+export const SESSION_SOURCES: SessionSource[] = ["claude", "codex", "gemini", "aider"];
+
+/** Whether a wire `source` string is one the source picker can switch to. */
+export function isSessionSource(s: string): s is SessionSource {
+  return (SESSION_SOURCES as string[]).includes(s);
+}
+
+const SOURCE_LABELS: Record<SessionSource, string> = {
+  claude: "Claude",
+  codex: "Codex",
+  gemini: "Gemini",
+  aider: "Aider",
+};
+
+/** Display name for a session source; unknown ids pass through. */
+export function sourceLabel(s: string): string {
+  return isSessionSource(s) ? SOURCE_LABELS[s] : s;
+}